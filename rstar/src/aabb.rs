@@ -1,4 +1,7 @@
-use crate::point::{max_inline, Point, PointExt};
+use crate::point::{
+    is_nan, max_inline, midpoint, min_inline, saturating_add, saturating_mul, saturating_sub,
+    total_cmp, Point, PointExt,
+};
 use crate::{Envelope, RTreeObject};
 use num_traits::{Bounded, One, Signed, Zero};
 
@@ -53,6 +56,13 @@ where
         self.upper
     }
 
+    /// Creates an AABB from its corners without reordering them.
+    ///
+    /// Unlike [from_corners](#method.from_corners), this keeps empty AABBs intact.
+    pub(crate) fn from_lower_and_upper(lower: P, upper: P) -> Self {
+        AABB { lower, upper }
+    }
+
     /// Creates a new AABB encompassing two points.
     pub fn from_corners(p1: P, p2: P) -> Self {
         AABB {
@@ -79,6 +89,16 @@ where
         }
     }
 
+    /// Returns `true` if any coordinate is NaN.
+    ///
+    /// Such envelopes are ignored when merging: Partially merging them would break the
+    /// assumption of nearest neighbor queries that an element touches each face of a node's
+    /// envelope.
+    fn has_nan(&self) -> bool {
+        self.lower.fold(false, |nan, x| nan || is_nan(x))
+            || self.upper.fold(false, |nan, x| nan || is_nan(x))
+    }
+
     /// Returns the point within this AABB closest to a given point.
     ///
     /// If `point` is contained within the AABB, `point` will be returned.
@@ -91,7 +111,7 @@ where
         if self.contains_point(point) {
             Zero::zero()
         } else {
-            self.min_point(point).sub(point).length_2()
+            self.min_point(point).saturating_sub(point).length_2()
         }
     }
 }
@@ -117,11 +137,13 @@ where
     }
 
     fn merge(&mut self, other: &Self) {
-        self.lower = self.lower.min_point(&other.lower);
-        self.upper = self.upper.max_point(&other.upper);
+        *self = self.merged(other);
     }
 
     fn merged(&self, other: &Self) -> Self {
+        if other.has_nan() {
+            return *self;
+        }
         AABB {
             lower: self.lower.min_point(&other.lower),
             upper: self.upper.max_point(&other.upper),
@@ -134,10 +156,27 @@ where
     }
 
     fn area(&self) -> P::Scalar {
-        let zero = P::Scalar::zero();
-        let one = P::Scalar::one();
-        let diag = self.upper.sub(&self.lower);
-        diag.fold(one, |acc, cur| max_inline(cur, zero) * acc)
+        // The most common dimensions are unrolled explicitly. `P::DIMENSIONS` is a constant,
+        // the compiler will only keep the matching branch.
+        match P::DIMENSIONS {
+            2 => saturating_mul(
+                extent(&self.lower, &self.upper, 0),
+                extent(&self.lower, &self.upper, 1),
+            ),
+            3 => saturating_mul(
+                saturating_mul(
+                    extent(&self.lower, &self.upper, 0),
+                    extent(&self.lower, &self.upper, 1),
+                ),
+                extent(&self.lower, &self.upper, 2),
+            ),
+            _ => {
+                let zero = P::Scalar::zero();
+                let one = P::Scalar::one();
+                let diag = self.upper.saturating_sub(&self.lower);
+                diag.fold(one, |acc, cur| saturating_mul(max_inline(cur, zero), acc))
+            }
+        }
     }
 
     fn distance_2(&self, point: &P) -> P::Scalar {
@@ -145,8 +184,8 @@ where
     }
 
     fn min_max_dist_2(&self, point: &P) -> <P as Point>::Scalar {
-        let l = self.lower.sub(point);
-        let u = self.upper.sub(point);
+        let l = self.lower.saturating_sub(point);
+        let u = self.upper.saturating_sub(point);
         let (mut min, mut max) = (P::new(), P::new());
         for i in 0..P::DIMENSIONS {
             if l.nth(i).abs() < u.nth(i).abs() {
@@ -171,32 +210,58 @@ where
     }
 
     fn center(&self) -> Self::Point {
-        let one = <Self::Point as Point>::Scalar::one();
-        let two = one + one;
-        self.lower.component_wise(&self.upper, |x, y| (x + y) / two)
+        self.lower.component_wise(&self.upper, midpoint)
+    }
+
+    fn bounds_along_axis(&self, axis: usize) -> (P::Scalar, P::Scalar) {
+        (self.lower.nth(axis), self.upper.nth(axis))
     }
 
     fn intersection_area(&self, other: &Self) -> <Self::Point as Point>::Scalar {
-        AABB {
-            lower: self.lower.max_point(&other.lower),
-            upper: self.upper.min_point(&other.upper),
+        let axis_overlap = |axis| {
+            let lower = max_inline(self.lower.nth(axis), other.lower.nth(axis));
+            let upper = min_inline(self.upper.nth(axis), other.upper.nth(axis));
+            max_inline(saturating_sub(upper, lower), Zero::zero())
+        };
+        match P::DIMENSIONS {
+            2 => saturating_mul(axis_overlap(0), axis_overlap(1)),
+            3 => saturating_mul(
+                saturating_mul(axis_overlap(0), axis_overlap(1)),
+                axis_overlap(2),
+            ),
+            _ => AABB {
+                lower: self.lower.max_point(&other.lower),
+                upper: self.upper.min_point(&other.upper),
+            }
+            .area(),
         }
-        .area()
     }
 
     fn perimeter_value(&self) -> P::Scalar {
-        let diag = self.upper.sub(&self.lower);
         let zero = P::Scalar::zero();
-        max_inline(diag.fold(zero, |acc, value| acc + value), zero)
+        let sum = match P::DIMENSIONS {
+            2 => saturating_add(
+                axis_length(&self.lower, &self.upper, 0),
+                axis_length(&self.lower, &self.upper, 1),
+            ),
+            3 => saturating_add(
+                saturating_add(
+                    axis_length(&self.lower, &self.upper, 0),
+                    axis_length(&self.lower, &self.upper, 1),
+                ),
+                axis_length(&self.lower, &self.upper, 2),
+            ),
+            _ => {
+                let diag = self.upper.saturating_sub(&self.lower);
+                diag.fold(zero, saturating_add)
+            }
+        };
+        max_inline(sum, zero)
     }
 
     fn sort_envelopes<T: RTreeObject<Envelope = Self>>(axis: usize, envelopes: &mut [T]) {
         envelopes.sort_by(|l, r| {
-            l.envelope()
-                .lower
-                .nth(axis)
-                .partial_cmp(&r.envelope().lower.nth(axis))
-                .unwrap()
+            total_cmp(&l.envelope().lower.nth(axis), &r.envelope().lower.nth(axis))
         });
     }
 
@@ -206,15 +271,19 @@ where
         selection_size: usize,
     ) {
         ::pdqselect::select_by(envelopes, selection_size, |l, r| {
-            l.envelope()
-                .lower
-                .nth(axis)
-                .partial_cmp(&r.envelope().lower.nth(axis))
-                .unwrap()
+            total_cmp(&l.envelope().lower.nth(axis), &r.envelope().lower.nth(axis))
         });
     }
 }
 
+fn axis_length<P: Point>(lower: &P, upper: &P, axis: usize) -> P::Scalar {
+    saturating_sub(upper.nth(axis), lower.nth(axis))
+}
+
+fn extent<P: Point>(lower: &P, upper: &P, axis: usize) -> P::Scalar {
+    max_inline(axis_length(lower, upper, axis), Zero::zero())
+}
+
 fn new_empty<P: Point>() -> AABB<P> {
     let max = P::Scalar::max_value();
     let min = P::Scalar::min_value();
@@ -223,3 +292,57 @@ fn new_empty<P: Point>() -> AABB<P> {
         upper: P::from_value(min),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::AABB;
+    use crate::envelope::Envelope;
+
+    #[test]
+    fn test_integer_measures_saturate() {
+        let huge = AABB::from_corners([-2_000_000_000, -3], [2_000_000_000, 2_000_000_000]);
+        assert_eq!(huge.area(), i32::MAX);
+        assert_eq!(huge.perimeter_value(), i32::MAX);
+        assert_eq!(huge.intersection_area(&huge), i32::MAX);
+        assert_eq!(huge.distance_2(&[i32::MIN, 0]), i32::MAX);
+        let small = AABB::from_corners([-4, 0], [4, 2]);
+        assert_eq!(small.area(), 16);
+        assert_eq!(small.intersection_area(&huge), 16);
+        assert_eq!(AABB::<[i8; 3]>::new_empty().area(), 0);
+        assert_eq!(AABB::<[i8; 4]>::new_empty().perimeter_value(), 0);
+
+        let floats = AABB::from_corners([-1e30, 0.0], [1e30, 1e30]);
+        assert_eq!(floats.area(), 2e30 * 1e30);
+        assert_eq!(huge.center(), [0, 999_999_998]);
+        assert_eq!(AABB::from_corners([-3, 3], [-2, 4]).center(), [-2, 3]);
+    }
+
+    #[test]
+    fn test_unrolled_envelope_measures() {
+        let rectangle = AABB::from_corners([0.0, 1.0], [2.0, 4.0]);
+        let other = AABB::from_corners([1.0, 0.0], [5.0, 2.0]);
+        assert_eq!(rectangle.area(), 6.0);
+        assert_eq!(rectangle.perimeter_value(), 5.0);
+        assert_eq!(rectangle.intersection_area(&other), 1.0);
+        assert_eq!(
+            rectangle.intersection_area(&AABB::from_point([3.0, 3.0])),
+            0.0
+        );
+
+        let cuboid = AABB::from_corners([0, 0, 0], [2, 3, 4]);
+        let other = AABB::from_corners([1, 1, 1], [5, 5, 5]);
+        assert_eq!(cuboid.area(), 24);
+        assert_eq!(cuboid.perimeter_value(), 9);
+        assert_eq!(cuboid.intersection_area(&other), 6);
+
+        // Other dimensions use the generic implementation
+        let hypercube = AABB::from_corners([0; 4], [2; 4]);
+        assert_eq!(hypercube.area(), 16);
+        assert_eq!(hypercube.perimeter_value(), 8);
+        assert_eq!(hypercube.intersection_area(&AABB::from_point([1; 4])), 0);
+        assert_eq!(
+            hypercube.intersection_area(&AABB::from_corners([1; 4], [3; 4])),
+            1
+        );
+    }
+}
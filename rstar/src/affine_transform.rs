@@ -0,0 +1,125 @@
+use crate::aabb::AABB;
+use crate::envelope::Envelope;
+use crate::point::{max_inline, min_inline, Point, PointExt};
+use num_traits::{One, Zero};
+
+/// An affine transformation between two coordinate frames.
+///
+/// A point `p` is mapped to `matrix * p + translation`.
+///
+/// Used by [locate_in_transformed_envelope_intersecting](struct.RTree.html#method.locate_in_transformed_envelope_intersecting)
+/// to query a tree with a region that is defined in a different coordinate frame.
+///
+/// # Example
+/// ```
+/// use rstar::AffineTransform;
+///
+/// // Rotates by 90 degrees counterclockwise, then moves one unit up
+/// let transform = AffineTransform::new(vec![[0, -1], [1, 0]], [0, 1]);
+/// assert_eq!(transform.transform_point(&[2, 0]), [0, 3]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct AffineTransform<P>
+where
+    P: Point,
+{
+    rows: Vec<P>,
+    translation: P,
+}
+
+impl<P> AffineTransform<P>
+where
+    P: Point,
+{
+    /// Creates a new transformation from the rows of its linear part and a translation.
+    ///
+    /// # Panics
+    /// Panics if the number of rows does not match the point's dimension.
+    pub fn new(rows: Vec<P>, translation: P) -> Self {
+        assert_eq!(
+            rows.len(),
+            P::DIMENSIONS,
+            "An affine transformation requires one row per dimension"
+        );
+        AffineTransform { rows, translation }
+    }
+
+    /// Returns a transformation that only translates by a given offset.
+    pub fn from_translation(translation: P) -> Self {
+        let rows = (0..P::DIMENSIONS)
+            .map(|row| {
+                P::generate(|column| {
+                    if row == column {
+                        One::one()
+                    } else {
+                        Zero::zero()
+                    }
+                })
+            })
+            .collect();
+        AffineTransform { rows, translation }
+    }
+
+    /// Maps a point into the target coordinate frame.
+    pub fn transform_point(&self, point: &P) -> P {
+        P::generate(|row| self.rows[row].dot(point) + self.translation.nth(row))
+    }
+
+    /// Returns the smallest AABB in the target frame that contains the transformed envelope.
+    ///
+    /// Unless the transformation only scales and translates, the result is larger than the
+    /// actual transformed box. It is always conservative: any point contained in `envelope`
+    /// is mapped into the returned AABB.
+    pub fn transform_envelope(&self, envelope: &AABB<P>) -> AABB<P> {
+        let (lower, upper) = (envelope.lower(), envelope.upper());
+        if !lower.all_component_wise(&upper, |l, u| l <= u) {
+            // Transforming the coordinates of an empty envelope may overflow
+            return AABB::new_empty();
+        }
+        let mut min = P::new();
+        let mut max = P::new();
+        for (row_index, row) in self.rows.iter().enumerate() {
+            let offset = self.translation.nth(row_index);
+            let (mut row_min, mut row_max) = (offset, offset);
+            for column in 0..P::DIMENSIONS {
+                let factor = row.nth(column);
+                let from_lower = factor * lower.nth(column);
+                let from_upper = factor * upper.nth(column);
+                row_min = row_min + min_inline(from_lower, from_upper);
+                row_max = row_max + max_inline(from_lower, from_upper);
+            }
+            *min.nth_mut(row_index) = row_min;
+            *max.nth_mut(row_index) = row_max;
+        }
+        AABB::from_corners(min, max)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::AffineTransform;
+    use crate::envelope::Envelope;
+    use crate::AABB;
+
+    #[test]
+    fn test_transform_envelope() {
+        let rotation = AffineTransform::new(vec![[0, -1], [1, 0]], [10, 0]);
+        let envelope = AABB::from_corners([1, 2], [3, 5]);
+        assert_eq!(
+            rotation.transform_envelope(&envelope),
+            AABB::from_corners([5, 1], [8, 3])
+        );
+
+        // A shear enlarges the envelope conservatively
+        let shear = AffineTransform::new(vec![[1, 1], [0, 1]], [0, 0]);
+        let transformed = shear.transform_envelope(&AABB::from_corners([0, 0], [1, 1]));
+        assert_eq!(transformed, AABB::from_corners([0, 0], [2, 1]));
+        for corner in &[[0, 0], [0, 1], [1, 0], [1, 1]] {
+            assert!(transformed.contains_point(&shear.transform_point(corner)));
+        }
+
+        let empty = AABB::<[f64; 2]>::new_empty();
+        let translation = AffineTransform::from_translation([1.0, 1.0]);
+        assert_eq!(translation.transform_envelope(&empty), empty);
+    }
+}
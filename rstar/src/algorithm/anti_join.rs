@@ -0,0 +1,163 @@
+use crate::aabb::AABB;
+use crate::node::{ParentNode, RTreeNode};
+use crate::object::{PointDistance, RTreeObject};
+use crate::point::{max_inline, Point};
+use num_traits::Zero;
+
+/// Returns all points of `node` that have no element of `other` within a squared distance of
+/// `min_distance_2`.
+///
+/// Both trees are descended simultaneously. For every node of the point tree, only those
+/// nodes of `other` that are close enough to its envelope are kept as candidates. Once no
+/// candidate is left, all points below the node are returned without any further distance
+/// calculation.
+pub fn locate_farther_than<'a, P, U>(
+    node: &'a ParentNode<P>,
+    other: &ParentNode<U>,
+    min_distance_2: P::Scalar,
+) -> Vec<&'a P>
+where
+    P: Point,
+    U: PointDistance<Envelope = AABB<P>>,
+{
+    let mut result = Vec::new();
+    let candidates: Vec<_> = other.children.iter().collect();
+    locate_farther_than_recursive(node, &candidates, min_distance_2, &mut result);
+    result
+}
+
+fn locate_farther_than_recursive<'a, P, U>(
+    node: &'a ParentNode<P>,
+    candidates: &[&RTreeNode<U>],
+    min_distance_2: P::Scalar,
+    result: &mut Vec<&'a P>,
+) where
+    P: Point,
+    U: PointDistance<Envelope = AABB<P>>,
+{
+    // Unpack one level of the other tree for each level of this tree
+    let mut close_candidates = Vec::new();
+    for candidate in candidates {
+        match candidate {
+            RTreeNode::Leaf(ref u) => {
+                if envelope_distance_2(&node.envelope, &u.envelope()) <= min_distance_2 {
+                    close_candidates.push(*candidate);
+                }
+            }
+            RTreeNode::Parent(ref data) => {
+                if envelope_distance_2(&node.envelope, &data.envelope) <= min_distance_2 {
+                    close_candidates.extend(data.children.iter().filter(|child| {
+                        envelope_distance_2(&node.envelope, &child.envelope()) <= min_distance_2
+                    }));
+                }
+            }
+        }
+    }
+
+    for child in &node.children {
+        match child {
+            RTreeNode::Leaf(ref point) => {
+                let is_covered = close_candidates
+                    .iter()
+                    .any(|candidate| is_within_distance(point, candidate, min_distance_2));
+                if !is_covered {
+                    result.push(point);
+                }
+            }
+            RTreeNode::Parent(ref data) => {
+                if close_candidates.is_empty() {
+                    collect_points(data, result);
+                } else {
+                    locate_farther_than_recursive(data, &close_candidates, min_distance_2, result);
+                }
+            }
+        }
+    }
+}
+
+fn is_within_distance<P, U>(point: &P, node: &RTreeNode<U>, max_distance_2: P::Scalar) -> bool
+where
+    P: Point,
+    U: PointDistance<Envelope = AABB<P>>,
+{
+    match node {
+        RTreeNode::Leaf(ref u) => u
+            .distance_2_if_less_or_equal(point, max_distance_2)
+            .is_some(),
+        RTreeNode::Parent(ref data) => {
+            data.envelope.distance_2(point) <= max_distance_2
+                && data
+                    .children
+                    .iter()
+                    .any(|child| is_within_distance(point, child, max_distance_2))
+        }
+    }
+}
+
+fn collect_points<'a, P>(node: &'a ParentNode<P>, result: &mut Vec<&'a P>)
+where
+    P: Point,
+{
+    for child in &node.children {
+        match child {
+            RTreeNode::Leaf(ref point) => result.push(point),
+            RTreeNode::Parent(ref data) => collect_points(data, result),
+        }
+    }
+}
+
+/// Returns the squared distance between the closest points of two envelopes.
+fn envelope_distance_2<P>(first: &AABB<P>, second: &AABB<P>) -> P::Scalar
+where
+    P: Point,
+{
+    let zero = P::Scalar::zero();
+    let mut result = zero;
+    for axis in 0..P::DIMENSIONS {
+        let gap = max_inline(
+            max_inline(
+                second.lower().nth(axis) - first.upper().nth(axis),
+                first.lower().nth(axis) - second.upper().nth(axis),
+            ),
+            zero,
+        );
+        result = result + gap * gap;
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::object::PointDistance;
+    use crate::test_utilities::{create_random_lines, create_random_points, SEED_1, SEED_2};
+    use crate::RTree;
+
+    #[test]
+    fn test_locate_farther_than() {
+        let points = create_random_points(1000, SEED_1);
+        let lines = create_random_lines(100, SEED_2);
+        let point_tree = RTree::bulk_load(points.clone());
+        let line_tree = RTree::bulk_load(lines.clone());
+        let min_distance_2 = 0.05 * 0.05;
+
+        let mut expected: Vec<_> = points
+            .iter()
+            .filter(|point| {
+                lines
+                    .iter()
+                    .all(|line| line.distance_2(point) > min_distance_2)
+            })
+            .collect();
+        let mut result = point_tree.locate_farther_than(&line_tree, min_distance_2);
+        assert!(!expected.is_empty() && expected.len() < points.len());
+        expected.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        result.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        assert_eq!(result, expected);
+
+        let empty: RTree<[f64; 2]> = RTree::new();
+        assert_eq!(
+            point_tree.locate_farther_than(&empty, min_distance_2).len(),
+            points.len()
+        );
+    }
+}
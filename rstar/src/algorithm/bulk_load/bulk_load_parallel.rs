@@ -0,0 +1,160 @@
+use crate::envelope::Envelope;
+use crate::node::{ParentNode, RTreeNode};
+use crate::object::RTreeObject;
+use crate::params::RTreeParams;
+use crate::point::Point;
+use rayon::prelude::*;
+
+use super::bulk_load_sequential::bulk_load_recursive;
+use super::cluster_group_iterator::{calculate_cluster_sizes, calculate_depth, split_into_slabs};
+use super::load_with_envelope_caching;
+
+/// Subtrees with fewer elements are loaded sequentially.
+const SEQUENTIAL_THRESHOLD: usize = 4096;
+
+/// Splits elements into the given slabs along an axis.
+///
+/// Creates the same slabs as `ClusterGroupIterator`, but splits the slabs in half
+/// recursively, which allows to partition both halves in parallel.
+fn partition_slabs<T>(
+    mut elements: Vec<T>,
+    mut slabs: Vec<Vec<usize>>,
+    axis: usize,
+) -> Vec<(Vec<T>, Vec<usize>)>
+where
+    T: RTreeObject + Send,
+{
+    if slabs.len() <= 1 {
+        return vec![(elements, slabs.pop().unwrap_or_default())];
+    }
+    let upper_slabs = slabs.split_off(slabs.len() / 2);
+    let split = slabs.iter().flatten().sum();
+    T::Envelope::partition_envelopes(axis, &mut elements, split);
+    let upper = elements.split_off(split);
+    let (mut lower, upper) = rayon::join(
+        || partition_slabs(elements, slabs, axis),
+        || partition_slabs(upper, upper_slabs, axis),
+    );
+    lower.extend(upper);
+    lower
+}
+
+/// Partitions elements into clusters along all axes, starting with `axis - 1`.
+fn partition_clusters<T>(elements: Vec<T>, cluster_sizes: Vec<usize>, axis: usize) -> Vec<Vec<T>>
+where
+    T: RTreeObject + Send,
+{
+    if axis == 0 {
+        return vec![elements];
+    }
+    let slabs = split_into_slabs(cluster_sizes, axis);
+    partition_slabs(elements, slabs, axis - 1)
+        .into_par_iter()
+        .flat_map(|(slab, cluster_sizes)| partition_clusters(slab, cluster_sizes, axis - 1))
+        .collect()
+}
+
+fn bulk_load_recursive_parallel<T, Params>(elements: Vec<T>, depth: usize) -> ParentNode<T>
+where
+    T: RTreeObject + Send,
+    T::Envelope: Send,
+    Params: RTreeParams,
+{
+    if elements.len() < SEQUENTIAL_THRESHOLD {
+        return bulk_load_recursive::<_, Params>(elements, depth);
+    }
+    let cluster_sizes = calculate_cluster_sizes::<Params>(elements.len(), depth);
+    let dimensions = <T::Envelope as Envelope>::Point::DIMENSIONS;
+    let children = partition_clusters(elements, cluster_sizes, dimensions)
+        .into_par_iter()
+        .map(|cluster| {
+            RTreeNode::Parent(bulk_load_recursive_parallel::<_, Params>(
+                cluster,
+                depth - 1,
+            ))
+        })
+        .collect();
+    ParentNode::new_parent(children)
+}
+
+/// A parallel implementation of the OMT bulk loading algorithm.
+///
+/// Creates the same nodes as `bulk_load_sequential`, the order of a node's children may differ.
+pub fn bulk_load_parallel<T, Params>(elements: Vec<T>) -> ParentNode<T>
+where
+    T: RTreeObject + Send,
+    T::Envelope: Send,
+    Params: RTreeParams,
+{
+    load_with_envelope_caching::<_, Params>(
+        elements,
+        load_parallel::<_, Params>,
+        load_parallel::<_, Params>,
+    )
+}
+
+fn load_parallel<T, Params>(elements: Vec<T>) -> ParentNode<T>
+where
+    T: RTreeObject + Send,
+    T::Envelope: Send,
+    Params: RTreeParams,
+{
+    let depth = calculate_depth::<Params>(elements.len());
+    bulk_load_recursive_parallel::<_, Params>(elements, depth)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::envelope::Envelope;
+    use crate::node::{ParentNode, RTreeNode};
+    use crate::object::RTreeObject;
+    use crate::test_utilities::{create_random_points, create_random_rectangles, SEED_1};
+    use crate::{RTree, AABB};
+
+    fn collect_envelopes<T: RTreeObject>(node: &ParentNode<T>, result: &mut Vec<T::Envelope>) {
+        result.push(node.envelope());
+        for child in node.children() {
+            if let RTreeNode::Parent(ref data) = child {
+                collect_envelopes(data, result);
+            }
+        }
+    }
+
+    fn sorted_envelopes(tree: &RTree<[f64; 2]>) -> Vec<AABB<[f64; 2]>> {
+        let mut result = Vec::new();
+        collect_envelopes(tree.root(), &mut result);
+        result.sort_by(|l, r| {
+            (l.lower(), l.upper())
+                .partial_cmp(&(r.lower(), r.upper()))
+                .unwrap()
+        });
+        result
+    }
+
+    #[test]
+    fn test_bulk_load_parallel() {
+        for size in [0, 1, 100, 5000, 20000] {
+            let points = create_random_points(size, SEED_1);
+            let tree = RTree::bulk_load_parallel(points.clone());
+            assert_eq!(tree.size(), size);
+            assert_eq!(tree.iter().count(), size);
+            assert!(points.iter().all(|p| tree.contains(p)));
+            // The parallel algorithm creates the same nodes
+            let sequential = RTree::bulk_load(points);
+            assert_eq!(tree.height(), sequential.height());
+            assert_eq!(sorted_envelopes(&tree), sorted_envelopes(&sequential));
+        }
+
+        let rectangles = create_random_rectangles(10000, SEED_1);
+        let tree = RTree::bulk_load_parallel(rectangles.clone());
+        let envelope = AABB::from_corners([0.2, 0.2], [0.5, 0.6]);
+        let expected = rectangles
+            .iter()
+            .filter(|r| r.envelope().intersects(&envelope))
+            .count();
+        assert_eq!(
+            tree.locate_in_envelope_intersecting(&envelope).count(),
+            expected
+        );
+    }
+}
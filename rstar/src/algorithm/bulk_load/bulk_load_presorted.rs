@@ -0,0 +1,69 @@
+use crate::node::{ParentNode, RTreeNode};
+use crate::object::RTreeObject;
+use crate::params::RTreeParams;
+
+/// Packs elements that are already sorted into a tree, level by level.
+///
+/// Consecutive elements are grouped into leaf nodes, consecutive nodes into their parents.
+/// No element is moved, the quality of the resulting tree solely depends on the given order.
+pub fn bulk_load_presorted<T, Params>(elements: Vec<T>) -> ParentNode<T>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    let mut nodes: Vec<_> = pack::<_, Params>(elements.into_iter().map(RTreeNode::Leaf).collect());
+    while nodes.len() > Params::MAX_SIZE {
+        nodes = pack::<_, Params>(nodes.into_iter().map(RTreeNode::Parent).collect());
+    }
+    if nodes.len() == 1 {
+        nodes.pop().unwrap()
+    } else {
+        ParentNode::new_parent(nodes.into_iter().map(RTreeNode::Parent).collect())
+    }
+}
+
+/// Groups consecutive nodes into as few parents as possible.
+///
+/// The nodes are distributed evenly, this guarantees that every parent contains at least
+/// `Params::MIN_SIZE` children.
+fn pack<T, Params>(nodes: Vec<RTreeNode<T>>) -> Vec<ParentNode<T>>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    let len = nodes.len();
+    let number_of_parents = ((len + Params::MAX_SIZE - 1) / Params::MAX_SIZE).max(1);
+    let mut nodes = nodes.into_iter();
+    (0..number_of_parents)
+        .map(|index| {
+            let size = (index + 1) * len / number_of_parents - index * len / number_of_parents;
+            ParentNode::new_parent(nodes.by_ref().take(size).collect())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_utilities::{create_random_points, SEED_1};
+    use crate::{DefaultParams, Envelope, RTree, AABB};
+
+    #[test]
+    fn test_bulk_load_presorted() {
+        for size in (0..60).map(|i| i * 17) {
+            let mut points = create_random_points(size, SEED_1);
+            points.sort_by(|l, r| l.partial_cmp(r).unwrap());
+            let tree = RTree::bulk_load_presorted(points.clone());
+            assert_eq!(tree.size(), size);
+            tree.root().sanity_check::<DefaultParams>();
+            for point in &points {
+                assert!(tree.contains(point));
+            }
+            let query = AABB::from_corners([0.2, 0.3], [0.6, 0.5]);
+            let expected = points
+                .iter()
+                .filter(|point| query.contains_point(point))
+                .count();
+            assert_eq!(tree.locate_in_envelope(&query).count(), expected);
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use crate::aabb::AABB;
+use crate::node::{ParentNode, RTreeNode};
+use crate::object::RTreeObject;
+use crate::params::RTreeParams;
+use crate::point::{total_cmp, Point};
+use std::cmp::Ordering;
+
+use super::load_with_envelope_caching;
+
+/// Packs elements into a Priority R-tree, level by level.
+///
+/// See Arge et al., "The Priority R-Tree: A Practically Efficient and Worst-Case Optimal
+/// R-Tree". The nodes of each level are grouped by a pseudo PR-tree, its leaves become the
+/// parents of the next level.
+pub fn bulk_load_priority<T, P, Params>(elements: Vec<T>) -> ParentNode<T>
+where
+    T: RTreeObject<Envelope = AABB<P>>,
+    P: Point,
+    Params: RTreeParams,
+{
+    load_with_envelope_caching::<_, Params>(
+        elements,
+        load_priority::<_, _, Params>,
+        load_priority::<_, _, Params>,
+    )
+}
+
+fn load_priority<T, P, Params>(elements: Vec<T>) -> ParentNode<T>
+where
+    T: RTreeObject<Envelope = AABB<P>>,
+    P: Point,
+    Params: RTreeParams,
+{
+    let mut nodes = pack::<_, _, Params>(elements.into_iter().map(RTreeNode::Leaf).collect());
+    while nodes.len() > Params::MAX_SIZE {
+        nodes = pack::<_, _, Params>(nodes.into_iter().map(RTreeNode::Parent).collect());
+    }
+    if nodes.len() == 1 {
+        nodes.pop().unwrap()
+    } else {
+        ParentNode::new_parent(nodes.into_iter().map(RTreeNode::Parent).collect())
+    }
+}
+
+fn pack<T, P, Params>(nodes: Vec<RTreeNode<T>>) -> Vec<ParentNode<T>>
+where
+    T: RTreeObject<Envelope = AABB<P>>,
+    P: Point,
+    Params: RTreeParams,
+{
+    let mut groups = Vec::new();
+    pseudo_pr_tree::<_, _, Params>(nodes, 0, &mut groups);
+    groups.into_iter().map(ParentNode::new_parent).collect()
+}
+
+/// Groups the nodes into the leaves of a pseudo PR-tree.
+///
+/// Each envelope is treated as a point of `2 * D` dimensions, its lower and upper corner.
+/// For each of these dimensions, the `MAX_SIZE` most extreme nodes form a priority leaf. The
+/// remaining nodes are split at their median like in a kd-tree, cycling through the
+/// dimensions. Every group contains at least `MIN_SIZE` nodes.
+fn pseudo_pr_tree<T, P, Params>(
+    mut nodes: Vec<RTreeNode<T>>,
+    depth: usize,
+    groups: &mut Vec<Vec<RTreeNode<T>>>,
+) where
+    T: RTreeObject<Envelope = AABB<P>>,
+    P: Point,
+    Params: RTreeParams,
+{
+    let directions = 2 * P::DIMENSIONS;
+    for direction in 0..directions {
+        if nodes.len() <= Params::MAX_SIZE {
+            break;
+        }
+        // Leave enough nodes for the remaining groups to reach the minimum size
+        let size = Params::MAX_SIZE.min(nodes.len() - Params::MIN_SIZE);
+        ::pdqselect::select_by(&mut nodes, size, |l, r| compare(direction, l, r));
+        let remaining = nodes.split_off(size);
+        groups.push(nodes);
+        nodes = remaining;
+    }
+    if nodes.len() <= Params::MAX_SIZE {
+        groups.push(nodes);
+        return;
+    }
+    let direction = depth % directions;
+    let median = nodes.len() / 2;
+    ::pdqselect::select_by(&mut nodes, median, |l, r| compare(direction, l, r));
+    let upper = nodes.split_off(median);
+    pseudo_pr_tree::<_, _, Params>(nodes, depth + 1, groups);
+    pseudo_pr_tree::<_, _, Params>(upper, depth + 1, groups);
+}
+
+/// Orders nodes along one of the `2 * D` dimensions, the most extreme node first.
+///
+/// The first `D` directions compare the lower corners, smallest first. The others compare
+/// the upper corners, largest first.
+fn compare<T, P>(direction: usize, l: &RTreeNode<T>, r: &RTreeNode<T>) -> Ordering
+where
+    T: RTreeObject<Envelope = AABB<P>>,
+    P: Point,
+{
+    let (l, r) = (l.envelope(), r.envelope());
+    if direction < P::DIMENSIONS {
+        total_cmp(&l.lower().nth(direction), &r.lower().nth(direction))
+    } else {
+        let axis = direction - P::DIMENSIONS;
+        total_cmp(&r.upper().nth(axis), &l.upper().nth(axis))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::primitives::Rectangle;
+    use crate::test_utilities::{create_random_points, create_random_rectangles, SEED_1, SEED_2};
+    use crate::{DefaultParams, Envelope, RTree, RTreeObject, AABB};
+
+    #[test]
+    fn test_bulk_load_priority() {
+        for size in (0..60).map(|i| i * 17) {
+            let rectangles = create_random_rectangles(size, SEED_1);
+            let tree = RTree::bulk_load_priority(rectangles.clone());
+            assert_eq!(tree.size(), size);
+            tree.root().sanity_check::<DefaultParams>();
+            for rectangle in &rectangles {
+                assert!(tree.contains(rectangle));
+            }
+        }
+    }
+
+    #[test]
+    fn test_bulk_load_priority_skinny_rectangles() {
+        // Long, thin rectangles in both orientations
+        let rectangles: Vec<_> = create_random_points(2000, SEED_1)
+            .into_iter()
+            .enumerate()
+            .map(|(index, [x, y])| {
+                if index < 1000 {
+                    Rectangle::from_corners([x, y], [x + 0.5, y + 0.001])
+                } else {
+                    Rectangle::from_corners([x, y], [x + 0.001, y + 0.5])
+                }
+            })
+            .collect();
+        let tree = RTree::bulk_load_priority(rectangles.clone());
+        tree.root().sanity_check::<DefaultParams>();
+        for [x, y] in create_random_points(50, SEED_2) {
+            let query = AABB::from_corners([x, y], [x + 0.05, y + 0.05]);
+            let expected = rectangles
+                .iter()
+                .filter(|r| r.envelope().intersects(&query))
+                .count();
+            assert_eq!(
+                tree.locate_in_envelope_intersecting(&query).count(),
+                expected
+            );
+        }
+    }
+}
@@ -4,9 +4,12 @@ use crate::object::RTreeObject;
 use crate::params::RTreeParams;
 use crate::point::Point;
 
-use super::cluster_group_iterator::{calculate_number_of_clusters_on_axis, ClusterGroupIterator};
+use super::cluster_group_iterator::{
+    calculate_cluster_sizes, calculate_depth, ClusterGroupIterator,
+};
+use super::load_with_envelope_caching;
 
-fn bulk_load_recursive<T, Params>(elements: Vec<T>, depth: usize) -> ParentNode<T>
+pub(super) fn bulk_load_recursive<T, Params>(elements: Vec<T>, depth: usize) -> ParentNode<T>
 where
     T: RTreeObject,
     <T::Envelope as Envelope>::Point: Point,
@@ -18,15 +21,14 @@ where
         let elements: Vec<_> = elements.into_iter().map(RTreeNode::Leaf).collect();
         return ParentNode::new_parent(elements);
     }
-    let number_of_clusters_on_axis =
-        calculate_number_of_clusters_on_axis::<T, Params>(elements.len());
+    let cluster_sizes = calculate_cluster_sizes::<Params>(elements.len(), depth);
 
     let iterator = PartitioningTask::<_, Params> {
-        number_of_clusters_on_axis,
         depth,
         work_queue: vec![PartitioningState {
             current_axis: <T::Envelope as Envelope>::Point::DIMENSIONS,
             elements,
+            cluster_sizes,
         }],
         _params: Default::default(),
     };
@@ -39,6 +41,7 @@ where
 /// along "current_axis" .
 struct PartitioningState<T: RTreeObject> {
     elements: Vec<T>,
+    cluster_sizes: Vec<usize>,
     current_axis: usize,
 }
 
@@ -46,7 +49,6 @@ struct PartitioningState<T: RTreeObject> {
 struct PartitioningTask<T: RTreeObject, Params: RTreeParams> {
     work_queue: Vec<PartitioningState<T>>,
     depth: usize,
-    number_of_clusters_on_axis: usize,
     _params: std::marker::PhantomData<Params>,
 }
 
@@ -57,6 +59,7 @@ impl<T: RTreeObject, Params: RTreeParams> Iterator for PartitioningTask<T, Param
         while let Some(next) = self.work_queue.pop() {
             let PartitioningState {
                 elements,
+                cluster_sizes,
                 current_axis,
             } = next;
             if current_axis == 0 {
@@ -67,12 +70,14 @@ impl<T: RTreeObject, Params: RTreeParams> Iterator for PartitioningTask<T, Param
                 // The cluster group needs to be partitioned further along the next axis
                 let iterator = ClusterGroupIterator::new(
                     elements,
-                    self.number_of_clusters_on_axis,
+                    cluster_sizes,
+                    current_axis,
                     current_axis - 1,
                 );
                 self.work_queue
-                    .extend(iterator.map(|slab| PartitioningState {
+                    .extend(iterator.map(|(slab, cluster_sizes)| PartitioningState {
                         elements: slab,
+                        cluster_sizes,
                         current_axis: current_axis - 1,
                     }));
             }
@@ -90,15 +95,26 @@ where
     <T::Envelope as Envelope>::Point: Point,
     Params: RTreeParams,
 {
-    let m = Params::MAX_SIZE;
-    let depth = (elements.len() as f32).log(m as f32).ceil() as usize;
+    load_with_envelope_caching::<_, Params>(
+        elements,
+        load_sequential::<_, Params>,
+        load_sequential::<_, Params>,
+    )
+}
+
+fn load_sequential<T, Params>(elements: Vec<T>) -> ParentNode<T>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    let depth = calculate_depth::<Params>(elements.len());
     bulk_load_recursive::<_, Params>(elements, depth)
 }
 
 #[cfg(test)]
 mod test {
     use crate::test_utilities::*;
-    use crate::{Point, RTree, RTreeObject};
+    use crate::{DefaultParams, Point, RTree, RTreeObject};
     use std::collections::HashSet;
     use std::fmt::Debug;
     use std::hash::Hash;
@@ -118,12 +134,105 @@ mod test {
     #[test]
     fn test_bulk_load_with_different_sizes() {
         for size in (0..100).map(|i| i * 7) {
+            let tree = RTree::bulk_load(create_random_points(size, SEED_1));
+            tree.root().sanity_check::<DefaultParams>();
             test_bulk_load_with_size_and_dimension::<[i32; 2]>(size);
             test_bulk_load_with_size_and_dimension::<[i32; 3]>(size);
             test_bulk_load_with_size_and_dimension::<[i32; 4]>(size);
         }
     }
 
+    #[test]
+    fn test_bulk_load_is_balanced_and_tight() {
+        use crate::node::{ParentNode, RTreeNode};
+        use crate::{Envelope, AABB};
+
+        fn check_node(node: &ParentNode<[f64; 2]>, depth: usize, leaf_depths: &mut Vec<usize>) {
+            let mut envelope = AABB::new_empty();
+            for child in node.children() {
+                envelope.merge(&child.envelope());
+                match child {
+                    RTreeNode::Leaf(_) => leaf_depths.push(depth),
+                    RTreeNode::Parent(ref data) => check_node(data, depth + 1, leaf_depths),
+                }
+            }
+            assert_eq!(node.envelope(), envelope);
+        }
+
+        for size in &[1, 6, 7, 100, 2000] {
+            let points = create_random_points(*size, SEED_1);
+            let tree = RTree::bulk_load(points.clone());
+            let mut leaf_depths = Vec::new();
+            check_node(tree.root(), 0, &mut leaf_depths);
+            assert_eq!(leaf_depths.len(), *size);
+            assert!(leaf_depths.iter().all(|depth| *depth == leaf_depths[0]));
+
+            let query = AABB::from_corners([0.2, 0.3], [0.6, 0.5]);
+            let expected = points
+                .iter()
+                .filter(|point| query.contains_point(point))
+                .count();
+            assert_eq!(tree.locate_in_envelope(&query).count(), expected);
+        }
+    }
+
+    #[test]
+    fn test_bulk_load_with_envelope_caching() {
+        use crate::{EnvelopeCaching, RStarInsertionStrategy, RTreeParams};
+
+        struct CachingParams;
+
+        impl RTreeParams for CachingParams {
+            const MIN_SIZE: usize = 3;
+            const MAX_SIZE: usize = 6;
+            const REINSERTION_COUNT: usize = 2;
+            const ENVELOPE_CACHING: EnvelopeCaching = EnvelopeCaching::BulkOperations;
+            type DefaultInsertionStrategy = RStarInsertionStrategy;
+        }
+
+        const SIZE: usize = 1000;
+        let points: Vec<_> = create_random_points(SIZE, SEED_1)
+            .into_iter()
+            .map(CountingPoint)
+            .collect();
+        take_envelope_calls();
+        let uncached: RTree<_, DefaultParams> = RTree::bulk_load_with_params(points.clone());
+        assert!(take_envelope_calls() > 2 * SIZE);
+        let mut cached: RTree<_, CachingParams> = RTree::bulk_load_with_params(points);
+        assert_eq!(take_envelope_calls(), SIZE);
+        // The same tree is created, only fewer envelopes are calculated
+        assert!(cached.iter().eq(uncached.iter()));
+        cached.rebalance();
+        assert_eq!(take_envelope_calls(), SIZE);
+        assert!(cached.iter().eq(uncached.iter()));
+        cached.root().sanity_check::<CachingParams>();
+    }
+
+    #[test]
+    fn test_bulk_load_with_params() {
+        use crate::{RStarInsertionStrategy, RTreeParams};
+
+        struct SmallNodeParams;
+        impl RTreeParams for SmallNodeParams {
+            const MIN_SIZE: usize = 2;
+            const MAX_SIZE: usize = 4;
+            const REINSERTION_COUNT: usize = 1;
+            type DefaultInsertionStrategy = RStarInsertionStrategy;
+        }
+
+        let points = create_random_points(1000, SEED_1);
+        let mut tree: RTree<_, SmallNodeParams> = RTree::bulk_load_with_params(points.clone());
+        tree.root().sanity_check::<SmallNodeParams>();
+        assert!(tree.height() > RTree::bulk_load(points.clone()).height());
+        let point = [0.5, 0.5];
+        tree.insert(point);
+        assert_eq!(tree.size(), points.len() + 1);
+        assert!(tree.contains(&point));
+        for p in &points {
+            assert!(tree.contains(p));
+        }
+    }
+
     fn test_bulk_load_with_size_and_dimension<P>(size: usize)
     where
         P: Point<Scalar = i32> + RTreeObject + Send + Sync + Eq + Clone + Debug + Hash + 'static,
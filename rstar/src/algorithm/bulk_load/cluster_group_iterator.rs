@@ -1,88 +1,127 @@
-use crate::{Envelope, Point, RTreeObject, RTreeParams};
+use crate::{Envelope, RTreeObject, RTreeParams};
 
 /// Partitions elements into groups of clusters along a specific axis.
+///
+/// Each group, or slab, holds exactly as many elements as its clusters.
 pub struct ClusterGroupIterator<T: RTreeObject> {
     remaining: Vec<T>,
-    slab_size: usize,
+    slabs: ::std::vec::IntoIter<Vec<usize>>,
     pub cluster_dimension: usize,
 }
 
 impl<T: RTreeObject> ClusterGroupIterator<T> {
     pub fn new(
         elements: Vec<T>,
-        number_of_clusters_on_axis: usize,
+        cluster_sizes: Vec<usize>,
+        remaining_axes: usize,
         cluster_dimension: usize,
     ) -> Self {
-        let slab_size = div_up(elements.len(), number_of_clusters_on_axis);
+        debug_assert_eq!(elements.len(), cluster_sizes.iter().sum::<usize>());
         ClusterGroupIterator {
             remaining: elements,
-            slab_size,
+            slabs: split_into_slabs(cluster_sizes, remaining_axes).into_iter(),
             cluster_dimension,
         }
     }
 }
 
 impl<T: RTreeObject> Iterator for ClusterGroupIterator<T> {
-    type Item = Vec<T>;
+    type Item = (Vec<T>, Vec<usize>);
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.remaining.len() {
-            0 => None,
-            len if len <= self.slab_size => ::std::mem::replace(&mut self.remaining, vec![]).into(),
-            _ => {
-                let slab_axis = self.cluster_dimension;
-                T::Envelope::partition_envelopes(slab_axis, &mut self.remaining, self.slab_size);
-                let off_split = self.remaining.split_off(self.slab_size);
-                ::std::mem::replace(&mut self.remaining, off_split).into()
-            }
+        let cluster_sizes = self.slabs.next()?;
+        let slab_size = cluster_sizes.iter().sum();
+        if slab_size < self.remaining.len() {
+            let slab_axis = self.cluster_dimension;
+            T::Envelope::partition_envelopes(slab_axis, &mut self.remaining, slab_size);
+            let off_split = self.remaining.split_off(slab_size);
+            Some((
+                ::std::mem::replace(&mut self.remaining, off_split),
+                cluster_sizes,
+            ))
+        } else {
+            Some((::std::mem::take(&mut self.remaining), cluster_sizes))
         }
     }
 }
 
-/// Calculates the desired number of clusters on any axis
-///
-/// A 'cluster' refers to a set of elements that will finally form an rtree node.
-pub fn calculate_number_of_clusters_on_axis<T, Params>(number_of_elements: usize) -> usize
+/// Calculates the depth of a tree that stores the given number of elements.
+pub fn calculate_depth<Params>(number_of_elements: usize) -> usize
 where
-    T: RTreeObject,
     Params: RTreeParams,
 {
-    let max_size = Params::MAX_SIZE as f32;
-    // The depth of the resulting tree, assuming all leaf nodes will be filled up to MAX_SIZE
-    let depth = (number_of_elements as f32).log(max_size).ceil() as usize;
-    // The number of elements each subtree will hold
-    let n_subtree = (max_size as f32).powi(depth as i32 - 1);
-    // How many clusters will this node contain
-    let number_of_clusters = (number_of_elements as f32 / n_subtree).ceil();
+    let mut depth = 1;
+    let mut capacity = Params::MAX_SIZE;
+    while capacity < number_of_elements {
+        capacity = capacity.saturating_mul(Params::MAX_SIZE);
+        depth += 1;
+    }
+    depth
+}
 
-    let max_dimension = <T::Envelope as Envelope>::Point::DIMENSIONS as f32;
-    // Try to split all clusters among all dimensions as evenly as possible by taking the nth root.
-    number_of_clusters.powf(1. / max_dimension).ceil() as usize
+/// Calculates the sizes of the clusters a node's elements are partitioned into.
+///
+/// A 'cluster' refers to a set of elements that will finally form an rtree node. Every
+/// subtree of the given depth can hold `MAX_SIZE.pow(depth - 1)` elements. The node
+/// receives as few clusters as possible, and the elements are distributed evenly. This
+/// ensures that all nodes created from these clusters have between `MIN_SIZE` and
+/// `MAX_SIZE` children.
+pub fn calculate_cluster_sizes<Params>(number_of_elements: usize, depth: usize) -> Vec<usize>
+where
+    Params: RTreeParams,
+{
+    let n_subtree = Params::MAX_SIZE.pow(depth as u32 - 1);
+    let number_of_clusters = (number_of_elements + n_subtree - 1) / n_subtree;
+    let size = number_of_elements / number_of_clusters;
+    let larger_clusters = number_of_elements % number_of_clusters;
+    (0..number_of_clusters)
+        .map(|index| size + (index < larger_clusters) as usize)
+        .collect()
 }
 
-fn div_up(dividend: usize, divisor: usize) -> usize {
-    (dividend + divisor - 1) / divisor
+/// Distributes consecutive clusters among the slabs along the current axis.
+///
+/// Tries to split all clusters among the remaining axes as evenly as possible by taking the
+/// nth root.
+pub fn split_into_slabs(mut cluster_sizes: Vec<usize>, remaining_axes: usize) -> Vec<Vec<usize>> {
+    let number_of_clusters = cluster_sizes.len();
+    let mut number_of_slabs: usize = 1;
+    while number_of_slabs.pow(remaining_axes as u32) < number_of_clusters {
+        number_of_slabs += 1;
+    }
+    let mut slabs: Vec<_> = (0..number_of_slabs)
+        .rev()
+        .map(|index| {
+            let first = index * number_of_clusters / number_of_slabs;
+            cluster_sizes.split_off(first)
+        })
+        .collect();
+    slabs.reverse();
+    slabs
 }
 
 #[cfg(test)]
 mod test {
-    use super::ClusterGroupIterator;
+    use super::{calculate_cluster_sizes, calculate_depth, ClusterGroupIterator};
+    use crate::{DefaultParams, RTreeParams};
 
     #[test]
     fn test_cluster_group_iterator() {
         const SIZE: usize = 374;
-        const NUMBER_OF_CLUSTERS_ON_AXIS: usize = 5;
+        const NUMBER_OF_CLUSTERS: usize = 5;
         let elements: Vec<_> = (0..SIZE as i32).map(|i| [-i, -i]).collect();
-        let slab_size = (elements.len()) / NUMBER_OF_CLUSTERS_ON_AXIS + 1;
-        let slabs: Vec<_> =
-            ClusterGroupIterator::new(elements, NUMBER_OF_CLUSTERS_ON_AXIS, 0).collect();
-        assert_eq!(slabs.len(), NUMBER_OF_CLUSTERS_ON_AXIS);
-        for slab in &slabs[0..slabs.len() - 1] {
-            assert_eq!(slab.len(), slab_size);
-        }
+        let cluster_sizes = vec![75, 75, 75, 75, 74];
+        let slabs: Vec<_> = ClusterGroupIterator::new(elements, cluster_sizes.clone(), 1, 0)
+            .map(|(slab, sizes)| {
+                assert_eq!(sizes, vec![slab.len()]);
+                slab
+            })
+            .collect();
+        assert_eq!(slabs.len(), NUMBER_OF_CLUSTERS);
         let mut total_size = 0;
-        let mut max_element_for_last_slab = i32::min_value();
-        for slab in &slabs {
+        let mut max_element_for_last_slab = i32::MIN;
+        for (slab, size) in slabs.iter().zip(&cluster_sizes) {
+            assert_eq!(slab.len(), *size);
             total_size += slab.len();
             let current_max = slab.iter().max_by_key(|point| point[0]).unwrap();
             assert!(current_max[0] > max_element_for_last_slab);
@@ -90,4 +129,21 @@ mod test {
         }
         assert_eq!(total_size, SIZE);
     }
+
+    #[test]
+    fn test_cluster_sizes() {
+        let max_size = DefaultParams::MAX_SIZE;
+        for number_of_elements in max_size + 1..2000 {
+            let depth = calculate_depth::<DefaultParams>(number_of_elements);
+            let sizes = calculate_cluster_sizes::<DefaultParams>(number_of_elements, depth);
+            assert!(sizes.len() >= DefaultParams::MIN_SIZE.min(2));
+            assert!(sizes.len() <= max_size);
+            assert_eq!(sizes.iter().sum::<usize>(), number_of_elements);
+            let subtree_capacity = max_size.pow(depth as u32 - 1);
+            for size in sizes {
+                assert!(size <= subtree_capacity);
+                assert!(size > subtree_capacity / max_size);
+            }
+        }
+    }
 }
@@ -1,4 +1,39 @@
+#[cfg(feature = "rayon")]
+mod bulk_load_parallel;
+mod bulk_load_presorted;
+mod bulk_load_priority;
 mod bulk_load_sequential;
 mod cluster_group_iterator;
 
+#[cfg(feature = "rayon")]
+pub use self::bulk_load_parallel::bulk_load_parallel;
+pub use self::bulk_load_presorted::bulk_load_presorted;
+pub use self::bulk_load_priority::bulk_load_priority;
 pub use self::bulk_load_sequential::bulk_load_sequential;
+
+use crate::node::ParentNode;
+use crate::object::RTreeObject;
+use crate::params::RTreeParams;
+use crate::primitives::CachedEnvelope;
+
+/// Bulk loads elements with cached envelopes if the parameters ask for it.
+///
+/// Partitioning requests the envelope of every element several times per level. If
+/// `Params::ENVELOPE_CACHING` caches bulk operations, the elements are wrapped in a
+/// `CachedEnvelope` and unwrapped again after the tree was built.
+fn load_with_envelope_caching<T, Params>(
+    elements: Vec<T>,
+    load: impl FnOnce(Vec<T>) -> ParentNode<T>,
+    load_cached: impl FnOnce(Vec<CachedEnvelope<T>>) -> ParentNode<CachedEnvelope<T>>,
+) -> ParentNode<T>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    if Params::ENVELOPE_CACHING.caches_bulk_operations() {
+        let elements = elements.into_iter().map(CachedEnvelope::new).collect();
+        load_cached(elements).into_uncached()
+    } else {
+        load(elements)
+    }
+}
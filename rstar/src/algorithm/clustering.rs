@@ -0,0 +1,193 @@
+use crate::envelope::Envelope;
+use crate::node::{ParentNode, RTreeNode};
+use crate::object::RTreeObject;
+use std::collections::BinaryHeap;
+
+/// An item returned by
+/// [locate_in_envelope_clustered](struct.RTree.html#method.locate_in_envelope_clustered).
+///
+/// Depending on the density of a region, it is either represented by its individual elements
+/// or by clusters that aggregate many elements.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClusteredItem<'a, T>
+where
+    T: RTreeObject,
+{
+    /// A single element of the tree
+    Element(&'a T),
+    /// An aggregate of several elements, represented by an internal tree node
+    Cluster {
+        /// The envelope of all elements of the cluster
+        envelope: T::Envelope,
+        /// The number of elements contained in the cluster
+        size: usize,
+    },
+}
+
+/// Counts the elements below a node.
+///
+/// All leaves of a tree have the same depth. The elements below a node whose children are
+/// leaves are thus counted without visiting them.
+fn count_elements<T>(node: &ParentNode<T>) -> usize
+where
+    T: RTreeObject,
+{
+    match node.children.first() {
+        Some(RTreeNode::Parent(_)) => node
+            .children
+            .iter()
+            .map(|child| match child {
+                RTreeNode::Leaf(_) => 1,
+                RTreeNode::Parent(ref data) => count_elements(data),
+            })
+            .sum(),
+        _ => node.children.len(),
+    }
+}
+
+/// Returns the number of parent nodes on a path from a node down to its leaves.
+fn node_height<T>(node: &ParentNode<T>) -> usize
+where
+    T: RTreeObject,
+{
+    match node.children.first() {
+        Some(RTreeNode::Parent(ref data)) => 1 + node_height(data),
+        _ => 1,
+    }
+}
+
+fn add_children<'a, T>(
+    node: &'a ParentNode<T>,
+    height: usize,
+    envelope: &T::Envelope,
+    result: &mut Vec<ClusteredItem<'a, T>>,
+    clusters: &mut Vec<(&'a ParentNode<T>, usize)>,
+    heap: &mut BinaryHeap<(usize, usize, usize)>,
+) where
+    T: RTreeObject,
+{
+    for child in &node.children {
+        if !child.envelope().intersects(envelope) {
+            continue;
+        }
+        match child {
+            RTreeNode::Leaf(ref t) => result.push(ClusteredItem::Element(t)),
+            RTreeNode::Parent(ref data) => {
+                heap.push((height - 1, data.children.len(), clusters.len()));
+                clusters.push((data, height - 1));
+            }
+        }
+    }
+}
+
+pub fn locate_in_envelope_clustered<'a, T>(
+    root: &'a ParentNode<T>,
+    envelope: &T::Envelope,
+    budget: usize,
+) -> Vec<ClusteredItem<'a, T>>
+where
+    T: RTreeObject,
+{
+    let mut result = Vec::new();
+    // Parent nodes that may still be split up. Nodes closer to the root contain more elements
+    // and are split first, their exact sizes are only counted for the remaining clusters.
+    let mut clusters = Vec::new();
+    let mut heap = BinaryHeap::new();
+
+    add_children(
+        root,
+        node_height(root),
+        envelope,
+        &mut result,
+        &mut clusters,
+        &mut heap,
+    );
+    let mut item_count = result.len() + heap.len();
+    let mut unsplit = Vec::new();
+    while let Some((_, _, index)) = heap.pop() {
+        let (node, height) = clusters[index];
+        let intersecting = node
+            .children
+            .iter()
+            .filter(|child| child.envelope().intersects(envelope))
+            .count();
+        // Replacing the cluster by its children must not exceed the budget
+        if item_count + intersecting <= budget + 1 {
+            item_count = item_count + intersecting - 1;
+            add_children(
+                node,
+                height,
+                envelope,
+                &mut result,
+                &mut clusters,
+                &mut heap,
+            );
+        } else {
+            unsplit.push(node);
+        }
+    }
+    result.extend(unsplit.into_iter().map(|node| ClusteredItem::Cluster {
+        envelope: node.envelope,
+        size: count_elements(node),
+    }));
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::ClusteredItem;
+    use crate::test_utilities::{create_random_points, SEED_1};
+    use crate::{Envelope, RTree, RTreeObject, AABB};
+
+    #[test]
+    fn test_locate_in_envelope_clustered() {
+        const SIZE: usize = 2000;
+        let points = create_random_points(SIZE, SEED_1);
+        let tree = RTree::bulk_load(points.clone());
+        let window = AABB::from_corners([0.1, 0.2], [0.6, 0.9]);
+        let intersecting = tree.locate_in_envelope_intersecting(&window).count();
+
+        for budget in &[10, 50, 200] {
+            let items = tree.locate_in_envelope_clustered(&window, *budget);
+            assert!(items.len() <= *budget);
+            assert!(items.iter().any(|item| match item {
+                ClusteredItem::Cluster { .. } => true,
+                ClusteredItem::Element(_) => false,
+            }));
+            let mut total = 0;
+            for item in &items {
+                match item {
+                    ClusteredItem::Element(p) => {
+                        assert!(p.envelope().intersects(&window));
+                        total += 1;
+                    }
+                    ClusteredItem::Cluster { envelope, size } => {
+                        assert!(envelope.intersects(&window));
+                        let contained =
+                            points.iter().filter(|p| envelope.contains_point(p)).count();
+                        assert!(contained >= *size);
+                        total += size;
+                    }
+                }
+            }
+            assert!(total >= intersecting);
+        }
+
+        let everything = tree.locate_in_envelope_clustered(&tree.root().envelope(), 30);
+        let total: usize = everything
+            .iter()
+            .map(|item| match item {
+                ClusteredItem::Element(_) => 1,
+                ClusteredItem::Cluster { size, .. } => *size,
+            })
+            .sum();
+        assert_eq!(total, SIZE);
+
+        let items = tree.locate_in_envelope_clustered(&window, SIZE);
+        assert_eq!(items.len(), intersecting);
+        assert!(items.iter().all(|item| match item {
+            ClusteredItem::Element(_) => true,
+            ClusteredItem::Cluster { .. } => false,
+        }));
+    }
+}
@@ -0,0 +1,592 @@
+use crate::algorithm::iterators::allow_duplicates;
+use crate::envelope::Envelope;
+use crate::insertion_metrics::InsertionMetrics;
+use crate::node::{keep_spare_children, take_spare_children, ParentNode, RTreeNode};
+use crate::object::RTreeObject;
+use crate::params::{InsertionStrategy, RTreeParams};
+use crate::point::{
+    max_inline, min_inline, saturating_add, saturating_mul, saturating_sub, total_cmp, Point,
+    RTreeNum,
+};
+use crate::primitives::CachedEnvelope;
+use crate::rtree::RTree;
+use num_traits::Zero;
+use std::cmp::Ordering;
+
+/// Inserts elements according to Guttman's original r-tree with a linear node split.
+///
+/// Compared to [RStarInsertionStrategy](struct.RStarInsertionStrategy.html), subtrees are
+/// chosen by their area increase only, overflowing nodes are split right away instead of
+/// reinserting some of their children, and the split distributes children in linear time.
+/// Insertion is much faster, but the resulting tree overlaps more and answers queries
+/// slower. This suits short-lived trees that are queried only a few times.
+///
+/// The split starts with the two children that are separated the most along any axis,
+/// relative to the width of all children along that axis. All other children are added to
+/// the group whose envelope grows the least.
+///
+/// # Example
+/// ```
+/// use rstar::{LinearInsertionStrategy, RTree, RTreeParams};
+///
+/// struct LinearParams;
+///
+/// impl RTreeParams for LinearParams {
+///     const MIN_SIZE: usize = 3;
+///     const MAX_SIZE: usize = 6;
+///     const REINSERTION_COUNT: usize = 0;
+///     type DefaultInsertionStrategy = LinearInsertionStrategy;
+/// }
+///
+/// let mut tree: RTree<_, LinearParams> = RTree::new_with_params();
+/// for x in 0..100 {
+///     tree.insert([x as f64, 0.5]);
+/// }
+/// assert_eq!(tree.nearest_neighbor(&[41.8, 0.0]), Some(&[42.0, 0.5]));
+/// ```
+pub enum LinearInsertionStrategy {}
+
+impl InsertionStrategy for LinearInsertionStrategy {
+    fn insert<T, Params>(tree: &mut RTree<T, Params>, t: T)
+    where
+        Params: RTreeParams,
+        T: RTreeObject,
+    {
+        let _ = insert::<_, Params, LinearSplit, _>(tree, t, allow_duplicates);
+    }
+
+    fn insert_unless<T, Params, F>(
+        tree: &mut RTree<T, Params>,
+        t: T,
+        is_rejected: F,
+    ) -> Result<(), T>
+    where
+        Params: RTreeParams,
+        T: RTreeObject,
+        F: Fn(&[RTreeNode<T>], &T) -> bool,
+    {
+        insert::<_, Params, LinearSplit, _>(tree, t, is_rejected)
+    }
+}
+
+/// Inserts elements according to Guttman's original r-tree with a quadratic node split.
+///
+/// This is the classic r-tree as described by Guttman. It chooses subtrees like
+/// [LinearInsertionStrategy](struct.LinearInsertionStrategy.html), but spends quadratic
+/// time on each split to create groups that overlap less. Queries are usually faster than
+/// with the linear split and slower than with
+/// [RStarInsertionStrategy](struct.RStarInsertionStrategy.html).
+///
+/// The split starts with the pair of children that would waste the most area if they were
+/// put in the same group. The remaining children are distributed in order of their
+/// preference for one of the groups.
+///
+/// # Example
+/// ```
+/// use rstar::{QuadraticInsertionStrategy, RTree, RTreeParams};
+///
+/// struct QuadraticParams;
+///
+/// impl RTreeParams for QuadraticParams {
+///     const MIN_SIZE: usize = 3;
+///     const MAX_SIZE: usize = 6;
+///     const REINSERTION_COUNT: usize = 0;
+///     type DefaultInsertionStrategy = QuadraticInsertionStrategy;
+/// }
+///
+/// let mut tree: RTree<_, QuadraticParams> = RTree::new_with_params();
+/// for x in 0..100 {
+///     tree.insert([x as f64, 0.5]);
+/// }
+/// assert_eq!(tree.nearest_neighbor(&[41.8, 0.0]), Some(&[42.0, 0.5]));
+/// ```
+pub enum QuadraticInsertionStrategy {}
+
+impl InsertionStrategy for QuadraticInsertionStrategy {
+    fn insert<T, Params>(tree: &mut RTree<T, Params>, t: T)
+    where
+        Params: RTreeParams,
+        T: RTreeObject,
+    {
+        let _ = insert::<_, Params, QuadraticSplit, _>(tree, t, allow_duplicates);
+    }
+
+    fn insert_unless<T, Params, F>(
+        tree: &mut RTree<T, Params>,
+        t: T,
+        is_rejected: F,
+    ) -> Result<(), T>
+    where
+        Params: RTreeParams,
+        T: RTreeObject,
+        F: Fn(&[RTreeNode<T>], &T) -> bool,
+    {
+        insert::<_, Params, QuadraticSplit, _>(tree, t, is_rejected)
+    }
+}
+
+/// Distributes the children of an overflowing node into two groups.
+trait SplitAlgorithm {
+    /// Returns the indices of the first children of both groups.
+    fn pick_seeds<C: RTreeObject>(children: &[C]) -> (usize, usize);
+
+    /// Returns the index of the child that is distributed next.
+    fn pick_next<C: RTreeObject>(
+        remaining: &[C],
+        first: &C::Envelope,
+        second: &C::Envelope,
+    ) -> usize;
+}
+
+enum LinearSplit {}
+
+impl SplitAlgorithm for LinearSplit {
+    fn pick_seeds<C: RTreeObject>(children: &[C]) -> (usize, usize) {
+        let envelopes: Vec<_> = children.iter().map(RTreeObject::envelope).collect();
+        // The largest separation found so far and the width it is normalized by
+        let mut best: Option<(Scalar<C>, Scalar<C>)> = None;
+        let mut seeds = (0, 1);
+        for axis in 0..<C::Envelope as Envelope>::Point::DIMENSIONS {
+            let lower = |index: usize| envelopes[index].bounds_along_axis(axis).0;
+            let upper = |index: usize| envelopes[index].bounds_along_axis(axis).1;
+            let highest_lower = (0..envelopes.len())
+                .max_by(|&l, &r| total_cmp(&lower(l), &lower(r)))
+                .unwrap();
+            let lowest_upper = (0..envelopes.len())
+                .filter(|&index| index != highest_lower)
+                .min_by(|&l, &r| total_cmp(&upper(l), &upper(r)))
+                .unwrap();
+            let width = saturating_sub(
+                (0..envelopes.len()).map(upper).fold(upper(0), max_inline),
+                (0..envelopes.len()).map(lower).fold(lower(0), min_inline),
+            );
+            if width.partial_cmp(&Zero::zero()) != Some(Ordering::Greater) {
+                // All children share the same coordinate along this axis
+                continue;
+            }
+            let separation = saturating_sub(lower(highest_lower), upper(lowest_upper));
+            // Compares separation / width without dividing, which would truncate integers
+            let is_better = best.map_or(true, |(best_separation, best_width)| {
+                saturating_mul(separation, best_width) > saturating_mul(best_separation, width)
+            });
+            if is_better {
+                best = Some((separation, width));
+                seeds = (lowest_upper, highest_lower);
+            }
+        }
+        seeds
+    }
+
+    fn pick_next<C: RTreeObject>(
+        remaining: &[C],
+        _first: &C::Envelope,
+        _second: &C::Envelope,
+    ) -> usize {
+        remaining.len() - 1
+    }
+}
+
+enum QuadraticSplit {}
+
+impl SplitAlgorithm for QuadraticSplit {
+    fn pick_seeds<C: RTreeObject>(children: &[C]) -> (usize, usize) {
+        let mut worst = None;
+        let mut seeds = (0, 1);
+        for (i, first) in children.iter().enumerate() {
+            let first = first.envelope();
+            for (j, second) in children.iter().enumerate().skip(i + 1) {
+                let second = second.envelope();
+                let merged = first.merged(&second);
+                let wasted = |measure: fn(&C::Envelope) -> Scalar<C>| {
+                    saturating_sub(
+                        measure(&merged),
+                        saturating_add(measure(&first), measure(&second)),
+                    )
+                };
+                // The perimeter tells apart envelopes without area
+                let waste = (wasted(Envelope::area), wasted(Envelope::perimeter_value));
+                let is_worse = worst.map_or(true, |(area, perimeter)| {
+                    total_cmp(&waste.0, &area).then_with(|| total_cmp(&waste.1, &perimeter))
+                        == Ordering::Greater
+                });
+                if is_worse {
+                    worst = Some(waste);
+                    seeds = (i, j);
+                }
+            }
+        }
+        seeds
+    }
+
+    fn pick_next<C: RTreeObject>(
+        remaining: &[C],
+        first: &C::Envelope,
+        second: &C::Envelope,
+    ) -> usize {
+        let preference = |child: &C| {
+            let envelope = child.envelope();
+            let first_cost = enlargement(first, &envelope);
+            let second_cost = enlargement(second, &envelope);
+            let difference = |l, r| max_inline(saturating_sub(l, r), saturating_sub(r, l));
+            (
+                difference(first_cost.0, second_cost.0),
+                difference(first_cost.2, second_cost.2),
+            )
+        };
+        let mut best = preference(&remaining[0]);
+        let mut best_index = 0;
+        for (index, child) in remaining.iter().enumerate().skip(1) {
+            let current = preference(child);
+            let ordering =
+                total_cmp(&current.0, &best.0).then_with(|| total_cmp(&current.1, &best.1));
+            if ordering == Ordering::Greater {
+                best = current;
+                best_index = index;
+            }
+        }
+        best_index
+    }
+}
+
+type Scalar<T> = <<<T as RTreeObject>::Envelope as Envelope>::Point as Point>::Scalar;
+
+fn insert<T, Params, Split, F>(tree: &mut RTree<T, Params>, t: T, is_rejected: F) -> Result<(), T>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+    Split: SplitAlgorithm,
+    F: Fn(&[RTreeNode<T>], &T) -> bool,
+{
+    let (root, metrics, spare_children) = tree.insertion_state_mut();
+    let split =
+        recursive_insert::<_, Params, Split, _>(root, t, &is_rejected, metrics, spare_children)?;
+    metrics.insertions += 1;
+    if let Some(node) = split {
+        // The root node was split, create a new root and increase height
+        let children = take_spare_children::<_, Params>(spare_children);
+        let new_envelope = root.envelope.merged(&node.envelope());
+        let new_root = ParentNode::new_with_envelope(children, new_envelope);
+        let old_root = ::std::mem::replace(root, new_root);
+        root.children.push(RTreeNode::Parent(old_root));
+        root.children.push(node);
+    }
+    Ok(())
+}
+
+// Returns `Err(t)` without changing the tree if `is_rejected` returns `true`
+fn recursive_insert<T, Params, Split, F>(
+    node: &mut ParentNode<T>,
+    t: T,
+    is_rejected: &F,
+    metrics: &mut InsertionMetrics,
+    spare_children: &mut Vec<Vec<RTreeNode<T>>>,
+) -> Result<Option<RTreeNode<T>>, T>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+    Split: SplitAlgorithm,
+    F: Fn(&[RTreeNode<T>], &T) -> bool,
+{
+    let envelope = t.envelope();
+    let is_leaf = node.children.first().map_or(true, RTreeNode::is_leaf);
+    let index = if is_leaf {
+        node.children.len()
+    } else {
+        choose_subtree(&node.children, &envelope)
+    };
+    // Conflicting elements may also be stored in the siblings of the chosen subtree
+    let (before, after) = node.children.split_at(index);
+    if is_rejected(before, &t) || is_rejected(after.get(1..).unwrap_or(&[]), &t) {
+        return Err(t);
+    }
+
+    if is_leaf {
+        node.children.push(RTreeNode::Leaf(t));
+    } else if let RTreeNode::Parent(ref mut child) = node.children[index] {
+        if let Some(split) =
+            recursive_insert::<_, Params, Split, _>(child, t, is_rejected, metrics, spare_children)?
+        {
+            node.children.push(split);
+        }
+    }
+
+    // Enlarged only after the descent, a rejected element must leave the tree unchanged
+    if node.envelope.contains_envelope(&envelope) {
+        metrics.skipped_envelope_merges += 1;
+    } else {
+        node.envelope.merge(&envelope);
+        metrics.envelope_merges += 1;
+    }
+
+    if node.children.len() > Params::MAX_SIZE {
+        metrics.overflows += 1;
+        Ok(Some(split::<_, Params, Split>(node, spare_children)))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Returns the child whose envelope grows the least, ties are broken by the smaller area.
+pub(crate) fn choose_subtree<C>(children: &[C], envelope: &C::Envelope) -> usize
+where
+    C: RTreeObject,
+{
+    let mut best = None;
+    let mut best_index = 0;
+    for (index, child) in children.iter().enumerate() {
+        let cost = enlargement(&child.envelope(), envelope);
+        let is_better = best.map_or(true, |best| compare_costs(cost, best) == Ordering::Less);
+        if is_better {
+            best = Some(cost);
+            best_index = index;
+        }
+    }
+    best_index
+}
+
+type Cost<S> = (S, S, S);
+
+/// Returns the area increase, area and perimeter increase of merging `envelope` into `group`.
+///
+/// Degenerate envelopes (e.g. of collinear points) have zero area, the perimeter increase
+/// is used to tell them apart.
+fn enlargement<E: Envelope>(group: &E, envelope: &E) -> Cost<<E::Point as Point>::Scalar> {
+    let merged = group.merged(envelope);
+    (
+        saturating_sub(merged.area(), group.area()),
+        group.area(),
+        saturating_sub(merged.perimeter_value(), group.perimeter_value()),
+    )
+}
+
+fn split<T, Params, Split>(
+    node: &mut ParentNode<T>,
+    spare_children: &mut Vec<Vec<RTreeNode<T>>>,
+) -> RTreeNode<T>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+    Split: SplitAlgorithm,
+{
+    let mut children = ::std::mem::take(&mut node.children);
+    let ((first_group, first_envelope), (second_group, second_envelope)) =
+        if Params::ENVELOPE_CACHING.caches_overflows() && children[0].is_leaf() {
+            let mut uncached = |(group, envelope): Group<CachedEnvelope<RTreeNode<T>>>| {
+                let mut children = take_spare_children::<_, Params>(spare_children);
+                children.extend(group.into_iter().map(CachedEnvelope::into_inner));
+                (children, envelope)
+            };
+            let mut cached = children.drain(..).map(CachedEnvelope::new).collect();
+            let (first, second) = distribute::<_, Params, Split>(
+                &mut cached,
+                Vec::with_capacity(Params::MAX_SIZE + 1),
+                Vec::with_capacity(Params::MAX_SIZE + 1),
+            );
+            (uncached(first), uncached(second))
+        } else {
+            let first_group = take_spare_children::<_, Params>(spare_children);
+            let second_group = take_spare_children::<_, Params>(spare_children);
+            distribute::<_, Params, Split>(&mut children, first_group, second_group)
+        };
+    // The distributed children leave their former vector empty
+    keep_spare_children(spare_children, children);
+    node.envelope = first_envelope;
+    node.children = first_group;
+    RTreeNode::Parent(ParentNode::new_with_envelope(second_group, second_envelope))
+}
+
+type Group<C> = (Vec<C>, <C as RTreeObject>::Envelope);
+
+/// Distributes the children of an overflowing node into two empty groups and returns them
+/// with their envelopes. `remaining` is left empty.
+fn distribute<C, Params, Split>(
+    remaining: &mut Vec<C>,
+    mut first_group: Vec<C>,
+    mut second_group: Vec<C>,
+) -> (Group<C>, Group<C>)
+where
+    C: RTreeObject,
+    Params: RTreeParams,
+    Split: SplitAlgorithm,
+{
+    let (first_seed, second_seed) = Split::pick_seeds(remaining);
+    // Remove the larger index first to keep the other one valid
+    let second = remaining.swap_remove(first_seed.max(second_seed));
+    let first = remaining.swap_remove(first_seed.min(second_seed));
+    let mut first_envelope = first.envelope();
+    let mut second_envelope = second.envelope();
+    first_group.push(first);
+    second_group.push(second);
+
+    while !remaining.is_empty() {
+        // Each group must receive at least MIN_SIZE children
+        if first_group.len() + remaining.len() <= Params::MIN_SIZE {
+            for child in remaining.iter() {
+                first_envelope.merge(&child.envelope());
+            }
+            first_group.append(remaining);
+            break;
+        }
+        if second_group.len() + remaining.len() <= Params::MIN_SIZE {
+            for child in remaining.iter() {
+                second_envelope.merge(&child.envelope());
+            }
+            second_group.append(remaining);
+            break;
+        }
+        let index = Split::pick_next(remaining, &first_envelope, &second_envelope);
+        let child = remaining.swap_remove(index);
+        let envelope = child.envelope();
+        let first_cost = enlargement(&first_envelope, &envelope);
+        let second_cost = enlargement(&second_envelope, &envelope);
+        let prefer_first = match compare_costs(first_cost, second_cost) {
+            Ordering::Less => true,
+            Ordering::Greater => false,
+            Ordering::Equal => first_group.len() <= second_group.len(),
+        };
+        if prefer_first {
+            first_envelope.merge(&envelope);
+            first_group.push(child);
+        } else {
+            second_envelope.merge(&envelope);
+            second_group.push(child);
+        }
+    }
+    (
+        (first_group, first_envelope),
+        (second_group, second_envelope),
+    )
+}
+
+fn compare_costs<S: RTreeNum>(l: Cost<S>, r: Cost<S>) -> Ordering {
+    total_cmp(&l.0, &r.0)
+        .then_with(|| total_cmp(&l.1, &r.1))
+        .then_with(|| total_cmp(&l.2, &r.2))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LinearInsertionStrategy, LinearSplit, QuadraticInsertionStrategy, SplitAlgorithm};
+    use crate::node::RTreeNode;
+    use crate::params::{InsertionStrategy, RTreeParams};
+    use crate::point::PointExt;
+    use crate::primitives::Rectangle;
+    use crate::test_utilities::{create_random_points, SEED_1, SEED_2};
+    use crate::{RTree, AABB};
+    use std::marker::PhantomData;
+
+    struct GuttmanParams<S>(PhantomData<fn() -> S>);
+
+    impl<S: InsertionStrategy> RTreeParams for GuttmanParams<S> {
+        const MIN_SIZE: usize = 3;
+        const MAX_SIZE: usize = 8;
+        const REINSERTION_COUNT: usize = 0;
+        type DefaultInsertionStrategy = S;
+    }
+
+    fn check_strategy<S: InsertionStrategy>() {
+        let points = create_random_points(1000, SEED_1);
+        let mut tree: RTree<_, GuttmanParams<S>> = RTree::new_with_params();
+        for point in &points {
+            tree.insert(*point);
+        }
+        assert_eq!(tree.size(), 1000);
+        tree.root().sanity_check::<GuttmanParams<S>>();
+        for query in create_random_points(100, SEED_2) {
+            let expected = points
+                .iter()
+                .map(|p| p.sub(&query).length_2())
+                .fold(f64::INFINITY, f64::min);
+            let nearest = tree.nearest_neighbor(&query).unwrap();
+            assert_eq!(nearest.sub(&query).length_2(), expected);
+        }
+        let envelope = AABB::from_corners([0.2, 0.3], [0.7, 0.6]);
+        let expected = points
+            .iter()
+            .filter(|p| p[0] >= 0.2 && p[0] <= 0.7 && p[1] >= 0.3 && p[1] <= 0.6)
+            .count();
+        assert_eq!(tree.locate_in_envelope(&envelope).count(), expected);
+        for point in &points[..500] {
+            assert_eq!(tree.remove(point), Some(*point));
+        }
+        tree.root().sanity_check::<GuttmanParams<S>>();
+        for point in &points[500..] {
+            assert!(tree.contains(point));
+        }
+        // Removed points are inserted again, contained points are rejected
+        for (index, point) in points.iter().enumerate() {
+            assert_eq!(tree.insert_unique(*point).is_ok(), index < 500);
+        }
+        assert_eq!(tree.size(), 1000);
+        tree.root().sanity_check::<GuttmanParams<S>>();
+
+        // Collinear points have envelopes without area
+        let mut tree: RTree<_, GuttmanParams<S>> = RTree::new_with_params();
+        for x in 0..200 {
+            tree.insert([x, 0]);
+        }
+        tree.root().sanity_check::<GuttmanParams<S>>();
+        assert_eq!(tree.nearest_neighbor(&[57, 3]), Some(&[57, 0]));
+    }
+
+    fn check_envelope_caching<S: InsertionStrategy>() {
+        use crate::test_utilities::{take_envelope_calls, CountingPoint};
+        use crate::EnvelopeCaching;
+
+        struct CachingParams<S>(PhantomData<fn() -> S>);
+
+        impl<S: InsertionStrategy> RTreeParams for CachingParams<S> {
+            const MIN_SIZE: usize = 3;
+            const MAX_SIZE: usize = 8;
+            const REINSERTION_COUNT: usize = 0;
+            const ENVELOPE_CACHING: EnvelopeCaching = EnvelopeCaching::Always;
+            type DefaultInsertionStrategy = S;
+        }
+
+        let points = create_random_points(1000, SEED_1);
+        let mut uncached: RTree<_, GuttmanParams<S>> = RTree::new_with_params();
+        take_envelope_calls();
+        for point in &points {
+            uncached.insert(CountingPoint(*point));
+        }
+        let uncached_calls = take_envelope_calls();
+        let mut cached: RTree<_, CachingParams<S>> = RTree::new_with_params();
+        for point in &points {
+            cached.insert(CountingPoint(*point));
+        }
+        assert!(take_envelope_calls() < uncached_calls);
+        cached.root().sanity_check::<CachingParams<S>>();
+        // Splits make the same decisions
+        assert!(cached.iter().eq(uncached.iter()));
+    }
+
+    #[test]
+    fn test_linear_insertion() {
+        check_strategy::<LinearInsertionStrategy>();
+        check_envelope_caching::<LinearInsertionStrategy>();
+    }
+
+    #[test]
+    fn test_quadratic_insertion() {
+        check_strategy::<QuadraticInsertionStrategy>();
+        check_envelope_caching::<QuadraticInsertionStrategy>();
+    }
+
+    #[test]
+    fn test_linear_pick_seeds_normalizes_separation() {
+        let children: Vec<_> = vec![
+            Rectangle::from_corners([0.0, 0.0], [100.0, 1.0]),
+            Rectangle::from_corners([90.0, 9.5], [100.0, 10.0]),
+            Rectangle::from_corners([0.0, 4.0], [10.0, 5.0]),
+        ]
+        .into_iter()
+        .map(RTreeNode::Leaf)
+        .collect();
+        // The separation along the x axis is larger, but small relative to the children's width
+        assert_eq!(LinearSplit::pick_seeds(&children), (0, 1));
+
+        // Children that coincide along all axes
+        let children = vec![RTreeNode::Leaf([1, 1]); 3];
+        assert_eq!(LinearSplit::pick_seeds(&children), (0, 1));
+    }
+}
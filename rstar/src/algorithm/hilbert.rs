@@ -0,0 +1,209 @@
+use crate::aabb::AABB;
+use crate::envelope::Envelope;
+use crate::node::{ParentNode, RTreeNode};
+use crate::object::RTreeObject;
+use crate::point::Point;
+
+/// The number of bits used per dimension, at most 64 bits are used in total.
+fn bits_per_dimension(dimensions: usize) -> u32 {
+    (64 / dimensions).clamp(1, 16) as u32
+}
+
+/// Maps a coordinate to a grid of `2^bits` cells between `lower` and `upper`.
+///
+/// The cells are found by bisection, which only requires the operations of `RTreeNum`.
+fn quantize<S: crate::RTreeNum>(value: S, mut lower: S, mut upper: S, bits: u32) -> u32 {
+    let two = S::one() + S::one();
+    let mut result = 0;
+    for bit in (0..bits).rev() {
+        let middle = lower / two + upper / two;
+        if value >= middle {
+            result |= 1 << bit;
+            lower = middle;
+        } else {
+            upper = middle;
+        }
+    }
+    result
+}
+
+/// Returns the position of a point along a Hilbert curve that fills `bounds`.
+///
+/// Uses Skilling's algorithm, see "Programming the Hilbert curve", AIP Conference
+/// Proceedings 707 (2004). Points outside of `bounds` are clamped to its boundary. Only the
+/// first 64 dimensions are considered.
+pub fn hilbert_key<P: Point>(point: &P, bounds: &AABB<P>) -> u64 {
+    let dimensions = P::DIMENSIONS.min(64);
+    let bits = bits_per_dimension(dimensions);
+    let (lower, upper) = (bounds.lower(), bounds.upper());
+    let mut x: Vec<u32> = (0..dimensions)
+        .map(|i| quantize(point.nth(i), lower.nth(i), upper.nth(i), bits))
+        .collect();
+
+    // Inverse undo
+    let mut q = 1 << (bits - 1);
+    while q > 1 {
+        let p = q - 1;
+        for i in 0..dimensions {
+            if x[i] & q != 0 {
+                x[0] ^= p;
+            } else {
+                let t = (x[0] ^ x[i]) & p;
+                x[0] ^= t;
+                x[i] ^= t;
+            }
+        }
+        q >>= 1;
+    }
+    // Gray encode
+    for i in 1..dimensions {
+        x[i] ^= x[i - 1];
+    }
+    let mut t = 0;
+    let mut q = 1 << (bits - 1);
+    while q > 1 {
+        if x[dimensions - 1] & q != 0 {
+            t ^= q - 1;
+        }
+        q >>= 1;
+    }
+    for coordinate in &mut x {
+        *coordinate ^= t;
+    }
+
+    // Interleave the transposed bits, most significant bit first
+    let mut key = 0;
+    for bit in (0..bits).rev() {
+        for coordinate in &x {
+            key = (key << 1) | u64::from((coordinate >> bit) & 1);
+        }
+    }
+    key
+}
+
+/// Iterates over a tree's elements approximately along a Hilbert curve.
+pub struct HilbertIterator<'a, T>
+where
+    T: RTreeObject + 'a,
+{
+    bounds: T::Envelope,
+    groups: ::std::vec::IntoIter<&'a ParentNode<T>>,
+    current: ::std::vec::IntoIter<&'a T>,
+}
+
+impl<'a, T, P> HilbertIterator<'a, T>
+where
+    T: RTreeObject<Envelope = AABB<P>>,
+    P: Point,
+{
+    pub(crate) fn new(root: &'a ParentNode<T>) -> Self {
+        let bounds = root.envelope;
+        let mut groups = Vec::new();
+        collect_leaf_parents(root, &mut groups);
+        groups.sort_by_cached_key(|node| hilbert_key(&node.envelope.center(), &bounds));
+        HilbertIterator {
+            bounds,
+            groups: groups.into_iter(),
+            current: Vec::new().into_iter(),
+        }
+    }
+}
+
+fn collect_leaf_parents<'a, T>(node: &'a ParentNode<T>, result: &mut Vec<&'a ParentNode<T>>)
+where
+    T: RTreeObject,
+{
+    if node.children.iter().any(RTreeNode::is_leaf) {
+        result.push(node);
+    }
+    for child in &node.children {
+        if let RTreeNode::Parent(ref data) = child {
+            collect_leaf_parents(data, result);
+        }
+    }
+}
+
+impl<'a, T, P> Iterator for HilbertIterator<'a, T>
+where
+    T: RTreeObject<Envelope = AABB<P>>,
+    P: Point,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            if let Some(next) = self.current.next() {
+                return Some(next);
+            }
+            let group = self.groups.next()?;
+            let bounds = &self.bounds;
+            let mut elements: Vec<_> = group
+                .children
+                .iter()
+                .filter_map(|child| match child {
+                    RTreeNode::Leaf(ref t) => Some(t),
+                    RTreeNode::Parent(_) => None,
+                })
+                .collect();
+            elements.sort_by_cached_key(|t| hilbert_key(&t.envelope().center(), bounds));
+            self.current = elements.into_iter();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::hilbert_key;
+    use crate::aabb::AABB;
+    use crate::rtree::RTree;
+
+    fn grid(size: usize) -> Vec<[f64; 2]> {
+        let cell = 1.0 / size as f64;
+        (0..size * size)
+            .map(|i| {
+                let (x, y) = (i / size, i % size);
+                [(x as f64 + 0.5) * cell, (y as f64 + 0.5) * cell]
+            })
+            .collect()
+    }
+
+    fn manhattan(l: &[f64; 2], r: &[f64; 2]) -> f64 {
+        (l[0] - r[0]).abs() + (l[1] - r[1]).abs()
+    }
+
+    #[test]
+    fn test_hilbert_key() {
+        let bounds = AABB::from_corners([0.0, 0.0], [1.0, 1.0]);
+        let mut points = grid(16);
+        points.sort_by_key(|p| hilbert_key(p, &bounds));
+        // Consecutive cells of a Hilbert curve are neighbors
+        for pair in points.windows(2) {
+            assert!((manhattan(&pair[0], &pair[1]) - 1.0 / 16.0).abs() < 1e-9);
+        }
+        let mut keys: Vec<_> = points.iter().map(|p| hilbert_key(p, &bounds)).collect();
+        keys.dedup();
+        assert_eq!(keys.len(), 256);
+    }
+
+    #[test]
+    fn test_iter_hilbert_sorted() {
+        let points = grid(64);
+        let tree = RTree::bulk_load(points.clone());
+        let sorted: Vec<_> = tree.iter_hilbert_sorted().cloned().collect();
+        assert_eq!(sorted.len(), points.len());
+        let mut expected = points.clone();
+        let mut located = sorted.clone();
+        expected.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        located.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        assert_eq!(located, expected);
+
+        let path_length = |points: &[[f64; 2]]| -> f64 {
+            points.windows(2).map(|w| manhattan(&w[0], &w[1])).sum()
+        };
+        let tree_order: Vec<_> = tree.iter().cloned().collect();
+        assert!(path_length(&sorted) < path_length(&tree_order));
+
+        let empty: RTree<[f64; 2]> = RTree::new();
+        assert_eq!(empty.iter_hilbert_sorted().count(), 0);
+    }
+}
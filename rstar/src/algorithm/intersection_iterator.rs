@@ -11,6 +11,17 @@ where
     todo_list: Vec<(&'a RTreeNode<T>, &'a RTreeNode<T>)>,
 }
 
+impl<'a, T> Clone for IntersectionIterator<'a, T>
+where
+    T: RTreeObject,
+{
+    fn clone(&self) -> Self {
+        IntersectionIterator {
+            todo_list: self.todo_list.clone(),
+        }
+    }
+}
+
 impl<'a, T> IntersectionIterator<'a, T>
 where
     T: RTreeObject,
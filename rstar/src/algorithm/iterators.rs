@@ -1,6 +1,9 @@
 use crate::algorithm::selection_functions::*;
-use crate::node::{ParentNode, RTreeNode};
+use crate::envelope::Envelope;
+use crate::node::{ParentNode, RTreeNode, TraversalOrder, TreeChild, UnpackedChild};
 use crate::object::RTreeObject;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
 
 pub type LocateAllAtPoint<'a, T> = SelectionIterator<'a, T, SelectAtPointFunction<T>>;
 pub type LocateAllAtPointMut<'a, T> = SelectionIteratorMut<'a, T, SelectAtPointFunction<T>>;
@@ -10,18 +13,40 @@ pub type LocateInEnvelopeIntersecting<'a, T> =
     SelectionIterator<'a, T, SelectInEnvelopeFuncIntersecting<T>>;
 pub type LocateInEnvelopeIntersectingMut<'a, T> =
     SelectionIteratorMut<'a, T, SelectInEnvelopeFuncIntersecting<T>>;
-pub type RTreeIterator<'a, T> = SelectionIterator<'a, T, SelectAllFunc>;
-pub type RTreeIteratorMut<'a, T> = SelectionIteratorMut<'a, T, SelectAllFunc>;
+pub type LocateInEnvelopeIntersectingExcluding<'a, T> =
+    SelectionIterator<'a, T, SelectInEnvelopeExcludingFunction<T>>;
+pub type LocateInTransformedEnvelopeIntersecting<'a, T, P> =
+    SelectionIterator<'a, T, SelectInTransformedEnvelopeIntersectingFunction<P>>;
 pub type LocateWithinDistanceIterator<'a, T> =
     SelectionIterator<'a, T, SelectWithinDistanceFunction<T>>;
+pub type LocateWithinMetricDistanceIterator<'a, T, M> =
+    SelectionIterator<'a, T, SelectWithinMetricDistanceFunction<T, M>>;
 
-pub struct SelectionIterator<'a, T, Func>
+pub struct SelectionIterator<'a, T, Func, C = RTreeNode<T>>
 where
     T: RTreeObject + 'a,
     Func: SelectionFunction<T>,
 {
     func: Func,
-    current_nodes: Vec<&'a RTreeNode<T>>,
+    current_nodes: Vec<&'a C>,
+    // Upper bound of the remaining elements, known if the tree's size was passed
+    max_remaining: Option<usize>,
+    _element: PhantomData<&'a T>,
+}
+
+impl<'a, T, Func, C> Clone for SelectionIterator<'a, T, Func, C>
+where
+    T: RTreeObject,
+    Func: SelectionFunction<T> + Clone,
+{
+    fn clone(&self) -> Self {
+        SelectionIterator {
+            func: self.func.clone(),
+            current_nodes: self.current_nodes.clone(),
+            max_remaining: self.max_remaining,
+            _element: PhantomData,
+        }
+    }
 }
 
 impl<'a, T, Func> SelectionIterator<'a, T, Func>
@@ -30,37 +55,153 @@ where
     Func: SelectionFunction<T>,
 {
     pub fn new(root: &'a ParentNode<T>, func: Func) -> Self {
-        let should_unpack_node = |node: &&RTreeNode<T>| match node {
-            RTreeNode::Leaf(ref t) => func.should_unpack_leaf(t),
-            RTreeNode::Parent(ref data) => func.should_unpack_parent(&data.envelope),
-        };
-        let current_nodes: Vec<_> = root.children.iter().filter(should_unpack_node).collect();
+        Self::new_with_children(&root.children, func)
+    }
+}
+
+impl<'a, T, Func, C> SelectionIterator<'a, T, Func, C>
+where
+    T: RTreeObject,
+    Func: SelectionFunction<T>,
+    C: TreeChild<T>,
+{
+    /// Creates an iterator over the children of a root node.
+    pub fn new_with_children(children: &'a [C], func: Func) -> Self {
+        // The children are checked once they are popped
+        let current_nodes: Vec<_> = children.iter().collect();
         SelectionIterator {
             func,
             current_nodes,
+            max_remaining: None,
+            _element: PhantomData,
         }
     }
+
+    /// Bounds the size hint by the size of the tree whose root's children are iterated.
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.max_remaining = Some(size);
+        self
+    }
 }
 
-impl<'a, T, Func> Iterator for SelectionIterator<'a, T, Func>
+/// Accepts every insertion, see `InsertionStrategy::insert_unless`.
+pub fn allow_duplicates<T>(_: &[RTreeNode<T>], _: &T) -> bool
 where
     T: RTreeObject,
+{
+    false
+}
+
+/// Rejects an insertion if the children contain an element equal (`==`) to `t`.
+pub fn contains_equal<T>(children: &[RTreeNode<T>], t: &T) -> bool
+where
+    T: RTreeObject + PartialEq,
+{
+    SelectionIterator::new_with_children(children, SelectEqualsFunction::new(t))
+        .next()
+        .is_some()
+}
 
+impl<'a, T, Func, C> Iterator for SelectionIterator<'a, T, Func, C>
+where
+    T: RTreeObject,
     Func: SelectionFunction<T>,
+    C: TreeChild<T>,
 {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<&'a T> {
         while let Some(next) = self.current_nodes.pop() {
+            match next.unpack() {
+                UnpackedChild::Leaf(t) => {
+                    if self.func.should_unpack_leaf(t) {
+                        if let Some(ref mut max_remaining) = self.max_remaining {
+                            *max_remaining = max_remaining.saturating_sub(1);
+                        }
+                        return Some(t);
+                    }
+                }
+                UnpackedChild::Parent(envelope, children) => {
+                    if self.func.should_unpack_parent(envelope) {
+                        self.current_nodes.extend(children);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.max_remaining)
+    }
+}
+
+pub struct DepthLimitedSelectionIterator<'a, T, Func>
+where
+    T: RTreeObject + 'a,
+    Func: SelectionFunction<T>,
+{
+    func: Func,
+    max_depth: usize,
+    current_nodes: Vec<(&'a RTreeNode<T>, usize)>,
+}
+
+impl<'a, T, Func> Clone for DepthLimitedSelectionIterator<'a, T, Func>
+where
+    T: RTreeObject,
+    Func: SelectionFunction<T> + Clone,
+{
+    fn clone(&self) -> Self {
+        DepthLimitedSelectionIterator {
+            func: self.func.clone(),
+            max_depth: self.max_depth,
+            current_nodes: self.current_nodes.clone(),
+        }
+    }
+}
+
+impl<'a, T, Func> DepthLimitedSelectionIterator<'a, T, Func>
+where
+    T: RTreeObject,
+    Func: SelectionFunction<T>,
+{
+    pub fn new(root: &'a ParentNode<T>, func: Func, max_depth: usize) -> Self {
+        let current_nodes = if max_depth > 0 {
+            root.children.iter().map(|child| (child, 1)).collect()
+        } else {
+            Vec::new()
+        };
+        DepthLimitedSelectionIterator {
+            func,
+            max_depth,
+            current_nodes,
+        }
+    }
+}
+
+impl<'a, T, Func> Iterator for DepthLimitedSelectionIterator<'a, T, Func>
+where
+    T: RTreeObject,
+    Func: SelectionFunction<T>,
+{
+    type Item = &'a RTreeNode<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((next, depth)) = self.current_nodes.pop() {
             match next {
                 RTreeNode::Leaf(ref t) => {
                     if self.func.should_unpack_leaf(t) {
-                        return Some(t);
+                        return Some(next);
                     }
                 }
                 RTreeNode::Parent(ref data) => {
                     if self.func.should_unpack_parent(&data.envelope) {
-                        self.current_nodes.extend(&data.children);
+                        if depth == self.max_depth {
+                            // Deeper nodes are not visited, the node itself is returned instead
+                            return Some(next);
+                        }
+                        self.current_nodes
+                            .extend(data.children.iter().map(|child| (child, depth + 1)));
                     }
                 }
             }
@@ -76,6 +217,8 @@ where
 {
     func: Func,
     current_nodes: Vec<&'a mut RTreeNode<T>>,
+    // Upper bound of the remaining elements, known if the tree's size was passed
+    max_remaining: Option<usize>,
 }
 
 impl<'a, T, Func> SelectionIteratorMut<'a, T, Func>
@@ -92,8 +235,15 @@ where
         SelectionIteratorMut {
             func,
             current_nodes,
+            max_remaining: None,
         }
     }
+
+    /// Bounds the size hint by the size of the tree whose root was passed.
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.max_remaining = Some(size);
+        self
+    }
 }
 
 impl<'a, T, Func> Iterator for SelectionIteratorMut<'a, T, Func>
@@ -107,7 +257,12 @@ where
         let func = &self.func;
         if let Some(next) = self.current_nodes.pop() {
             match next {
-                RTreeNode::Leaf(ref mut t) => Some(t),
+                RTreeNode::Leaf(ref mut t) => {
+                    if let Some(ref mut max_remaining) = self.max_remaining {
+                        *max_remaining = max_remaining.saturating_sub(1);
+                    }
+                    Some(t)
+                }
                 RTreeNode::Parent(ref mut data) => {
                     self.current_nodes.extend(
                         data.children
@@ -121,6 +276,444 @@ where
             None
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.max_remaining)
+    }
+}
+
+pub struct LocateInEnvelopes<'a, T>
+where
+    T: RTreeObject + 'a,
+{
+    envelopes: Vec<T::Envelope>,
+    current_nodes: Vec<(&'a RTreeNode<T>, Vec<usize>)>,
+    pending: Vec<(usize, &'a T)>,
+}
+
+impl<'a, T> Clone for LocateInEnvelopes<'a, T>
+where
+    T: RTreeObject,
+{
+    fn clone(&self) -> Self {
+        LocateInEnvelopes {
+            envelopes: self.envelopes.clone(),
+            current_nodes: self.current_nodes.clone(),
+            pending: self.pending.clone(),
+        }
+    }
+}
+
+impl<'a, T> LocateInEnvelopes<'a, T>
+where
+    T: RTreeObject,
+{
+    pub fn new(root: &'a ParentNode<T>, envelopes: Vec<T::Envelope>) -> Self {
+        let all_indices: Vec<_> = (0..envelopes.len()).collect();
+        let current_nodes = if all_indices.is_empty() {
+            Vec::new()
+        } else {
+            root.children
+                .iter()
+                .map(|child| (child, all_indices.clone()))
+                .collect()
+        };
+        LocateInEnvelopes {
+            envelopes,
+            current_nodes,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for LocateInEnvelopes<'a, T>
+where
+    T: RTreeObject,
+{
+    type Item = (usize, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(result) = self.pending.pop() {
+                return Some(result);
+            }
+            let (next, indices) = self.current_nodes.pop()?;
+            match next {
+                RTreeNode::Leaf(ref t) => {
+                    let envelope = t.envelope();
+                    let envelopes = &self.envelopes;
+                    self.pending.extend(
+                        indices
+                            .into_iter()
+                            .rev()
+                            .filter(|index| envelopes[*index].contains_envelope(&envelope))
+                            .map(|index| (index, t)),
+                    );
+                }
+                RTreeNode::Parent(ref data) => {
+                    // Only carry those envelopes down the tree that are still relevant
+                    let relevant: Vec<_> = indices
+                        .into_iter()
+                        .filter(|index| self.envelopes[*index].intersects(&data.envelope))
+                        .collect();
+                    if !relevant.is_empty() {
+                        self.current_nodes
+                            .extend(data.children.iter().map(|child| (child, relevant.clone())));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Iterates over all elements of a tree, the number of remaining elements is known.
+pub struct RTreeIterator<'a, T>
+where
+    T: RTreeObject + 'a,
+{
+    inner: SelectionIterator<'a, T, SelectAllFunc>,
+    remaining: usize,
+}
+
+impl<'a, T> Clone for RTreeIterator<'a, T>
+where
+    T: RTreeObject,
+{
+    fn clone(&self) -> Self {
+        RTreeIterator {
+            inner: self.inner.clone(),
+            remaining: self.remaining,
+        }
+    }
+}
+
+impl<'a, T> RTreeIterator<'a, T>
+where
+    T: RTreeObject,
+{
+    pub fn new(root: &'a ParentNode<T>, size: usize) -> Self {
+        RTreeIterator {
+            inner: SelectionIterator::new(root, SelectAllFunc),
+            remaining: size,
+        }
+    }
+}
+
+impl<'a, T> Iterator for RTreeIterator<'a, T>
+where
+    T: RTreeObject,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let next = self.inner.next();
+        if next.is_some() {
+            self.remaining = self.remaining.saturating_sub(1);
+        }
+        next
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for RTreeIterator<'a, T> where T: RTreeObject {}
+
+/// Mutable variant of [RTreeIterator].
+pub struct RTreeIteratorMut<'a, T>
+where
+    T: RTreeObject + 'a,
+{
+    inner: SelectionIteratorMut<'a, T, SelectAllFunc>,
+    remaining: usize,
+}
+
+impl<'a, T> RTreeIteratorMut<'a, T>
+where
+    T: RTreeObject,
+{
+    pub fn new(root: &'a mut ParentNode<T>, size: usize) -> Self {
+        RTreeIteratorMut {
+            inner: SelectionIteratorMut::new(root, SelectAllFunc),
+            remaining: size,
+        }
+    }
+}
+
+impl<'a, T> Iterator for RTreeIteratorMut<'a, T>
+where
+    T: RTreeObject,
+{
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        let next = self.inner.next();
+        if next.is_some() {
+            self.remaining = self.remaining.saturating_sub(1);
+        }
+        next
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for RTreeIteratorMut<'a, T> where T: RTreeObject {}
+
+/// Iterates over the nodes that contain elements, together with their envelope.
+pub struct LeafGroupIterator<'a, T>
+where
+    T: RTreeObject + 'a,
+{
+    current_nodes: Vec<&'a ParentNode<T>>,
+}
+
+impl<'a, T> LeafGroupIterator<'a, T>
+where
+    T: RTreeObject,
+{
+    pub fn new(root: &'a ParentNode<T>) -> Self {
+        LeafGroupIterator {
+            current_nodes: vec![root],
+        }
+    }
+}
+
+impl<'a, T> Iterator for LeafGroupIterator<'a, T>
+where
+    T: RTreeObject,
+{
+    type Item = (T::Envelope, LeafGroup<'a, T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.current_nodes.pop() {
+            let mut has_leaves = false;
+            for child in &node.children {
+                match child {
+                    RTreeNode::Leaf(_) => has_leaves = true,
+                    RTreeNode::Parent(ref data) => self.current_nodes.push(data),
+                }
+            }
+            if has_leaves {
+                let group = LeafGroup {
+                    children: node.children.iter(),
+                };
+                return Some((node.envelope, group));
+            }
+        }
+        None
+    }
+}
+
+/// The elements stored directly in a node.
+pub struct LeafGroup<'a, T>
+where
+    T: RTreeObject + 'a,
+{
+    children: ::std::slice::Iter<'a, RTreeNode<T>>,
+}
+
+impl<'a, T> Clone for LeafGroup<'a, T>
+where
+    T: RTreeObject,
+{
+    fn clone(&self) -> Self {
+        LeafGroup {
+            children: self.children.clone(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for LeafGroup<'a, T>
+where
+    T: RTreeObject,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.children.find_map(|child| match child {
+            RTreeNode::Leaf(ref t) => Some(t),
+            RTreeNode::Parent(_) => None,
+        })
+    }
+}
+
+/// Consumes a tree and yields its elements by taking its nodes apart.
+pub struct RTreeIntoIterator<T>
+where
+    T: RTreeObject,
+{
+    // The remaining children of each node on the path to the current node
+    stack: Vec<::std::vec::IntoIter<RTreeNode<T>>>,
+    remaining: usize,
+}
+
+impl<T> RTreeIntoIterator<T>
+where
+    T: RTreeObject,
+{
+    pub fn new(root: ParentNode<T>, size: usize) -> Self {
+        RTreeIntoIterator {
+            stack: vec![root.children.into_iter()],
+            remaining: size,
+        }
+    }
+}
+
+impl<T> Iterator for RTreeIntoIterator<T>
+where
+    T: RTreeObject,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some(RTreeNode::Leaf(t)) => {
+                    self.remaining = self.remaining.saturating_sub(1);
+                    return Some(t);
+                }
+                Some(RTreeNode::Parent(data)) => self.stack.push(data.children.into_iter()),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for RTreeIntoIterator<T> where T: RTreeObject {}
+
+/// Iterates over all nodes of a tree and their depth.
+pub struct NodeIterator<'a, T>
+where
+    T: RTreeObject + 'a,
+{
+    nodes: VecDeque<(&'a RTreeNode<T>, usize)>,
+    order: TraversalOrder,
+}
+
+impl<'a, T> Clone for NodeIterator<'a, T>
+where
+    T: RTreeObject,
+{
+    fn clone(&self) -> Self {
+        NodeIterator {
+            nodes: self.nodes.clone(),
+            order: self.order,
+        }
+    }
+}
+
+impl<'a, T> NodeIterator<'a, T>
+where
+    T: RTreeObject,
+{
+    pub(crate) fn new(root: &'a ParentNode<T>, order: TraversalOrder) -> Self {
+        let mut result = NodeIterator {
+            nodes: VecDeque::new(),
+            order,
+        };
+        result.push_all(&root.children, 1);
+        result
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<(&'a RTreeNode<T>, usize)> {
+        match self.order {
+            TraversalOrder::DepthFirst => self.nodes.pop_back(),
+            TraversalOrder::BreadthFirst => self.nodes.pop_front(),
+        }
+    }
+
+    pub(crate) fn push_children(&mut self, node: &'a RTreeNode<T>, depth: usize) {
+        if let RTreeNode::Parent(ref data) = node {
+            self.push_all(&data.children, depth + 1);
+        }
+    }
+
+    fn push_all(&mut self, children: &'a [RTreeNode<T>], depth: usize) {
+        match self.order {
+            // The deque is used as a stack, push in reverse to pop the first child first
+            TraversalOrder::DepthFirst => self
+                .nodes
+                .extend(children.iter().rev().map(|child| (child, depth))),
+            TraversalOrder::BreadthFirst => self
+                .nodes
+                .extend(children.iter().map(|child| (child, depth))),
+        }
+    }
+}
+
+impl<'a, T> Iterator for NodeIterator<'a, T>
+where
+    T: RTreeObject,
+{
+    type Item = (&'a RTreeNode<T>, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, depth) = self.pop()?;
+        self.push_children(node, depth);
+        Some((node, depth))
+    }
+}
+
+/// Iterates over the envelopes of all nodes of a tree and their depth.
+pub struct EnvelopeIterator<'a, T>
+where
+    T: RTreeObject + 'a,
+{
+    root: Option<T::Envelope>,
+    nodes: NodeIterator<'a, T>,
+}
+
+impl<'a, T> Clone for EnvelopeIterator<'a, T>
+where
+    T: RTreeObject,
+{
+    fn clone(&self) -> Self {
+        EnvelopeIterator {
+            root: self.root,
+            nodes: self.nodes.clone(),
+        }
+    }
+}
+
+impl<'a, T> EnvelopeIterator<'a, T>
+where
+    T: RTreeObject,
+{
+    pub(crate) fn new(root: &'a ParentNode<T>) -> Self {
+        EnvelopeIterator {
+            root: if root.children.is_empty() {
+                None
+            } else {
+                Some(root.envelope)
+            },
+            nodes: NodeIterator::new(root, TraversalOrder::DepthFirst),
+        }
+    }
+}
+
+impl<'a, T> Iterator for EnvelopeIterator<'a, T>
+where
+    T: RTreeObject,
+{
+    type Item = (usize, T::Envelope);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(envelope) = self.root.take() {
+            return Some((0, envelope));
+        }
+        self.nodes
+            .next()
+            .map(|(node, depth)| (depth, node.envelope()))
+    }
 }
 
 #[cfg(test)]
@@ -129,7 +722,35 @@ mod test {
     use crate::envelope::Envelope;
     use crate::object::RTreeObject;
     use crate::rtree::RTree;
-    use crate::test_utilities::{create_random_points, create_random_rectangles, SEED_1};
+    use crate::test_utilities::{create_random_points, create_random_rectangles, SEED_1, SEED_2};
+
+    #[test]
+    fn test_iter_envelopes() {
+        let points = create_random_points(500, SEED_1);
+        let tree = RTree::bulk_load(points.clone());
+        let envelopes: Vec<_> = tree.iter_envelopes().collect();
+        assert_eq!(envelopes[0], (0, tree.root().envelope()));
+        let leaves: Vec<_> = envelopes
+            .iter()
+            .filter(|(depth, _)| *depth == tree.height())
+            .collect();
+        assert_eq!(leaves.len(), points.len());
+        for point in &points {
+            assert!(leaves.contains(&&(tree.height(), point.envelope())));
+        }
+        // Each envelope is contained in the envelope of its parent
+        let mut parents = Vec::new();
+        for &(depth, envelope) in &envelopes {
+            parents.truncate(depth);
+            if let Some(parent) = parents.last() {
+                assert!(Envelope::contains_envelope(parent, &envelope));
+            }
+            parents.push(envelope);
+        }
+
+        let empty: RTree<[f64; 2]> = RTree::new();
+        assert_eq!(empty.iter_envelopes().count(), 0);
+    }
 
     #[test]
     fn test_locate_all() {
@@ -176,6 +797,77 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_locate_size_hint() {
+        let points = create_random_points(100, SEED_1);
+        let mut tree = RTree::bulk_load(points.clone());
+        let envelope = AABB::from_corners([0.5, 0.5], [1.0, 1.0]);
+        let mut iter = tree.locate_in_envelope(&envelope);
+        assert_eq!(iter.size_hint(), (0, Some(100)));
+        iter.next();
+        assert_eq!(iter.size_hint(), (0, Some(99)));
+        assert_eq!(
+            tree.locate_all_at_point(&points[0]).size_hint(),
+            (0, Some(100))
+        );
+        let mut iter = tree.locate_in_envelope_mut(&envelope);
+        iter.next();
+        assert_eq!(iter.size_hint(), (0, Some(99)));
+        assert_eq!(
+            tree.locate_with_selection_function(super::SelectAllFunc)
+                .size_hint(),
+            (0, Some(100))
+        );
+    }
+
+    #[test]
+    fn test_locate_in_envelope_intersecting_excluding() {
+        let rectangles = create_random_rectangles(500, SEED_1);
+        let tree = RTree::bulk_load(rectangles.clone());
+        let envelope = AABB::from_corners([0.2, 0.2], [0.8, 0.8]);
+        let excluded = [
+            AABB::from_corners([0.0, 0.0], [0.5, 0.5]),
+            AABB::from_corners([0.6, 0.1], [0.7, 0.9]),
+        ];
+        let expected: Vec<_> = rectangles
+            .iter()
+            .filter(|r| r.envelope().intersects(&envelope))
+            .filter(|r| !excluded.iter().any(|e| e.intersects(&r.envelope())))
+            .collect();
+        let located: Vec<_> = tree
+            .locate_in_envelope_intersecting_excluding(&envelope, &excluded)
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(located.len(), expected.len());
+        for rectangle in &expected {
+            assert!(located.contains(rectangle));
+        }
+    }
+
+    #[test]
+    fn test_locate_in_envelopes() {
+        let rectangles = create_random_rectangles(500, SEED_1);
+        let tree = RTree::bulk_load(rectangles.clone());
+        let windows: Vec<_> = create_random_points(20, SEED_2)
+            .iter()
+            .map(|p| AABB::from_corners(*p, [p[0] + 0.3, p[1] + 0.2]))
+            .collect();
+        let located: Vec<_> = tree.locate_in_envelopes(&windows).collect();
+        for (index, window) in windows.iter().enumerate() {
+            let mut expected: Vec<_> = tree.locate_in_envelope(window).collect();
+            let mut from_batch: Vec<_> = located
+                .iter()
+                .filter(|(i, _)| *i == index)
+                .map(|(_, r)| *r)
+                .collect();
+            expected.sort_by(|l, r| l.partial_cmp(r).unwrap());
+            from_batch.sort_by(|l, r| l.partial_cmp(r).unwrap());
+            assert_eq!(expected, from_batch);
+        }
+        assert!(located.iter().all(|(index, _)| *index < windows.len()));
+        assert_eq!(tree.locate_in_envelopes(&[]).count(), 0);
+    }
+
     #[test]
     fn test_locate_with_selection_func() {
         use crate::SelectionFunction;
@@ -225,6 +917,12 @@ mod test {
             count += 1;
         }
         assert_eq!(count, NUM_POINTS);
+        let mut iter = tree.iter();
+        assert_eq!(iter.len(), NUM_POINTS);
+        iter.nth(9);
+        assert_eq!(iter.len(), NUM_POINTS - 10);
+        assert_eq!(iter.clone().count(), NUM_POINTS - 10);
+        assert_eq!(tree.iter_mut().len(), NUM_POINTS);
         count = 0;
         for p in tree.iter_mut() {
             assert!(points.iter().any(|q| q == p));
@@ -235,6 +933,55 @@ mod test {
             assert!(tree.iter().any(|q| q == p));
             assert!(tree.iter_mut().any(|q| q == p));
         }
+
+        let mut into_iter = tree.into_iter();
+        assert_eq!(into_iter.len(), NUM_POINTS);
+        into_iter.next();
+        assert_eq!(into_iter.len(), NUM_POINTS - 1);
+        let mut owned: Vec<_> = into_iter.collect();
+        owned.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        let mut expected = points.clone();
+        expected.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        assert_eq!(owned.len(), NUM_POINTS - 1);
+        assert!(owned.iter().all(|p| expected
+            .binary_search_by(|q| q.partial_cmp(p).unwrap())
+            .is_ok()));
+    }
+
+    #[test]
+    fn test_iteration_with_wrong_size() {
+        use super::{RTreeIntoIterator, RTreeIterator};
+
+        // A tree with a corrupted size, e.g. deserialized from untrusted input, must not panic
+        let tree = RTree::bulk_load(create_random_points(100, SEED_1));
+        let mut iter = RTreeIterator::new(tree.root(), 10);
+        assert_eq!(iter.by_ref().count(), 100);
+        assert_eq!(iter.len(), 0);
+        let root = tree.root().clone();
+        assert_eq!(RTreeIntoIterator::new(root, 10).count(), 100);
+    }
+
+    #[test]
+    fn test_leaf_groups() {
+        let empty: RTree<[f64; 2]> = RTree::new();
+        assert_eq!(empty.leaf_groups().count(), 0);
+
+        let rectangles = create_random_rectangles(1000, SEED_1);
+        let tree = RTree::bulk_load(rectangles);
+        let mut count = 0;
+        for (envelope, group) in tree.leaf_groups() {
+            let group: Vec<_> = group.collect();
+            assert!(!group.is_empty());
+            assert!(group.len() <= <crate::DefaultParams as crate::RTreeParams>::MAX_SIZE);
+            assert_eq!(
+                group
+                    .iter()
+                    .fold(AABB::new_empty(), |acc, r| acc.merged(&r.envelope())),
+                envelope
+            );
+            count += group.len();
+        }
+        assert_eq!(count, 1000);
     }
 
     #[test]
@@ -260,4 +1007,77 @@ mod test {
             assert!(located.contains(point));
         }
     }
+
+    #[test]
+    fn test_locate_up_to_depth() {
+        use super::SelectionIterator;
+        use crate::algorithm::selection_functions::SelectInEnvelopeFuncIntersecting;
+        use crate::node::RTreeNode;
+        use crate::DefaultParams;
+
+        let rectangles = create_random_rectangles(1000, SEED_1);
+        let mut tree = RTree::new();
+        for rectangle in rectangles {
+            tree.insert(rectangle);
+        }
+        let height = tree.height();
+        assert_eq!(tree.root().sanity_check::<DefaultParams>(), Some(height));
+        assert!(height > 2);
+
+        let envelope = AABB::from_corners([0.3, 0.2], [0.6, 0.5]);
+        let mut expected: Vec<_> = tree.locate_in_envelope_intersecting(&envelope).collect();
+        expected.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        assert_eq!(
+            tree.locate_in_envelope_intersecting_up_to_depth(&envelope, 0)
+                .count(),
+            0
+        );
+        for max_depth in 1..=height {
+            let mut covered = Vec::new();
+            for node in tree.locate_in_envelope_intersecting_up_to_depth(&envelope, max_depth) {
+                assert!(node.envelope().intersects(&envelope));
+                match node {
+                    RTreeNode::Leaf(ref t) => covered.push(t),
+                    RTreeNode::Parent(ref data) => {
+                        assert!(max_depth < height);
+                        covered.extend(SelectionIterator::new(
+                            data,
+                            SelectInEnvelopeFuncIntersecting::new(envelope),
+                        ));
+                    }
+                }
+            }
+            covered.sort_by(|l, r| l.partial_cmp(r).unwrap());
+            assert_eq!(covered, expected);
+        }
+    }
+
+    #[test]
+    fn test_clone_partially_consumed_iterators() {
+        let points = create_random_points(500, SEED_1);
+        let tree = RTree::bulk_load(points);
+        let other = RTree::bulk_load(create_random_points(500, SEED_2));
+        let envelope = AABB::from_corners([0.2, 0.2], [0.7, 0.7]);
+
+        let mut in_envelope = tree.locate_in_envelope(&envelope);
+        in_envelope.nth(10);
+        let fork = in_envelope.clone();
+        assert!(fork.clone().count() > 0);
+        assert!(in_envelope.eq(fork));
+
+        let mut nearest = tree.nearest_neighbor_iter(&[0.5, 0.5]);
+        nearest.nth(10);
+        let fork = nearest.clone();
+        assert!(nearest.eq(fork));
+
+        let mut intersections = tree.intersection_candidates_with_other_tree(&other);
+        intersections.next();
+        let fork = intersections.clone();
+        assert!(intersections.eq(fork));
+
+        let mut in_envelopes = tree.locate_in_envelopes(&[envelope]);
+        in_envelopes.next();
+        let fork = in_envelopes.clone();
+        assert!(in_envelopes.eq(fork));
+    }
 }
@@ -1,7 +1,16 @@
+pub mod anti_join;
 pub mod bulk_load;
+pub mod clustering;
+pub mod guttman;
+pub mod hilbert;
 pub mod intersection_iterator;
 pub mod iterators;
 pub mod nearest_neighbor;
+pub mod overlay;
+#[cfg(feature = "rayon")]
+pub mod parallel_iterators;
 pub mod removal;
 pub mod rstar;
 pub mod selection_functions;
+pub mod spanning_tree;
+pub mod weighted_sampling;
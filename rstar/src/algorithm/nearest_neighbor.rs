@@ -1,112 +1,197 @@
-use crate::node::{ParentNode, RTreeNode};
-use crate::point::{min_inline, Point};
-use crate::{Envelope, PointDistance, RTreeObject};
-use num_traits::Bounded;
+use crate::algorithm::selection_functions::{SelectAllFunc, SelectionFunction};
+use crate::metric::{Metric, SquaredEuclidean};
+use crate::node::{ParentNode, RTreeNode, TreeChild, UnpackedChild};
+use crate::point::{min_inline, total_cmp, Point, PointExt};
+use crate::query_stats::QueryStats;
+use crate::{Envelope, PointDistance, RTreeObject, AABB};
+use num_traits::{Bounded, One, Zero};
 use std::collections::binary_heap::BinaryHeap;
 
-struct RTreeNodeDistanceWrapper<'a, T>
+struct RTreeNodeDistanceWrapper<'a, T, C = RTreeNode<T>>
 where
-    T: PointDistance + 'a,
+    T: RTreeObject + 'a,
 {
-    node: &'a RTreeNode<T>,
+    node: &'a C,
     distance: <<T::Envelope as Envelope>::Point as Point>::Scalar,
 }
 
-impl<'a, T> PartialEq for RTreeNodeDistanceWrapper<'a, T>
+impl<'a, T, C> Clone for RTreeNodeDistanceWrapper<'a, T, C>
 where
-    T: PointDistance,
+    T: RTreeObject,
+{
+    fn clone(&self) -> Self {
+        RTreeNodeDistanceWrapper {
+            node: self.node,
+            distance: self.distance,
+        }
+    }
+}
+
+impl<'a, T, C> PartialEq for RTreeNodeDistanceWrapper<'a, T, C>
+where
+    T: RTreeObject,
 {
     fn eq(&self, other: &Self) -> bool {
         self.distance == other.distance
     }
 }
 
-impl<'a, T> PartialOrd for RTreeNodeDistanceWrapper<'a, T>
+impl<'a, T, C> PartialOrd for RTreeNodeDistanceWrapper<'a, T, C>
 where
-    T: PointDistance,
+    T: RTreeObject,
 {
     fn partial_cmp(&self, other: &Self) -> Option<::std::cmp::Ordering> {
-        // Inverse comparison creates a min heap
-        other.distance.partial_cmp(&self.distance)
+        Some(self.cmp(other))
     }
 }
 
-impl<'a, T> Eq for RTreeNodeDistanceWrapper<'a, T> where T: PointDistance {}
+impl<'a, T, C> Eq for RTreeNodeDistanceWrapper<'a, T, C> where T: RTreeObject {}
 
-impl<'a, T> Ord for RTreeNodeDistanceWrapper<'a, T>
+impl<'a, T, C> Ord for RTreeNodeDistanceWrapper<'a, T, C>
 where
-    T: PointDistance,
+    T: RTreeObject,
 {
     fn cmp(&self, other: &Self) -> ::std::cmp::Ordering {
-        self.partial_cmp(other).unwrap()
+        // Inverse comparison creates a min heap
+        total_cmp(&other.distance, &self.distance)
     }
 }
 
-impl<'a, T> NearestNeighborDistanceIterator<'a, T>
+impl<'a, T, M> NearestNeighborDistanceIterator<'a, T, M>
 where
-    T: PointDistance,
+    T: RTreeObject,
+    M: Metric<T>,
 {
-    pub fn new(root: &'a ParentNode<T>, query_point: <T::Envelope as Envelope>::Point) -> Self {
+    pub fn new(
+        root: &'a ParentNode<T>,
+        query_point: <T::Envelope as Envelope>::Point,
+        metric: M,
+    ) -> Self {
+        Self::new_with_selection_function(root, query_point, metric, SelectAllFunc)
+    }
+}
+
+impl<'a, T, M, Func> NearestNeighborDistanceIterator<'a, T, M, Func>
+where
+    T: RTreeObject,
+    M: Metric<T>,
+    Func: SelectionFunction<T>,
+{
+    /// Creates an iterator that only visits nodes selected by a selection function.
+    pub fn new_with_selection_function(
+        root: &'a ParentNode<T>,
+        query_point: <T::Envelope as Envelope>::Point,
+        metric: M,
+        func: Func,
+    ) -> Self {
+        Self::new_with_children(&root.children, query_point, metric, func)
+    }
+}
+
+impl<'a, T, M, Func, C> NearestNeighborDistanceIterator<'a, T, M, Func, C>
+where
+    T: RTreeObject,
+    M: Metric<T>,
+    Func: SelectionFunction<T>,
+    C: TreeChild<T>,
+{
+    /// Creates an iterator over the children of a root node.
+    pub fn new_with_children(
+        children: &'a [C],
+        query_point: <T::Envelope as Envelope>::Point,
+        metric: M,
+        func: Func,
+    ) -> Self {
         let mut result = NearestNeighborDistanceIterator {
             nodes: BinaryHeap::with_capacity(20),
             query_point,
+            metric,
+            func,
         };
-        result.extend_heap(&root.children);
+        result.extend_heap(children);
         result
     }
 
-    fn extend_heap(&mut self, children: &'a [RTreeNode<T>]) {
+    fn extend_heap(&mut self, children: &'a [C]) {
         let &mut NearestNeighborDistanceIterator {
             ref mut nodes,
             ref query_point,
+            ref metric,
+            ref func,
         } = self;
-        nodes.extend(children.iter().map(|child| {
-            let distance = match child {
-                RTreeNode::Parent(ref data) => data.envelope.distance_2(query_point),
-                RTreeNode::Leaf(ref t) => t.distance_2(query_point),
+        nodes.extend(children.iter().filter_map(|child| {
+            let distance = match child.unpack() {
+                UnpackedChild::Parent(envelope, _) => {
+                    if !func.should_unpack_parent(envelope) {
+                        return None;
+                    }
+                    metric.envelope_distance(envelope, query_point)
+                }
+                UnpackedChild::Leaf(t) => {
+                    if !func.should_unpack_leaf(t) {
+                        return None;
+                    }
+                    metric.distance(t, query_point)
+                }
             };
 
-            RTreeNodeDistanceWrapper {
+            Some(RTreeNodeDistanceWrapper {
                 node: child,
                 distance,
-            }
+            })
         }));
     }
 }
 
-impl<'a, T> Iterator for NearestNeighborDistanceIterator<'a, T>
+impl<'a, T, M, Func, C> Iterator for NearestNeighborDistanceIterator<'a, T, M, Func, C>
 where
-    T: PointDistance,
+    T: RTreeObject,
+    M: Metric<T>,
+    Func: SelectionFunction<T>,
+    C: TreeChild<T>,
 {
     type Item = (&'a T, <<T::Envelope as Envelope>::Point as Point>::Scalar);
 
     fn next(&mut self) -> Option<Self::Item> {
         while let Some(current) = self.nodes.pop() {
-            match current {
-                RTreeNodeDistanceWrapper {
-                    node: RTreeNode::Parent(ref data),
-                    ..
-                } => {
-                    self.extend_heap(&data.children);
-                }
-                RTreeNodeDistanceWrapper {
-                    node: RTreeNode::Leaf(ref t),
-                    distance,
-                } => {
-                    return Some((t, distance));
-                }
+            match current.node.unpack() {
+                UnpackedChild::Parent(_, children) => self.extend_heap(children),
+                UnpackedChild::Leaf(t) => return Some((t, current.distance)),
             }
         }
         None
     }
 }
 
-pub struct NearestNeighborDistanceIterator<'a, T>
-where
-    T: PointDistance + 'a,
+pub struct NearestNeighborDistanceIterator<
+    'a,
+    T,
+    M = SquaredEuclidean,
+    Func = SelectAllFunc,
+    C = RTreeNode<T>,
+> where
+    T: RTreeObject + 'a,
 {
-    nodes: BinaryHeap<RTreeNodeDistanceWrapper<'a, T>>,
+    nodes: BinaryHeap<RTreeNodeDistanceWrapper<'a, T, C>>,
     query_point: <T::Envelope as Envelope>::Point,
+    metric: M,
+    func: Func,
+}
+
+impl<'a, T, M, Func, C> Clone for NearestNeighborDistanceIterator<'a, T, M, Func, C>
+where
+    T: RTreeObject,
+    M: Clone,
+    Func: Clone,
+{
+    fn clone(&self) -> Self {
+        NearestNeighborDistanceIterator {
+            nodes: self.nodes.clone(),
+            query_point: self.query_point,
+            metric: self.metric.clone(),
+            func: self.func.clone(),
+        }
+    }
 }
 
 impl<'a, T> NearestNeighborIterator<'a, T>
@@ -115,7 +200,7 @@ where
 {
     pub fn new(root: &'a ParentNode<T>, query_point: <T::Envelope as Envelope>::Point) -> Self {
         NearestNeighborIterator {
-            iter: NearestNeighborDistanceIterator::new(root, query_point),
+            iter: NearestNeighborDistanceIterator::new(root, query_point, SquaredEuclidean),
         }
     }
 }
@@ -138,10 +223,185 @@ where
     iter: NearestNeighborDistanceIterator<'a, T>,
 }
 
+impl<'a, T> Clone for NearestNeighborIterator<'a, T>
+where
+    T: PointDistance,
+{
+    fn clone(&self) -> Self {
+        NearestNeighborIterator {
+            iter: self.iter.clone(),
+        }
+    }
+}
+
 pub fn nearest_neighbor<'a, T>(
     node: &'a ParentNode<T>,
     query_point: <T::Envelope as Envelope>::Point,
+    max_distance_2: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+) -> Option<&'a T>
+where
+    T: PointDistance,
+{
+    nearest_neighbor_with_stats(
+        node,
+        query_point,
+        max_distance_2,
+        &mut QueryStats::default(),
+    )
+}
+
+/// Looks up the nearest neighbor and counts the nodes and elements it examines.
+pub fn nearest_neighbor_with_stats<'a, T>(
+    node: &'a ParentNode<T>,
+    query_point: <T::Envelope as Envelope>::Point,
+    max_distance_2: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+    stats: &mut QueryStats,
 ) -> Option<&'a T>
+where
+    T: PointDistance,
+{
+    let mut nodes = BinaryHeap::with_capacity(20);
+    nearest_neighbor_with_heap(&mut nodes, node, query_point, max_distance_2, stats)
+        .map(|(nearest, _)| nearest)
+}
+
+/// Looks up the nearest neighbor of each query point, reusing the same heap for all queries.
+pub fn nearest_neighbor_for_each<'a, T, F>(
+    node: &'a ParentNode<T>,
+    query_points: &[<T::Envelope as Envelope>::Point],
+    mut f: F,
+) where
+    T: PointDistance,
+    F: FnMut(Option<&'a T>),
+{
+    let mut nodes = BinaryHeap::with_capacity(20);
+    for query_point in query_points {
+        f(
+            nearest_neighbor_with_fallback(&mut nodes, node, *query_point)
+                .map(|(nearest, _)| nearest),
+        );
+    }
+}
+
+/// Looks up the distance to the nearest neighbor of query points, reusing the same heap for all
+/// queries.
+pub struct NearestDistanceSearch<'a, T>
+where
+    T: RTreeObject + 'a,
+{
+    nodes: BinaryHeap<RTreeNodeDistanceWrapper<'a, T>>,
+}
+
+impl<'a, T> NearestDistanceSearch<'a, T>
+where
+    T: PointDistance,
+{
+    pub fn new() -> Self {
+        NearestDistanceSearch {
+            nodes: BinaryHeap::with_capacity(20),
+        }
+    }
+
+    /// Returns the squared distance of a point to its nearest neighbor below `root`.
+    pub fn distance_2(
+        &mut self,
+        root: &'a ParentNode<T>,
+        query_point: <T::Envelope as Envelope>::Point,
+    ) -> Option<<<T::Envelope as Envelope>::Point as Point>::Scalar> {
+        nearest_neighbor_with_fallback(&mut self.nodes, root, query_point)
+            .map(|(_, distance_2)| distance_2)
+    }
+}
+
+/// Looks up the nearest neighbor and its squared distance, reusing the given heap.
+///
+/// The search may in rare cases return `None` due to rounding issues, the nearest neighbor
+/// iterator is used as fallback then.
+fn nearest_neighbor_with_fallback<'a, T>(
+    nodes: &mut BinaryHeap<RTreeNodeDistanceWrapper<'a, T>>,
+    node: &'a ParentNode<T>,
+    query_point: <T::Envelope as Envelope>::Point,
+) -> Option<(&'a T, <<T::Envelope as Envelope>::Point as Point>::Scalar)>
+where
+    T: PointDistance,
+{
+    nearest_neighbor_with_heap(
+        nodes,
+        node,
+        query_point,
+        Bounded::max_value(),
+        &mut QueryStats::default(),
+    )
+    .or_else(|| NearestNeighborDistanceIterator::new(node, query_point, SquaredEuclidean).next())
+}
+
+/// Looks up the `k` nearest neighbors of query points, reusing the same heap for all queries.
+#[cfg(feature = "rayon")]
+pub struct KNearestNeighborSearch<'a, T>
+where
+    T: RTreeObject + 'a,
+{
+    nodes: BinaryHeap<RTreeNodeDistanceWrapper<'a, T>>,
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T> KNearestNeighborSearch<'a, T>
+where
+    T: PointDistance,
+{
+    pub fn new() -> Self {
+        KNearestNeighborSearch {
+            nodes: BinaryHeap::with_capacity(20),
+        }
+    }
+
+    /// Returns the `k` elements closest to a point, the closest element first.
+    ///
+    /// `size` is the number of elements below `root`.
+    pub fn search(
+        &mut self,
+        root: &'a ParentNode<T>,
+        size: usize,
+        query_point: <T::Envelope as Envelope>::Point,
+        k: usize,
+    ) -> Vec<&'a T> {
+        let nodes = &mut self.nodes;
+        let mut result = Vec::with_capacity(k.min(size));
+        nodes.clear();
+        let push_children = |nodes: &mut BinaryHeap<_>, children: &'a [RTreeNode<T>]| {
+            nodes.extend(children.iter().map(|child| RTreeNodeDistanceWrapper {
+                node: child,
+                distance: match child {
+                    RTreeNode::Parent(ref data) => data.envelope.distance_2(&query_point),
+                    RTreeNode::Leaf(ref t) => t.distance_2(&query_point),
+                },
+            }));
+        };
+        if k > 0 {
+            push_children(nodes, &root.children);
+        }
+        while let Some(current) = nodes.pop() {
+            match current.node {
+                RTreeNode::Parent(ref data) => push_children(nodes, &data.children),
+                RTreeNode::Leaf(ref t) => {
+                    result.push(t);
+                    if result.len() == k {
+                        break;
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+fn nearest_neighbor_with_heap<'a, T>(
+    nodes: &mut BinaryHeap<RTreeNodeDistanceWrapper<'a, T>>,
+    node: &'a ParentNode<T>,
+    query_point: <T::Envelope as Envelope>::Point,
+    max_distance_2: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+    stats: &mut QueryStats,
+) -> Option<(&'a T, <<T::Envelope as Envelope>::Point as Point>::Scalar)>
 where
     T: PointDistance,
 {
@@ -150,12 +410,15 @@ where
         node: &'a ParentNode<T>,
         query_point: <T::Envelope as Envelope>::Point,
         min_max_distance: &mut <<T::Envelope as Envelope>::Point as Point>::Scalar,
+        stats: &mut QueryStats,
     ) where
         T: PointDistance + 'a,
     {
+        stats.nodes_visited += 1;
         for child in &node.children {
             let distance_if_less_or_equal = match child {
                 RTreeNode::Parent(ref data) => {
+                    stats.envelopes_tested += 1;
                     let distance = data.envelope.distance_2(&query_point);
                     if distance <= *min_max_distance {
                         Some(distance)
@@ -164,6 +427,7 @@ where
                     }
                 }
                 RTreeNode::Leaf(ref t) => {
+                    stats.leaves_scanned += 1;
                     t.distance_2_if_less_or_equal(&query_point, *min_max_distance)
                 }
             };
@@ -180,30 +444,158 @@ where
         }
     }
 
-    // Calculate smallest minmax-distance
-    let mut smallest_min_max: <<T::Envelope as Envelope>::Point as Point>::Scalar =
-        Bounded::max_value();
-    let mut nodes = BinaryHeap::with_capacity(20);
-    extend_heap(&mut nodes, node, query_point, &mut smallest_min_max);
+    // Calculate smallest minmax-distance, no element beyond the given maximum is accepted
+    let mut smallest_min_max = max_distance_2;
+    nodes.clear();
+    extend_heap(nodes, node, query_point, &mut smallest_min_max, stats);
     while let Some(current) = nodes.pop() {
         match current {
             RTreeNodeDistanceWrapper {
                 node: RTreeNode::Parent(ref data),
                 ..
             } => {
-                extend_heap(&mut nodes, data, query_point, &mut smallest_min_max);
+                extend_heap(nodes, data, query_point, &mut smallest_min_max, stats);
             }
             RTreeNodeDistanceWrapper {
                 node: RTreeNode::Leaf(ref t),
-                ..
+                distance,
             } => {
-                return Some(t);
+                return Some((t, distance));
             }
         }
     }
     None
 }
 
+pub fn nearest_neighbor_approx<T>(
+    node: &ParentNode<T>,
+    query_point: <T::Envelope as Envelope>::Point,
+    epsilon: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+) -> Option<&T>
+where
+    T: PointDistance,
+{
+    fn extend_heap<'a, T>(
+        nodes: &mut BinaryHeap<RTreeNodeDistanceWrapper<'a, T>>,
+        node: &'a ParentNode<T>,
+        query_point: <T::Envelope as Envelope>::Point,
+        factor: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+        best: &mut Option<(&'a T, <<T::Envelope as Envelope>::Point as Point>::Scalar)>,
+    ) where
+        T: PointDistance + 'a,
+    {
+        for child in &node.children {
+            match child {
+                RTreeNode::Parent(ref data) => {
+                    let distance = data.envelope.distance_2(&query_point);
+                    let is_relevant = match best {
+                        Some((_, best_distance)) => distance * factor < *best_distance,
+                        None => true,
+                    };
+                    if is_relevant {
+                        nodes.push(RTreeNodeDistanceWrapper {
+                            node: child,
+                            distance,
+                        });
+                    }
+                }
+                RTreeNode::Leaf(ref t) => {
+                    let distance = t.distance_2(&query_point);
+                    let is_better = match best {
+                        Some((_, best_distance)) => distance < *best_distance,
+                        None => true,
+                    };
+                    if is_better {
+                        *best = Some((t, distance));
+                    }
+                }
+            }
+        }
+    }
+
+    // All distances are squared, so is the allowed error factor
+    let one: <<T::Envelope as Envelope>::Point as Point>::Scalar = One::one();
+    let factor = (one + epsilon) * (one + epsilon);
+    let mut best = None;
+    let mut nodes = BinaryHeap::with_capacity(20);
+    extend_heap(&mut nodes, node, query_point, factor, &mut best);
+    while let Some(current) = nodes.pop() {
+        if let Some((_, best_distance)) = best {
+            // No remaining node can contain an element that is closer by more than the allowed factor
+            if best_distance <= current.distance * factor {
+                break;
+            }
+        }
+        if let RTreeNode::Parent(ref data) = current.node {
+            extend_heap(&mut nodes, data, query_point, factor, &mut best);
+        }
+    }
+    best.map(|(t, _distance)| t)
+}
+
+pub fn reverse_nearest_neighbors<P>(node: &ParentNode<P>, query_point: P) -> Vec<&P>
+where
+    P: Point,
+{
+    // Checks if every point of the envelope is strictly closer to one of the visited points than
+    // to the query point. Such points cannot have the query point as their nearest neighbor.
+    fn is_dominated<P: Point>(envelope: &AABB<P>, visited: &[P], query_point: &P) -> bool {
+        let zero = Zero::zero();
+        visited.iter().any(|p| {
+            // d(x, p)^2 - d(x, query_point)^2 is linear in x, it becomes maximal at the envelope
+            // corner lying furthest in the direction from p towards the query point.
+            let direction = query_point.sub(p);
+            let corner = P::generate(|index| {
+                if direction.nth(index) > zero {
+                    envelope.upper().nth(index)
+                } else {
+                    envelope.lower().nth(index)
+                }
+            });
+            corner.sub(p).length_2() < corner.sub(query_point).length_2()
+        })
+    }
+
+    fn push_children<'a, P: Point>(
+        nodes: &mut BinaryHeap<RTreeNodeDistanceWrapper<'a, P>>,
+        data: &'a ParentNode<P>,
+        query_point: &P,
+    ) {
+        for child in &data.children {
+            nodes.push(RTreeNodeDistanceWrapper {
+                node: child,
+                distance: child.envelope().distance_2(query_point),
+            });
+        }
+    }
+
+    let mut nodes = BinaryHeap::with_capacity(20);
+    push_children(&mut nodes, node, &query_point);
+
+    let mut visited = Vec::new();
+    let mut result = Vec::new();
+    while let Some(current) = nodes.pop() {
+        if is_dominated(&current.node.envelope(), &visited, &query_point) {
+            continue;
+        }
+        match current.node {
+            RTreeNode::Parent(ref data) => push_children(&mut nodes, data, &query_point),
+            RTreeNode::Leaf(ref point) => {
+                visited.push(*point);
+                // The pruning is not exhaustive, the candidate's own nearest neighbor decides
+                let has_closer_neighbor =
+                    NearestNeighborDistanceIterator::new(node, *point, SquaredEuclidean)
+                        .take_while(|(_, distance)| *distance < current.distance)
+                        .any(|(other, _)| !::std::ptr::eq(other, point));
+                if !has_closer_neighbor {
+                    result.push(point);
+                }
+            }
+        }
+    }
+    result
+}
+
 #[cfg(test)]
 mod test {
     use crate::object::PointDistance;
@@ -237,6 +629,265 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_nearest_neighbor_join_into() {
+        use crate::primitives::PointWithData;
+
+        let points = create_random_points(1000, SEED_1);
+        let tree = RTree::bulk_load(
+            points
+                .iter()
+                .enumerate()
+                .map(|(index, point)| PointWithData::new(index, *point))
+                .collect(),
+        );
+        let queries = create_random_points(100, SEED_2);
+        let mut out = vec![None; 3];
+        tree.nearest_neighbor_join_into(&queries, &mut out);
+        assert_eq!(out.len(), queries.len());
+        for (query_point, index) in queries.iter().zip(&out) {
+            let expected = tree.nearest_neighbor(query_point).map(|p| p.data);
+            assert_eq!(*index, expected);
+            assert_eq!(
+                points[index.unwrap()].distance_2(query_point),
+                tree.distance_2_to_nearest(query_point).unwrap()
+            );
+        }
+
+        let empty = RTree::<PointWithData<usize, [f64; 2]>>::new();
+        empty.nearest_neighbor_join_into(&queries, &mut out);
+        assert!(out.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_nearest_neighbors() {
+        let points = create_random_integers::<[i32; 2]>(1000, SEED_1)
+            .into_iter()
+            .map(|p| [p[0] / 10_000, p[1] / 10_000])
+            .collect::<Vec<_>>();
+        let tree = RTree::bulk_load(points.clone());
+        assert!(RTree::<[i32; 2]>::new()
+            .nearest_neighbors(&[0, 0])
+            .is_empty());
+
+        for sample_point in &create_random_integers::<[i32; 2]>(100, SEED_2) {
+            let sample_point = [sample_point[0] / 10_000, sample_point[1] / 10_000];
+            let min_distance = points
+                .iter()
+                .map(|p| p.distance_2(&sample_point))
+                .min()
+                .unwrap();
+            let mut expected: Vec<_> = points
+                .iter()
+                .filter(|p| p.distance_2(&sample_point) == min_distance)
+                .collect();
+            let mut nearest = tree.nearest_neighbors(&sample_point);
+            expected.sort();
+            nearest.sort();
+            assert_eq!(nearest, expected);
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_nearest_neighbors_batch() {
+        let points = create_random_points(1000, SEED_1);
+        let tree = RTree::bulk_load(points);
+        let queries = create_random_points(200, SEED_2);
+        for k in [0, 1, 10, 2000, usize::MAX] {
+            let result = tree.nearest_neighbors_batch(&queries, k);
+            assert_eq!(result.len(), queries.len());
+            for (query, nearest) in queries.iter().zip(result) {
+                let distances: Vec<_> = nearest.iter().map(|p| p.distance_2(query)).collect();
+                let expected: Vec<_> = tree
+                    .nearest_neighbor_iter(query)
+                    .take(k)
+                    .map(|p| p.distance_2(query))
+                    .collect();
+                assert_eq!(distances, expected);
+            }
+        }
+        let empty: RTree<[f64; 2]> = RTree::new();
+        let result = empty.nearest_neighbors_batch(&queries[..2], 3);
+        assert!(result.iter().all(|nearest| nearest.is_empty()));
+    }
+
+    #[test]
+    fn test_reverse_nearest_neighbors() {
+        let points = create_random_integers::<[i32; 2]>(1000, SEED_1)
+            .into_iter()
+            .map(|p| [p[0] / 1000, p[1] / 1000])
+            .collect::<Vec<_>>();
+        let tree = RTree::bulk_load(points.clone());
+        assert!(RTree::<[i32; 2]>::new()
+            .reverse_nearest_neighbors(&[0, 0])
+            .is_empty());
+
+        for sample_point in &create_random_integers::<[i32; 2]>(100, SEED_2) {
+            let sample_point = [sample_point[0] / 1000, sample_point[1] / 1000];
+            let mut expected: Vec<_> = points
+                .iter()
+                .enumerate()
+                .filter(|(index, p)| {
+                    let distance = p.distance_2(&sample_point);
+                    points
+                        .iter()
+                        .enumerate()
+                        .all(|(other, q)| other == *index || q.distance_2(p) >= distance)
+                })
+                .map(|(_, p)| p)
+                .collect();
+            let mut reverse_nearest = tree.reverse_nearest_neighbors(&sample_point);
+            expected.sort();
+            reverse_nearest.sort();
+            assert_eq!(reverse_nearest, expected);
+        }
+    }
+
+    #[test]
+    fn test_distances_2_to_nearest() {
+        let points = create_random_points(1000, SEED_1);
+        let tree = RTree::bulk_load(points.clone());
+        let sample_points = create_random_points(100, SEED_2);
+
+        let distances = tree.distances_2_to_nearest(&sample_points);
+        assert_eq!(distances.len(), sample_points.len());
+        for (sample_point, distance) in sample_points.iter().zip(distances) {
+            let expected = points
+                .iter()
+                .map(|p| p.distance_2(sample_point))
+                .fold(f64::INFINITY, f64::min);
+            assert_eq!(distance, Some(expected));
+        }
+        let empty: RTree<[f64; 2]> = RTree::new();
+        assert_eq!(
+            empty.distances_2_to_nearest(&sample_points[..1]),
+            vec![None]
+        );
+        assert!(tree.distances_2_to_nearest(&[]).is_empty());
+        #[cfg(feature = "rayon")]
+        assert_eq!(
+            tree.par_distances_2_to_nearest(&sample_points),
+            tree.distances_2_to_nearest(&sample_points)
+        );
+
+        let rectangles = RTree::bulk_load(create_random_rectangles(200, SEED_1));
+        for sample_point in &sample_points {
+            let nearest = rectangles.nearest_neighbor(sample_point).unwrap();
+            assert_eq!(
+                rectangles.distance_2_to_nearest(sample_point),
+                Some(nearest.distance_2(sample_point))
+            );
+        }
+
+        let integers = RTree::bulk_load(vec![[0, 0], [10, 10]]);
+        let grid: Vec<_> = (0..12).map(|x| [x, x]).collect();
+        let expected: Vec<_> = grid
+            .iter()
+            .map(|point| integers.distance_2_to_nearest(point))
+            .collect();
+        assert_eq!(integers.distances_2_to_nearest(&grid), expected);
+        #[cfg(feature = "rayon")]
+        assert_eq!(integers.par_distances_2_to_nearest(&grid), expected);
+    }
+
+    #[test]
+    fn test_nearest_neighbor_within() {
+        let points = create_random_points(1000, SEED_1);
+        let tree = RTree::bulk_load(points.clone());
+
+        let sample_points = create_random_points(100, SEED_2);
+        let max_distance_2 = 0.02 * 0.02;
+        let mut found_count = 0;
+        for sample_point in &sample_points {
+            let nearest = tree.nearest_neighbor(sample_point).unwrap();
+            let within = tree.nearest_neighbor_within(sample_point, max_distance_2);
+            if nearest.distance_2(sample_point) <= max_distance_2 {
+                assert_eq!(within, Some(nearest));
+                found_count += 1;
+            } else {
+                assert_eq!(within, None);
+            }
+        }
+        // Make sure that both cases are tested
+        assert!(0 < found_count && found_count < sample_points.len());
+    }
+
+    #[test]
+    fn test_nearest_neighbor_where() {
+        let points = create_random_points(1000, SEED_1);
+        let tree = RTree::bulk_load(points.clone());
+
+        let sample_points = create_random_points(100, SEED_2);
+        let predicate = |p: &[f64; 2]| p[0] > 0.5;
+        for sample_point in &sample_points {
+            let mut nearest = None;
+            let mut closest_dist = f64::INFINITY;
+            for point in points.iter().filter(|p| predicate(p)) {
+                let new_dist = point.distance_2(sample_point);
+                if new_dist < closest_dist {
+                    closest_dist = new_dist;
+                    nearest = Some(point);
+                }
+            }
+            assert_eq!(
+                nearest,
+                tree.nearest_neighbor_where(sample_point, predicate)
+            );
+        }
+        assert!(tree
+            .nearest_neighbor_where(&[0.0, 0.0], |_| false)
+            .is_none());
+    }
+
+    #[test]
+    fn test_locate_in_envelope_ordered() {
+        use crate::{Envelope, AABB};
+
+        let points = create_random_points(1000, SEED_1);
+        let tree = RTree::bulk_load(points.clone());
+        let envelope = AABB::from_corners([0.25, 0.5], [0.75, 1.0]);
+
+        let sample_points = create_random_points(20, SEED_2);
+        for sample_point in &sample_points {
+            let mut expected: Vec<_> = points
+                .iter()
+                .filter(|p| envelope.contains_point(p))
+                .collect();
+            expected.sort_by(|r, l| {
+                r.distance_2(sample_point)
+                    .partial_cmp(&l.distance_2(sample_point))
+                    .unwrap()
+            });
+            let located: Vec<_> = tree
+                .locate_in_envelope_ordered(&envelope, sample_point)
+                .collect();
+            assert_eq!(located, expected);
+        }
+    }
+
+    #[test]
+    fn test_nearest_neighbor_approx() {
+        let points = create_random_points(1000, SEED_1);
+        let tree = RTree::bulk_load(points.clone());
+
+        let sample_points = create_random_points(100, SEED_2);
+        for sample_point in &sample_points {
+            let nearest = tree.nearest_neighbor(sample_point).unwrap();
+            let exact = tree.nearest_neighbor_approx(sample_point, 0.0).unwrap();
+            assert_eq!(
+                exact.distance_2(sample_point),
+                nearest.distance_2(sample_point)
+            );
+
+            let epsilon = 0.5;
+            let approximate = tree.nearest_neighbor_approx(sample_point, epsilon).unwrap();
+            let max_distance_2 =
+                nearest.distance_2(sample_point) * (1.0 + epsilon) * (1.0 + epsilon);
+            assert!(approximate.distance_2(sample_point) <= max_distance_2);
+        }
+    }
+
     #[test]
     fn test_nearest_neighbor_iterator() {
         let mut points = create_random_points(1000, SEED_1);
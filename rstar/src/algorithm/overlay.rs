@@ -0,0 +1,203 @@
+use crate::aabb::AABB;
+use crate::envelope::Envelope;
+use crate::node::{ParentNode, RTreeNode};
+use crate::object::RTreeObject;
+use crate::point::Point;
+
+/// Returns regions that are covered by the envelopes of `node`'s elements but not by any
+/// envelope of `other`'s elements.
+///
+/// Both trees are descended simultaneously, only nodes of `other` that intersect the
+/// current node of the first tree are kept as candidates. The envelope of each element is
+/// then cut into boxes that avoid all intersecting candidate envelopes.
+pub fn difference<T, U, P>(node: &ParentNode<T>, other: &ParentNode<U>) -> Vec<AABB<P>>
+where
+    T: RTreeObject<Envelope = AABB<P>>,
+    U: RTreeObject<Envelope = AABB<P>>,
+    P: Point,
+{
+    let mut result = Vec::new();
+    let candidates: Vec<_> = other.children.iter().collect();
+    difference_recursive(node, &candidates, &mut result);
+    result
+}
+
+fn difference_recursive<T, U, P>(
+    node: &ParentNode<T>,
+    candidates: &[&RTreeNode<U>],
+    result: &mut Vec<AABB<P>>,
+) where
+    T: RTreeObject<Envelope = AABB<P>>,
+    U: RTreeObject<Envelope = AABB<P>>,
+    P: Point,
+{
+    // Unpack one level of the other tree for each level of this tree
+    let mut close_candidates = Vec::new();
+    for candidate in candidates {
+        match candidate {
+            RTreeNode::Leaf(_) => {
+                if node.envelope.intersects(&candidate.envelope()) {
+                    close_candidates.push(*candidate);
+                }
+            }
+            RTreeNode::Parent(ref data) => {
+                if node.envelope.intersects(&data.envelope) {
+                    close_candidates.extend(
+                        data.children
+                            .iter()
+                            .filter(|child| node.envelope.intersects(&child.envelope())),
+                    );
+                }
+            }
+        }
+    }
+
+    for child in &node.children {
+        match child {
+            RTreeNode::Leaf(ref t) => {
+                let mut remaining = vec![t.envelope()];
+                for candidate in &close_candidates {
+                    subtract_node(candidate, &mut remaining);
+                    if remaining.is_empty() {
+                        break;
+                    }
+                }
+                result.extend(remaining);
+            }
+            RTreeNode::Parent(ref data) => {
+                difference_recursive(data, &close_candidates, result);
+            }
+        }
+    }
+}
+
+/// Removes the envelopes of all elements below `node` from a set of boxes.
+fn subtract_node<U, P>(node: &RTreeNode<U>, boxes: &mut Vec<AABB<P>>)
+where
+    U: RTreeObject<Envelope = AABB<P>>,
+    P: Point,
+{
+    let envelope = node.envelope();
+    if !boxes.iter().any(|b| b.intersects(&envelope)) {
+        return;
+    }
+    match node {
+        RTreeNode::Leaf(_) => {
+            let mut pieces = Vec::with_capacity(boxes.len());
+            for b in boxes.drain(..) {
+                subtract(&b, &envelope, &mut pieces);
+            }
+            *boxes = pieces;
+        }
+        RTreeNode::Parent(ref data) => {
+            for child in &data.children {
+                subtract_node(child, boxes);
+            }
+        }
+    }
+}
+
+/// Cuts `from` into at most `2 * P::DIMENSIONS` disjoint boxes that cover `from` except
+/// for its intersection with `other`.
+fn subtract<P>(from: &AABB<P>, other: &AABB<P>, result: &mut Vec<AABB<P>>)
+where
+    P: Point,
+{
+    if !from.intersects(other) {
+        result.push(*from);
+        return;
+    }
+    let mut lower = from.lower();
+    let mut upper = from.upper();
+    for axis in 0..P::DIMENSIONS {
+        if lower.nth(axis) < other.lower().nth(axis) {
+            // Slab below other
+            let mut slab_upper = upper;
+            *slab_upper.nth_mut(axis) = other.lower().nth(axis);
+            result.push(AABB::from_corners(lower, slab_upper));
+            *lower.nth_mut(axis) = other.lower().nth(axis);
+        }
+        if upper.nth(axis) > other.upper().nth(axis) {
+            // Slab above other
+            let mut slab_lower = lower;
+            *slab_lower.nth_mut(axis) = other.upper().nth(axis);
+            result.push(AABB::from_corners(slab_lower, upper));
+            *upper.nth_mut(axis) = other.upper().nth(axis);
+        }
+    }
+    // The remaining box [lower, upper] is covered by other
+}
+
+#[cfg(test)]
+mod test {
+    use super::subtract;
+    use crate::primitives::Rectangle;
+    use crate::test_utilities::{create_random_points, create_random_rectangles, SEED_1, SEED_2};
+    use crate::{Envelope, RTree, RTreeObject, AABB};
+
+    #[test]
+    fn test_subtract() {
+        let from = AABB::from_corners([0.0, 0.0], [4.0, 4.0]);
+        let mut pieces = Vec::new();
+        subtract(
+            &from,
+            &AABB::from_corners([1.0, 1.0], [2.0, 5.0]),
+            &mut pieces,
+        );
+        assert_eq!(
+            pieces,
+            vec![
+                AABB::from_corners([0.0, 0.0], [1.0, 4.0]),
+                AABB::from_corners([2.0, 0.0], [4.0, 4.0]),
+                AABB::from_corners([1.0, 0.0], [2.0, 1.0]),
+            ]
+        );
+        let area: f64 = pieces.iter().map(|piece| piece.area()).sum();
+        assert_eq!(area, 16.0 - 3.0);
+    }
+
+    #[test]
+    fn test_overlay_difference() {
+        let rectangles = create_random_rectangles(100, SEED_1);
+        let others = create_random_rectangles(100, SEED_2);
+        let tree = RTree::bulk_load(rectangles.clone());
+        let other_tree = RTree::bulk_load(others.clone());
+        let regions = tree.overlay_difference(&other_tree);
+
+        for region in &regions {
+            assert!(rectangles
+                .iter()
+                .any(|r| r.envelope().contains_envelope(region)));
+            // Regions may only touch the removed rectangles
+            assert!(others
+                .iter()
+                .all(|r| r.envelope().intersection_area(region) <= 1e-12));
+        }
+        let untouched: Vec<Rectangle<_>> = rectangles
+            .iter()
+            .filter(|r| {
+                !others
+                    .iter()
+                    .any(|o| o.envelope().intersects(&r.envelope()))
+            })
+            .cloned()
+            .collect();
+        for r in &untouched {
+            assert!(regions.contains(&r.envelope()));
+        }
+
+        // Sample points inside and outside of the difference
+        for point in create_random_points(500, SEED_1) {
+            let is_in_difference = rectangles
+                .iter()
+                .any(|r| r.envelope().contains_point(&point))
+                && !others.iter().any(|r| r.envelope().contains_point(&point));
+            let is_in_region = regions.iter().any(|region| region.contains_point(&point));
+            assert_eq!(is_in_region, is_in_difference);
+        }
+
+        assert!(tree.overlay_difference(&tree).is_empty());
+        let empty: RTree<Rectangle<[f64; 2]>> = RTree::new();
+        assert_eq!(tree.overlay_difference(&empty).len(), rectangles.len());
+    }
+}
@@ -0,0 +1,345 @@
+use crate::algorithm::selection_functions::*;
+use crate::node::{ParentNode, RTreeNode};
+use crate::object::RTreeObject;
+use rayon::iter::plumbing::{bridge_unindexed, Folder, UnindexedConsumer, UnindexedProducer};
+use rayon::iter::ParallelIterator;
+
+pub type ParallelRTreeIterator<'a, T> = ParallelSelectionIterator<'a, T, SelectAllFunc>;
+pub type ParallelLocateInEnvelope<'a, T> =
+    ParallelSelectionIterator<'a, T, SelectInEnvelopeFunction<T>>;
+pub type ParallelLocateInEnvelopeIntersecting<'a, T> =
+    ParallelSelectionIterator<'a, T, SelectInEnvelopeFuncIntersecting<T>>;
+
+/// Splits nodes until a producer only contains a single element or a pruned subtree.
+///
+/// A single parent node is replaced by its children, everything else is split in half.
+/// Returns `None` if `nodes` cannot be split further.
+fn split_nodes<'a, T, Func>(
+    mut nodes: &'a [RTreeNode<T>],
+    func: &Func,
+) -> (&'a [RTreeNode<T>], Option<&'a [RTreeNode<T>]>)
+where
+    T: RTreeObject,
+    Func: SelectionFunction<T>,
+{
+    while let [RTreeNode::Parent(ref data)] = nodes {
+        if !func.should_unpack_parent(&data.envelope) {
+            return (&[], None);
+        }
+        nodes = &data.children;
+    }
+    if nodes.len() < 2 {
+        return (nodes, None);
+    }
+    let (left, right) = nodes.split_at(nodes.len() / 2);
+    (left, Some(right))
+}
+
+fn fold_nodes<'a, T, Func, F>(nodes: &'a [RTreeNode<T>], func: &Func, mut folder: F) -> F
+where
+    T: RTreeObject,
+    Func: SelectionFunction<T>,
+    F: Folder<&'a T>,
+{
+    for node in nodes {
+        if folder.full() {
+            break;
+        }
+        match node {
+            RTreeNode::Leaf(ref t) => {
+                if func.should_unpack_leaf(t) {
+                    folder = folder.consume(t);
+                }
+            }
+            RTreeNode::Parent(ref data) => {
+                if func.should_unpack_parent(&data.envelope) {
+                    folder = fold_nodes(&data.children, func, folder);
+                }
+            }
+        }
+    }
+    folder
+}
+
+/// A parallel iterator over all elements selected by a selection function.
+pub struct ParallelSelectionIterator<'a, T, Func>
+where
+    T: RTreeObject + 'a,
+    Func: SelectionFunction<T>,
+{
+    nodes: &'a [RTreeNode<T>],
+    func: Func,
+}
+
+impl<'a, T, Func> ParallelSelectionIterator<'a, T, Func>
+where
+    T: RTreeObject,
+    Func: SelectionFunction<T>,
+{
+    pub(crate) fn new(root: &'a ParentNode<T>, func: Func) -> Self {
+        ParallelSelectionIterator {
+            nodes: &root.children,
+            func,
+        }
+    }
+}
+
+impl<'a, T, Func> ParallelIterator for ParallelSelectionIterator<'a, T, Func>
+where
+    T: RTreeObject + Sync,
+    T::Envelope: Sync,
+    Func: SelectionFunction<T> + Clone + Send,
+{
+    type Item = &'a T;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        bridge_unindexed(self, consumer)
+    }
+}
+
+impl<'a, T, Func> UnindexedProducer for ParallelSelectionIterator<'a, T, Func>
+where
+    T: RTreeObject + Sync,
+    T::Envelope: Sync,
+    Func: SelectionFunction<T> + Clone + Send,
+{
+    type Item = &'a T;
+
+    fn split(self) -> (Self, Option<Self>) {
+        let (left, right) = split_nodes(self.nodes, &self.func);
+        let right = right.map(|nodes| ParallelSelectionIterator {
+            nodes,
+            func: self.func.clone(),
+        });
+        let left = ParallelSelectionIterator {
+            nodes: left,
+            func: self.func,
+        };
+        (left, right)
+    }
+
+    fn fold_with<F>(self, folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        fold_nodes(self.nodes, &self.func, folder)
+    }
+}
+
+/// A parallel iterator over all mutable elements of a tree.
+pub struct ParallelRTreeIteratorMut<'a, T>
+where
+    T: RTreeObject + 'a,
+{
+    nodes: &'a mut [RTreeNode<T>],
+}
+
+impl<'a, T> ParallelRTreeIteratorMut<'a, T>
+where
+    T: RTreeObject,
+{
+    pub(crate) fn new(root: &'a mut ParentNode<T>) -> Self {
+        ParallelRTreeIteratorMut {
+            nodes: &mut root.children,
+        }
+    }
+}
+
+fn fold_nodes_mut<'a, T, F>(nodes: &'a mut [RTreeNode<T>], mut folder: F) -> F
+where
+    T: RTreeObject,
+    F: Folder<&'a mut T>,
+{
+    for node in nodes {
+        if folder.full() {
+            break;
+        }
+        match node {
+            RTreeNode::Leaf(ref mut t) => folder = folder.consume(t),
+            RTreeNode::Parent(ref mut data) => folder = fold_nodes_mut(&mut data.children, folder),
+        }
+    }
+    folder
+}
+
+impl<'a, T> ParallelIterator for ParallelRTreeIteratorMut<'a, T>
+where
+    T: RTreeObject + Send,
+    T::Envelope: Send,
+{
+    type Item = &'a mut T;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        bridge_unindexed(self, consumer)
+    }
+}
+
+impl<'a, T> UnindexedProducer for ParallelRTreeIteratorMut<'a, T>
+where
+    T: RTreeObject + Send,
+    T::Envelope: Send,
+{
+    type Item = &'a mut T;
+
+    fn split(self) -> (Self, Option<Self>) {
+        let mut nodes = self.nodes;
+        while let [RTreeNode::Parent(ref mut data)] = nodes {
+            nodes = &mut data.children;
+        }
+        if nodes.len() < 2 {
+            return (ParallelRTreeIteratorMut { nodes }, None);
+        }
+        let (left, right) = nodes.split_at_mut(nodes.len() / 2);
+        (
+            ParallelRTreeIteratorMut { nodes: left },
+            Some(ParallelRTreeIteratorMut { nodes: right }),
+        )
+    }
+
+    fn fold_with<F>(self, folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        fold_nodes_mut(self.nodes, folder)
+    }
+}
+
+/// A parallel iterator that consumes a tree and returns its elements.
+pub struct ParallelRTreeIntoIterator<T>
+where
+    T: RTreeObject,
+{
+    nodes: Vec<RTreeNode<T>>,
+}
+
+impl<T> ParallelRTreeIntoIterator<T>
+where
+    T: RTreeObject,
+{
+    pub(crate) fn new(root: ParentNode<T>) -> Self {
+        ParallelRTreeIntoIterator {
+            nodes: root.children,
+        }
+    }
+}
+
+fn fold_nodes_owned<T, F>(nodes: Vec<RTreeNode<T>>, mut folder: F) -> F
+where
+    T: RTreeObject,
+    F: Folder<T>,
+{
+    for node in nodes {
+        if folder.full() {
+            break;
+        }
+        match node {
+            RTreeNode::Leaf(t) => folder = folder.consume(t),
+            RTreeNode::Parent(data) => folder = fold_nodes_owned(data.children, folder),
+        }
+    }
+    folder
+}
+
+impl<T> ParallelIterator for ParallelRTreeIntoIterator<T>
+where
+    T: RTreeObject + Send,
+    T::Envelope: Send,
+{
+    type Item = T;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        bridge_unindexed(self, consumer)
+    }
+}
+
+impl<T> UnindexedProducer for ParallelRTreeIntoIterator<T>
+where
+    T: RTreeObject + Send,
+    T::Envelope: Send,
+{
+    type Item = T;
+
+    fn split(self) -> (Self, Option<Self>) {
+        let mut nodes = self.nodes;
+        while let [RTreeNode::Parent(_)] = nodes.as_slice() {
+            match nodes.pop() {
+                Some(RTreeNode::Parent(data)) => nodes = data.children,
+                _ => unreachable!(),
+            }
+        }
+        if nodes.len() < 2 {
+            return (ParallelRTreeIntoIterator { nodes }, None);
+        }
+        let right = nodes.split_off(nodes.len() / 2);
+        (
+            ParallelRTreeIntoIterator { nodes },
+            Some(ParallelRTreeIntoIterator { nodes: right }),
+        )
+    }
+
+    fn fold_with<F>(self, folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        fold_nodes_owned(self.nodes, folder)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::aabb::AABB;
+    use crate::rtree::RTree;
+    use crate::test_utilities::{create_random_points, create_random_rectangles, SEED_1};
+    use rayon::prelude::*;
+
+    #[test]
+    fn test_parallel_iterators() {
+        for size in [0, 1, 7, 1000] {
+            let points = create_random_points(size, SEED_1);
+            let mut tree = RTree::bulk_load(points.clone());
+            let mut expected = points.clone();
+            expected.sort_by(|l, r| l.partial_cmp(r).unwrap());
+
+            let mut located: Vec<_> = tree.par_iter().cloned().collect();
+            located.sort_by(|l, r| l.partial_cmp(r).unwrap());
+            assert_eq!(located, expected);
+
+            tree.par_iter_mut().for_each(|point| point[0] += 0.0);
+            let mut located: Vec<_> = (&mut tree).into_par_iter().map(|p| *p).collect();
+            located.sort_by(|l, r| l.partial_cmp(r).unwrap());
+            assert_eq!(located, expected);
+
+            assert_eq!((&tree).into_par_iter().count(), size);
+            let mut located: Vec<_> = tree.into_par_iter().collect();
+            located.sort_by(|l, r| l.partial_cmp(r).unwrap());
+            assert_eq!(located, expected);
+        }
+    }
+
+    #[test]
+    fn test_parallel_locate() {
+        let rectangles = create_random_rectangles(1000, SEED_1);
+        let tree = RTree::bulk_load(rectangles);
+        let envelope = AABB::from_corners([0.2, 0.3], [0.7, 0.6]);
+        let mut expected: Vec<_> = tree.locate_in_envelope(&envelope).collect();
+        let mut located: Vec<_> = tree.par_locate_in_envelope(&envelope).collect();
+        expected.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        located.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        assert_eq!(located, expected);
+
+        let expected = tree.locate_in_envelope_intersecting(&envelope).count();
+        assert_eq!(
+            tree.par_locate_in_envelope_intersecting(&envelope).count(),
+            expected
+        );
+    }
+}
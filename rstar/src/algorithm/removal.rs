@@ -1,5 +1,6 @@
 use crate::algorithm::selection_functions::SelectionFunction;
-use crate::node::{ParentNode, RTreeNode};
+use crate::envelope::Envelope;
+use crate::node::{keep_spare_children, ParentNode, RTreeNode};
 use crate::object::RTreeObject;
 use crate::params::RTreeParams;
 
@@ -11,8 +12,15 @@ use crate::params::RTreeParams;
 /// removed. Once the first node is found, the process stops and the element is removed and
 /// returned.
 ///
-/// If a tree node becomes empty by the removal, it is also removed from its parent node.
-pub fn remove<T, Params, R>(node: &mut ParentNode<T>, removal_function: &R) -> Option<T>
+/// If a tree node underflows by the removal, i.e. it has less than `Params::MIN_SIZE` children,
+/// it is removed from its parent node. All elements contained in the removed node are pushed
+/// to `orphans` and must be reinserted by the caller.
+pub fn remove<T, Params, R>(
+    node: &mut ParentNode<T>,
+    removal_function: &R,
+    orphans: &mut Vec<T>,
+    spare_children: &mut Vec<Vec<RTreeNode<T>>>,
+) -> Option<T>
 where
     T: RTreeObject,
     Params: RTreeParams,
@@ -24,10 +32,11 @@ where
         for (index, child) in node.children.iter_mut().enumerate() {
             match child {
                 RTreeNode::Parent(ref mut data) => {
-                    result = remove::<_, Params, _>(data, removal_function);
+                    result =
+                        remove::<_, Params, _>(data, removal_function, orphans, spare_children);
                     if result.is_some() {
-                        if data.children.is_empty() {
-                            // Mark child for removal if it has become empty
+                        if data.children.len() < Params::MIN_SIZE {
+                            // Mark child for removal if it has underflown
                             removal_index = Some(index);
                         }
                         break;
@@ -45,12 +54,9 @@ where
         // Perform the actual removal outside of the self.children borrow
         if let Some(removal_index) = removal_index {
             let child = node.children.swap_remove(removal_index);
-            if result.is_none() {
-                if let RTreeNode::Leaf(t) = child {
-                    result = Some(t);
-                } else {
-                    unreachable!("This is a bug.");
-                }
+            match child {
+                RTreeNode::Leaf(t) => result = Some(t),
+                RTreeNode::Parent(data) => collect_leaves(data, orphans, spare_children),
             }
         }
     }
@@ -61,12 +67,284 @@ where
     result
 }
 
+/// The outcome of `update`.
+pub enum UpdateResult<T> {
+    /// No element was selected.
+    NotFound,
+    /// The element was updated and still fits into its parent's envelope.
+    Updated,
+    /// The updated element has left its parent's envelope and was removed from the tree.
+    /// It must be reinserted by the caller, as well as all `orphans`.
+    Escaped(T),
+}
+
+/// Applies `update` to the first element selected by a selection function.
+///
+/// The element is only removed if its new envelope is not contained in the envelope of its
+/// parent node anymore. In this case, underflown nodes are handled just like in `remove`.
+/// Otherwise, the element stays in place and only the envelopes along its path are
+/// recalculated.
+pub fn update<T, Params, R, U>(
+    node: &mut ParentNode<T>,
+    selection_function: &R,
+    update: &mut Option<U>,
+    orphans: &mut Vec<T>,
+    spare_children: &mut Vec<Vec<RTreeNode<T>>>,
+) -> UpdateResult<T>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+    R: SelectionFunction<T>,
+    U: FnOnce(&mut T),
+{
+    let mut result = UpdateResult::NotFound;
+    if selection_function.should_unpack_parent(&node.envelope) {
+        let mut removal_index = None;
+        for (index, child) in node.children.iter_mut().enumerate() {
+            match child {
+                RTreeNode::Parent(ref mut data) => {
+                    result = self::update::<_, Params, _, _>(
+                        data,
+                        selection_function,
+                        update,
+                        orphans,
+                        spare_children,
+                    );
+                    match result {
+                        UpdateResult::NotFound => continue,
+                        UpdateResult::Escaped(_) if data.children.len() < Params::MIN_SIZE => {
+                            removal_index = Some(index);
+                        }
+                        _ => {}
+                    }
+                    break;
+                }
+                RTreeNode::Leaf(ref mut t) => {
+                    if selection_function.should_unpack_leaf(t) {
+                        if let Some(update) = update.take() {
+                            update(t);
+                        }
+                        if node.envelope.contains_envelope(&t.envelope()) {
+                            result = UpdateResult::Updated;
+                        } else {
+                            removal_index = Some(index);
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+        if let Some(removal_index) = removal_index {
+            match node.children.swap_remove(removal_index) {
+                RTreeNode::Leaf(t) => result = UpdateResult::Escaped(t),
+                RTreeNode::Parent(data) => collect_leaves(data, orphans, spare_children),
+            }
+        }
+    }
+    match result {
+        UpdateResult::NotFound => {}
+        _ => node.envelope = crate::node::envelope_for_children(&node.children),
+    }
+    result
+}
+
+/// Removes all elements selected by a selection function.
+///
+/// Removed elements are pushed to `drained`. Just like `remove`, nodes that underflow are
+/// dissolved and their remaining elements are pushed to `orphans`.
+pub fn drain<T, Params, R>(
+    node: &mut ParentNode<T>,
+    selection_function: &R,
+    drained: &mut Vec<T>,
+    orphans: &mut Vec<T>,
+    spare_children: &mut Vec<Vec<RTreeNode<T>>>,
+) where
+    T: RTreeObject,
+    Params: RTreeParams,
+    R: SelectionFunction<T>,
+{
+    let drained_before = drained.len();
+    let mut index = 0;
+    while index < node.children.len() {
+        let should_remove = match node.children[index] {
+            RTreeNode::Leaf(ref t) => selection_function.should_unpack_leaf(t),
+            RTreeNode::Parent(ref mut data) => {
+                if selection_function.should_unpack_parent(&data.envelope) {
+                    drain::<_, Params, _>(
+                        data,
+                        selection_function,
+                        drained,
+                        orphans,
+                        spare_children,
+                    );
+                    data.children.len() < Params::MIN_SIZE
+                } else {
+                    false
+                }
+            }
+        };
+        if should_remove {
+            match node.children.swap_remove(index) {
+                RTreeNode::Leaf(t) => drained.push(t),
+                RTreeNode::Parent(data) => collect_leaves(data, orphans, spare_children),
+            }
+        } else {
+            index += 1;
+        }
+    }
+    if drained.len() != drained_before {
+        node.envelope = crate::node::envelope_for_children(&node.children);
+    }
+}
+
+/// Removes all elements contained in `envelope` and pushes them to `split`.
+///
+/// Subtrees that are fully contained in `envelope` are detached as they are and pushed to
+/// `subtrees` together with their height. `height` is the height of `node`, a node with leaf
+/// children has height 1. Underflown nodes are dissolved and their remaining elements are
+/// pushed to `orphans`.
+pub fn split_off_in_envelope<T, Params>(
+    node: &mut ParentNode<T>,
+    envelope: &T::Envelope,
+    height: usize,
+    split: &mut Vec<T>,
+    subtrees: &mut Vec<(ParentNode<T>, usize)>,
+    orphans: &mut Vec<T>,
+    spare_children: &mut Vec<Vec<RTreeNode<T>>>,
+) where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    let removed_before = split.len() + subtrees.len();
+    let mut index = 0;
+    while index < node.children.len() {
+        let (should_remove, is_contained) = match node.children[index] {
+            RTreeNode::Leaf(ref t) => (envelope.contains_envelope(&t.envelope()), true),
+            RTreeNode::Parent(ref mut data) => {
+                if envelope.contains_envelope(&data.envelope) {
+                    (true, true)
+                } else if envelope.intersects(&data.envelope) {
+                    split_off_in_envelope::<_, Params>(
+                        data,
+                        envelope,
+                        height - 1,
+                        split,
+                        subtrees,
+                        orphans,
+                        spare_children,
+                    );
+                    (data.children.len() < Params::MIN_SIZE, false)
+                } else {
+                    (false, false)
+                }
+            }
+        };
+        if should_remove {
+            match node.children.swap_remove(index) {
+                RTreeNode::Leaf(t) => split.push(t),
+                RTreeNode::Parent(data) if is_contained => subtrees.push((data, height - 1)),
+                RTreeNode::Parent(data) => collect_leaves(data, orphans, spare_children),
+            }
+        } else {
+            index += 1;
+        }
+    }
+    if split.len() + subtrees.len() != removed_before {
+        node.envelope = crate::node::envelope_for_children(&node.children);
+    }
+}
+
+/// Returns the number of elements below a node.
+pub fn count_leaves<T>(node: &ParentNode<T>) -> usize
+where
+    T: RTreeObject,
+{
+    node.children
+        .iter()
+        .map(|child| match child {
+            RTreeNode::Leaf(_) => 1,
+            RTreeNode::Parent(data) => count_leaves(data),
+        })
+        .sum()
+}
+
+/// Removes all elements for which `f` returns `false` and returns their number.
+///
+/// All node envelopes are recalculated since `f` may modify the elements. Underflown nodes
+/// are dissolved and their remaining elements are pushed to `orphans`.
+pub fn retain<T, Params, F>(
+    node: &mut ParentNode<T>,
+    f: &mut F,
+    orphans: &mut Vec<T>,
+    spare_children: &mut Vec<Vec<RTreeNode<T>>>,
+) -> usize
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+    F: FnMut(&mut T) -> bool,
+{
+    let mut removed = 0;
+    let mut index = 0;
+    while index < node.children.len() {
+        let keep = match node.children[index] {
+            RTreeNode::Leaf(ref mut t) => f(t),
+            RTreeNode::Parent(ref mut data) => {
+                removed += retain::<_, Params, _>(data, f, orphans, spare_children);
+                data.children.len() >= Params::MIN_SIZE
+            }
+        };
+        if keep {
+            index += 1;
+        } else {
+            match node.children.swap_remove(index) {
+                RTreeNode::Leaf(_) => removed += 1,
+                RTreeNode::Parent(data) => collect_leaves(data, orphans, spare_children),
+            }
+        }
+    }
+    node.envelope = crate::node::envelope_for_children(&node.children);
+    removed
+}
+
+/// Shortens the tree as long as the root only contains a single parent node.
+pub fn shorten_root<T>(root: &mut ParentNode<T>)
+where
+    T: RTreeObject,
+{
+    while root.children.len() == 1 && !root.children[0].is_leaf() {
+        if let Some(RTreeNode::Parent(child)) = root.children.pop() {
+            *root = child;
+        }
+    }
+}
+
+/// Moves all elements below a node into `leaves`.
+///
+/// The emptied child vectors are kept in `spare_children` for later node splits, as long as
+/// its reserved capacity suffices.
+pub fn collect_leaves<T>(
+    node: ParentNode<T>,
+    leaves: &mut Vec<T>,
+    spare_children: &mut Vec<Vec<RTreeNode<T>>>,
+) where
+    T: RTreeObject,
+{
+    let mut children = node.children;
+    for child in children.drain(..) {
+        match child {
+            RTreeNode::Leaf(t) => leaves.push(t),
+            RTreeNode::Parent(data) => collect_leaves(data, leaves, spare_children),
+        }
+    }
+    keep_spare_children(spare_children, children);
+}
+
 #[cfg(test)]
 mod test {
     use crate::point::PointExt;
     use crate::primitives::Line;
     use crate::test_utilities::{create_random_points, create_random_rectangles, SEED_1, SEED_2};
-    use crate::RTree;
+    use crate::{DefaultParams, RTree};
 
     #[test]
     fn test_remove_and_insert() {
@@ -145,4 +423,320 @@ mod test {
             assert_eq!(size_before_removal - 1, tree.size());
         }
     }
+
+    #[test]
+    fn test_remove_condenses_tree() {
+        const SIZE: usize = 1000;
+        let points = create_random_points(SIZE, SEED_1);
+        let mut tree = RTree::new();
+        for point in &points {
+            tree.insert(*point);
+        }
+        for (index, point) in points.iter().enumerate() {
+            assert_eq!(tree.remove(point), Some(*point));
+            tree.root().sanity_check::<DefaultParams>();
+            assert_eq!(tree.size(), SIZE - index - 1);
+            assert_eq!(tree.iter().count(), tree.size());
+        }
+        assert!(tree.root().children().is_empty());
+    }
+
+    #[test]
+    fn test_remove_at_point_without_equality() {
+        // Removing at a point must not require `PartialEq`
+        struct Marker {
+            position: [f64; 2],
+            id: usize,
+        }
+
+        impl crate::RTreeObject for Marker {
+            type Envelope = crate::AABB<[f64; 2]>;
+
+            fn envelope(&self) -> Self::Envelope {
+                crate::AABB::from_point(self.position)
+            }
+        }
+
+        impl crate::PointDistance for Marker {
+            fn distance_2(&self, point: &[f64; 2]) -> f64 {
+                self.position.sub(point).length_2()
+            }
+        }
+
+        let points = create_random_points(100, SEED_1);
+        let markers = points
+            .iter()
+            .enumerate()
+            .map(|(id, position)| Marker {
+                position: *position,
+                id,
+            })
+            .collect();
+        let mut tree = RTree::bulk_load(markers);
+        for (id, point) in points.iter().enumerate().rev() {
+            assert_eq!(
+                tree.remove_at_point(point).map(|marker| marker.id),
+                Some(id)
+            );
+            assert!(tree.remove_at_point(point).is_none());
+        }
+        assert_eq!(tree.size(), 0);
+    }
+
+    #[test]
+    fn test_remove_with_selection_function() {
+        use crate::primitives::PointWithData;
+        use crate::{Envelope, RTreeObject, SelectionFunction, AABB};
+
+        struct LowIndexInEnvelope(AABB<[f64; 2]>);
+
+        impl SelectionFunction<PointWithData<usize, [f64; 2]>> for LowIndexInEnvelope {
+            fn should_unpack_parent(&self, envelope: &AABB<[f64; 2]>) -> bool {
+                self.0.intersects(envelope)
+            }
+
+            fn should_unpack_leaf(&self, leaf: &PointWithData<usize, [f64; 2]>) -> bool {
+                leaf.data < 250 && self.0.contains_envelope(&leaf.envelope())
+            }
+        }
+
+        let points = create_random_points(500, SEED_1);
+        let mut tree = RTree::new();
+        for (index, point) in points.iter().enumerate() {
+            tree.insert(PointWithData::new(index, *point));
+        }
+        let envelope = AABB::from_corners([-0.5, -0.5], [0.5, 0.5]);
+        let expected = points
+            .iter()
+            .enumerate()
+            .filter(|(index, point)| *index < 250 && envelope.contains_point(point))
+            .count();
+        let mut removed = 0;
+        while let Some(point) = tree.remove_with_selection_function(LowIndexInEnvelope(envelope)) {
+            assert!(point.data < 250);
+            assert!(envelope.contains_point(point.position()));
+            tree.root().sanity_check::<DefaultParams>();
+            removed += 1;
+        }
+        assert_eq!(removed, expected);
+        assert_eq!(tree.size(), points.len() - expected);
+    }
+
+    #[test]
+    fn test_update_at_point() {
+        let points = create_random_points(500, SEED_1);
+        let mut tree = RTree::new();
+        for point in &points {
+            tree.insert(*point);
+        }
+        let mut moved = Vec::new();
+        for (index, point) in points.iter().enumerate() {
+            // Small movements first, then jumps across the whole tree
+            let target = if index < points.len() / 2 {
+                [point[0] + 1e-6, point[1] - 1e-6]
+            } else {
+                [-point[0], point[1] + 2.0]
+            };
+            assert!(tree.update_at_point(point, |p| *p = target));
+            tree.root().sanity_check::<DefaultParams>();
+            moved.push(target);
+        }
+        assert_eq!(tree.size(), points.len());
+        assert!(moved.iter().all(|p| tree.contains(p)));
+        assert!(!tree.update_at_point(&[10.0, 10.0], |_| panic!("must not be called")));
+    }
+
+    #[test]
+    fn test_remove_all() {
+        let points = create_random_points(1000, SEED_1);
+        let mut tree = RTree::new();
+        for point in &points {
+            tree.insert(*point);
+        }
+        let (to_remove, to_keep) = points.split_at(700);
+        let mut removed = tree.remove_all(to_remove.iter().chain(to_remove.iter().take(10)));
+        tree.root().sanity_check::<DefaultParams>();
+        assert_eq!(tree.size(), to_keep.len());
+        let mut expected = to_remove.to_vec();
+        removed.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        expected.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        assert_eq!(removed, expected);
+        assert!(to_keep.iter().all(|point| tree.contains(point)));
+    }
+
+    #[test]
+    fn test_pop_nearest_neighbor() {
+        let points = create_random_points(1000, SEED_1);
+        let query_point = [0.5, 0.5];
+        let mut expected = points.clone();
+        expected.sort_by(|l, r| {
+            l.distance_2(&query_point)
+                .partial_cmp(&r.distance_2(&query_point))
+                .unwrap()
+        });
+        let mut tree = RTree::new();
+        for point in &points {
+            tree.insert(*point);
+        }
+        let mut popped = Vec::new();
+        while let Some(nearest) = tree.pop_nearest_neighbor(&query_point) {
+            tree.root().sanity_check::<DefaultParams>();
+            popped.push(nearest);
+        }
+        assert_eq!(popped, expected);
+        assert_eq!(tree.size(), 0);
+    }
+
+    #[test]
+    fn test_drain_in_envelope() {
+        use crate::{Envelope, AABB};
+
+        const SIZE: usize = 1000;
+        let points = create_random_points(SIZE, SEED_1);
+        let mut tree = RTree::new();
+        for point in &points {
+            tree.insert(*point);
+        }
+        let envelope = AABB::from_corners([0.2, 0.1], [0.7, 0.5]);
+        let (mut expected, remaining): (Vec<_>, Vec<_>) = points
+            .iter()
+            .cloned()
+            .partition(|p| envelope.contains_point(p));
+        assert!(!expected.is_empty());
+        let mut drained: Vec<_> = tree.drain_in_envelope(&envelope).collect();
+        tree.root().sanity_check::<DefaultParams>();
+        let sort = |l: &[f64; 2], r: &[f64; 2]| l.partial_cmp(r).unwrap();
+        drained.sort_by(sort);
+        expected.sort_by(sort);
+        assert_eq!(drained, expected);
+        assert_eq!(tree.size(), remaining.len());
+        assert_eq!(tree.iter().count(), remaining.len());
+        assert!(remaining.iter().all(|p| tree.contains(p)));
+        assert_eq!(tree.drain_in_envelope(&envelope).count(), 0);
+
+        let everything = AABB::from_corners([0.0, 0.0], [1.0, 1.0]);
+        assert_eq!(tree.drain_in_envelope(&everything).count(), remaining.len());
+        assert_eq!(tree.size(), 0);
+        assert!(tree.root().children().is_empty());
+    }
+
+    #[test]
+    fn test_drain_within_distance() {
+        const SIZE: usize = 1000;
+        let points = create_random_points(SIZE, SEED_1);
+        let mut tree = RTree::new();
+        for point in &points {
+            tree.insert(*point);
+        }
+        let query_point = [0.4, 0.6];
+        let max_distance_2 = 0.1;
+        let within_count = points
+            .iter()
+            .filter(|p| p.distance_2(&query_point) <= max_distance_2)
+            .count();
+        assert!(within_count > 0);
+        let drained: Vec<_> = tree
+            .drain_within_distance(query_point, max_distance_2)
+            .collect();
+        tree.root().sanity_check::<DefaultParams>();
+        assert_eq!(drained.len(), within_count);
+        assert!(drained
+            .iter()
+            .all(|p| p.distance_2(&query_point) <= max_distance_2));
+        assert_eq!(tree.size(), SIZE - within_count);
+        assert_eq!(
+            tree.locate_within_distance(query_point, max_distance_2)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_split_off_in_envelope() {
+        use crate::{Envelope, RTreeNode, TraversalOrder, AABB};
+
+        const SIZE: usize = 1000;
+        let points = create_random_points(SIZE, SEED_1);
+        let mut tree = RTree::new();
+        for point in &points {
+            tree.insert(*point);
+        }
+        let envelope = AABB::from_corners([0.1, 0.2], [0.7, 0.6]);
+        let (inside, outside): (Vec<_>, Vec<_>) =
+            points.iter().partition(|p| envelope.contains_point(p));
+        assert!(!inside.is_empty());
+        let contained_ids = |tree: &RTree<[f64; 2]>| -> Vec<u64> {
+            let mut ids: Vec<_> = tree
+                .root()
+                .nodes(TraversalOrder::DepthFirst)
+                .filter_map(|(node, _)| match node {
+                    RTreeNode::Parent(data) if envelope.contains_envelope(&data.envelope()) => {
+                        Some(data.id())
+                    }
+                    _ => None,
+                })
+                .collect();
+            ids.sort_unstable();
+            ids
+        };
+        let detached = contained_ids(&tree);
+        assert!(!detached.is_empty());
+        let split = tree.split_off_in_envelope(&envelope);
+        tree.root().sanity_check::<DefaultParams>();
+        split.root().sanity_check::<DefaultParams>();
+        // Contained subtrees are moved into the new tree as they are
+        let split_ids = contained_ids(&split);
+        assert!(detached
+            .iter()
+            .all(|id| split_ids.binary_search(id).is_ok()));
+        assert_eq!(split.size(), inside.len());
+        assert_eq!(tree.size(), outside.len());
+        for p in inside {
+            assert!(split.contains(p));
+            assert!(!tree.contains(p));
+        }
+        for p in &outside {
+            assert!(tree.contains(p));
+        }
+        assert_eq!(tree.split_off_in_envelope(&envelope).size(), 0);
+
+        let everything = AABB::from_corners([0.0, 0.0], [1.0, 1.0]);
+        let all = tree.split_off_in_envelope(&everything);
+        all.root().sanity_check::<DefaultParams>();
+        assert_eq!(all.size(), outside.len());
+        assert_eq!(tree.size(), 0);
+        assert!(tree.root().children().is_empty());
+    }
+
+    #[test]
+    fn test_retain() {
+        const SIZE: usize = 1000;
+        let points = create_random_points(SIZE, SEED_1);
+        let mut tree = RTree::new();
+        for point in &points {
+            tree.insert(*point);
+        }
+        let predicate = |p: &[f64; 2]| p[0] < 0.3 || p[1] > 0.8;
+        let expected: Vec<_> = points.iter().filter(|p| predicate(p)).collect();
+        tree.retain(predicate);
+        tree.root().sanity_check::<DefaultParams>();
+        assert_eq!(tree.size(), expected.len());
+        assert_eq!(tree.iter().count(), expected.len());
+        assert!(expected.iter().all(|p| tree.contains(p)));
+
+        // Moving elements keeps all envelopes valid
+        tree.retain_mut(|p| {
+            p[0] += 1.0;
+            p[1] < 0.95
+        });
+        tree.root().sanity_check::<DefaultParams>();
+        assert_eq!(tree.size(), expected.iter().filter(|p| p[1] < 0.95).count());
+        for p in expected.iter().filter(|p| p[1] < 0.95) {
+            assert!(tree.contains(&[p[0] + 1.0, p[1]]));
+        }
+
+        tree.retain(|_| false);
+        assert_eq!(tree.size(), 0);
+        assert!(tree.root().children().is_empty());
+    }
 }
@@ -1,8 +1,13 @@
+use crate::algorithm::iterators::allow_duplicates;
 use crate::envelope::Envelope;
-use crate::node::{envelope_for_children, ParentNode, RTreeNode};
+use crate::insertion_metrics::InsertionMetrics;
+use crate::node::{envelope_for_children, take_spare_children, ParentNode, RTreeNode};
 use crate::object::RTreeObject;
-use crate::params::{InsertionStrategy, RTreeParams};
-use crate::point::{Point, PointExt};
+use crate::params::{InsertionStrategy, RTreeParams, ReinsertionOrder};
+use crate::point::{
+    max_inline, min_inline, saturating_add, saturating_sub, total_cmp, Point, PointExt,
+};
+use crate::primitives::CachedEnvelope;
 use crate::rtree::RTree;
 use num_traits::{Bounded, Zero};
 
@@ -21,44 +26,224 @@ where
     T: RTreeObject,
 {
     Split(RTreeNode<T>),
-    Reinsert(Vec<RTreeNode<T>>, usize),
+    // Nodes to reinsert, their height and the path from the root to the overflown node
+    Reinsert(Vec<RTreeNode<T>>, usize, Vec<usize>),
+    // An element equal to the inserted one is already contained, the tree is left unchanged
+    Rejected(RTreeNode<T>),
     Complete,
 }
 
+// After this many reinsertions, the adaptive mode starts to judge their effectiveness
+const ADAPTIVE_REINSERTION_WARMUP: usize = 64;
+// Reinsertion is considered ineffective if less than 1 in this many reinserted nodes relocates
+const ADAPTIVE_REINSERTION_MIN_RATIO: usize = 10;
+// While reinsertion is ineffective, it is still attempted for every nth overflow.
+// Must be a power of two.
+const ADAPTIVE_REINSERTION_PROBE_INTERVAL: usize = 32;
+
+struct InsertionContext<'a, T>
+where
+    T: RTreeObject,
+{
+    metrics: &'a mut InsertionMetrics,
+    adaptive_reinsertion: bool,
+    reinsertion_count: usize,
+    reinsertion_order: ReinsertionOrder,
+    // Child indices leading from the root to the currently visited node
+    path: Vec<usize>,
+    // Preallocated children vectors for nodes created by splits
+    spare_children: &'a mut Vec<Vec<RTreeNode<T>>>,
+}
+
+impl<'a, T> InsertionContext<'a, T>
+where
+    T: RTreeObject,
+{
+    fn should_reinsert(&mut self) -> bool {
+        let metrics = &mut *self.metrics;
+        metrics.overflows += 1;
+        if self.reinsertion_count == 0 {
+            return false;
+        }
+        let is_effective = metrics.reinsertions < ADAPTIVE_REINSERTION_WARMUP
+            || metrics.relocated_reinsertions * ADAPTIVE_REINSERTION_MIN_RATIO
+                >= metrics.reinsertions;
+        if !self.adaptive_reinsertion
+            || is_effective
+            || metrics.overflows & (ADAPTIVE_REINSERTION_PROBE_INTERVAL - 1) == 0
+        {
+            true
+        } else {
+            metrics.skipped_reinsertions += 1;
+            false
+        }
+    }
+}
+
 impl InsertionStrategy for RStarInsertionStrategy {
     fn insert<T, Params>(tree: &mut RTree<T, Params>, t: T)
     where
         Params: RTreeParams,
         T: RTreeObject,
     {
-        let first = recursive_insert::<_, Params>(tree.root_mut(), RTreeNode::Leaf(t), 0);
+        let _ = Self::insert_unless(tree, t, allow_duplicates);
+    }
+
+    fn insert_unless<T, Params, F>(
+        tree: &mut RTree<T, Params>,
+        t: T,
+        is_rejected: F,
+    ) -> Result<(), T>
+    where
+        Params: RTreeParams,
+        T: RTreeObject,
+        F: Fn(&[RTreeNode<T>], &T) -> bool,
+    {
+        let adaptive_reinsertion = tree.adaptive_reinsertion();
+        let reinsertion_policy = tree.reinsertion_policy();
+        let reinsertion_count = reinsertion_policy.count::<Params>();
+        let reinsertion_order = reinsertion_policy.order();
+        let (root, metrics, spare_children) = tree.insertion_state_mut();
+        let mut context = InsertionContext {
+            metrics,
+            adaptive_reinsertion,
+            reinsertion_count,
+            reinsertion_order,
+            path: Vec::new(),
+            spare_children,
+        };
+        let first = recursive_insert::<_, Params, _>(
+            root,
+            RTreeNode::Leaf(t),
+            0,
+            &is_rejected,
+            &mut context,
+        );
+        if let InsertionResult::Rejected(RTreeNode::Leaf(t)) = first {
+            return Err(t);
+        }
+        context.metrics.insertions += 1;
         let mut insertion_stack = vec![first];
         let mut start_insertion_height = 0;
         while let Some(next) = insertion_stack.pop() {
             match next {
                 InsertionResult::Split(node) => {
                     // The root node was split, create a new root and increase height
-                    let new_root = ParentNode::new_root::<Params>();
-                    let old_root = ::std::mem::replace(tree.root_mut(), new_root);
-                    let new_envelope = old_root.envelope.merged(&node.envelope());
-                    let root = tree.root_mut();
-                    root.envelope = new_envelope;
+                    let (root, _, spare_children) = tree.insertion_state_mut();
+                    let children = take_spare_children::<_, Params>(spare_children);
+                    let new_envelope = root.envelope.merged(&node.envelope());
+                    let new_root = ParentNode::new_with_envelope(children, new_envelope);
+                    let old_root = ::std::mem::replace(root, new_root);
                     root.children.push(RTreeNode::Parent(old_root));
                     root.children.push(node);
                     start_insertion_height += 1;
                 }
-                InsertionResult::Reinsert(nodes_to_reinsert, target_height) => {
+                InsertionResult::Reinsert(nodes_to_reinsert, target_height, origin) => {
                     let final_height = target_height + start_insertion_height;
-                    let root = tree.root_mut();
-                    insertion_stack.extend(
-                        nodes_to_reinsert
-                            .into_iter()
-                            .map(|node| forced_insertion::<T, Params>(root, node, final_height)),
-                    );
+                    let (root, metrics, spare_children) = tree.insertion_state_mut();
+                    let mut context = InsertionContext {
+                        metrics,
+                        adaptive_reinsertion,
+                        reinsertion_count,
+                        reinsertion_order,
+                        path: Vec::new(),
+                        spare_children,
+                    };
+                    insertion_stack.extend(nodes_to_reinsert.into_iter().map(|node| {
+                        context.path.clear();
+                        forced_insertion::<T, Params>(
+                            root,
+                            node,
+                            final_height,
+                            &origin,
+                            &mut context,
+                        )
+                    }));
                 }
+                InsertionResult::Rejected(_) => unreachable!("This is a bug in rstar."),
                 InsertionResult::Complete => (),
             }
         }
+        Ok(())
+    }
+}
+
+/// Inserts a detached subtree with `height` levels into a tree that is at least as high.
+///
+/// The subtree is attached as it is to a node on the matching level. Overflowing nodes are
+/// split, but never reinserted.
+pub(crate) fn insert_subtree<T, Params>(
+    tree: &mut RTree<T, Params>,
+    subtree: ParentNode<T>,
+    height: usize,
+) where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    let tree_height = tree.height();
+    debug_assert!(height <= tree_height);
+    let (root, _, spare_children) = tree.insertion_state_mut();
+    let subtree = RTreeNode::Parent(subtree);
+    let off_split = if height == tree_height {
+        Some(subtree)
+    } else {
+        insert_at_depth::<_, Params>(root, subtree, tree_height - height - 1, spare_children)
+    };
+    if let Some(node) = off_split {
+        let children = take_spare_children::<_, Params>(spare_children);
+        let new_envelope = root.envelope.merged(&node.envelope());
+        let new_root = ParentNode::new_with_envelope(children, new_envelope);
+        let old_root = ::std::mem::replace(root, new_root);
+        root.children.push(RTreeNode::Parent(old_root));
+        root.children.push(node);
+    }
+}
+
+// Returns the node split off from `node`, if any
+fn insert_at_depth<T, Params>(
+    node: &mut ParentNode<T>,
+    t: RTreeNode<T>,
+    depth: usize,
+    spare_children: &mut Vec<Vec<RTreeNode<T>>>,
+) -> Option<RTreeNode<T>>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    node.envelope.merge(&t.envelope());
+    if depth == 0 {
+        node.children.push(t);
+    } else {
+        let expand_index = choose_subtree(node, &t);
+        if let RTreeNode::Parent(ref mut follow) = node.children[expand_index] {
+            if let Some(child) = insert_at_depth::<_, Params>(follow, t, depth - 1, spare_children)
+            {
+                node.children.push(child);
+            }
+        } else {
+            unreachable!("This is a bug in rstar.")
+        }
+    }
+    if node.children.len() > Params::MAX_SIZE {
+        Some(split::<_, Params>(node, spare_children))
+    } else {
+        None
+    }
+}
+
+fn merge_envelope<T>(
+    node: &mut ParentNode<T>,
+    envelope: &T::Envelope,
+    metrics: &mut InsertionMetrics,
+) where
+    T: RTreeObject,
+{
+    // Dense regions often insert into nodes that already contain the new envelope
+    if node.envelope.contains_envelope(envelope) {
+        metrics.skipped_envelope_merges += 1;
+    } else {
+        node.envelope.merge(envelope);
+        metrics.envelope_merges += 1;
     }
 }
 
@@ -66,26 +251,33 @@ fn forced_insertion<T, Params>(
     node: &mut ParentNode<T>,
     t: RTreeNode<T>,
     target_height: usize,
+    origin: &[usize],
+    context: &mut InsertionContext<T>,
 ) -> InsertionResult<T>
 where
     T: RTreeObject,
     Params: RTreeParams,
 {
-    node.envelope.merge(&t.envelope());
+    merge_envelope(node, &t.envelope(), context.metrics);
     let expand_index = choose_subtree(node, &t);
 
     if target_height == 0 || node.children.len() < expand_index {
         // Force insertion into this node
         node.children.push(t);
-        return resolve_overflow_without_reinsertion::<_, Params>(node);
+        context.metrics.reinsertions += 1;
+        if context.path != origin {
+            context.metrics.relocated_reinsertions += 1;
+        }
+        return resolve_overflow_without_reinsertion::<_, Params>(node, context);
     }
 
     if let RTreeNode::Parent(ref mut follow) = node.children[expand_index] {
-        match forced_insertion::<_, Params>(follow, t, target_height - 1) {
+        context.path.push(expand_index);
+        match forced_insertion::<_, Params>(follow, t, target_height - 1, origin, context) {
             InsertionResult::Split(child) => {
                 node.envelope.merge(&child.envelope());
                 node.children.push(child);
-                resolve_overflow_without_reinsertion::<_, Params>(node)
+                resolve_overflow_without_reinsertion::<_, Params>(node, context)
             }
             other => other,
         }
@@ -94,39 +286,61 @@ where
     }
 }
 
-fn recursive_insert<T, Params>(
+fn recursive_insert<T, Params, F>(
     node: &mut ParentNode<T>,
     t: RTreeNode<T>,
     current_height: usize,
+    is_rejected: &F,
+    context: &mut InsertionContext<T>,
 ) -> InsertionResult<T>
 where
     T: RTreeObject,
     Params: RTreeParams,
+    F: Fn(&[RTreeNode<T>], &T) -> bool,
 {
-    node.envelope.merge(&t.envelope());
     let expand_index = choose_subtree(node, &t);
+    if let RTreeNode::Leaf(ref element) = t {
+        // Conflicting elements may also be stored in the siblings of the chosen subtree
+        let (before, after) = node
+            .children
+            .split_at(expand_index.min(node.children.len()));
+        if is_rejected(before, element) || is_rejected(after.get(1..).unwrap_or(&[]), element) {
+            return InsertionResult::Rejected(t);
+        }
+    }
+    let envelope = t.envelope();
 
     if node.children.len() < expand_index {
         // Force insertion into this node
+        merge_envelope(node, &envelope, context.metrics);
         node.children.push(t);
-        return resolve_overflow::<_, Params>(node, current_height);
+        return resolve_overflow::<_, Params>(node, current_height, context);
     }
 
     let expand = if let RTreeNode::Parent(ref mut follow) = node.children[expand_index] {
-        recursive_insert::<_, Params>(follow, t, current_height + 1)
+        context.path.push(expand_index);
+        let result =
+            recursive_insert::<_, Params, _>(follow, t, current_height + 1, is_rejected, context);
+        context.path.pop();
+        result
     } else {
         panic!("This is a bug in rstar.")
     };
+    if let InsertionResult::Rejected(_) = expand {
+        return expand;
+    }
+    // Enlarged only after the descent, a rejected element must leave the tree unchanged
+    merge_envelope(node, &envelope, context.metrics);
 
     match expand {
         InsertionResult::Split(child) => {
             node.envelope.merge(&child.envelope());
             node.children.push(child);
-            resolve_overflow::<_, Params>(node, current_height)
+            resolve_overflow::<_, Params>(node, current_height, context)
         }
-        InsertionResult::Reinsert(a, b) => {
+        InsertionResult::Reinsert(a, b, c) => {
             node.envelope = envelope_for_children(&node.children);
-            InsertionResult::Reinsert(a, b)
+            InsertionResult::Reinsert(a, b, c)
         }
         other => other,
     }
@@ -149,13 +363,15 @@ where
     let zero: <<T::Envelope as Envelope>::Point as Point>::Scalar = Zero::zero();
     let insertion_envelope = to_insert.envelope();
     let mut inclusion_count = 0;
-    let mut min_area = <<T::Envelope as Envelope>::Point as Point>::Scalar::max_value();
+    let max_value = <<T::Envelope as Envelope>::Point as Point>::Scalar::max_value();
+    let mut min_area = (max_value, max_value);
     let mut min_index = 0;
     for (index, child) in node.children.iter().enumerate() {
         let envelope = child.envelope();
         if envelope.contains_envelope(&insertion_envelope) {
             inclusion_count += 1;
-            let area = envelope.area();
+            // Degenerate envelopes all have zero area, use the perimeter as tie breaker
+            let area = (envelope.area(), envelope.perimeter_value());
             if area < min_area {
                 min_area = area;
                 min_index = index;
@@ -164,7 +380,7 @@ where
     }
     if inclusion_count == 0 {
         // No inclusion found, subtree depends on overlap and area increase
-        let mut min = (zero, zero, zero);
+        let mut min = (zero, zero, zero, zero);
 
         for (index, child1) in node.children.iter().enumerate() {
             let envelope = child1.envelope();
@@ -178,9 +394,9 @@ where
                     if child1 as *const _ != child2 as *const _ {
                         let child_envelope = child2.envelope();
                         let temp1 = envelope.intersection_area(&child_envelope);
-                        overlap = overlap + temp1;
+                        overlap = saturating_add(overlap, temp1);
                         let temp2 = new_envelope.intersection_area(&child_envelope);
-                        new_overlap = new_overlap + temp2;
+                        new_overlap = saturating_add(new_overlap, temp2);
                     }
                 }
                 new_overlap - overlap
@@ -191,7 +407,10 @@ where
             // Calculate area increase and area
             let area = new_envelope.area();
             let area_increase = area - envelope.area();
-            let new_min = (overlap_increase, area_increase, area);
+            // Degenerate envelopes (e.g. of collinear points) have zero area, the perimeter
+            // increase is used to tell them apart.
+            let perimeter_increase = new_envelope.perimeter_value() - envelope.perimeter_value();
+            let new_min = (overlap_increase, area_increase, area, perimeter_increase);
             if new_min < min || index == 0 {
                 min = new_min;
                 min_index = index;
@@ -202,52 +421,89 @@ where
 }
 
 // Does never return a request for reinsertion
-fn resolve_overflow_without_reinsertion<T, Params>(node: &mut ParentNode<T>) -> InsertionResult<T>
+fn resolve_overflow_without_reinsertion<T, Params>(
+    node: &mut ParentNode<T>,
+    context: &mut InsertionContext<T>,
+) -> InsertionResult<T>
 where
     T: RTreeObject,
     Params: RTreeParams,
 {
     if node.children.len() > Params::MAX_SIZE {
-        let off_split = split::<_, Params>(node);
+        let off_split = split::<_, Params>(node, context.spare_children);
         InsertionResult::Split(off_split)
     } else {
         InsertionResult::Complete
     }
 }
 
-fn resolve_overflow<T, Params>(node: &mut ParentNode<T>, current_depth: usize) -> InsertionResult<T>
+fn resolve_overflow<T, Params>(
+    node: &mut ParentNode<T>,
+    current_depth: usize,
+    context: &mut InsertionContext<T>,
+) -> InsertionResult<T>
 where
     T: RTreeObject,
     Params: RTreeParams,
 {
-    if Params::REINSERTION_COUNT == 0 {
-        resolve_overflow_without_reinsertion::<_, Params>(node)
-    } else if node.children.len() > Params::MAX_SIZE {
-        let nodes_for_reinsertion = get_nodes_for_reinsertion::<_, Params>(node);
-        InsertionResult::Reinsert(nodes_for_reinsertion, current_depth)
-    } else {
+    if node.children.len() <= Params::MAX_SIZE {
         InsertionResult::Complete
+    } else if context.should_reinsert() {
+        let nodes_for_reinsertion = get_nodes_for_reinsertion::<_, Params>(node, context);
+        InsertionResult::Reinsert(nodes_for_reinsertion, current_depth, context.path.clone())
+    } else {
+        resolve_overflow_without_reinsertion::<_, Params>(node, context)
     }
 }
 
-fn split<T, Params>(node: &mut ParentNode<T>) -> RTreeNode<T>
+fn split<T, Params>(
+    node: &mut ParentNode<T>,
+    spare_children: &mut Vec<Vec<RTreeNode<T>>>,
+) -> RTreeNode<T>
 where
     T: RTreeObject,
     Params: RTreeParams,
 {
-    let axis = get_split_axis::<_, Params>(node);
-    let zero = <<T::Envelope as Envelope>::Point as Point>::Scalar::zero();
     debug_assert!(node.children.len() >= 2);
+    let (best_index, first_envelope, second_envelope) =
+        if Params::ENVELOPE_CACHING.caches_overflows() && node.children[0].is_leaf() {
+            let mut children: Vec<_> = node.children.drain(..).map(CachedEnvelope::new).collect();
+            let result = sort_for_split::<_, Params>(&mut children);
+            node.children
+                .extend(children.into_iter().map(CachedEnvelope::into_inner));
+            result
+        } else {
+            sort_for_split::<_, Params>(&mut node.children)
+        };
+    // Both nodes keep enough capacity to grow until their next split
+    let mut off_split = take_spare_children::<_, Params>(spare_children);
+    off_split.extend(node.children.drain(best_index..));
+    node.envelope = first_envelope;
+    RTreeNode::Parent(ParentNode::new_with_envelope(off_split, second_envelope))
+}
+
+/// Sorts the children of an overflowing node along the best split axis.
+///
+/// Returns the index at which the children are split and the envelopes of both groups.
+pub(crate) fn sort_for_split<C, Params>(children: &mut [C]) -> (usize, C::Envelope, C::Envelope)
+where
+    C: RTreeObject,
+    Params: RTreeParams,
+{
+    let axis = get_split_axis::<_, Params>(children);
+    let zero = <<C::Envelope as Envelope>::Point as Point>::Scalar::zero();
     // Sort along axis
-    T::Envelope::sort_envelopes(axis, &mut node.children);
-    let mut best = (zero, zero);
+    C::Envelope::sort_envelopes(axis, children);
+    let mut best = (zero, zero, zero, 0);
     let min_size = Params::MIN_SIZE;
     let mut best_index = min_size;
+    let mut best_envelopes = (C::Envelope::new_empty(), C::Envelope::new_empty());
+    let len = children.len();
 
-    for k in min_size..=node.children.len() - min_size {
-        let mut first_envelope = node.children[k - 1].envelope();
-        let mut second_envelope = node.children[k].envelope();
-        let (l, r) = node.children.split_at(k);
+    for k in min_size..=len - min_size {
+        let mut first_envelope = children[k - 1].envelope();
+        let mut second_envelope = children[k].envelope();
+        let (l, r) = children.split_at(k);
         for child in l {
             first_envelope.merge(&child.envelope());
         }
@@ -256,42 +512,49 @@ where
         }
 
         let overlap_value = first_envelope.intersection_area(&second_envelope);
-        let area_value = first_envelope.area() + second_envelope.area();
-        let new_best = (overlap_value, area_value);
+        let area_value = saturating_add(first_envelope.area(), second_envelope.area());
+        // If all distributions have zero overlap and area (e.g. for points on a line),
+        // prefer the smallest perimeter and, finally, the most balanced split. Otherwise,
+        // the first distribution would always be chosen, creating minimally filled nodes.
+        let perimeter_value = saturating_add(
+            first_envelope.perimeter_value(),
+            second_envelope.perimeter_value(),
+        );
+        let imbalance = (2 * k).max(len) - (2 * k).min(len);
+        let new_best = (overlap_value, area_value, perimeter_value, imbalance);
         if new_best < best || k == min_size {
             best = new_best;
             best_index = k;
+            best_envelopes = (first_envelope, second_envelope);
         }
     }
-    let off_split = node.children.split_off(best_index);
-    node.envelope = envelope_for_children(&node.children);
-    RTreeNode::Parent(ParentNode::new_parent(off_split))
+    (best_index, best_envelopes.0, best_envelopes.1)
 }
 
-fn get_split_axis<T, Params>(node: &mut ParentNode<T>) -> usize
+fn get_split_axis<C, Params>(children: &mut [C]) -> usize
 where
-    T: RTreeObject,
+    C: RTreeObject,
     Params: RTreeParams,
 {
-    let mut best_goodness = <<T::Envelope as Envelope>::Point as Point>::Scalar::max_value();
+    let mut best_goodness = <<C::Envelope as Envelope>::Point as Point>::Scalar::max_value();
     let mut best_axis = 0;
     let min_size = Params::MIN_SIZE;
-    let until = node.children.len() - min_size + 1;
-    for axis in 0..<T::Envelope as Envelope>::Point::DIMENSIONS {
+    let until = children.len() - min_size + 1;
+    for axis in split_axis_candidates::<_, Params>(children) {
         // Sort children along the current axis
-        T::Envelope::sort_envelopes(axis, &mut node.children);
-        let mut first_envelope = T::Envelope::new_empty();
-        let mut second_envelope = T::Envelope::new_empty();
-        for child in &node.children[..min_size] {
+        C::Envelope::sort_envelopes(axis, children);
+        let mut first_envelope = C::Envelope::new_empty();
+        let mut second_envelope = C::Envelope::new_empty();
+        for child in &children[..min_size] {
             first_envelope.merge(&child.envelope());
         }
-        for child in &node.children[until..] {
+        for child in &children[until..] {
             second_envelope.merge(&child.envelope());
         }
         for k in min_size..until {
             let mut first_modified = first_envelope;
             let mut second_modified = second_envelope;
-            let (l, r) = node.children.split_at(k);
+            let (l, r) = children.split_at(k);
             for child in l {
                 first_modified.merge(&child.envelope());
             }
@@ -299,8 +562,10 @@ where
                 second_modified.merge(&child.envelope());
             }
 
-            let perimeter_value =
-                first_modified.perimeter_value() + second_modified.perimeter_value();
+            let perimeter_value = saturating_add(
+                first_modified.perimeter_value(),
+                second_modified.perimeter_value(),
+            );
             if best_goodness > perimeter_value {
                 best_axis = axis;
                 best_goodness = perimeter_value;
@@ -310,26 +575,339 @@ where
     best_axis
 }
 
-fn get_nodes_for_reinsertion<T, Params>(node: &mut ParentNode<T>) -> Vec<RTreeNode<T>>
+/// Returns the axes that are evaluated by `get_split_axis`.
+///
+/// If there are more dimensions than `Params::MAX_SPLIT_AXES`, the axes with the largest
+/// spread of the children's centers are chosen.
+fn split_axis_candidates<C, Params>(children: &[C]) -> Vec<usize>
+where
+    C: RTreeObject,
+    Params: RTreeParams,
+{
+    let dimensions = <C::Envelope as Envelope>::Point::DIMENSIONS;
+    let mut axes: Vec<_> = (0..dimensions).collect();
+    if dimensions <= Params::MAX_SPLIT_AXES {
+        return axes;
+    }
+    let centers: Vec<_> = children
+        .iter()
+        .map(|child| child.envelope().center())
+        .collect();
+    let spread = |axis: usize| {
+        let (min, max) = centers.iter().fold(
+            (centers[0].nth(axis), centers[0].nth(axis)),
+            |(min, max), center| {
+                (
+                    min_inline(min, center.nth(axis)),
+                    max_inline(max, center.nth(axis)),
+                )
+            },
+        );
+        saturating_sub(max, min)
+    };
+    let spreads: Vec<_> = axes.iter().map(|axis| spread(*axis)).collect();
+    // Largest spread first
+    axes.sort_by(|l, r| total_cmp(&spreads[*r], &spreads[*l]));
+    axes.truncate(Params::MAX_SPLIT_AXES.max(1));
+    axes
+}
+
+fn get_nodes_for_reinsertion<T, Params>(
+    node: &mut ParentNode<T>,
+    context: &InsertionContext<T>,
+) -> Vec<RTreeNode<T>>
 where
     T: RTreeObject,
     Params: RTreeParams,
 {
     let center = node.envelope.center();
     // Sort with increasing order so we can use Vec::split_off
-    node.children.sort_by(|l, r| {
-        let l_center = l.envelope().center();
-        let r_center = r.envelope().center();
-        l_center
-            .sub(&center)
-            .length_2()
-            .partial_cmp(&(r_center.sub(&center)).length_2())
-            .unwrap()
-    });
+    if Params::ENVELOPE_CACHING.caches_overflows() && node.children[0].is_leaf() {
+        let mut children: Vec<_> = node.children.drain(..).map(CachedEnvelope::new).collect();
+        sort_by_center_distance(&mut children, &center);
+        node.children
+            .extend(children.into_iter().map(CachedEnvelope::into_inner));
+    } else {
+        sort_by_center_distance(&mut node.children, &center);
+    }
     let num_children = node.children.len();
-    let result = node
+    let mut result = node
         .children
-        .split_off(num_children - Params::REINSERTION_COUNT);
+        .split_off(num_children - context.reinsertion_count);
     node.envelope = envelope_for_children(&node.children);
+    // The children are reinserted in order
+    if context.reinsertion_order == ReinsertionOrder::Far {
+        result.reverse();
+    }
     result
 }
+
+fn sort_by_center_distance<C>(children: &mut [C], center: &<C::Envelope as Envelope>::Point)
+where
+    C: RTreeObject,
+{
+    children.sort_by(|l, r| {
+        let l_center = l.envelope().center();
+        let r_center = r.envelope().center();
+        total_cmp(
+            &l_center.sub(center).length_2(),
+            &r_center.sub(center).length_2(),
+        )
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use crate::point::PointExt;
+    use crate::test_utilities::{create_random_integers, create_random_points, SEED_1, SEED_2};
+    use crate::{DefaultParams, Envelope, RTree, ReinsertionOrder, ReinsertionPolicy, AABB};
+
+    #[test]
+    fn test_insert_degenerate_envelopes() {
+        const SIZE: usize = 500;
+        let collinear: Vec<_> = create_random_integers::<[i32; 2]>(SIZE, SEED_1)
+            .iter()
+            .map(|p| [p[0] / 100, 0])
+            .collect();
+        let duplicates = vec![[1, 1]; SIZE];
+        for points in &[collinear, duplicates] {
+            let mut tree = RTree::new();
+            for p in points {
+                tree.insert(*p);
+                tree.root().sanity_check::<DefaultParams>();
+            }
+            for p in points {
+                assert!(tree.contains(p));
+            }
+            assert_eq!(
+                tree.locate_all_at_point(&points[0]).count(),
+                points.iter().filter(|p| **p == points[0]).count()
+            );
+        }
+    }
+
+    #[test]
+    fn test_insert_large_integers() {
+        // Areas and distances of these points exceed the range of i32
+        let points: Vec<[i32; 2]> = create_random_integers::<[i32; 2]>(1000, SEED_1)
+            .iter()
+            .map(|p| [p[0] * 20_000, p[1] * 20_000])
+            .collect();
+        let mut tree = RTree::new();
+        for p in &points {
+            tree.insert(*p);
+        }
+        tree.root().sanity_check::<DefaultParams>();
+        for p in &points {
+            assert!(tree.contains(p));
+        }
+        let query = AABB::from_corners([-500_000_000, 0], [i32::MAX, i32::MAX]);
+        assert_eq!(
+            tree.locate_in_envelope(&query).count(),
+            points.iter().filter(|p| query.contains_point(p)).count()
+        );
+
+        // Saturated distances are still comparable up to i32::MAX
+        assert!(tree.nearest_neighbor(&[0, 0]).is_some());
+
+        // Distances to far away points saturate, the nearest neighbor is still found
+        let wide: Vec<[i64; 2]> = points
+            .iter()
+            .map(|p| [i64::from(p[0]) * 1_000, i64::from(p[1]) * 1_000])
+            .collect();
+        let tree = RTree::bulk_load(wide.clone());
+        for query in &wide[..50] {
+            let query = [query[0] + 12_345, query[1] - 6_789];
+            let expected = wide
+                .iter()
+                .min_by_key(|p| {
+                    let (x, y) = (i128::from(p[0] - query[0]), i128::from(p[1] - query[1]));
+                    x * x + y * y
+                })
+                .unwrap();
+            assert_eq!(tree.nearest_neighbor(&query), Some(expected));
+        }
+    }
+
+    #[test]
+    fn test_insertion_metrics() {
+        const SIZE: usize = 1000;
+        let mut tree = RTree::new();
+        // Inserting the corners first makes all further insertions fit into the root envelope
+        tree.insert([-1.0, -1.0]);
+        tree.insert([1.0, 1.0]);
+        assert_eq!(tree.insertion_metrics().skipped_envelope_merges(), 0);
+        for p in create_random_points(SIZE, SEED_1) {
+            tree.insert(p);
+        }
+        tree.root().sanity_check::<DefaultParams>();
+        let metrics = *tree.insertion_metrics();
+        assert_eq!(metrics.insertions(), SIZE + 2);
+        assert!(metrics.skipped_envelope_merges() > metrics.envelope_merges());
+
+        tree.reset_insertion_metrics();
+        assert_eq!(tree.insertion_metrics().insertions(), 0);
+        let bulk_loaded = RTree::bulk_load(create_random_points(SIZE, SEED_1));
+        assert_eq!(bulk_loaded.insertion_metrics().insertions(), 0);
+    }
+
+    #[test]
+    fn test_reinsertion_metrics() {
+        const SIZE: usize = 2000;
+        let mut tree = RTree::new();
+        for p in create_random_points(SIZE, SEED_1) {
+            tree.insert(p);
+        }
+        let metrics = *tree.insertion_metrics();
+        assert!(metrics.overflows() > 0);
+        assert!(metrics.reinsertions() > 0);
+        assert!(metrics.relocated_reinsertions() > 0);
+        assert!(metrics.relocated_reinsertions() <= metrics.reinsertions());
+        assert_eq!(metrics.skipped_reinsertions(), 0);
+
+        // Reinserting duplicates always leads back to the same node
+        for &adaptive in &[false, true] {
+            let mut tree = RTree::new();
+            tree.set_adaptive_reinsertion(adaptive);
+            for _ in 0..SIZE {
+                tree.insert([1.0, 1.0]);
+            }
+            tree.root().sanity_check::<DefaultParams>();
+            assert_eq!(tree.size(), SIZE);
+            let metrics = *tree.insertion_metrics();
+            assert_eq!(metrics.skipped_reinsertions() > 0, adaptive);
+        }
+    }
+
+    #[test]
+    fn test_reinsertion_policies() {
+        let default = ReinsertionPolicy::default();
+        assert_eq!(default.count::<DefaultParams>(), 2);
+        assert_eq!(default.with_fraction(0.2).count::<DefaultParams>(), 1);
+        // Too large counts are limited to keep nodes above their minimum size
+        assert_eq!(default.with_fraction(1.0).count::<DefaultParams>(), 2);
+
+        let points = create_random_points(1000, SEED_1);
+        let policies = [
+            default.with_order(ReinsertionOrder::Far),
+            default.with_fraction(0.2),
+            default.with_fraction(0.0),
+        ];
+        for policy in &policies {
+            let mut tree = RTree::new();
+            tree.set_reinsertion_policy(*policy);
+            for p in &points {
+                tree.insert(*p);
+            }
+            tree.root().sanity_check::<DefaultParams>();
+            assert!(points.iter().all(|p| tree.contains(p)));
+            let metrics = *tree.insertion_metrics();
+            assert_eq!(
+                metrics.reinsertions() > 0,
+                policy.count::<DefaultParams>() > 0
+            );
+        }
+    }
+
+    #[test]
+    fn test_insert_non_finite_coordinates() {
+        use crate::primitives::Rectangle;
+
+        let points = create_random_points(1000, SEED_1);
+        let mut tree = RTree::new();
+        for (index, point) in points.iter().enumerate() {
+            tree.insert(*point);
+            if index % 10 == 3 {
+                tree.insert([f64::NAN, point[1]]);
+                tree.insert([point[0], f64::INFINITY]);
+            }
+        }
+        assert_eq!(tree.size(), 1200);
+        for point in &points {
+            assert!(tree.contains(point));
+        }
+        for query in create_random_points(100, SEED_2) {
+            let expected = points
+                .iter()
+                .map(|p| p.sub(&query).length_2())
+                .fold(f64::INFINITY, f64::min);
+            let nearest = tree.nearest_neighbor(&query).unwrap();
+            assert_eq!(nearest.sub(&query).length_2(), expected);
+        }
+        assert!(tree.try_insert([0.5, f64::NAN]).is_err());
+        assert_eq!(tree.size(), 1200);
+
+        let mut rectangles = RTree::new();
+        let huge = Rectangle::from_corners([-1e308, -1e308], [1e308, 1e308]);
+        assert_eq!(rectangles.try_insert(huge), Ok(()));
+        let invalid = Rectangle::from_corners([0.0, 0.0], [1.0, f64::INFINITY]);
+        assert_eq!(rectangles.try_insert(invalid), Err(invalid));
+        assert_eq!(rectangles.size(), 1);
+    }
+
+    #[test]
+    fn test_insert_with_envelope_caching() {
+        use crate::test_utilities::{take_envelope_calls, CountingPoint};
+        use crate::{EnvelopeCaching, RStarInsertionStrategy, RTreeParams};
+
+        struct CachingParams;
+
+        impl RTreeParams for CachingParams {
+            const MIN_SIZE: usize = 3;
+            const MAX_SIZE: usize = 6;
+            const REINSERTION_COUNT: usize = 2;
+            const ENVELOPE_CACHING: EnvelopeCaching = EnvelopeCaching::Always;
+            type DefaultInsertionStrategy = RStarInsertionStrategy;
+        }
+
+        let points = create_random_points(1000, SEED_1);
+        let mut uncached: RTree<_, DefaultParams> = RTree::new_with_params();
+        take_envelope_calls();
+        for point in &points {
+            uncached.insert(CountingPoint(*point));
+        }
+        let uncached_calls = take_envelope_calls();
+        let mut cached: RTree<_, CachingParams> = RTree::new_with_params();
+        for point in &points {
+            cached.insert(CountingPoint(*point));
+        }
+        assert!(take_envelope_calls() * 2 < uncached_calls);
+        cached.root().sanity_check::<CachingParams>();
+        // Splits and reinsertions make the same decisions
+        assert!(cached.iter().eq(uncached.iter()));
+    }
+
+    #[test]
+    fn test_sampled_split_axes() {
+        use crate::{RStarInsertionStrategy, RTreeParams};
+
+        struct SampledAxesParams;
+
+        impl RTreeParams for SampledAxesParams {
+            const MIN_SIZE: usize = 3;
+            const MAX_SIZE: usize = 8;
+            const REINSERTION_COUNT: usize = 2;
+            const MAX_SPLIT_AXES: usize = 3;
+            type DefaultInsertionStrategy = RStarInsertionStrategy;
+        }
+
+        let points: Vec<[f64; 16]> = create_random_points(8000, SEED_1)
+            .chunks(8)
+            .map(|chunk| {
+                let mut point = [0.0; 16];
+                for (i, coordinate) in point.iter_mut().enumerate() {
+                    *coordinate = chunk[i / 2][i % 2];
+                }
+                point
+            })
+            .collect();
+        let mut tree: RTree<_, SampledAxesParams> = RTree::new_with_params();
+        for point in &points {
+            tree.insert(*point);
+        }
+        tree.root().sanity_check::<SampledAxesParams>();
+        for point in &points[..100] {
+            assert_eq!(tree.nearest_neighbor(point), Some(point));
+        }
+    }
+}
@@ -1,4 +1,7 @@
+use crate::aabb::AABB;
+use crate::affine_transform::AffineTransform;
 use crate::envelope::Envelope;
+use crate::metric::Metric;
 use crate::object::PointDistance;
 use crate::object::RTreeObject;
 use crate::Point;
@@ -41,6 +44,17 @@ where
     envelope: T::Envelope,
 }
 
+impl<T> Clone for SelectInEnvelopeFunction<T>
+where
+    T: RTreeObject,
+{
+    fn clone(&self) -> Self {
+        SelectInEnvelopeFunction {
+            envelope: self.envelope,
+        }
+    }
+}
+
 impl<T> SelectInEnvelopeFunction<T>
 where
     T: RTreeObject,
@@ -63,6 +77,54 @@ where
     }
 }
 
+pub struct SelectInEnvelopeWhereFunction<T, F>
+where
+    T: RTreeObject,
+{
+    envelope: T::Envelope,
+    predicate: F,
+}
+
+impl<T, F> Clone for SelectInEnvelopeWhereFunction<T, F>
+where
+    T: RTreeObject,
+    F: Clone,
+{
+    fn clone(&self) -> Self {
+        SelectInEnvelopeWhereFunction {
+            envelope: self.envelope,
+            predicate: self.predicate.clone(),
+        }
+    }
+}
+
+impl<T, F> SelectInEnvelopeWhereFunction<T, F>
+where
+    T: RTreeObject,
+    F: Fn(&T) -> bool,
+{
+    pub fn new(envelope: T::Envelope, predicate: F) -> Self {
+        SelectInEnvelopeWhereFunction {
+            envelope,
+            predicate,
+        }
+    }
+}
+
+impl<T, F> SelectionFunction<T> for SelectInEnvelopeWhereFunction<T, F>
+where
+    T: RTreeObject,
+    F: Fn(&T) -> bool,
+{
+    fn should_unpack_parent(&self, envelope: &T::Envelope) -> bool {
+        self.envelope.intersects(envelope)
+    }
+
+    fn should_unpack_leaf(&self, leaf: &T) -> bool {
+        self.envelope.contains_envelope(&leaf.envelope()) && (self.predicate)(leaf)
+    }
+}
+
 pub struct SelectInEnvelopeFuncIntersecting<T>
 where
     T: RTreeObject,
@@ -70,6 +132,17 @@ where
     envelope: T::Envelope,
 }
 
+impl<T> Clone for SelectInEnvelopeFuncIntersecting<T>
+where
+    T: RTreeObject,
+{
+    fn clone(&self) -> Self {
+        SelectInEnvelopeFuncIntersecting {
+            envelope: self.envelope,
+        }
+    }
+}
+
 impl<T> SelectInEnvelopeFuncIntersecting<T>
 where
     T: RTreeObject,
@@ -92,6 +165,98 @@ where
     }
 }
 
+#[derive(Clone)]
+pub struct SelectInTransformedEnvelopeIntersectingFunction<P>
+where
+    P: Point,
+{
+    envelope: AABB<P>,
+    transform: AffineTransform<P>,
+}
+
+impl<P> SelectInTransformedEnvelopeIntersectingFunction<P>
+where
+    P: Point,
+{
+    pub fn new(envelope: AABB<P>, transform: AffineTransform<P>) -> Self {
+        SelectInTransformedEnvelopeIntersectingFunction {
+            envelope,
+            transform,
+        }
+    }
+}
+
+impl<T, P> SelectionFunction<T> for SelectInTransformedEnvelopeIntersectingFunction<P>
+where
+    T: RTreeObject<Envelope = AABB<P>>,
+    P: Point,
+{
+    fn should_unpack_parent(&self, envelope: &AABB<P>) -> bool {
+        self.transform
+            .transform_envelope(envelope)
+            .intersects(&self.envelope)
+    }
+
+    fn should_unpack_leaf(&self, leaf: &T) -> bool {
+        self.transform
+            .transform_envelope(&leaf.envelope())
+            .intersects(&self.envelope)
+    }
+}
+
+pub struct SelectInEnvelopeExcludingFunction<T>
+where
+    T: RTreeObject,
+{
+    envelope: T::Envelope,
+    excluded: Vec<T::Envelope>,
+}
+
+impl<T> Clone for SelectInEnvelopeExcludingFunction<T>
+where
+    T: RTreeObject,
+{
+    fn clone(&self) -> Self {
+        SelectInEnvelopeExcludingFunction {
+            envelope: self.envelope,
+            excluded: self.excluded.clone(),
+        }
+    }
+}
+
+impl<T> SelectInEnvelopeExcludingFunction<T>
+where
+    T: RTreeObject,
+{
+    pub fn new(envelope: T::Envelope, excluded: Vec<T::Envelope>) -> Self {
+        SelectInEnvelopeExcludingFunction { envelope, excluded }
+    }
+}
+
+impl<T> SelectionFunction<T> for SelectInEnvelopeExcludingFunction<T>
+where
+    T: RTreeObject,
+{
+    fn should_unpack_parent(&self, envelope: &T::Envelope) -> bool {
+        // Any child of a parent fully contained in an excluded envelope is excluded as well
+        self.envelope.intersects(envelope)
+            && !self
+                .excluded
+                .iter()
+                .any(|excluded| excluded.contains_envelope(envelope))
+    }
+
+    fn should_unpack_leaf(&self, leaf: &T) -> bool {
+        let envelope = leaf.envelope();
+        envelope.intersects(&self.envelope)
+            && !self
+                .excluded
+                .iter()
+                .any(|excluded| excluded.intersects(&envelope))
+    }
+}
+
+#[derive(Clone, Copy)]
 pub struct SelectAllFunc;
 
 impl<T> SelectionFunction<T> for SelectAllFunc
@@ -116,6 +281,15 @@ where
     point: <T::Envelope as Envelope>::Point,
 }
 
+impl<T> Clone for SelectAtPointFunction<T>
+where
+    T: RTreeObject,
+{
+    fn clone(&self) -> Self {
+        SelectAtPointFunction { point: self.point }
+    }
+}
+
 impl<T> SelectAtPointFunction<T>
 where
     T: PointDistance,
@@ -178,6 +352,18 @@ where
     squared_max_distance: <<T::Envelope as Envelope>::Point as Point>::Scalar,
 }
 
+impl<T> Clone for SelectWithinDistanceFunction<T>
+where
+    T: RTreeObject + PointDistance,
+{
+    fn clone(&self) -> Self {
+        SelectWithinDistanceFunction {
+            circle_origin: self.circle_origin,
+            squared_max_distance: self.squared_max_distance,
+        }
+    }
+}
+
 impl<T> SelectWithinDistanceFunction<T>
 where
     T: RTreeObject + PointDistance,
@@ -208,6 +394,64 @@ where
     }
 }
 
+pub struct SelectWithinMetricDistanceFunction<T, M>
+where
+    T: RTreeObject,
+    M: Metric<T>,
+{
+    circle_origin: <T::Envelope as Envelope>::Point,
+    max_distance: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+    metric: M,
+}
+
+impl<T, M> Clone for SelectWithinMetricDistanceFunction<T, M>
+where
+    T: RTreeObject,
+    M: Metric<T> + Clone,
+{
+    fn clone(&self) -> Self {
+        SelectWithinMetricDistanceFunction {
+            circle_origin: self.circle_origin,
+            max_distance: self.max_distance,
+            metric: self.metric.clone(),
+        }
+    }
+}
+
+impl<T, M> SelectWithinMetricDistanceFunction<T, M>
+where
+    T: RTreeObject,
+    M: Metric<T>,
+{
+    pub fn new(
+        circle_origin: <T::Envelope as Envelope>::Point,
+        max_distance: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+        metric: M,
+    ) -> Self {
+        SelectWithinMetricDistanceFunction {
+            circle_origin,
+            max_distance,
+            metric,
+        }
+    }
+}
+
+impl<T, M> SelectionFunction<T> for SelectWithinMetricDistanceFunction<T, M>
+where
+    T: RTreeObject,
+    M: Metric<T>,
+{
+    fn should_unpack_parent(&self, parent_envelope: &T::Envelope) -> bool {
+        self.metric
+            .envelope_distance(parent_envelope, &self.circle_origin)
+            <= self.max_distance
+    }
+
+    fn should_unpack_leaf(&self, leaf: &T) -> bool {
+        self.metric.distance(leaf, &self.circle_origin) <= self.max_distance
+    }
+}
+
 pub struct SelectByAddressFunction<T>
 where
     T: RTreeObject,
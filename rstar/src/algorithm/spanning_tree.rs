@@ -0,0 +1,244 @@
+use crate::aabb::AABB;
+use crate::node::{ParentNode, RTreeNode};
+use crate::point::{Point, PointExt};
+
+/// A copy of the tree's structure that tracks which component of the spanning forest each
+/// node belongs to.
+enum ComponentNode<P>
+where
+    P: Point,
+{
+    Leaf(usize),
+    Parent {
+        envelope: AABB<P>,
+        // Set if all points within this node belong to the same component
+        component: Option<usize>,
+        children: Vec<ComponentNode<P>>,
+    },
+}
+
+impl<P> ComponentNode<P>
+where
+    P: Point,
+{
+    fn new<'a>(node: &'a ParentNode<P>, points: &mut Vec<&'a P>) -> Self {
+        let children = node
+            .children
+            .iter()
+            .map(|child| match child {
+                RTreeNode::Leaf(ref point) => {
+                    points.push(point);
+                    ComponentNode::Leaf(points.len() - 1)
+                }
+                RTreeNode::Parent(ref data) => ComponentNode::new(data, points),
+            })
+            .collect();
+        ComponentNode::Parent {
+            envelope: node.envelope,
+            component: None,
+            children,
+        }
+    }
+
+    fn update_components(&mut self, components: &[usize]) -> Option<usize> {
+        match self {
+            ComponentNode::Leaf(index) => Some(components[*index]),
+            ComponentNode::Parent {
+                component,
+                children,
+                ..
+            } => {
+                let mut result = None;
+                let mut is_uniform = true;
+                for child in children.iter_mut() {
+                    let child_component = child.update_components(components);
+                    if result.is_none() {
+                        result = child_component;
+                    }
+                    is_uniform &= child_component.is_some() && child_component == result;
+                }
+                *component = if is_uniform { result } else { None };
+                *component
+            }
+        }
+    }
+}
+
+/// The shortest edge found so far, as `(distance_2, from, to)` with `from < to`.
+type Edge<S> = (S, usize, usize);
+
+/// Orders edges by length. Ties are broken by the points' indices, making all edge weights
+/// distinct. Otherwise, components could pick edges that form a cycle.
+fn is_shorter<S: PartialOrd>(edge: &Edge<S>, other: &Option<Edge<S>>) -> bool {
+    match other {
+        None => true,
+        Some((distance_2, from, to)) => {
+            edge.0 < *distance_2 || (edge.0 == *distance_2 && (edge.1, edge.2) < (*from, *to))
+        }
+    }
+}
+
+struct EdgeSearch<'a, 'b, P>
+where
+    P: Point,
+{
+    points: &'b [&'a P],
+    components: &'b [usize],
+    query_index: usize,
+    query_component: usize,
+}
+
+impl<'a, 'b, P> EdgeSearch<'a, 'b, P>
+where
+    P: Point,
+{
+    fn search(&self, node: &ComponentNode<P>, best: &mut Option<Edge<P::Scalar>>) {
+        match node {
+            ComponentNode::Leaf(index) => {
+                if self.components[*index] != self.query_component {
+                    let query_point = self.points[self.query_index];
+                    let distance_2 = query_point.sub(self.points[*index]).length_2();
+                    let (from, to) = if self.query_index < *index {
+                        (self.query_index, *index)
+                    } else {
+                        (*index, self.query_index)
+                    };
+                    let edge = (distance_2, from, to);
+                    if is_shorter(&edge, best) {
+                        *best = Some(edge);
+                    }
+                }
+            }
+            ComponentNode::Parent {
+                envelope,
+                component,
+                children,
+            } => {
+                if *component == Some(self.query_component) {
+                    // The node cannot contain any edge leaving the component
+                    return;
+                }
+                if let Some((distance_2, _, _)) = best {
+                    if envelope.distance_2(self.points[self.query_index]) > *distance_2 {
+                        return;
+                    }
+                }
+                for child in children {
+                    self.search(child, best);
+                }
+            }
+        }
+    }
+}
+
+fn find(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+/// Calculates the euclidean minimum spanning tree of all points in a tree using a single-tree
+/// variant of Borůvka's algorithm.
+///
+/// Each round, the shortest edge leaving each component of the spanning forest is searched
+/// for every point. Nodes that only contain points of the query's own component are pruned,
+/// as well as nodes that are farther away than the best edge of the component found so far.
+///
+/// Every round at least halves the number of components, so there are at most `log2(n)`
+/// rounds of `n` searches each. Unlike dual-tree Borůvka, nodes of query points are not
+/// pruned as a whole. For evenly distributed points, a search visits `O(log(n))` nodes, which
+/// results in `O(n log²(n))`. In the worst case, each search visits all nodes and the runtime
+/// degrades to `O(n² log(n))`.
+pub fn euclidean_mst<P>(root: &ParentNode<P>) -> Vec<(&P, &P, P::Scalar)>
+where
+    P: Point,
+{
+    let mut points = Vec::new();
+    let mut component_tree = ComponentNode::new(root, &mut points);
+    let mut parents: Vec<_> = (0..points.len()).collect();
+    let mut result = Vec::with_capacity(points.len().saturating_sub(1));
+
+    while result.len() + 1 < points.len() {
+        let components: Vec<_> = (0..points.len())
+            .map(|index| find(&mut parents, index))
+            .collect();
+        component_tree.update_components(&components);
+
+        let mut best_edges = vec![None; points.len()];
+        for (query_index, &query_component) in components.iter().enumerate() {
+            let search = EdgeSearch {
+                points: &points,
+                components: &components,
+                query_index,
+                query_component,
+            };
+            search.search(&component_tree, &mut best_edges[query_component]);
+        }
+
+        for (distance_2, from, to) in best_edges.into_iter().flatten() {
+            let (from_root, to_root) = (find(&mut parents, from), find(&mut parents, to));
+            if from_root != to_root {
+                parents[from_root] = to_root;
+                result.push((points[from], points[to], distance_2));
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::point::PointExt;
+    use crate::test_utilities::{create_random_points, SEED_1};
+    use crate::RTree;
+
+    #[test]
+    fn test_euclidean_mst() {
+        let points = create_random_points(300, SEED_1);
+        let mut tree = RTree::bulk_load(points.clone());
+        // Duplicates must be connected by edges of length zero
+        tree.insert(points[0]);
+
+        // Compare against Prim's algorithm
+        let mut expected = 0.0;
+        let mut distances: Vec<_> = points
+            .iter()
+            .map(|p| p.sub(&points[0]).length_2())
+            .collect();
+        let mut is_connected = vec![false; points.len()];
+        is_connected[0] = true;
+        for _ in 1..points.len() {
+            let (next, distance_2) = distances
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !is_connected[*index])
+                .min_by(|l, r| l.1.partial_cmp(r.1).unwrap())
+                .map(|(index, distance_2)| (index, *distance_2))
+                .unwrap();
+            expected += distance_2;
+            is_connected[next] = true;
+            for (index, point) in points.iter().enumerate() {
+                let new_distance = point.sub(&points[next]).length_2();
+                if new_distance < distances[index] {
+                    distances[index] = new_distance;
+                }
+            }
+        }
+
+        let edges = tree.euclidean_mst();
+        assert_eq!(edges.len(), points.len());
+        let total: f64 = edges.iter().map(|edge| edge.2).sum();
+        assert!((total - expected).abs() < 1e-9);
+        assert_eq!(edges.iter().filter(|edge| edge.2 == 0.0).count(), 1);
+        for (from, to, distance_2) in &edges {
+            assert_eq!(from.sub(to).length_2(), *distance_2);
+        }
+
+        assert!(RTree::<[f64; 2]>::new().euclidean_mst().is_empty());
+        assert!(RTree::bulk_load(vec![[0.0, 1.0]])
+            .euclidean_mst()
+            .is_empty());
+    }
+}
@@ -0,0 +1,334 @@
+use crate::envelope::Envelope;
+use crate::node::{ParentNode, RTreeNode};
+use crate::object::RTreeObject;
+
+/// Samples elements of an r-tree with a probability proportional to their weight.
+///
+/// Created by [RTree::weighted_sampler](struct.RTree.html#method.weighted_sampler). The
+/// sampler stores the weight sums of all tree nodes, drawing a sample takes `O(log(n))`.
+/// Since it borrows the tree, the sums can never become outdated. The weights of elements in a
+/// region can be changed with [update_weights_in_envelope](#method.update_weights_in_envelope),
+/// which only recalculates the sums of the affected nodes.
+///
+/// This crate does not depend on a random number generator, each sample is instead chosen
+/// by a number in the range `[0, 1)` that should be drawn from a uniform distribution.
+///
+/// # Example
+/// ```
+/// use rstar::RTree;
+/// use rstar::primitives::PointWithData;
+///
+/// let tree = RTree::bulk_load(vec![
+///     PointWithData::new(1.0, [0.0, 0.0]),
+///     PointWithData::new(3.0, [1.0, 0.0]),
+/// ]);
+/// let sampler = tree.weighted_sampler(|point| point.data).unwrap();
+/// assert_eq!(sampler.total_weight(), 4.0);
+/// // The second point covers 75% of the range
+/// assert_eq!(sampler.sample(0.1).unwrap().data, 1.0);
+/// assert_eq!(sampler.sample(0.3).unwrap().data, 3.0);
+/// assert_eq!(sampler.sample(0.9).unwrap().data, 3.0);
+/// ```
+pub struct WeightedSampler<'a, T>
+where
+    T: RTreeObject,
+{
+    root: &'a ParentNode<T>,
+    weights: NodeWeights,
+}
+
+struct NodeWeights {
+    // Weights of the node's children, the weight of a parent node is the sum of its children
+    weights: Vec<f64>,
+    total: f64,
+    // Weights of all children that are parent nodes, empty for nodes containing leaves.
+    // A node never contains both leaves and parent nodes.
+    children: Vec<NodeWeights>,
+}
+
+fn is_valid_weight(weight: f64) -> bool {
+    weight >= 0.0 && weight.is_finite()
+}
+
+impl NodeWeights {
+    fn new<T, F>(node: &ParentNode<T>, weight: &mut F) -> Option<Self>
+    where
+        T: RTreeObject,
+        F: FnMut(&T) -> f64,
+    {
+        let mut weights = Vec::with_capacity(node.children.len());
+        let mut children = Vec::new();
+        for child in &node.children {
+            weights.push(match child {
+                RTreeNode::Leaf(ref t) => {
+                    let weight = weight(t);
+                    if !is_valid_weight(weight) {
+                        return None;
+                    }
+                    weight
+                }
+                RTreeNode::Parent(ref data) => {
+                    let weights = NodeWeights::new(data, weight)?;
+                    let total = weights.total;
+                    children.push(weights);
+                    total
+                }
+            });
+        }
+        let total = weights.iter().sum();
+        Some(NodeWeights {
+            weights,
+            total,
+            children,
+        })
+    }
+
+    fn update<T, I>(&mut self, node: &ParentNode<T>, envelope: &T::Envelope, new_weights: &mut I)
+    where
+        T: RTreeObject,
+        I: Iterator<Item = f64>,
+    {
+        for (index, child) in node.children.iter().enumerate() {
+            if !child.envelope().intersects(envelope) {
+                continue;
+            }
+            match child {
+                RTreeNode::Leaf(_) => {
+                    self.weights[index] = new_weights.next().expect("Missing element weight")
+                }
+                RTreeNode::Parent(ref data) => {
+                    let weights = &mut self.children[index];
+                    weights.update(data, envelope, new_weights);
+                    self.weights[index] = weights.total;
+                }
+            }
+        }
+        self.total = self.weights.iter().sum();
+    }
+}
+
+/// Collects the weights of all elements intersecting `envelope` in the order in which
+/// `NodeWeights::update` visits them. Returns `false` on the first invalid weight.
+fn collect_weights<T, F>(
+    node: &ParentNode<T>,
+    envelope: &T::Envelope,
+    weight: &mut F,
+    result: &mut Vec<f64>,
+) -> bool
+where
+    T: RTreeObject,
+    F: FnMut(&T) -> f64,
+{
+    node.children
+        .iter()
+        .filter(|child| child.envelope().intersects(envelope))
+        .all(|child| match child {
+            RTreeNode::Leaf(ref t) => {
+                let weight = weight(t);
+                result.push(weight);
+                is_valid_weight(weight)
+            }
+            RTreeNode::Parent(ref data) => collect_weights(data, envelope, weight, result),
+        })
+}
+
+impl<'a, T> WeightedSampler<'a, T>
+where
+    T: RTreeObject,
+{
+    pub(crate) fn new<F>(root: &'a ParentNode<T>, mut weight: F) -> Option<Self>
+    where
+        F: FnMut(&T) -> f64,
+    {
+        NodeWeights::new(root, &mut weight)
+            .filter(|weights| weights.total > 0.0 && weights.total.is_finite())
+            .map(|weights| WeightedSampler { root, weights })
+    }
+
+    /// Returns the sum of all weights.
+    pub fn total_weight(&self) -> f64 {
+        self.weights.total
+    }
+
+    /// Changes the weights of all elements intersecting an envelope.
+    ///
+    /// `weight` is called once for every element intersecting `envelope`, the weights of all
+    /// other elements are kept. Only the sums of nodes intersecting `envelope` are
+    /// recalculated.
+    ///
+    /// Returns `false` and leaves the sampler unchanged if a new weight is negative, NaN or
+    /// infinite, or if the total weight would become infinite.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, AABB};
+    ///
+    /// let tree = RTree::bulk_load(vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]]);
+    /// let mut sampler = tree.weighted_sampler(|_| 1.0).unwrap();
+    /// let region = AABB::from_corners([0.5, -1.0], [2.5, 1.0]);
+    /// assert!(sampler.update_weights_in_envelope(&region, |_| 0.0));
+    /// assert_eq!(sampler.total_weight(), 1.0);
+    /// assert_eq!(sampler.sample(0.9), Some(&[0.0, 0.0]));
+    /// assert!(!sampler.update_weights_in_envelope(&region, |_| -1.0));
+    /// assert_eq!(sampler.total_weight(), 1.0);
+    /// ```
+    pub fn update_weights_in_envelope<F>(&mut self, envelope: &T::Envelope, mut weight: F) -> bool
+    where
+        F: FnMut(&T) -> f64,
+    {
+        let mut new_weights = Vec::new();
+        if !collect_weights(self.root, envelope, &mut weight, &mut new_weights) {
+            return false;
+        }
+        // The weights that are replaced are non-negative, so the sums of all nodes stay below
+        // this bound
+        let new_sum: f64 = new_weights.iter().sum();
+        if !(self.weights.total + new_sum).is_finite() {
+            return false;
+        }
+        self.weights
+            .update(self.root, envelope, &mut new_weights.into_iter());
+        true
+    }
+
+    /// Returns the element at relative position `u` of the accumulated weights.
+    ///
+    /// If `u` is drawn uniformly from `[0, 1)`, each element is returned with a probability
+    /// proportional to its weight. Elements with a weight of zero are never returned.
+    /// Returns `None` if all weights have been set to zero with
+    /// [update_weights_in_envelope](#method.update_weights_in_envelope).
+    pub fn sample(&self, u: f64) -> Option<&'a T> {
+        if self.weights.total <= 0.0 {
+            return None;
+        }
+        let mut target = u.max(0.0) * self.weights.total;
+        let mut node = self.root;
+        let mut weights = &self.weights;
+        loop {
+            let mut index = None;
+            for (child_index, weight) in weights.weights.iter().enumerate() {
+                if *weight <= 0.0 {
+                    continue;
+                }
+                index = Some(child_index);
+                if target < *weight {
+                    break;
+                }
+                target -= weight;
+            }
+            // Rounding errors may push the target beyond the last child, the last child with a
+            // positive weight is chosen in this case
+            let index = index?;
+            target = target.min(weights.weights[index]);
+            match node.children[index] {
+                RTreeNode::Leaf(ref t) => return Some(t),
+                RTreeNode::Parent(ref data) => {
+                    node = data;
+                    weights = &weights.children[index];
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::primitives::PointWithData;
+    use crate::test_utilities::{create_random_points, SEED_1};
+    use crate::{Envelope, RTree, AABB};
+
+    #[test]
+    fn test_weighted_sampling() {
+        const SIZE: usize = 500;
+        const SAMPLES: usize = 100_000;
+        let points: Vec<_> = create_random_points(SIZE, SEED_1)
+            .into_iter()
+            .enumerate()
+            .map(|(index, point)| PointWithData::new(index, point))
+            .collect();
+        let weight = |index: usize| (index % 5) as f64;
+        let tree = RTree::bulk_load(points);
+        let sampler = tree.weighted_sampler(|point| weight(point.data)).unwrap();
+        let total: f64 = (0..SIZE).map(weight).sum();
+        assert_eq!(sampler.total_weight(), total);
+
+        let mut counts = vec![0; SIZE];
+        for sample in 0..SAMPLES {
+            let u = (sample as f64 + 0.5) / SAMPLES as f64;
+            counts[sampler.sample(u).unwrap().data] += 1;
+        }
+        for (index, count) in counts.iter().enumerate() {
+            let expected = weight(index) / total * SAMPLES as f64;
+            assert!((*count as f64 - expected).abs() <= 1.0);
+        }
+        assert_eq!(
+            sampler.sample(1.0).map(|p| weight(p.data) > 0.0),
+            Some(true)
+        );
+        assert!(RTree::<PointWithData<usize, [f64; 2]>>::new()
+            .weighted_sampler(|_| 1.0)
+            .is_none());
+    }
+
+    #[test]
+    fn test_weighted_sampler_rejects_invalid_weights() {
+        let tree = RTree::bulk_load(create_random_points(500, SEED_1));
+        assert!(tree.weighted_sampler(|_| 0.0).is_none());
+        assert!(tree.weighted_sampler(|_| f64::MAX).is_none());
+        for invalid in &[-1.0, f64::NAN, f64::INFINITY] {
+            let mut calls = 0;
+            let sampler = tree.weighted_sampler(|_| {
+                calls += 1;
+                if calls == 250 {
+                    *invalid
+                } else {
+                    1.0
+                }
+            });
+            assert!(sampler.is_none());
+        }
+    }
+
+    #[test]
+    fn test_update_weights_in_envelope() {
+        const SIZE: usize = 500;
+        let points = create_random_points(SIZE, SEED_1);
+        let tree = RTree::bulk_load(points.clone());
+        let mut sampler = tree.weighted_sampler(|_| 1.0).unwrap();
+        let region = AABB::from_corners([-0.2, -0.2], [0.3, 0.4]);
+        let inside = points.iter().filter(|p| region.contains_point(p)).count();
+        assert!(inside > 0);
+
+        assert!(!sampler.update_weights_in_envelope(&region, |_| f64::NAN));
+        assert_eq!(sampler.total_weight(), SIZE as f64);
+
+        let mut calls = 0;
+        assert!(sampler.update_weights_in_envelope(&region, |_| {
+            calls += 1;
+            0.0
+        }));
+        assert_eq!(calls, inside);
+        assert_eq!(sampler.total_weight(), (SIZE - inside) as f64);
+        for sample in 0..1000 {
+            let point = sampler.sample(sample as f64 / 1000.0).unwrap();
+            assert!(!region.contains_point(point));
+        }
+
+        let all = tree.root().envelope();
+        assert!(
+            sampler.update_weights_in_envelope(&all, |p| if region.contains_point(p) {
+                1.0
+            } else {
+                0.0
+            })
+        );
+        assert_eq!(sampler.total_weight(), inside as f64);
+        for sample in 0..1000 {
+            let point = sampler.sample(sample as f64 / 1000.0).unwrap();
+            assert!(region.contains_point(point));
+        }
+
+        assert!(sampler.update_weights_in_envelope(&all, |_| 0.0));
+        assert!(sampler.sample(0.5).is_none());
+    }
+}
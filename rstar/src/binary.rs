@@ -0,0 +1,750 @@
+use crate::aabb::AABB;
+use crate::envelope::Envelope;
+use crate::metadata::RTreeMetadata;
+use crate::node::{ParentNode, RTreeNode};
+use crate::object::RTreeObject;
+use crate::params::RTreeParams;
+use crate::point::{scalar_tag, Point};
+use crate::primitives::{Ball, GeomWithData, Line, PointWithData, Rectangle, Triangle};
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+
+/// Identifies the binary format, followed by the format version.
+const MAGIC: &[u8; 4] = b"RSTR";
+const VERSION: u16 = 2;
+
+pub const LEAF_TAG: u8 = 0;
+pub const NODE_TAG: u8 = 1;
+pub const ROOT_TAG: u8 = 2;
+
+/// Types that can be stored in rstar's binary format.
+///
+/// The format is used by [RTree::write_to](struct.RTree.html#method.write_to) and
+/// [RTree::read_from](struct.RTree.html#method.read_from). It is implemented for all primitive
+/// number types, arrays, [AABB](struct.AABB.html)s and the crate's
+/// [primitives](primitives/index.html). All numbers are stored in little endian byte order.
+///
+/// # Example
+/// ```
+/// use rstar::{BinaryCodable, RTree};
+/// use rstar::primitives::PointWithData;
+/// use std::io::{self, Read, Write};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct SensorId(u32);
+///
+/// impl BinaryCodable for SensorId {
+///     fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+///         self.0.write_to(writer)
+///     }
+///
+///     fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+///         u32::read_from(reader).map(SensorId)
+///     }
+/// }
+///
+/// let tree = RTree::bulk_load(vec![PointWithData::new(SensorId(7), [1.0, 2.0])]);
+/// let bytes = tree.to_bytes();
+/// let loaded: RTree<PointWithData<SensorId, [f64; 2]>> = RTree::from_bytes(&bytes).unwrap();
+/// assert_eq!(loaded.iter().next().unwrap().data, SensorId(7));
+/// ```
+pub trait BinaryCodable: Sized {
+    /// Writes the value to a writer.
+    fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()>;
+
+    /// Reads a value that was written by [write_to](#tymethod.write_to).
+    fn read_from<R: Read>(reader: &mut R) -> io::Result<Self>;
+}
+
+macro_rules! implement_binary_codable_for_number {
+    ($($number:ty),*) => {
+        $(
+            impl BinaryCodable for $number {
+                fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+                    writer.write_all(&self.to_le_bytes())
+                }
+
+                fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+                    let mut bytes = [0; std::mem::size_of::<$number>()];
+                    reader.read_exact(&mut bytes)?;
+                    Ok(<$number>::from_le_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+implement_binary_codable_for_number!(i8, i16, i32, i64, i128, u8, u16, u32, u64, u128, f32, f64);
+
+impl<S, const N: usize> BinaryCodable for [S; N]
+where
+    S: BinaryCodable + Copy + Default,
+{
+    fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.iter().try_for_each(|value| value.write_to(writer))
+    }
+
+    fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut result = [S::default(); N];
+        for value in &mut result {
+            *value = S::read_from(reader)?;
+        }
+        Ok(result)
+    }
+}
+
+impl<P> BinaryCodable for AABB<P>
+where
+    P: Point + BinaryCodable,
+{
+    fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.lower().write_to(writer)?;
+        self.upper().write_to(writer)
+    }
+
+    fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let lower = P::read_from(reader)?;
+        let upper = P::read_from(reader)?;
+        Ok(AABB::from_lower_and_upper(lower, upper))
+    }
+}
+
+impl<P> BinaryCodable for Rectangle<P>
+where
+    P: Point + BinaryCodable,
+{
+    fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.envelope().write_to(writer)
+    }
+
+    fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        AABB::read_from(reader).map(Rectangle::from_aabb)
+    }
+}
+
+impl<P> BinaryCodable for Ball<P>
+where
+    P: Point + BinaryCodable,
+    P::Scalar: BinaryCodable,
+{
+    fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.center().write_to(writer)?;
+        self.radius().write_to(writer)
+    }
+
+    fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let center = P::read_from(reader)?;
+        let radius = P::Scalar::read_from(reader)?;
+        let is_valid = radius >= num_traits::Zero::zero();
+        if !is_valid {
+            return Err(invalid_data("Invalid ball radius"));
+        }
+        Ok(Ball::new(center, radius))
+    }
+}
+
+impl<P> BinaryCodable for Line<P>
+where
+    P: Point + BinaryCodable,
+{
+    fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.from.write_to(writer)?;
+        self.to.write_to(writer)
+    }
+
+    fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let from = P::read_from(reader)?;
+        let to = P::read_from(reader)?;
+        Ok(Line::new(from, to))
+    }
+}
+
+impl<P> BinaryCodable for Triangle<P>
+where
+    P: Point + BinaryCodable,
+{
+    fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.a.write_to(writer)?;
+        self.b.write_to(writer)?;
+        self.c.write_to(writer)
+    }
+
+    fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let a = P::read_from(reader)?;
+        let b = P::read_from(reader)?;
+        let c = P::read_from(reader)?;
+        Ok(Triangle::new(a, b, c))
+    }
+}
+
+impl<T, P> BinaryCodable for PointWithData<T, P>
+where
+    T: BinaryCodable,
+    P: Point + BinaryCodable,
+{
+    fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.data.write_to(writer)?;
+        self.position().write_to(writer)
+    }
+
+    fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let data = T::read_from(reader)?;
+        let position = P::read_from(reader)?;
+        Ok(PointWithData::new(data, position))
+    }
+}
+
+impl<G, T> BinaryCodable for GeomWithData<G, T>
+where
+    G: BinaryCodable,
+    T: BinaryCodable,
+{
+    fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.geom().write_to(writer)?;
+        self.data.write_to(writer)
+    }
+
+    fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let geom = G::read_from(reader)?;
+        let data = T::read_from(reader)?;
+        Ok(GeomWithData::new(geom, data))
+    }
+}
+
+type ScalarOf<T> = <<<T as RTreeObject>::Envelope as Envelope>::Point as Point>::Scalar;
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_string<W: Write>(string: &str, writer: &mut W) -> io::Result<()> {
+    (string.len() as u64).write_to(writer)?;
+    writer.write_all(string.as_bytes())
+}
+
+fn read_string<R: Read>(reader: &mut R) -> io::Result<String> {
+    let len = u64::read_from(reader)?;
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    String::from_utf8(bytes).map_err(|_| invalid_data("Invalid UTF-8 in metadata"))
+}
+
+/// Counts the bytes written so far, these are used as record offsets.
+struct PositionWriter<'a, W> {
+    inner: &'a mut W,
+    position: u64,
+}
+
+impl<'a, W: Write> Write for PositionWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.position += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Writes a tree in the binary format.
+///
+/// The header contains the format version, the tree's dimension, scalar type and
+/// parameters as well as its metadata. The nodes follow in post order: Every record is
+/// either a tagged leaf containing an element or a tagged node containing its envelope,
+/// its [id](struct.ParentNode.html#method.id) and the offsets of its children's records.
+/// Children are always stored before their parent, the root's offset is appended last.
+/// This allows to read a tree sequentially as well as to query it in place, see
+/// [MappedRTree](struct.MappedRTree.html).
+pub fn write_tree<T, Params, W>(
+    root: &ParentNode<T>,
+    metadata: &RTreeMetadata,
+    writer: &mut W,
+) -> io::Result<()>
+where
+    T: RTreeObject + BinaryCodable,
+    T::Envelope: BinaryCodable,
+    Params: RTreeParams,
+    W: Write,
+{
+    let mut writer = PositionWriter {
+        inner: writer,
+        position: 0,
+    };
+    let writer = &mut writer;
+    writer.write_all(MAGIC)?;
+    VERSION.write_to(writer)?;
+    (<T::Envelope as Envelope>::Point::DIMENSIONS as u64).write_to(writer)?;
+    write_string(&scalar_tag::<ScalarOf<T>>(), writer)?;
+    (Params::MIN_SIZE as u64).write_to(writer)?;
+    (Params::MAX_SIZE as u64).write_to(writer)?;
+    match metadata.build_timestamp() {
+        Some(timestamp) => {
+            1u8.write_to(writer)?;
+            timestamp.write_to(writer)?;
+        }
+        None => 0u8.write_to(writer)?,
+    }
+    (metadata.user_data().len() as u64).write_to(writer)?;
+    for (key, value) in metadata.user_data() {
+        write_string(key, writer)?;
+        write_string(value, writer)?;
+    }
+    (metadata.element_count() as u64).write_to(writer)?;
+    let root_offset = write_node(root, ROOT_TAG, writer)?;
+    root_offset.write_to(writer)
+}
+
+/// Writes a node's subtree and returns the offset of the node's record.
+fn write_node<T, W>(
+    node: &ParentNode<T>,
+    tag: u8,
+    writer: &mut PositionWriter<W>,
+) -> io::Result<u64>
+where
+    T: RTreeObject + BinaryCodable,
+    T::Envelope: BinaryCodable,
+    W: Write,
+{
+    let mut offsets = Vec::with_capacity(node.children.len());
+    for child in &node.children {
+        match child {
+            RTreeNode::Leaf(ref t) => {
+                offsets.push(writer.position);
+                LEAF_TAG.write_to(writer)?;
+                t.write_to(writer)?;
+            }
+            RTreeNode::Parent(ref data) => {
+                offsets.push(write_node(data, NODE_TAG, writer)?);
+            }
+        }
+    }
+    let offset = writer.position;
+    tag.write_to(writer)?;
+    node.envelope.write_to(writer)?;
+    node.id().write_to(writer)?;
+    (offsets.len() as u64).write_to(writer)?;
+    for child_offset in offsets {
+        child_offset.write_to(writer)?;
+    }
+    Ok(offset)
+}
+
+/// The header of a tree in binary format.
+pub struct Header {
+    pub metadata: RTreeMetadata,
+    pub size: u64,
+}
+
+/// Reads the header and checks that it matches the tree's type and parameters.
+pub fn read_header<T, Params, R>(reader: &mut R) -> io::Result<Header>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+    R: Read,
+{
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("Not an r-tree in binary format"));
+    }
+    if u16::read_from(reader)? != VERSION {
+        return Err(invalid_data("Unsupported binary format version"));
+    }
+    if u64::read_from(reader)? != <T::Envelope as Envelope>::Point::DIMENSIONS as u64 {
+        return Err(invalid_data("Dimension mismatch"));
+    }
+    if read_string(reader)? != scalar_tag::<ScalarOf<T>>() {
+        return Err(invalid_data("Scalar type mismatch"));
+    }
+    let min_size = u64::read_from(reader)?;
+    let max_size = u64::read_from(reader)?;
+    if min_size != Params::MIN_SIZE as u64 || max_size != Params::MAX_SIZE as u64 {
+        return Err(invalid_data("Tree parameter mismatch"));
+    }
+    let build_timestamp = match u8::read_from(reader)? {
+        0 => None,
+        1 => Some(u64::read_from(reader)?),
+        _ => return Err(invalid_data("Invalid build timestamp")),
+    };
+    let mut metadata = RTreeMetadata::with_build_timestamp(build_timestamp);
+    let mut user_data = BTreeMap::new();
+    for _ in 0..u64::read_from(reader)? {
+        let key = read_string(reader)?;
+        let value = read_string(reader)?;
+        user_data.insert(key, value);
+    }
+    *metadata.user_data_mut() = user_data;
+    let size = u64::read_from(reader)?;
+    Ok(Header { metadata, size })
+}
+
+/// Reads a tree written by [write_tree] and returns its root, size and metadata.
+///
+/// Fails if the header does not match the tree's type and parameters, if the number of
+/// elements differs from the size recorded in the header or if nodes are nested deeper than
+/// a tree of this size allows. The stored envelopes are trusted and not recalculated.
+pub fn read_tree<T, Params, R>(reader: &mut R) -> io::Result<(ParentNode<T>, usize, RTreeMetadata)>
+where
+    T: RTreeObject + BinaryCodable,
+    T::Envelope: BinaryCodable,
+    Params: RTreeParams,
+    R: Read,
+{
+    let Header { metadata, size } = read_header::<T, Params, _>(reader)?;
+    let max_height = max_height::<Params>(size);
+    // Records are read in post order, a node's children are the last entries of the stack
+    let mut stack = Vec::new();
+    // The height of each stack entry, leaves have height 0
+    let mut heights = Vec::new();
+    let mut element_count = 0u64;
+    let root = loop {
+        match u8::read_from(reader)? {
+            LEAF_TAG => {
+                element_count += 1;
+                stack.push(RTreeNode::Leaf(T::read_from(reader)?));
+                heights.push(0);
+            }
+            tag @ NODE_TAG | tag @ ROOT_TAG => {
+                let envelope = T::Envelope::read_from(reader)?;
+                let id = u64::read_from(reader)?;
+                let len = u64::read_from(reader)?;
+                if len > stack.len() as u64 {
+                    return Err(invalid_data("Missing child records"));
+                }
+                for _ in 0..len {
+                    // Offsets are only required for queries in place
+                    u64::read_from(reader)?;
+                }
+                let children = stack.split_off(stack.len() - len as usize);
+                let height = 1 + heights
+                    .drain(heights.len() - len as usize..)
+                    .max()
+                    .unwrap_or(0);
+                // Deeply nested nodes would overflow the stack when the tree is dropped
+                if height > max_height {
+                    return Err(invalid_data("Nesting too deep for the tree size"));
+                }
+                let node = ParentNode::restored(children, envelope, id)
+                    .ok_or_else(|| invalid_data("Node id out of range"))?;
+                if tag == ROOT_TAG {
+                    break node;
+                }
+                stack.push(RTreeNode::Parent(node));
+                heights.push(height);
+            }
+            _ => return Err(invalid_data("Invalid record tag")),
+        }
+    };
+    // Skip the root offset
+    u64::read_from(reader)?;
+    if !stack.is_empty() {
+        return Err(invalid_data("Records without parent"));
+    }
+    if element_count != size {
+        return Err(invalid_data("Element count mismatch"));
+    }
+    Ok((root, element_count as usize, metadata))
+}
+
+/// Returns the largest height of a tree with `size` elements, a node with leaf children has
+/// height 1.
+///
+/// All nodes but the root contain at least `Params::MIN_SIZE` children, which bounds the
+/// height by `log_{MIN_SIZE}(size) + 2`. A `MIN_SIZE` below 2 is treated as 2.
+fn max_height<Params: RTreeParams>(size: u64) -> usize {
+    let min_size = Params::MIN_SIZE.max(2) as u64;
+    let mut max_height = 2;
+    let mut min_leaves = 1u64;
+    while min_leaves < size {
+        min_leaves = min_leaves.saturating_mul(min_size);
+        max_height += 1;
+    }
+    max_height
+}
+
+/// Checks the structure of a tree read by [read_tree].
+///
+/// All leaves must be located at the same depth, every node's envelope must contain its
+/// children's envelopes and every node must contain at most `Params::MAX_SIZE` children.
+/// Nodes below the root must contain at least `Params::MIN_SIZE` children.
+pub fn validate_tree<T, Params>(root: &ParentNode<T>) -> io::Result<()>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    if root.children.len() > Params::MAX_SIZE {
+        return Err(invalid_data("Overfull node"));
+    }
+    let mut leaf_depth = None;
+    let mut todo = vec![(root, 1)];
+    while let Some((node, depth)) = todo.pop() {
+        for child in &node.children {
+            if !node.envelope.contains_envelope(&child.envelope()) {
+                return Err(invalid_data("Envelope does not contain child"));
+            }
+            match child {
+                RTreeNode::Leaf(_) => {
+                    if *leaf_depth.get_or_insert(depth) != depth {
+                        return Err(invalid_data("Leaves at different heights"));
+                    }
+                }
+                RTreeNode::Parent(ref data) => {
+                    if data.children.len() < Params::MIN_SIZE.max(1) {
+                        return Err(invalid_data("Underfull node"));
+                    }
+                    if data.children.len() > Params::MAX_SIZE {
+                        return Err(invalid_data("Overfull node"));
+                    }
+                    todo.push((data, depth + 1));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::node::ParentNode;
+    use crate::primitives::{Ball, GeomWithData, Line, PointWithData, Rectangle};
+    use crate::test_utilities::{create_random_lines, create_random_points, SEED_1};
+    use crate::TraversalOrder;
+    use crate::{DefaultParams, RStarInsertionStrategy, RTree, RTreeNode, RTreeParams};
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_binary_round_trip() {
+        let points = create_random_points(1000, SEED_1);
+        let mut tree = RTree::bulk_load(points.clone());
+        tree.user_metadata_mut()
+            .insert("source".to_string(), "test".to_string());
+        let bytes = tree.to_bytes();
+        let loaded: RTree<[f64; 2]> = RTree::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.size(), tree.size());
+        assert_eq!(loaded.metadata(), tree.metadata());
+        assert_eq!(loaded.root().envelope(), tree.root().envelope());
+        assert_eq!(loaded.height(), tree.height());
+        let mut expected: Vec<_> = tree.iter().collect();
+        let mut actual: Vec<_> = loaded.iter().collect();
+        expected.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        actual.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        assert_eq!(actual, expected);
+
+        // Leaves the tree's structure intact
+        let mut inserted = RTree::new();
+        for p in &points {
+            inserted.insert(*p);
+        }
+        let loaded: RTree<[f64; 2]> = RTree::from_bytes(&inserted.to_bytes()).unwrap();
+        loaded.root().sanity_check::<DefaultParams>();
+        // Keeps all node ids, new nodes receive different ones
+        let ids = |tree: &RTree<[f64; 2]>| -> Vec<u64> {
+            tree.root()
+                .nodes(TraversalOrder::DepthFirst)
+                .filter_map(|(node, _)| match node {
+                    RTreeNode::Parent(data) => Some(data.id()),
+                    RTreeNode::Leaf(_) => None,
+                })
+                .collect()
+        };
+        assert_eq!(ids(&loaded), ids(&inserted));
+        assert!(!ids(&loaded).contains(&ParentNode::<[f64; 2]>::new_empty().id()));
+        assert_eq!(loaded.root().id(), inserted.root().id());
+
+        let empty: RTree<[i32; 3]> = RTree::new();
+        let loaded: RTree<[i32; 3]> = RTree::from_bytes(&empty.to_bytes()).unwrap();
+        assert_eq!(loaded.size(), 0);
+    }
+
+    #[test]
+    fn test_binary_primitives() {
+        let lines = RTree::bulk_load(create_random_lines(100, SEED_1));
+        let loaded: RTree<Line<[f64; 2]>> = RTree::from_bytes(&lines.to_bytes()).unwrap();
+        assert_eq!(loaded.size(), 100);
+        assert!(lines.iter().all(|line| loaded.contains(line)));
+
+        let rectangles = RTree::bulk_load(vec![Rectangle::from_corners([0.0f32, 1.0], [2.0, 3.0])]);
+        let loaded: RTree<Rectangle<[f32; 2]>> = RTree::from_bytes(&rectangles.to_bytes()).unwrap();
+        assert!(loaded.contains(&Rectangle::from_corners([0.0, 1.0], [2.0, 3.0])));
+
+        let with_data = RTree::bulk_load(vec![PointWithData::new(17u64, [1i64, 2])]);
+        let loaded: RTree<PointWithData<u64, [i64; 2]>> =
+            RTree::from_bytes(&with_data.to_bytes()).unwrap();
+        assert_eq!(loaded.iter().next().unwrap().data, 17);
+
+        let line = GeomWithData::new(Line::new([0.0, 1.0], [2.0, 3.0]), 5u8);
+        let loaded: RTree<GeomWithData<Line<[f64; 2]>, u8>> =
+            RTree::from_bytes(&RTree::bulk_load(vec![line]).to_bytes()).unwrap();
+        assert_eq!(loaded.iter().next(), Some(&line));
+
+        let ball = Ball::new([1.0f32, 2.0], 0.5);
+        let loaded: RTree<Ball<[f32; 2]>> =
+            RTree::from_bytes(&RTree::bulk_load(vec![ball]).to_bytes()).unwrap();
+        assert_eq!(loaded.iter().next(), Some(&ball));
+    }
+
+    #[test]
+    fn test_binary_errors() {
+        struct OtherParams;
+        impl RTreeParams for OtherParams {
+            const MIN_SIZE: usize = 3;
+            const MAX_SIZE: usize = 8;
+            const REINSERTION_COUNT: usize = 2;
+            type DefaultInsertionStrategy = RStarInsertionStrategy;
+        }
+
+        let tree = RTree::bulk_load(create_random_points(100, SEED_1));
+        let bytes = tree.to_bytes();
+        let kind = |result: Result<RTree<[f64; 2]>, std::io::Error>| result.err().unwrap().kind();
+        assert_eq!(
+            kind(RTree::from_bytes(&bytes[..bytes.len() - 1])),
+            ErrorKind::UnexpectedEof
+        );
+        assert_eq!(kind(RTree::from_bytes(b"JSON")), ErrorKind::InvalidData);
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] = 99;
+        assert_eq!(
+            kind(RTree::from_bytes(&wrong_version)),
+            ErrorKind::InvalidData
+        );
+        assert!(RTree::<[f64; 3]>::from_bytes(&bytes).is_err());
+        assert!(RTree::<[i64; 2]>::from_bytes(&bytes).is_err());
+        assert!(RTree::<[f32; 2]>::from_bytes(&bytes).is_err());
+        let integers = RTree::bulk_load(vec![[1i32, 2]]).to_bytes();
+        assert!(RTree::<[f32; 2]>::from_bytes(&integers).is_err());
+        assert!(RTree::<[f64; 2], OtherParams>::from_bytes(&bytes).is_err());
+
+        // The root's id follows its tag and envelope
+        let mut invalid_id = bytes.clone();
+        let mut root_offset = [0; 8];
+        root_offset.copy_from_slice(&bytes[bytes.len() - 8..]);
+        let id = u64::from_le_bytes(root_offset) as usize + 1 + 32;
+        invalid_id[id..id + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(kind(RTree::from_bytes(&invalid_id)), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_binary_nesting_depth() {
+        use super::{BinaryCodable, LEAF_TAG, NODE_TAG, ROOT_TAG};
+        use crate::AABB;
+
+        let tree = RTree::bulk_load(vec![[0.0, 0.0]]);
+        let bytes = tree.to_bytes();
+        // A single leaf record and a root record with one child follow the header
+        let root_record = 1 + 32 + 8 + 8 + 8;
+        let header = &bytes[..bytes.len() - 8 - root_record - (1 + 16)];
+        let nested = |depth: usize| {
+            let mut bytes = header.to_vec();
+            LEAF_TAG.write_to(&mut bytes).unwrap();
+            [0.0f64, 0.0].write_to(&mut bytes).unwrap();
+            let envelope = AABB::from_point([0.0f64, 0.0]);
+            for index in 0..depth {
+                let tag = if index + 1 == depth {
+                    ROOT_TAG
+                } else {
+                    NODE_TAG
+                };
+                tag.write_to(&mut bytes).unwrap();
+                envelope.write_to(&mut bytes).unwrap();
+                (index as u64).write_to(&mut bytes).unwrap();
+                1u64.write_to(&mut bytes).unwrap();
+                0u64.write_to(&mut bytes).unwrap();
+            }
+            0u64.write_to(&mut bytes).unwrap();
+            bytes
+        };
+        assert_eq!(nested(1).len(), bytes.len());
+        assert_eq!(RTree::<[f64; 2]>::from_bytes(&nested(1)).unwrap().size(), 1);
+        assert!(RTree::<[f64; 2]>::from_bytes(&nested(2)).is_ok());
+        // Dropping this chain of nodes would overflow the stack
+        assert_eq!(
+            RTree::<[f64; 2]>::from_bytes(&nested(1_000_000))
+                .err()
+                .unwrap()
+                .kind(),
+            ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_binary_validation() {
+        let tree = RTree::bulk_load(create_random_points(1000, SEED_1));
+        let bytes = tree.to_bytes();
+        let loaded: RTree<[f64; 2]> = RTree::from_bytes_validated(&bytes).unwrap();
+        assert_eq!(loaded.size(), tree.size());
+        let empty: RTree<[f64; 2]> = RTree::new();
+        assert!(RTree::<[f64; 2]>::from_bytes_validated(&empty.to_bytes()).is_ok());
+
+        // Shrink the root's envelope, the root is the last record before the trailer
+        let mut corrupt = bytes.clone();
+        let mut root_offset = [0; 8];
+        root_offset.copy_from_slice(&bytes[bytes.len() - 8..]);
+        let upper_x = u64::from_le_bytes(root_offset) as usize + 1 + 16;
+        corrupt[upper_x..upper_x + 8].copy_from_slice(&0.5f64.to_le_bytes());
+        // The unvalidated path accepts the corrupt index
+        assert!(RTree::<[f64; 2]>::from_bytes(&corrupt).is_ok());
+        assert_eq!(
+            RTree::<[f64; 2]>::from_bytes_validated(&corrupt)
+                .err()
+                .unwrap()
+                .kind(),
+            ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_validate_tree() {
+        use super::validate_tree;
+        use crate::node::{ParentNode, RTreeNode};
+        use crate::AABB;
+
+        struct SmallParams;
+        impl RTreeParams for SmallParams {
+            const MIN_SIZE: usize = 2;
+            const MAX_SIZE: usize = 4;
+            const REINSERTION_COUNT: usize = 1;
+            type DefaultInsertionStrategy = RStarInsertionStrategy;
+        }
+        let validate = |root: &ParentNode<[f64; 2]>| validate_tree::<_, SmallParams>(root);
+
+        let leaf = |x: f64| RTreeNode::Leaf([x, x]);
+        let parent = |children| RTreeNode::Parent(ParentNode::new_parent(children));
+        let balanced = ParentNode::new_parent(vec![
+            parent(vec![leaf(0.0), leaf(1.0)]),
+            parent(vec![leaf(2.0), leaf(3.0)]),
+        ]);
+        assert!(validate(&balanced).is_ok());
+        // The root may contain less than MIN_SIZE children
+        assert!(validate(&ParentNode::new_parent(vec![leaf(0.0)])).is_ok());
+
+        let unbalanced =
+            ParentNode::new_parent(vec![parent(vec![leaf(0.0), leaf(1.0)]), leaf(2.0)]);
+        assert!(validate(&unbalanced).is_err());
+
+        let with_empty_node = ParentNode::new_parent(vec![parent(vec![])]);
+        assert!(validate(&with_empty_node).is_err());
+
+        let underfull = ParentNode::new_parent(vec![
+            parent(vec![leaf(0.0), leaf(1.0)]),
+            parent(vec![leaf(2.0)]),
+        ]);
+        assert!(validate(&underfull).is_err());
+
+        let leaves = || (0..5).map(|x| leaf(x as f64)).collect::<Vec<_>>();
+        assert!(validate(&ParentNode::new_parent(leaves())).is_err());
+        let overfull = ParentNode::new_parent(vec![parent(leaves())]);
+        assert!(validate(&overfull).is_err());
+
+        let mut shrunk = match parent(vec![leaf(0.0), leaf(1.0)]) {
+            RTreeNode::Parent(data) => data,
+            RTreeNode::Leaf(_) => unreachable!(),
+        };
+        shrunk.envelope = AABB::from_point([0.0, 0.0]);
+        let wrong_envelope = ParentNode::new_parent(vec![RTreeNode::Parent(shrunk)]);
+        assert!(validate(&wrong_envelope).is_err());
+    }
+}
@@ -0,0 +1,371 @@
+use crate::point::{total_cmp, Point, PointExt};
+use crate::{Envelope, RTreeObject};
+use num_traits::{Float, One, Zero};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An n-dimensional bounding sphere.
+///
+/// This is an alternative [envelope](trait.Envelope.html) to [AABB](struct.AABB.html)s. An
+/// AABB's corners reach much further than its center's neighborhood in high dimensions,
+/// spheres bound roughly isotropic data more tightly and thus prune better during queries.
+/// The envelope is chosen per object type by setting `RTreeObject::Envelope` accordingly.
+///
+/// Merged spheres are not minimal, merging only guarantees to contain both inputs. The merged
+/// radius is enlarged by a few units in the last place to compensate rounding errors.
+///
+/// # Type arguments
+/// `P`: The sphere's point type. Bounding spheres require a floating point scalar type.
+///
+/// # Example
+/// ```
+/// use rstar::{BoundingSphere, PointDistance, RTree, RTreeObject};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Sample([f64; 6]);
+///
+/// impl RTreeObject for Sample {
+///     type Envelope = BoundingSphere<[f64; 6]>;
+///
+///     fn envelope(&self) -> Self::Envelope {
+///         BoundingSphere::from_point(self.0)
+///     }
+/// }
+///
+/// impl PointDistance for Sample {
+///     fn distance_2(&self, point: &[f64; 6]) -> f64 {
+///         self.0.distance_2(point)
+///     }
+/// }
+///
+/// let tree = RTree::bulk_load(vec![
+///     Sample([0.0; 6]),
+///     Sample([1.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+///     Sample([0.5; 6]),
+/// ]);
+/// let nearest = tree.nearest_neighbor(&[0.4; 6]).unwrap();
+/// assert_eq!(nearest, &Sample([0.5; 6]));
+/// ```
+#[derive(Clone, Debug, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BoundingSphere<P>
+where
+    P: Point,
+{
+    center: P,
+    radius: P::Scalar,
+}
+
+impl<P> BoundingSphere<P>
+where
+    P: Point,
+    P::Scalar: Float,
+{
+    /// Creates a new bounding sphere.
+    ///
+    /// # Panics
+    /// Panics if the radius is negative.
+    pub fn new(center: P, radius: P::Scalar) -> Self {
+        assert!(radius >= Zero::zero(), "Radius must not be negative");
+        BoundingSphere { center, radius }
+    }
+
+    /// Returns the bounding sphere encompassing a single point.
+    pub fn from_point(point: P) -> Self {
+        BoundingSphere {
+            center: point,
+            radius: Zero::zero(),
+        }
+    }
+
+    /// Creates a bounding sphere encompassing a collection of points.
+    pub fn from_points<'a, I>(i: I) -> Self
+    where
+        I: IntoIterator<Item = &'a P> + 'a,
+        P: 'a,
+    {
+        i.into_iter().fold(Self::new_empty(), |sphere, p| {
+            sphere.merged(&Self::from_point(*p))
+        })
+    }
+
+    /// Returns the sphere's center.
+    pub fn center(&self) -> P {
+        self.center
+    }
+
+    /// Returns the sphere's radius.
+    ///
+    /// The radius of an empty sphere is negative.
+    pub fn radius(&self) -> P::Scalar {
+        self.radius
+    }
+
+    fn is_empty(&self) -> bool {
+        self.radius < Zero::zero()
+    }
+}
+
+/// Enlarges a distance to cover the rounding errors of its calculation.
+fn pad<P>(distance: P::Scalar) -> P::Scalar
+where
+    P: Point,
+    P::Scalar: Float,
+{
+    // Computing a euclidean distance rounds at most D + 1 times
+    let ulps = (0..P::DIMENSIONS + 4).fold(P::Scalar::zero(), |acc, _| acc + One::one());
+    distance + distance * P::Scalar::epsilon() * ulps
+}
+
+impl<P> Envelope for BoundingSphere<P>
+where
+    P: Point,
+    P::Scalar: Float,
+{
+    type Point = P;
+
+    fn new_empty() -> Self {
+        BoundingSphere {
+            center: P::new(),
+            radius: -P::Scalar::one(),
+        }
+    }
+
+    fn contains_point(&self, point: &P) -> bool {
+        point.sub(&self.center).length_2() <= self.radius * self.radius && !self.is_empty()
+    }
+
+    fn contains_envelope(&self, other: &Self) -> bool {
+        other.is_empty()
+            || distance_between(&self.center, &other.center) + other.radius <= self.radius
+    }
+
+    fn merge(&mut self, other: &Self) {
+        *self = self.merged(other);
+    }
+
+    fn merged(&self, other: &Self) -> Self {
+        if other.is_empty() {
+            return *self;
+        }
+        if self.is_empty() {
+            return *other;
+        }
+        let distance = pad::<P>(distance_between(&self.center, &other.center));
+        if distance + other.radius <= self.radius {
+            return *self;
+        }
+        if distance + self.radius <= other.radius {
+            return *other;
+        }
+        let two = P::Scalar::one() + One::one();
+        let radius = (distance + self.radius + other.radius) / two;
+        let offset = other.center.sub(&self.center);
+        let center = self
+            .center
+            .add(&offset.mul((radius - self.radius) / distance));
+        // The center is rounded, make sure that both spheres are still contained
+        let radius = (pad::<P>(distance_between(&center, &self.center)) + self.radius)
+            .max(pad::<P>(distance_between(&center, &other.center)) + other.radius)
+            .max(radius);
+        BoundingSphere {
+            center,
+            radius: pad::<P>(radius),
+        }
+    }
+
+    fn intersects(&self, other: &Self) -> bool {
+        let radii = self.radius + other.radius;
+        !self.is_empty()
+            && !other.is_empty()
+            && self.center.sub(&other.center).length_2() <= radii * radii
+    }
+
+    fn intersection_area(&self, other: &Self) -> P::Scalar {
+        // The lens shaped intersection is approximated by the sphere spanning its width
+        if self.is_empty() || other.is_empty() {
+            return Zero::zero();
+        }
+        let width = self.radius + other.radius - distance_between(&self.center, &other.center);
+        let width = width
+            .min(self.radius + self.radius)
+            .min(other.radius + other.radius);
+        if width <= Zero::zero() {
+            return Zero::zero();
+        }
+        let two = P::Scalar::one() + One::one();
+        BoundingSphere {
+            center: P::new(),
+            radius: width / two,
+        }
+        .area()
+    }
+
+    /// Returns a value proportional to the sphere's volume.
+    fn area(&self) -> P::Scalar {
+        if self.is_empty() {
+            return Zero::zero();
+        }
+        (0..P::DIMENSIONS).fold(P::Scalar::one(), |acc, _| acc * self.radius)
+    }
+
+    fn distance_2(&self, point: &P) -> P::Scalar {
+        let distance_to_surface = distance_between(&self.center, point) - self.radius;
+        if distance_to_surface > Zero::zero() {
+            distance_to_surface * distance_to_surface
+        } else {
+            Zero::zero()
+        }
+    }
+
+    fn min_max_dist_2(&self, point: &P) -> P::Scalar {
+        // Children are not guaranteed to touch the sphere's surface, only the distance to its
+        // farthest point is a safe upper bound.
+        let distance = distance_between(&self.center, point) + self.radius;
+        distance * distance
+    }
+
+    fn center(&self) -> Self::Point {
+        self.center
+    }
+
+    fn bounds_along_axis(&self, axis: usize) -> (P::Scalar, P::Scalar) {
+        let center = self.center.nth(axis);
+        (center - self.radius, center + self.radius)
+    }
+
+    fn perimeter_value(&self) -> P::Scalar {
+        self.radius.max(Zero::zero())
+    }
+
+    fn sort_envelopes<T: RTreeObject<Envelope = Self>>(axis: usize, envelopes: &mut [T]) {
+        envelopes.sort_by(|l, r| {
+            total_cmp(
+                &l.envelope().center.nth(axis),
+                &r.envelope().center.nth(axis),
+            )
+        });
+    }
+
+    fn partition_envelopes<T: RTreeObject<Envelope = Self>>(
+        axis: usize,
+        envelopes: &mut [T],
+        selection_size: usize,
+    ) {
+        ::pdqselect::select_by(envelopes, selection_size, |l, r| {
+            total_cmp(
+                &l.envelope().center.nth(axis),
+                &r.envelope().center.nth(axis),
+            )
+        });
+    }
+}
+
+fn distance_between<P>(from: &P, to: &P) -> P::Scalar
+where
+    P: Point,
+    P::Scalar: Float,
+{
+    from.sub(to).length_2().sqrt()
+}
+
+#[cfg(test)]
+mod test {
+    use super::BoundingSphere;
+    use crate::envelope::Envelope;
+    use crate::object::{PointDistance, RTreeObject};
+    use crate::point::PointExt;
+    use crate::test_utilities::{create_random_points, SEED_1, SEED_2};
+    use crate::RTree;
+
+    #[derive(Debug, PartialEq)]
+    struct Sample([f64; 4]);
+
+    impl RTreeObject for Sample {
+        type Envelope = BoundingSphere<[f64; 4]>;
+
+        fn envelope(&self) -> Self::Envelope {
+            BoundingSphere::from_point(self.0)
+        }
+    }
+
+    impl PointDistance for Sample {
+        fn distance_2(&self, point: &[f64; 4]) -> f64 {
+            self.0.sub(point).length_2()
+        }
+    }
+
+    fn create_random_samples(num_samples: usize) -> Vec<Sample> {
+        create_random_points(num_samples, SEED_1)
+            .into_iter()
+            .zip(create_random_points(num_samples, SEED_2))
+            .map(|(a, b)| Sample([a[0], a[1], b[0], b[1]]))
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_contains_inputs() {
+        let points = create_random_points(200, SEED_1);
+        let radii = create_random_points(200, SEED_2);
+        let mut merged = BoundingSphere::new_empty();
+        for (point, radius) in points.iter().zip(&radii) {
+            let sphere = BoundingSphere::new(point.mul(1e3), radius[0]);
+            let previous = merged;
+            merged.merge(&sphere);
+            assert!(merged.contains_envelope(&sphere));
+            assert!(merged.contains_envelope(&previous));
+            assert!(merged.contains_point(&sphere.center()));
+        }
+        assert_eq!(merged.merged(&BoundingSphere::new_empty()), merged);
+
+        let a = BoundingSphere::new([0.0f64, 0.0], 1.0);
+        let b = BoundingSphere::new([4.0, 0.0], 1.0);
+        let ab = a.merged(&b);
+        assert!((ab.radius() - 3.0).abs() < 1e-12);
+        assert!(ab.center().sub(&[2.0, 0.0]).length_2() < 1e-24);
+        assert_eq!(a.merged(&BoundingSphere::from_point([0.5, 0.0])), a);
+        assert!(!a.intersects(&b));
+        assert_eq!(a.intersection_area(&b), 0.0);
+        assert!(a.intersects(&BoundingSphere::new([1.5, 0.0], 1.0)));
+        assert_eq!(a.area(), 1.0);
+        assert_eq!(a.distance_2(&[3.0, 0.0]), 4.0);
+        assert_eq!(a.min_max_dist_2(&[3.0, 0.0]), 16.0);
+        assert_eq!(BoundingSphere::<[f64; 2]>::new_empty().area(), 0.0);
+        assert!(!BoundingSphere::new_empty().contains_point(&[0.0, 0.0]));
+    }
+
+    #[test]
+    fn test_bounding_sphere_queries() {
+        let samples = create_random_samples(1000);
+        let mut tree = RTree::new();
+        for sample in create_random_samples(1000) {
+            tree.insert(sample);
+        }
+        let bulk_loaded = RTree::bulk_load(create_random_samples(1000));
+        for tree in &[tree, bulk_loaded] {
+            assert_eq!(tree.size(), 1000);
+            for query in create_random_samples(50) {
+                let query = query.0.map(|x| x * 1.2 - 0.1);
+                let expected = samples
+                    .iter()
+                    .min_by(|l, r| {
+                        l.distance_2(&query)
+                            .partial_cmp(&r.distance_2(&query))
+                            .unwrap()
+                    })
+                    .unwrap();
+                assert_eq!(tree.nearest_neighbor(&query), Some(expected));
+                let within = tree.locate_within_distance(query, 0.05).count();
+                let expected = samples
+                    .iter()
+                    .filter(|s| s.distance_2(&query) <= 0.05)
+                    .count();
+                assert_eq!(within, expected);
+            }
+            for sample in &samples {
+                assert!(tree.contains(sample));
+            }
+        }
+    }
+}
@@ -4,8 +4,8 @@ use crate::{Point, RTreeObject};
 ///
 /// An envelope defines how different bounding boxes of inserted children in an r-tree can interact,
 /// e.g. how they can be merged or intersected.
-/// This trait is not meant to be implemented by the user. Currently, only one implementation
-/// exists ([AABB](struct.AABB.html)) and should be used.
+/// This trait is not meant to be implemented by the user. Use [AABB](struct.AABB.html), or
+/// [BoundingSphere](struct.BoundingSphere.html) for roughly isotropic, high dimensional data.
 pub trait Envelope: Clone + Copy + PartialEq + ::std::fmt::Debug {
     /// The envelope's point type.
     type Point: Point;
@@ -20,6 +20,11 @@ pub trait Envelope: Clone + Copy + PartialEq + ::std::fmt::Debug {
     fn contains_envelope(&self, aabb: &Self) -> bool;
 
     /// Extends `self` to contain another envelope.
+    ///
+    /// Merging must not round: the merged envelope must contain both inputs exactly,
+    /// regardless of the scalar type's precision. Otherwise, envelopes could drift
+    /// smaller than their children after many merges and queries would miss elements.
+    /// [AABB](struct.AABB.html) fulfills this by only taking component-wise minima and maxima.
     fn merge(&mut self, other: &Self);
     /// Returns the minimal envelope containing `self` and another envelope.
     fn merged(&self, other: &Self) -> Self;
@@ -47,6 +52,15 @@ pub trait Envelope: Clone + Copy + PartialEq + ::std::fmt::Debug {
     /// Returns the envelope's center point.
     fn center(&self) -> Self::Point;
 
+    /// Returns the lowest and the highest coordinate of the envelope along an axis.
+    fn bounds_along_axis(
+        &self,
+        axis: usize,
+    ) -> (
+        <Self::Point as Point>::Scalar,
+        <Self::Point as Point>::Scalar,
+    );
+
     /// Returns a value proportional to the envelope's perimeter.
     fn perimeter_value(&self) -> <Self::Point as Point>::Scalar;
 
@@ -0,0 +1,324 @@
+//! Export of trees as [GeoJSON](https://tools.ietf.org/html/rfc7946).
+//!
+//! Requires the `geojson` feature. Use
+//! [RTree::write_geojson](../struct.RTree.html#method.write_geojson) to write a tree's
+//! elements and, optionally, its node envelopes as a `FeatureCollection`. The result can be
+//! opened with GIS tools like QGIS or geojson.io, which is useful to inspect how well a tree
+//! is packed.
+//!
+//! Coordinates are written in the tree's coordinate system, GeoJSON viewers expect
+//! `[longitude, latitude]` positions. Rectangles and envelopes are exported as polygons
+//! spanned by their first two coordinates.
+//!
+//! # Example
+//! ```
+//! use rstar::RTree;
+//!
+//! let tree = RTree::bulk_load(vec![[13.4, 52.5], [2.35, 48.86]]);
+//! let mut geojson = Vec::new();
+//! tree.write_geojson(&mut geojson, true).unwrap();
+//! let geojson = String::from_utf8(geojson).unwrap();
+//! assert!(geojson.starts_with(r#"{"type":"FeatureCollection","features":["#));
+//! assert!(geojson.contains(r#""type":"Point","coordinates":[13.4,52.5]"#));
+//! ```
+use crate::aabb::AABB;
+use crate::node::{ParentNode, RTreeNode};
+use crate::object::RTreeObject;
+use crate::point::{Point, RTreeNum};
+use crate::primitives::{
+    AnyGeometry, CachedEnvelope, GeomWithData, Line, PointWithData, Rectangle,
+};
+use std::io::{self, Write};
+
+/// Geometries that can be exported as GeoJSON.
+///
+/// The trait is implemented for all point arrays, [AABB](../struct.AABB.html)s and the
+/// crate's [primitives](../primitives/index.html).
+///
+/// # Example
+/// ```
+/// use rstar::geojson::GeoJsonGeometry;
+/// use rstar::{RTree, RTreeObject, AABB};
+/// use std::io::{self, Write};
+///
+/// struct Station {
+///     position: [f64; 2],
+/// }
+///
+/// impl RTreeObject for Station {
+///     type Envelope = AABB<[f64; 2]>;
+///
+///     fn envelope(&self) -> Self::Envelope {
+///         AABB::from_point(self.position)
+///     }
+/// }
+///
+/// impl GeoJsonGeometry for Station {
+///     fn write_geometry<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+///         self.position.write_geometry(writer)
+///     }
+/// }
+///
+/// let tree = RTree::bulk_load(vec![Station { position: [0.5, 1.0] }]);
+/// let mut geojson = Vec::new();
+/// tree.write_geojson(&mut geojson, false).unwrap();
+/// ```
+pub trait GeoJsonGeometry {
+    /// Writes the value as GeoJSON geometry object.
+    fn write_geometry<W: Write>(&self, writer: &mut W) -> io::Result<()>;
+}
+
+/// Writes a position, the scalars' debug representation is a valid JSON number for all
+/// finite values of the primitive number types.
+fn write_position<P: Point, W: Write>(point: &P, writer: &mut W) -> io::Result<()> {
+    writer.write_all(b"[")?;
+    for axis in 0..P::DIMENSIONS {
+        if axis > 0 {
+            writer.write_all(b",")?;
+        }
+        write!(writer, "{:?}", point.nth(axis))?;
+    }
+    writer.write_all(b"]")
+}
+
+fn write_point<P: Point, W: Write>(point: &P, writer: &mut W) -> io::Result<()> {
+    writer.write_all(br#"{"type":"Point","coordinates":"#)?;
+    write_position(point, writer)?;
+    writer.write_all(b"}")
+}
+
+fn write_polygon<P: Point, W: Write>(envelope: &AABB<P>, writer: &mut W) -> io::Result<()> {
+    let (lower, upper) = (envelope.lower(), envelope.upper());
+    let corner = |x: &P, y: &P| [x.nth(0), y.nth(1)];
+    let ring = [
+        corner(&lower, &lower),
+        corner(&upper, &lower),
+        corner(&upper, &upper),
+        corner(&lower, &upper),
+        corner(&lower, &lower),
+    ];
+    writer.write_all(br#"{"type":"Polygon","coordinates":[["#)?;
+    for (index, position) in ring.iter().enumerate() {
+        if index > 0 {
+            writer.write_all(b",")?;
+        }
+        write_position(position, writer)?;
+    }
+    writer.write_all(b"]]}")
+}
+
+impl<S, const N: usize> GeoJsonGeometry for [S; N]
+where
+    S: RTreeNum,
+    [S; N]: Point,
+{
+    fn write_geometry<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_point(self, writer)
+    }
+}
+
+impl<P> GeoJsonGeometry for AABB<P>
+where
+    P: Point,
+{
+    fn write_geometry<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_polygon(self, writer)
+    }
+}
+
+impl<P> GeoJsonGeometry for Rectangle<P>
+where
+    P: Point,
+{
+    fn write_geometry<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_polygon(&self.envelope(), writer)
+    }
+}
+
+impl<P> GeoJsonGeometry for Line<P>
+where
+    P: Point,
+{
+    fn write_geometry<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(br#"{"type":"LineString","coordinates":["#)?;
+        write_position(&self.from, writer)?;
+        writer.write_all(b",")?;
+        write_position(&self.to, writer)?;
+        writer.write_all(b"]}")
+    }
+}
+
+impl<T, P> GeoJsonGeometry for PointWithData<T, P>
+where
+    P: Point,
+{
+    fn write_geometry<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_point(self.position(), writer)
+    }
+}
+
+impl<G, T> GeoJsonGeometry for GeomWithData<G, T>
+where
+    G: GeoJsonGeometry,
+{
+    fn write_geometry<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.geom().write_geometry(writer)
+    }
+}
+
+impl<P> GeoJsonGeometry for AnyGeometry<P>
+where
+    P: Point,
+{
+    fn write_geometry<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            AnyGeometry::Point(point) => write_point(point, writer),
+            AnyGeometry::Line(line) => line.write_geometry(writer),
+            AnyGeometry::Rectangle(rectangle) => rectangle.write_geometry(writer),
+        }
+    }
+}
+
+impl<T> GeoJsonGeometry for CachedEnvelope<T>
+where
+    T: RTreeObject + GeoJsonGeometry,
+{
+    fn write_geometry<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.inner().write_geometry(writer)
+    }
+}
+
+fn write_feature<G, W>(geometry: &G, properties: &str, writer: &mut W) -> io::Result<()>
+where
+    G: GeoJsonGeometry,
+    W: Write,
+{
+    writer.write_all(br#"{"type":"Feature","geometry":"#)?;
+    geometry.write_geometry(writer)?;
+    write!(writer, r#","properties":{}}}"#, properties)
+}
+
+/// Writes a tree as `FeatureCollection`.
+///
+/// Elements are exported with the property `"kind": "element"`. If `include_nodes` is set,
+/// every node's envelope is exported as well, with the properties `"kind": "node"`, its
+/// `"level"` (the root has level 0), `"id"` and number of `"children"`.
+pub fn write_geojson<T, W>(
+    root: &ParentNode<T>,
+    include_nodes: bool,
+    writer: &mut W,
+) -> io::Result<()>
+where
+    T: RTreeObject + GeoJsonGeometry,
+    T::Envelope: GeoJsonGeometry,
+    W: Write,
+{
+    writer.write_all(br#"{"type":"FeatureCollection","features":["#)?;
+    let mut is_first = true;
+    let mut separate = |writer: &mut W| {
+        if is_first {
+            is_first = false;
+            Ok(())
+        } else {
+            writer.write_all(b",")
+        }
+    };
+    let mut todo = vec![(root, 0)];
+    while let Some((node, level)) = todo.pop() {
+        if include_nodes && !node.children.is_empty() {
+            separate(writer)?;
+            let properties = format!(
+                r#"{{"kind":"node","level":{},"id":{},"children":{}}}"#,
+                level,
+                node.id(),
+                node.children.len()
+            );
+            write_feature(&node.envelope, &properties, writer)?;
+        }
+        for child in &node.children {
+            match child {
+                RTreeNode::Leaf(ref t) => {
+                    separate(writer)?;
+                    write_feature(t, r#"{"kind":"element"}"#, writer)?;
+                }
+                RTreeNode::Parent(ref data) => todo.push((data, level + 1)),
+            }
+        }
+    }
+    writer.write_all(b"]}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::GeoJsonGeometry;
+    use crate::primitives::{Line, Rectangle};
+    use crate::test_utilities::{create_random_points, SEED_1};
+    use crate::RTree;
+    use serde_json::Value;
+
+    fn to_json<G: GeoJsonGeometry>(geometry: &G) -> Value {
+        let mut result = Vec::new();
+        geometry.write_geometry(&mut result).unwrap();
+        serde_json::from_slice(&result).unwrap()
+    }
+
+    #[test]
+    fn test_geometries() {
+        assert_eq!(
+            to_json(&[1, -2, 3]),
+            serde_json::json!({"type": "Point", "coordinates": [1, -2, 3]})
+        );
+        assert_eq!(
+            to_json(&Line::new([0.5, 1.0], [2.0, 3.0])),
+            serde_json::json!({"type": "LineString", "coordinates": [[0.5, 1.0], [2.0, 3.0]]})
+        );
+        assert_eq!(
+            to_json(&Rectangle::from_corners([0.0, 1.0], [2.0, 3.0])),
+            serde_json::json!({
+                "type": "Polygon",
+                "coordinates": [[[0.0, 1.0], [2.0, 1.0], [2.0, 3.0], [0.0, 3.0], [0.0, 1.0]]]
+            })
+        );
+    }
+
+    #[test]
+    fn test_write_geojson() {
+        let tree = RTree::bulk_load(create_random_points(500, SEED_1));
+        let mut bytes = Vec::new();
+        tree.write_geojson(&mut bytes, false).unwrap();
+        let collection: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(collection["type"], "FeatureCollection");
+        assert_eq!(collection["features"].as_array().unwrap().len(), 500);
+
+        bytes.clear();
+        tree.write_geojson(&mut bytes, true).unwrap();
+        let collection: Value = serde_json::from_slice(&bytes).unwrap();
+        let features = collection["features"].as_array().unwrap();
+        let nodes: Vec<_> = features
+            .iter()
+            .filter(|feature| feature["properties"]["kind"] == "node")
+            .collect();
+        assert_eq!(features.len() - nodes.len(), 500);
+        assert_eq!(
+            nodes
+                .iter()
+                .filter(|node| node["properties"]["level"] == 0)
+                .count(),
+            1
+        );
+        let max_level = nodes
+            .iter()
+            .map(|node| node["properties"]["level"].as_u64().unwrap())
+            .max()
+            .unwrap();
+        assert_eq!(max_level as usize + 1, tree.height());
+
+        let empty: RTree<[f64; 2]> = RTree::new();
+        bytes.clear();
+        empty.write_geojson(&mut bytes, true).unwrap();
+        assert_eq!(
+            bytes,
+            br#"{"type":"FeatureCollection","features":[]}"#.to_vec()
+        );
+    }
+}
@@ -0,0 +1,76 @@
+/// Counters collected while inserting elements into an r-tree.
+///
+/// Every tree keeps track of these counters for insertions carried out by the built-in
+/// insertion strategies, see
+/// [RTree::insertion_metrics](struct.RTree.html#method.insertion_metrics).
+/// Bulk loading does not affect them. The metrics are not part of the tree's serialized
+/// representation.
+///
+/// # Example
+/// ```
+/// use rstar::RTree;
+///
+/// let mut tree = RTree::new();
+/// tree.insert([0.0, 0.0]);
+/// tree.insert([1.0, 1.0]);
+/// // [0.5, 0.5] lies within the root's envelope, no envelope needs to be updated.
+/// tree.insert([0.5, 0.5]);
+/// let metrics = tree.insertion_metrics();
+/// assert_eq!(metrics.insertions(), 3);
+/// assert_eq!(metrics.skipped_envelope_merges(), 1);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InsertionMetrics {
+    pub(crate) insertions: usize,
+    pub(crate) envelope_merges: usize,
+    pub(crate) skipped_envelope_merges: usize,
+    pub(crate) overflows: usize,
+    pub(crate) reinsertions: usize,
+    pub(crate) relocated_reinsertions: usize,
+    pub(crate) skipped_reinsertions: usize,
+}
+
+impl InsertionMetrics {
+    /// Returns the number of inserted elements.
+    pub fn insertions(&self) -> usize {
+        self.insertions
+    }
+
+    /// Returns how often a node's envelope had to be enlarged to fit a new child.
+    pub fn envelope_merges(&self) -> usize {
+        self.envelope_merges
+    }
+
+    /// Returns how often enlarging a node's envelope was skipped since it already contained
+    /// the new child.
+    pub fn skipped_envelope_merges(&self) -> usize {
+        self.skipped_envelope_merges
+    }
+
+    /// Returns how often a node exceeded its maximum size during an insertion.
+    ///
+    /// An overflown node is either split or some of its children are reinserted.
+    pub fn overflows(&self) -> usize {
+        self.overflows
+    }
+
+    /// Returns the number of nodes that were removed from an overflown node and reinserted.
+    pub fn reinsertions(&self) -> usize {
+        self.reinsertions
+    }
+
+    /// Returns how many reinserted nodes ended up in a different node than they were
+    /// removed from.
+    ///
+    /// Reinsertion is only effective if the reinserted nodes relocate. The remaining
+    /// `reinsertions() - relocated_reinsertions()` nodes returned to their original node.
+    pub fn relocated_reinsertions(&self) -> usize {
+        self.relocated_reinsertions
+    }
+
+    /// Returns how often reinsertion was skipped by the
+    /// [adaptive reinsertion](struct.RTree.html#method.set_adaptive_reinsertion) mode.
+    pub fn skipped_reinsertions(&self) -> usize {
+        self.skipped_reinsertions
+    }
+}
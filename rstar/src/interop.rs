@@ -0,0 +1,140 @@
+//! Point implementations for vector types of other crates.
+//!
+//! Each implementation is enabled by the feature of the same name as the crate. Supported are
+//! `nalgebra::Point2` and `nalgebra::Point3` as well as `cgmath::Point2` and `cgmath::Point3`.
+//! Other crates, e.g. `mint`, are not supported. Their points can be converted into arrays,
+//! which implement [Point](../trait.Point.html) for any dimension.
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra_points {
+    use crate::{Point, RTreeNum};
+    use nalgebra::{Point2, Point3, Scalar};
+
+    macro_rules! implement_point_for_nalgebra {
+        ($point:ident, $dimensions:expr, $($index:expr),*) => {
+            impl<S> Point for $point<S>
+            where
+                S: RTreeNum + Scalar,
+            {
+                type Scalar = S;
+
+                const DIMENSIONS: usize = $dimensions;
+
+                fn generate(generator: impl Fn(usize) -> S) -> Self {
+                    $point::new($(generator($index)),*)
+                }
+
+                fn nth(&self, index: usize) -> S {
+                    self[index]
+                }
+
+                fn nth_mut(&mut self, index: usize) -> &mut S {
+                    &mut self[index]
+                }
+            }
+        };
+    }
+
+    implement_point_for_nalgebra!(Point2, 2, 0, 1);
+    implement_point_for_nalgebra!(Point3, 3, 0, 1, 2);
+
+    #[cfg(test)]
+    mod test {
+        use crate::test_utilities::{create_random_points, SEED_1};
+        use crate::{RTree, AABB};
+        use nalgebra::{Point2, Point3};
+
+        #[test]
+        fn test_nalgebra_points() {
+            let points: Vec<_> = create_random_points(200, SEED_1)
+                .into_iter()
+                .map(|[x, y]| Point2::new(x, y))
+                .collect();
+            let tree = RTree::bulk_load(points.clone());
+            for point in &points {
+                assert_eq!(tree.nearest_neighbor(point), Some(point));
+            }
+            let envelope = AABB::from_corners(Point2::new(0.0, 0.0), Point2::new(0.5, 0.5));
+            let expected = points.iter().filter(|p| p.x <= 0.5 && p.y <= 0.5).count();
+            assert_eq!(tree.locate_in_envelope(&envelope).count(), expected);
+
+            let mut tree = RTree::new();
+            tree.insert(Point3::new(1, 2, 3));
+            tree.insert(Point3::new(-1, 0, 1));
+            assert_eq!(
+                tree.nearest_neighbor(&Point3::new(0, 0, 0)),
+                Some(&Point3::new(-1, 0, 1))
+            );
+        }
+    }
+}
+
+#[cfg(feature = "cgmath")]
+mod cgmath_points {
+    use crate::{Point, RTreeNum};
+    use cgmath::{Point2, Point3};
+
+    macro_rules! implement_point_for_cgmath {
+        ($point:ident, $dimensions:expr, $($index:expr => $field:ident),*) => {
+            impl<S> Point for $point<S>
+            where
+                S: RTreeNum,
+            {
+                type Scalar = S;
+
+                const DIMENSIONS: usize = $dimensions;
+
+                fn generate(generator: impl Fn(usize) -> S) -> Self {
+                    $point::new($(generator($index)),*)
+                }
+
+                fn nth(&self, index: usize) -> S {
+                    match index {
+                        $($index => self.$field,)*
+                        _ => unreachable!(),
+                    }
+                }
+
+                fn nth_mut(&mut self, index: usize) -> &mut S {
+                    match index {
+                        $($index => &mut self.$field,)*
+                        _ => unreachable!(),
+                    }
+                }
+            }
+        };
+    }
+
+    implement_point_for_cgmath!(Point2, 2, 0 => x, 1 => y);
+    implement_point_for_cgmath!(Point3, 3, 0 => x, 1 => y, 2 => z);
+
+    #[cfg(test)]
+    mod test {
+        use crate::test_utilities::{create_random_points, SEED_1};
+        use crate::{RTree, AABB};
+        use cgmath::{Point2, Point3};
+
+        #[test]
+        fn test_cgmath_points() {
+            let points: Vec<_> = create_random_points(200, SEED_1)
+                .into_iter()
+                .map(|[x, y]| Point2::new(x, y))
+                .collect();
+            let tree = RTree::bulk_load(points.clone());
+            for point in &points {
+                assert_eq!(tree.nearest_neighbor(point), Some(point));
+            }
+            let envelope = AABB::from_corners(Point2::new(0.0, 0.0), Point2::new(0.5, 0.5));
+            let expected = points.iter().filter(|p| p.x <= 0.5 && p.y <= 0.5).count();
+            assert_eq!(tree.locate_in_envelope(&envelope).count(), expected);
+
+            let mut tree = RTree::new();
+            tree.insert(Point3::new(1, 2, 3));
+            tree.insert(Point3::new(-1, 0, 1));
+            assert_eq!(
+                tree.nearest_neighbor(&Point3::new(0, 0, 0)),
+                Some(&Point3::new(-1, 0, 1))
+            );
+        }
+    }
+}
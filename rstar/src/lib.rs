@@ -20,28 +20,84 @@
 //! # (De)Serialization
 //! Enable the `serde` feature for [Serde](https://crates.io/crates/serde) support.
 //!
+//! # GeoJSON export
+//! Enable the `geojson` feature to export trees for inspection in GIS tools, see the
+//! [geojson module](geojson/index.html).
+//!
+//! # Other point types
+//! Enable the `nalgebra` feature to use `nalgebra::Point2` and `nalgebra::Point3` as points,
+//! or the `cgmath` feature to use `cgmath::Point2` and `cgmath::Point3`.
+//!
+//! # Parallel iteration
+//! Enable the `rayon` feature to iterate over a tree's elements with
+//! [Rayon](https://crates.io/crates/rayon), e.g. with
+//! [par_iter](struct.RTree.html#method.par_iter).
+//!
+//! # Well-known text
+//! Enable the `wkt` feature to parse and format primitives as WKT, see the
+//! [wkt module](wkt/index.html).
+//!
 #![deny(missing_docs)]
 #![forbid(unsafe_code)]
 
 mod aabb;
+mod affine_transform;
 mod algorithm;
+mod binary;
+mod bounding_sphere;
 mod envelope;
+#[cfg(feature = "geojson")]
+pub mod geojson;
+mod insertion_metrics;
+mod interop;
+mod mapped;
+mod metadata;
+mod metric;
 mod node;
 mod object;
 mod params;
+mod persistent;
 mod point;
 pub mod primitives;
+pub mod profiling;
+mod query_stats;
+mod rplus;
 mod rtree;
+mod shared;
+mod space_time;
+mod static_tree;
+pub mod tiles;
+#[cfg(feature = "wkt")]
+pub mod wkt;
 
 #[cfg(test)]
 mod test_utilities;
 
 pub use crate::aabb::AABB;
+pub use crate::affine_transform::AffineTransform;
+pub use crate::algorithm::clustering::ClusteredItem;
+pub use crate::algorithm::guttman::{LinearInsertionStrategy, QuadraticInsertionStrategy};
 pub use crate::algorithm::rstar::RStarInsertionStrategy;
 pub use crate::algorithm::selection_functions::SelectionFunction;
+pub use crate::algorithm::weighted_sampling::WeightedSampler;
+pub use crate::binary::BinaryCodable;
+pub use crate::bounding_sphere::BoundingSphere;
 pub use crate::envelope::Envelope;
-pub use crate::node::{ParentNode, RTreeNode};
+pub use crate::insertion_metrics::InsertionMetrics;
+pub use crate::mapped::MappedRTree;
+pub use crate::metadata::RTreeMetadata;
+pub use crate::metric::{Chebyshev, Manhattan, Metric, SquaredEuclidean};
+pub use crate::node::{ParentNode, RTreeNode, TraversalOrder};
 pub use crate::object::{PointDistance, RTreeObject};
-pub use crate::params::{DefaultParams, InsertionStrategy, RTreeParams};
+pub use crate::params::{
+    DefaultParams, EnvelopeCaching, InsertionStrategy, RTreeParams, ReinsertionOrder,
+    ReinsertionPolicy,
+};
+pub use crate::persistent::PersistentRTree;
 pub use crate::point::{Point, RTreeNum};
+pub use crate::query_stats::QueryStats;
+pub use crate::rplus::RPlusTree;
 pub use crate::rtree::RTree;
+pub use crate::shared::SharedRTree;
+pub use crate::space_time::SpaceTimeEnvelope;
+pub use crate::static_tree::StaticRTree;
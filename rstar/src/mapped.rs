@@ -0,0 +1,372 @@
+use crate::algorithm::selection_functions::*;
+use crate::binary::{self, BinaryCodable, LEAF_TAG, NODE_TAG, ROOT_TAG};
+use crate::envelope::Envelope;
+use crate::metadata::RTreeMetadata;
+use crate::object::{PointDistance, RTreeObject};
+use crate::params::{DefaultParams, RTreeParams};
+use crate::point::{total_cmp, Point};
+use num_traits::Zero;
+use std::collections::BinaryHeap;
+use std::io;
+
+/// A read-only r-tree that is queried directly within its
+/// [binary representation](struct.RTree.html#method.write_to).
+///
+/// Creating a `MappedRTree` only reads the header, no node is deserialized in advance.
+/// Queries decode the visited nodes on the fly and return owned copies of the found elements.
+/// Combined with a memory mapped index file, a large prebuilt index is available right after
+/// startup, and only the pages touched by queries are ever loaded. Mapping the file is left to
+/// crates like `memmap2`, which require `unsafe` code.
+///
+/// The bytes are not validated in advance. Queries that encounter corrupt data return an
+/// error of kind [InvalidData](https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidData)
+/// or [UnexpectedEof](https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.UnexpectedEof)
+/// instead of panicking.
+///
+/// # Example
+/// ```
+/// use rstar::{MappedRTree, RTree, AABB};
+///
+/// let tree = RTree::bulk_load(vec![[0.0, 0.0], [1.0, 1.0], [2.0, 0.5]]);
+/// // The bytes could as well be a memory mapped file
+/// let bytes = tree.to_bytes();
+/// let mapped: MappedRTree<[f64; 2]> = MappedRTree::new(&bytes).unwrap();
+/// assert_eq!(mapped.size(), 3);
+/// assert_eq!(mapped.nearest_neighbor(&[1.8, 0.4]).unwrap(), Some([2.0, 0.5]));
+/// let unit_square = AABB::from_corners([0.0, 0.0], [1.0, 1.0]);
+/// assert_eq!(mapped.locate_in_envelope(&unit_square).unwrap().len(), 2);
+/// ```
+pub struct MappedRTree<'a, T, Params = DefaultParams>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    bytes: &'a [u8],
+    metadata: RTreeMetadata,
+    size: usize,
+    root_offset: usize,
+    _marker: ::std::marker::PhantomData<(T, Params)>,
+}
+
+enum Record<'a, T>
+where
+    T: RTreeObject,
+{
+    Leaf(T),
+    Node {
+        envelope: T::Envelope,
+        // The encoded offsets of all children
+        children: &'a [u8],
+    },
+}
+
+const OFFSET_SIZE: usize = std::mem::size_of::<u64>();
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl<'a, T, Params> MappedRTree<'a, T, Params>
+where
+    T: RTreeObject + BinaryCodable,
+    T::Envelope: BinaryCodable,
+    Params: RTreeParams,
+{
+    /// Creates a view into an r-tree in binary format.
+    ///
+    /// Only the header is read. Fails if the header does not match the element type or
+    /// parameters.
+    pub fn new(bytes: &'a [u8]) -> io::Result<Self> {
+        let mut reader = bytes;
+        let header = binary::read_header::<T, Params, _>(&mut reader)?;
+        let records_start = bytes.len() - reader.len();
+        if reader.len() < OFFSET_SIZE {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let mut trailer = &bytes[bytes.len() - OFFSET_SIZE..];
+        let root_offset = u64::read_from(&mut trailer)? as usize;
+        if root_offset < records_start || root_offset >= bytes.len() - OFFSET_SIZE {
+            return Err(invalid_data("Invalid root offset"));
+        }
+        let size = header.size as usize;
+        Ok(MappedRTree {
+            bytes,
+            metadata: header.metadata,
+            size,
+            root_offset,
+            _marker: Default::default(),
+        })
+    }
+
+    /// Returns the number of elements, as recorded in the header.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the tree's [metadata](struct.RTreeMetadata.html).
+    pub fn metadata(&self) -> RTreeMetadata {
+        self.metadata.refreshed::<T, Params>(self.size)
+    }
+
+    fn read_record(&self, offset: usize) -> io::Result<Record<'a, T>> {
+        let mut reader = self
+            .bytes
+            .get(offset..)
+            .ok_or_else(|| invalid_data("Invalid record offset"))?;
+        match u8::read_from(&mut reader)? {
+            LEAF_TAG => T::read_from(&mut reader).map(Record::Leaf),
+            NODE_TAG | ROOT_TAG => {
+                let envelope = T::Envelope::read_from(&mut reader)?;
+                // Skip the node id
+                u64::read_from(&mut reader)?;
+                let len = u64::read_from(&mut reader)? as usize;
+                let children = len
+                    .checked_mul(OFFSET_SIZE)
+                    .and_then(|children_size| reader.get(..children_size))
+                    .ok_or_else(|| invalid_data("Invalid child count"))?;
+                Ok(Record::Node { envelope, children })
+            }
+            _ => Err(invalid_data("Invalid record tag")),
+        }
+    }
+
+    /// Returns the children's offsets of the node record at `offset`.
+    fn children(&self, offset: usize) -> io::Result<Vec<usize>> {
+        match self.read_record(offset)? {
+            Record::Node { children, .. } => children
+                .chunks(OFFSET_SIZE)
+                .map(|mut chunk| {
+                    let child_offset = u64::read_from(&mut chunk)? as usize;
+                    // Children precede their parent, this rules out cycles
+                    if child_offset < offset {
+                        Ok(child_offset)
+                    } else {
+                        Err(invalid_data("Invalid child offset"))
+                    }
+                })
+                .collect(),
+            Record::Leaf(_) => Err(invalid_data("Expected a node record")),
+        }
+    }
+
+    /// Returns all elements selected by a
+    /// [SelectionFunction](trait.SelectionFunction.html).
+    pub fn locate_with_selection_function<S: SelectionFunction<T>>(
+        &self,
+        selection_function: S,
+    ) -> io::Result<Vec<T>> {
+        let mut result = Vec::new();
+        let mut todo = vec![self.root_offset];
+        while let Some(offset) = todo.pop() {
+            for child_offset in self.children(offset)? {
+                match self.read_record(child_offset)? {
+                    Record::Leaf(t) => {
+                        if selection_function.should_unpack_leaf(&t) {
+                            result.push(t);
+                        }
+                    }
+                    Record::Node { envelope, .. } => {
+                        if selection_function.should_unpack_parent(&envelope) {
+                            todo.push(child_offset);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns all elements, see [RTree::iter](struct.RTree.html#method.iter).
+    pub fn elements(&self) -> io::Result<Vec<T>> {
+        self.locate_with_selection_function(SelectAllFunc)
+    }
+
+    /// Returns all elements contained in an envelope, see
+    /// [RTree::locate_in_envelope](struct.RTree.html#method.locate_in_envelope).
+    pub fn locate_in_envelope(&self, envelope: &T::Envelope) -> io::Result<Vec<T>> {
+        self.locate_with_selection_function(SelectInEnvelopeFunction::new(*envelope))
+    }
+
+    /// Returns all elements whose envelope intersects an envelope, see
+    /// [RTree::locate_in_envelope_intersecting](struct.RTree.html#method.locate_in_envelope_intersecting).
+    pub fn locate_in_envelope_intersecting(&self, envelope: &T::Envelope) -> io::Result<Vec<T>> {
+        self.locate_with_selection_function(SelectInEnvelopeFuncIntersecting::new(*envelope))
+    }
+}
+
+impl<'a, T, Params> MappedRTree<'a, T, Params>
+where
+    T: PointDistance + BinaryCodable,
+    T::Envelope: BinaryCodable,
+    Params: RTreeParams,
+{
+    /// Returns all elements that contain a point, see
+    /// [RTree::locate_all_at_point](struct.RTree.html#method.locate_all_at_point).
+    pub fn locate_all_at_point(
+        &self,
+        point: &<T::Envelope as Envelope>::Point,
+    ) -> io::Result<Vec<T>> {
+        self.locate_with_selection_function(SelectAtPointFunction::new(*point))
+    }
+
+    /// Returns all elements within a squared distance of a point, see
+    /// [RTree::locate_within_distance](struct.RTree.html#method.locate_within_distance).
+    pub fn locate_within_distance(
+        &self,
+        query_point: <T::Envelope as Envelope>::Point,
+        max_squared_radius: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+    ) -> io::Result<Vec<T>> {
+        self.locate_with_selection_function(SelectWithinDistanceFunction::new(
+            query_point,
+            max_squared_radius,
+        ))
+    }
+
+    /// Returns the nearest neighbor of a point, see
+    /// [RTree::nearest_neighbor](struct.RTree.html#method.nearest_neighbor).
+    ///
+    /// Returns `Ok(None)` if the tree is empty.
+    pub fn nearest_neighbor(
+        &self,
+        query_point: &<T::Envelope as Envelope>::Point,
+    ) -> io::Result<Option<T>> {
+        let mut heap = BinaryHeap::new();
+        heap.push(HeapEntry {
+            distance_2: Zero::zero(),
+            offset: self.root_offset,
+            element: None,
+        });
+        while let Some(entry) = heap.pop() {
+            if let Some(element) = entry.element {
+                return Ok(Some(element));
+            }
+            for child_offset in self.children(entry.offset)? {
+                let (distance_2, element) = match self.read_record(child_offset)? {
+                    Record::Leaf(t) => (t.distance_2(query_point), Some(t)),
+                    Record::Node { envelope, .. } => (envelope.distance_2(query_point), None),
+                };
+                heap.push(HeapEntry {
+                    distance_2,
+                    offset: child_offset,
+                    element,
+                });
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// A node or element of a nearest neighbor query, ordered by reversed distance.
+struct HeapEntry<T>
+where
+    T: RTreeObject,
+{
+    distance_2: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+    offset: usize,
+    element: Option<T>,
+}
+
+impl<T> PartialEq for HeapEntry<T>
+where
+    T: RTreeObject,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.distance_2 == other.distance_2
+    }
+}
+
+impl<T> Eq for HeapEntry<T> where T: RTreeObject {}
+
+impl<T> PartialOrd for HeapEntry<T>
+where
+    T: RTreeObject,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<::std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for HeapEntry<T>
+where
+    T: RTreeObject,
+{
+    fn cmp(&self, other: &Self) -> ::std::cmp::Ordering {
+        // Inverse comparison creates a min heap
+        total_cmp(&other.distance_2, &self.distance_2)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MappedRTree;
+    use crate::primitives::Line;
+    use crate::test_utilities::{create_random_lines, create_random_points, SEED_1, SEED_2};
+    use crate::{RTree, AABB};
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_mapped_queries() {
+        let points = create_random_points(1000, SEED_1);
+        let tree = RTree::bulk_load(points.clone());
+        let bytes = tree.to_bytes();
+        let mapped: MappedRTree<[f64; 2]> = MappedRTree::new(&bytes).unwrap();
+        assert_eq!(mapped.size(), tree.size());
+        assert_eq!(mapped.metadata(), tree.metadata());
+        assert_eq!(mapped.elements().unwrap().len(), points.len());
+
+        let envelope = AABB::from_corners([0.2, 0.3], [0.6, 0.5]);
+        let mut expected: Vec<_> = tree.locate_in_envelope(&envelope).cloned().collect();
+        let mut actual = mapped.locate_in_envelope(&envelope).unwrap();
+        expected.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        actual.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        assert_eq!(actual, expected);
+        assert_eq!(
+            mapped
+                .locate_within_distance([0.5, 0.5], 0.01)
+                .unwrap()
+                .len(),
+            tree.locate_within_distance([0.5, 0.5], 0.01).count()
+        );
+
+        for query_point in create_random_points(100, SEED_2) {
+            assert_eq!(
+                mapped.nearest_neighbor(&query_point).unwrap().as_ref(),
+                tree.nearest_neighbor(&query_point)
+            );
+        }
+        assert!(mapped
+            .locate_all_at_point(&points[3])
+            .unwrap()
+            .contains(&points[3]));
+
+        let lines = RTree::bulk_load(create_random_lines(100, SEED_1));
+        let bytes = lines.to_bytes();
+        let mapped: MappedRTree<Line<[f64; 2]>> = MappedRTree::new(&bytes).unwrap();
+        assert_eq!(
+            mapped.nearest_neighbor(&[0.5, 0.5]).unwrap().as_ref(),
+            lines.nearest_neighbor(&[0.5, 0.5])
+        );
+
+        let empty: RTree<[f64; 2]> = RTree::new();
+        let bytes = empty.to_bytes();
+        let mapped: MappedRTree<[f64; 2]> = MappedRTree::new(&bytes).unwrap();
+        assert_eq!(mapped.nearest_neighbor(&[0.0, 0.0]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_mapped_corrupt_data() {
+        let tree = RTree::bulk_load(create_random_points(100, SEED_1));
+        let mut bytes = tree.to_bytes();
+        assert!(MappedRTree::<[f64; 3]>::new(&bytes).is_err());
+        let len = bytes.len();
+        // Point the root offset at itself
+        bytes[len - 8..].copy_from_slice(&((len - 8) as u64).to_le_bytes());
+        assert!(MappedRTree::<[f64; 2]>::new(&bytes).is_err());
+        // Point the root offset at a leaf, queries must fail instead of panicking
+        bytes[len - 8..].copy_from_slice(&(len as u64 - 100).to_le_bytes());
+        if let Ok(mapped) = MappedRTree::<[f64; 2]>::new(&bytes) {
+            let kind = mapped.elements().err().unwrap().kind();
+            assert!(kind == ErrorKind::InvalidData || kind == ErrorKind::UnexpectedEof);
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use crate::envelope::Envelope;
+use crate::object::RTreeObject;
+use crate::params::RTreeParams;
+use crate::point::{scalar_tag, Point};
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer};
+
+/// Describes an r-tree's contents and configuration.
+///
+/// Use [RTree::metadata](struct.RTree.html#method.metadata) to obtain the metadata of a tree.
+/// Arbitrary key-value pairs can be attached to a tree with
+/// [RTree::user_metadata_mut](struct.RTree.html#method.user_metadata_mut).
+///
+/// # (De)Serialization
+/// Serialized trees contain their metadata before any of their elements.
+/// [from_serialized_tree](#method.from_serialized_tree) reads only the metadata block,
+/// which allows to inspect and validate index files without loading their content.
+///
+/// # Example
+/// ```
+/// use rstar::RTree;
+///
+/// let mut tree = RTree::bulk_load(vec![[0.0f32, 1.0], [2.0, 3.0]]);
+/// tree.user_metadata_mut()
+///     .insert("source".to_string(), "survey.csv".to_string());
+/// let metadata = tree.metadata();
+/// assert_eq!(metadata.element_count(), 2);
+/// assert_eq!(metadata.dimensions(), 2);
+/// assert_eq!(metadata.scalar_type(), "f32");
+/// assert_eq!(metadata.user_data()["source"], "survey.csv");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RTreeMetadata {
+    element_count: usize,
+    dimensions: usize,
+    scalar_type: String,
+    min_size: usize,
+    max_size: usize,
+    reinsertion_count: usize,
+    build_timestamp: Option<u64>,
+    user_data: BTreeMap<String, String>,
+}
+
+impl RTreeMetadata {
+    pub(crate) fn with_build_timestamp(build_timestamp: Option<u64>) -> Self {
+        RTreeMetadata {
+            build_timestamp,
+            ..Default::default()
+        }
+    }
+
+    /// Returns a copy with all fields derived from the tree being up to date.
+    pub(crate) fn refreshed<T, Params>(&self, element_count: usize) -> Self
+    where
+        T: RTreeObject,
+        Params: RTreeParams,
+    {
+        RTreeMetadata {
+            element_count,
+            dimensions: <T::Envelope as Envelope>::Point::DIMENSIONS,
+            scalar_type: scalar_tag::<<<T::Envelope as Envelope>::Point as Point>::Scalar>(),
+            min_size: Params::MIN_SIZE,
+            max_size: Params::MAX_SIZE,
+            reinsertion_count: Params::REINSERTION_COUNT,
+            build_timestamp: self.build_timestamp,
+            user_data: self.user_data.clone(),
+        }
+    }
+
+    pub(crate) fn set_build_timestamp(&mut self, build_timestamp: Option<u64>) {
+        self.build_timestamp = build_timestamp;
+    }
+
+    pub(crate) fn user_data_mut(&mut self) -> &mut BTreeMap<String, String> {
+        &mut self.user_data
+    }
+
+    /// Returns the number of elements contained in the tree.
+    pub fn element_count(&self) -> usize {
+        self.element_count
+    }
+
+    /// Returns the number of dimensions of the tree's points.
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// Returns the name of the points' scalar type, e.g. `"f64"`.
+    ///
+    /// The name consists of `i` for integral or `f` for other types, followed by the type's
+    /// size in bits. It matches the name of the primitive number types and is stable across
+    /// compiler versions.
+    pub fn scalar_type(&self) -> &str {
+        &self.scalar_type
+    }
+
+    /// Returns the tree's [MIN_SIZE](trait.RTreeParams.html#associatedconstant.MIN_SIZE) parameter.
+    pub fn min_size(&self) -> usize {
+        self.min_size
+    }
+
+    /// Returns the tree's [MAX_SIZE](trait.RTreeParams.html#associatedconstant.MAX_SIZE) parameter.
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Returns the tree's
+    /// [REINSERTION_COUNT](trait.RTreeParams.html#associatedconstant.REINSERTION_COUNT) parameter.
+    pub fn reinsertion_count(&self) -> usize {
+        self.reinsertion_count
+    }
+
+    /// Returns the timestamp set with
+    /// [RTree::set_build_timestamp](struct.RTree.html#method.set_build_timestamp).
+    ///
+    /// The tree does not read the system clock itself, bulk loading and serializing the same
+    /// elements always yields the same result. The timestamp is unset by default.
+    pub fn build_timestamp(&self) -> Option<u64> {
+        self.build_timestamp
+    }
+
+    /// Returns the user defined key-value pairs attached to the tree.
+    pub fn user_data(&self) -> &BTreeMap<String, String> {
+        &self.user_data
+    }
+
+    /// Reads the metadata block of a serialized r-tree.
+    ///
+    /// Only the metadata is deserialized, the tree's elements are skipped or, for formats
+    /// that are read sequentially, not read at all.
+    ///
+    /// # Example
+    /// ```
+    /// # #[cfg(feature = "serde")]
+    /// # {
+    /// use rstar::{RTree, RTreeMetadata};
+    ///
+    /// let tree = RTree::bulk_load(vec![[0, 1], [2, 3], [4, 5]]);
+    /// let json = serde_json::to_string(&tree).unwrap();
+    /// let mut deserializer = serde_json::Deserializer::from_str(&json);
+    /// let metadata = RTreeMetadata::from_serialized_tree(&mut deserializer).unwrap();
+    /// assert_eq!(metadata.element_count(), 3);
+    /// # }
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn from_serialized_tree<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename = "RTree")]
+        struct Header {
+            metadata: RTreeMetadata,
+        }
+        Header::deserialize(deserializer).map(|header| header.metadata)
+    }
+}
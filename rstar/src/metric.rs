@@ -0,0 +1,174 @@
+use crate::aabb::AABB;
+use crate::envelope::Envelope;
+use crate::object::{PointDistance, RTreeObject};
+use crate::point::{max_inline, Point, PointExt};
+use num_traits::{Signed, Zero};
+
+/// Defines the distance measured by nearest neighbor and within-distance queries.
+///
+/// By default, r-tree queries use the _squared_ euclidean distance as defined by
+/// [PointDistance](trait.PointDistance.html). Methods like
+/// [nearest_neighbor_with_metric](struct.RTree.html#method.nearest_neighbor_with_metric)
+/// accept a different metric instead.
+///
+/// A metric needs to provide two distances: The distance to an object and a lower bound
+/// for the distance of any object contained within an envelope. The latter is used to prune
+/// parent nodes during a search. It is a logic error if an object is closer to a point
+/// than its envelope.
+///
+/// The following metrics are already implemented:
+///  - [SquaredEuclidean](struct.SquaredEuclidean.html) for any object implementing `PointDistance`
+///  - [Manhattan](struct.Manhattan.html) for points
+///  - [Chebyshev](struct.Chebyshev.html) for points
+///
+/// # Example
+/// ```
+/// use rstar::{RTree, Manhattan};
+///
+/// let tree = RTree::bulk_load(vec![[0, 3], [2, 2]]);
+/// // Both points have a squared euclidean distance of 8 or 9 to the origin...
+/// assert_eq!(tree.nearest_neighbor(&[0, 0]), Some(&[2, 2]));
+/// // ...but [0, 3] is closer to it when measured along the axes.
+/// assert_eq!(tree.nearest_neighbor_with_metric(&[0, 0], Manhattan), Some(&[0, 3]));
+/// ```
+pub trait Metric<T>
+where
+    T: RTreeObject,
+{
+    /// Returns the distance between an object and a point.
+    fn distance(
+        &self,
+        object: &T,
+        point: &<T::Envelope as Envelope>::Point,
+    ) -> <<T::Envelope as Envelope>::Point as Point>::Scalar;
+
+    /// Returns a lower bound for the distance between a point and any object within an envelope.
+    fn envelope_distance(
+        &self,
+        envelope: &T::Envelope,
+        point: &<T::Envelope as Envelope>::Point,
+    ) -> <<T::Envelope as Envelope>::Point as Point>::Scalar;
+}
+
+/// The squared euclidean distance.
+///
+/// This metric is used by all queries that don't take a metric explicitly. Distances are
+/// calculated with [PointDistance::distance_2](trait.PointDistance.html#tymethod.distance_2).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SquaredEuclidean;
+
+impl<T> Metric<T> for SquaredEuclidean
+where
+    T: PointDistance,
+{
+    fn distance(
+        &self,
+        object: &T,
+        point: &<T::Envelope as Envelope>::Point,
+    ) -> <<T::Envelope as Envelope>::Point as Point>::Scalar {
+        object.distance_2(point)
+    }
+
+    fn envelope_distance(
+        &self,
+        envelope: &T::Envelope,
+        point: &<T::Envelope as Envelope>::Point,
+    ) -> <<T::Envelope as Envelope>::Point as Point>::Scalar {
+        envelope.distance_2(point)
+    }
+}
+
+/// The manhattan (taxicab) distance, the sum of all absolute coordinate differences.
+///
+/// Unlike [SquaredEuclidean](struct.SquaredEuclidean.html), this metric is _not_ squared.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Manhattan;
+
+impl Manhattan {
+    fn length<P: Point>(diff: &P) -> P::Scalar {
+        diff.fold(Zero::zero(), |acc, value| acc + value.abs())
+    }
+}
+
+impl<P> Metric<P> for Manhattan
+where
+    P: Point,
+{
+    fn distance(&self, object: &P, point: &P) -> P::Scalar {
+        Self::length(&object.sub(point))
+    }
+
+    fn envelope_distance(&self, envelope: &AABB<P>, point: &P) -> P::Scalar {
+        Self::length(&envelope.min_point(point).sub(point))
+    }
+}
+
+/// The chebyshev (chessboard) distance, the largest absolute coordinate difference.
+///
+/// Unlike [SquaredEuclidean](struct.SquaredEuclidean.html), this metric is _not_ squared.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Chebyshev;
+
+impl Chebyshev {
+    fn length<P: Point>(diff: &P) -> P::Scalar {
+        diff.fold(Zero::zero(), |acc, value| max_inline(acc, value.abs()))
+    }
+}
+
+impl<P> Metric<P> for Chebyshev
+where
+    P: Point,
+{
+    fn distance(&self, object: &P, point: &P) -> P::Scalar {
+        Self::length(&object.sub(point))
+    }
+
+    fn envelope_distance(&self, envelope: &AABB<P>, point: &P) -> P::Scalar {
+        Self::length(&envelope.min_point(point).sub(point))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Chebyshev, Manhattan, Metric};
+    use crate::test_utilities::{create_random_points, SEED_1, SEED_2};
+    use crate::RTree;
+
+    #[test]
+    fn test_metric_distances() {
+        assert_eq!(Manhattan.distance(&[1, -2], &[-1, 1]), 5);
+        assert_eq!(Chebyshev.distance(&[1, -2], &[-1, 1]), 3);
+    }
+
+    fn check_nearest_neighbor_iter_with_metric<M: Metric<[f64; 2]> + Copy>(metric: M) {
+        let points = create_random_points(500, SEED_1);
+        let tree = RTree::bulk_load(points.clone());
+        for query_point in &create_random_points(20, SEED_2) {
+            let mut expected: Vec<_> = points
+                .iter()
+                .map(|p| metric.distance(p, query_point))
+                .collect();
+            expected.sort_by(|l, r| l.partial_cmp(r).unwrap());
+            let distances: Vec<_> = tree
+                .nearest_neighbor_iter_with_metric(query_point, metric)
+                .map(|(_, distance)| distance)
+                .collect();
+            assert_eq!(distances, expected);
+
+            let max_distance = 0.3;
+            let within_count = points
+                .iter()
+                .filter(|p| metric.distance(p, query_point) <= max_distance)
+                .count();
+            let located =
+                tree.locate_within_distance_with_metric(*query_point, max_distance, metric);
+            assert_eq!(located.count(), within_count);
+        }
+    }
+
+    #[test]
+    fn test_nearest_neighbor_iter_with_metric() {
+        check_nearest_neighbor_iter_with_metric(Manhattan);
+        check_nearest_neighbor_iter_with_metric(Chebyshev);
+    }
+}
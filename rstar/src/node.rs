@@ -1,6 +1,10 @@
+use crate::algorithm::iterators::NodeIterator;
 use crate::envelope::Envelope;
 use crate::object::RTreeObject;
 use crate::params::RTreeParams;
+use crate::primitives::CachedEnvelope;
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -31,7 +35,7 @@ where
 /// Represents an internal parent node.
 ///
 /// For most applications, using this type should not be required. Allows read access to this
-/// node's envelope and its children.
+/// node's envelope, its children and its [id](#method.id).
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParentNode<T>
@@ -40,6 +44,54 @@ where
 {
     pub(crate) children: Vec<RTreeNode<T>>,
     pub(crate) envelope: T::Envelope,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default = "next_node_id", deserialize_with = "deserialize_node_id")
+    )]
+    id: u64,
+}
+
+/// The order in which the nodes of a tree are traversed.
+///
+/// All elements of a tree are stored at the same depth. Thus, breadth first traversal visits
+/// the tree level by level, and the depth of the visited nodes never decreases.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TraversalOrder {
+    /// Visits all descendants of a node before its next sibling.
+    #[default]
+    DepthFirst,
+    /// Visits all nodes of a depth before any node of the next depth.
+    BreadthFirst,
+}
+
+// AtomicU64 is not available on all targets
+static NEXT_NODE_ID: AtomicUsize = AtomicUsize::new(0);
+
+fn next_node_id() -> u64 {
+    // Wrapping around would hand out ids that are already in use
+    NEXT_NODE_ID
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |id| id.checked_add(1))
+        .expect("Node ids are exhausted") as u64
+}
+
+/// Makes sure that no new node will receive a restored node's id.
+///
+/// Returns `None` if the id cannot be handed out on this target, i.e. if it is not smaller
+/// than `usize::MAX`.
+fn restore_node_id(id: u64) -> Option<u64> {
+    let next = usize::try_from(id).ok()?.checked_add(1)?;
+    NEXT_NODE_ID.fetch_max(next, Ordering::Relaxed);
+    Some(id)
+}
+
+/// Deserializes a node id and makes sure that no new node will receive the same id.
+#[cfg(feature = "serde")]
+fn deserialize_node_id<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let id = u64::deserialize(deserializer)?;
+    restore_node_id(id).ok_or_else(|| serde::de::Error::custom("node id out of range"))
 }
 
 impl<T> RTreeObject for RTreeNode<T>
@@ -56,6 +108,40 @@ where
     }
 }
 
+/// A child of a node, as seen by the query algorithms.
+///
+/// Besides `RTreeNode`, the children of a `SharedRTree`'s nodes implement this trait, which
+/// lets both trees share their traversal code.
+pub trait TreeChild<T>: Sized
+where
+    T: RTreeObject,
+{
+    /// Returns the element of a leaf, or the envelope and children of a parent node.
+    fn unpack(&self) -> UnpackedChild<'_, T, Self>;
+}
+
+/// The contents of a [TreeChild](trait.TreeChild.html).
+pub enum UnpackedChild<'a, T, C>
+where
+    T: RTreeObject,
+{
+    Leaf(&'a T),
+    Parent(&'a T::Envelope, &'a [C]),
+}
+
+impl<T> TreeChild<T> for RTreeNode<T>
+where
+    T: RTreeObject,
+{
+    #[inline]
+    fn unpack(&self) -> UnpackedChild<'_, T, Self> {
+        match self {
+            RTreeNode::Leaf(ref t) => UnpackedChild::Leaf(t),
+            RTreeNode::Parent(ref data) => UnpackedChild::Parent(&data.envelope, &data.children),
+        }
+    }
+}
+
 #[doc(hidden)]
 impl<T> RTreeNode<T>
 where
@@ -83,24 +169,152 @@ where
         self.envelope
     }
 
-    pub(crate) fn new_root<Params>() -> Self
+    /// Returns this node's id.
+    ///
+    /// Ids never change during a node's lifetime, even if children are added or removed. They
+    /// are kept when a tree is serialized, which allows external storage to refer to a
+    /// subtree across reloads. Nodes created by splitting a node receive a new id, and so
+    /// does the root if the tree grows in height.
+    ///
+    /// New nodes receive ids from a counter shared by all trees of the process. Loading a
+    /// tree advances the counter past the loaded ids. Ids are thus unique within a single
+    /// tree that is only loaded and modified in this process, but not necessarily across
+    /// trees: Cloning a tree clones the ids of all nodes, and trees that were created by
+    /// different processes may contain the same ids.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Visits all nodes below this node in depth first order.
+    ///
+    /// The visitor is called with each node and its depth, this node's children have a depth
+    /// of one. The children of a parent node are only visited if the visitor returns `true`
+    /// for it, which allows to prune whole subtrees.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, RTreeNode};
+    ///
+    /// let tree = RTree::bulk_load((0..100).map(|i| [i as f64, 0.0]).collect());
+    /// let mut leaves_per_depth = vec![0; tree.height()];
+    /// tree.root().visit(|node, depth| {
+    ///     match node {
+    ///         RTreeNode::Leaf(_) => leaves_per_depth[depth - 1] += 1,
+    ///         RTreeNode::Parent(parent) => assert!(!parent.children().is_empty()),
+    ///     }
+    ///     true
+    /// });
+    /// // All elements are stored at the lowest level
+    /// assert_eq!(leaves_per_depth.last(), Some(&100));
+    /// ```
+    pub fn visit<F>(&self, visitor: F)
     where
-        Params: RTreeParams,
+        F: FnMut(&RTreeNode<T>, usize) -> bool,
     {
+        self.visit_with_order(TraversalOrder::DepthFirst, visitor);
+    }
+
+    /// Visits all nodes below this node in the given order.
+    ///
+    /// Behaves like [visit](#method.visit), pruned subtrees are skipped in either order.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, TraversalOrder};
+    ///
+    /// let tree = RTree::bulk_load((0..100).map(|i| [i as f64, 0.0]).collect());
+    /// let mut depths = Vec::new();
+    /// tree.root().visit_with_order(TraversalOrder::BreadthFirst, |_, depth| {
+    ///     depths.push(depth);
+    ///     true
+    /// });
+    /// // Nodes are visited level by level
+    /// assert!(depths.windows(2).all(|w| w[0] <= w[1]));
+    /// ```
+    pub fn visit_with_order<F>(&self, order: TraversalOrder, mut visitor: F)
+    where
+        F: FnMut(&RTreeNode<T>, usize) -> bool,
+    {
+        let mut iter = NodeIterator::new(self, order);
+        while let Some((node, depth)) = iter.pop() {
+            if visitor(node, depth) {
+                iter.push_children(node, depth);
+            }
+        }
+    }
+
+    /// Returns an iterator over all nodes below this node and their depth.
+    ///
+    /// This node's children have a depth of one. Use
+    /// [visit_with_order](#method.visit_with_order) to skip subtrees.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, TraversalOrder};
+    ///
+    /// let tree = RTree::bulk_load((0..100).map(|i| [i as f64, 0.0]).collect());
+    /// let mut nodes_per_depth = vec![0; tree.height()];
+    /// for (_, depth) in tree.root().nodes(TraversalOrder::BreadthFirst) {
+    ///     nodes_per_depth[depth - 1] += 1;
+    /// }
+    /// assert_eq!(nodes_per_depth.last(), Some(&100));
+    /// ```
+    pub fn nodes(&self, order: TraversalOrder) -> NodeIterator<'_, T> {
+        NodeIterator::new(self, order)
+    }
+
+    pub(crate) fn new_empty() -> Self {
         ParentNode {
             envelope: Envelope::new_empty(),
-            children: Vec::with_capacity(Params::MAX_SIZE + 1),
+            children: Vec::new(),
+            id: next_node_id(),
         }
     }
 
     pub(crate) fn new_parent(children: Vec<RTreeNode<T>>) -> Self {
         let envelope = envelope_for_children(&children);
 
-        ParentNode { envelope, children }
+        ParentNode {
+            envelope,
+            children,
+            id: next_node_id(),
+        }
+    }
+
+    pub(crate) fn new_with_envelope(children: Vec<RTreeNode<T>>, envelope: T::Envelope) -> Self {
+        ParentNode {
+            envelope,
+            children,
+            id: next_node_id(),
+        }
+    }
+
+    /// Creates a node that keeps the id it had when it was stored.
+    ///
+    /// Returns `None` if the id is out of the range of ids this target can hand out.
+    pub(crate) fn restored(
+        children: Vec<RTreeNode<T>>,
+        envelope: T::Envelope,
+        id: u64,
+    ) -> Option<Self> {
+        Some(ParentNode {
+            envelope,
+            children,
+            id: restore_node_id(id)?,
+        })
+    }
+
+    pub(crate) fn shrink_to_fit(&mut self) {
+        self.children.shrink_to_fit();
+        for child in &mut self.children {
+            if let RTreeNode::Parent(ref mut data) = child {
+                data.shrink_to_fit();
+            }
+        }
     }
 
     #[cfg(test)]
-    pub fn sanity_check<Params>(&self) -> Option<usize>
+    pub(crate) fn sanity_check<Params>(&self) -> Option<usize>
     where
         Params: RTreeParams,
     {
@@ -145,6 +359,55 @@ where
     }
 }
 
+impl<T> ParentNode<CachedEnvelope<T>>
+where
+    T: RTreeObject,
+{
+    /// Unwraps all elements of a tree whose envelopes were cached during a bulk operation.
+    ///
+    /// The nodes keep their envelopes and ids, no envelope is calculated again.
+    pub(crate) fn into_uncached(self) -> ParentNode<T> {
+        let mut children = Vec::with_capacity(self.children.capacity());
+        children.extend(self.children.into_iter().map(|child| match child {
+            RTreeNode::Leaf(t) => RTreeNode::Leaf(t.into_inner()),
+            RTreeNode::Parent(data) => RTreeNode::Parent(data.into_uncached()),
+        }));
+        ParentNode {
+            envelope: self.envelope,
+            children,
+            id: self.id,
+        }
+    }
+}
+
+/// Returns a children vector for a new node, preferring one kept for reuse.
+///
+/// New vectors have room for `MAX_SIZE + 1` children, enough to grow until the next split.
+pub(crate) fn take_spare_children<T, Params>(
+    spare_children: &mut Vec<Vec<RTreeNode<T>>>,
+) -> Vec<RTreeNode<T>>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    spare_children
+        .pop()
+        .unwrap_or_else(|| Vec::with_capacity(Params::MAX_SIZE + 1))
+}
+
+/// Keeps an emptied children vector for reuse, as long as the reserved capacity suffices.
+pub(crate) fn keep_spare_children<T>(
+    spare_children: &mut Vec<Vec<RTreeNode<T>>>,
+    children: Vec<RTreeNode<T>>,
+) where
+    T: RTreeObject,
+{
+    debug_assert!(children.is_empty());
+    if spare_children.len() < spare_children.capacity() {
+        spare_children.push(children);
+    }
+}
+
 pub fn envelope_for_children<T>(children: &[RTreeNode<T>]) -> T::Envelope
 where
     T: RTreeObject,
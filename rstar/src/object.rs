@@ -230,3 +230,73 @@ where
         }
     }
 }
+
+/// Points paired with arbitrary data can be inserted directly, e.g. for quick prototypes.
+///
+/// Queries only consider the point, the data is ignored.
+/// [PointWithData](primitives/struct.PointWithData.html) offers the same with named fields.
+///
+/// # Example
+/// ```
+/// use rstar::RTree;
+///
+/// let tree = RTree::bulk_load(vec![([0.0, 0.0], "origin"), ([1.0, 1.0], "corner")]);
+/// assert_eq!(tree.nearest_neighbor(&[0.9, 0.8]).unwrap().1, "corner");
+/// ```
+impl<P, T> RTreeObject for (P, T)
+where
+    P: Point,
+{
+    type Envelope = AABB<P>;
+
+    fn envelope(&self) -> AABB<P> {
+        AABB::from_point(self.0)
+    }
+}
+
+impl<P, T> PointDistance for (P, T)
+where
+    P: Point,
+{
+    fn distance_2(&self, point: &P) -> P::Scalar {
+        <P as PointExt>::distance_2(&self.0, point)
+    }
+
+    fn contains_point(&self, point: &<Self::Envelope as Envelope>::Point) -> bool {
+        self.0 == *point
+    }
+
+    fn distance_2_if_less_or_equal(
+        &self,
+        point: &<Self::Envelope as Envelope>::Point,
+        max_distance_2: <<Self::Envelope as Envelope>::Point as Point>::Scalar,
+    ) -> Option<P::Scalar> {
+        <P as PointDistance>::distance_2_if_less_or_equal(&self.0, point, max_distance_2)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_utilities::{create_random_points, SEED_1};
+    use crate::RTree;
+
+    #[test]
+    fn test_tuple_objects() {
+        let points = create_random_points(200, SEED_1);
+        let mut tree = RTree::bulk_load(points.iter().cloned().zip(0..).collect());
+        for (index, point) in points.iter().enumerate() {
+            assert_eq!(tree.nearest_neighbor(point), Some(&(*point, index)));
+            assert_eq!(tree.locate_at_point(point), Some(&(*point, index)));
+        }
+        assert_eq!(
+            tree.locate_within_distance([0.5, 0.5], 0.01).count(),
+            points
+                .iter()
+                .filter(|p| (p[0] - 0.5).powi(2) + (p[1] - 0.5).powi(2) <= 0.01)
+                .count()
+        );
+        assert_eq!(tree.remove(&(points[0], 0)), Some((points[0], 0)));
+        assert_eq!(tree.remove(&(points[1], 0)), None);
+        assert_eq!(tree.size(), 199);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::algorithm::rstar::RStarInsertionStrategy;
-use crate::{Envelope, Point, RTree, RTreeObject};
+use crate::{Envelope, Point, RTree, RTreeNode, RTreeObject};
+use std::marker::PhantomData;
 
 /// Defines static parameters for an r-tree.
 ///
@@ -34,6 +35,24 @@ use crate::{Envelope, Point, RTree, RTreeObject};
 /// let tree: LargeNodeRTree<_> = RTree::bulk_load_with_params(some_elements);
 /// # }
 /// ```
+///
+/// Invalid parameters are rejected at compile time once a tree uses them:
+///
+/// ```compile_fail
+/// use rstar::{RTreeParams, RTree, RStarInsertionStrategy};
+///
+/// struct InvalidParameters;
+///
+/// impl RTreeParams for InvalidParameters {
+///     // MIN_SIZE must be at most half as large as MAX_SIZE
+///     const MIN_SIZE: usize = 6;
+///     const MAX_SIZE: usize = 8;
+///     const REINSERTION_COUNT: usize = 1;
+///     type DefaultInsertionStrategy = RStarInsertionStrategy;
+/// }
+///
+/// let tree: RTree<[f32; 2], InvalidParameters> = RTree::new_with_params();
+/// ```
 pub trait RTreeParams: Send + Sync {
     /// The minimum size of an internal node. Must be at most half as large as `MAX_SIZE`.
     /// Choosing a value around one half or one third of `MAX_SIZE` is recommended. Higher
@@ -52,6 +71,64 @@ pub trait RTreeParams: Send + Sync {
 
     /// The insertion strategy which is used when calling [insert](struct.RTree.html#method.insert).
     type DefaultInsertionStrategy: InsertionStrategy;
+
+    /// The maximum number of axes that are evaluated when an overflowing node is split.
+    ///
+    /// By default, all axes are evaluated. Choosing the split axis takes time quadratic in
+    /// the number of dimensions, which dominates insertion for high dimensional points like
+    /// embeddings. If the points have more dimensions than this value, only the axes along
+    /// which the children's centers are spread the furthest are evaluated.
+    ///
+    /// For such data, consider a [BoundingSphere](struct.BoundingSphere.html) envelope as
+    /// well, it prunes better than an AABB in high dimensions.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTreeParams, RTree, RStarInsertionStrategy};
+    ///
+    /// struct EmbeddingParams;
+    ///
+    /// impl RTreeParams for EmbeddingParams {
+    ///     const MIN_SIZE: usize = 5;
+    ///     const MAX_SIZE: usize = 16;
+    ///     const REINSERTION_COUNT: usize = 3;
+    ///     const MAX_SPLIT_AXES: usize = 8;
+    ///     type DefaultInsertionStrategy = RStarInsertionStrategy;
+    /// }
+    ///
+    /// let mut tree: RTree<[f32; 64], EmbeddingParams> = RTree::new_with_params();
+    /// tree.insert([0.5; 64]);
+    /// ```
+    const MAX_SPLIT_AXES: usize = usize::MAX;
+
+    /// Determines when the tree caches the envelopes of its elements.
+    ///
+    /// By default, an element's envelope is calculated whenever it is required. See
+    /// [EnvelopeCaching](enum.EnvelopeCaching.html) for elements whose envelope is expensive
+    /// to calculate.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{EnvelopeCaching, RTreeParams, RTree, RStarInsertionStrategy};
+    /// use rstar::primitives::Line;
+    ///
+    /// struct CachingParams;
+    ///
+    /// impl RTreeParams for CachingParams {
+    ///     const MIN_SIZE: usize = 3;
+    ///     const MAX_SIZE: usize = 6;
+    ///     const REINSERTION_COUNT: usize = 2;
+    ///     const ENVELOPE_CACHING: EnvelopeCaching = EnvelopeCaching::BulkOperations;
+    ///     type DefaultInsertionStrategy = RStarInsertionStrategy;
+    /// }
+    ///
+    /// let lines: Vec<_> = (0..100)
+    ///     .map(|i| Line::new([i as f64, 0.0], [i as f64, 1.0]))
+    ///     .collect();
+    /// let tree: RTree<_, CachingParams> = RTree::bulk_load_with_params(lines);
+    /// assert_eq!(tree.size(), 100);
+    /// ```
+    const ENVELOPE_CACHING: EnvelopeCaching = EnvelopeCaching::Never;
 }
 
 /// The default parameters used when creating an r-tree without specific parameters.
@@ -65,16 +142,147 @@ impl RTreeParams for DefaultParams {
     type DefaultInsertionStrategy = RStarInsertionStrategy;
 }
 
+/// Determines how many and in which order children of an overflowing node are reinserted.
+///
+/// Before splitting an overflowing node, the
+/// [r*-insertion strategy](struct.RStarInsertionStrategy.html) removes the children farthest
+/// from the node's center and inserts them again. By default,
+/// [REINSERTION_COUNT](trait.RTreeParams.html#associatedconstant.REINSERTION_COUNT) children
+/// are removed and reinserted starting with the child closest to the center.
+///
+/// A policy is set at runtime with
+/// [RTree::set_reinsertion_policy](struct.RTree.html#method.set_reinsertion_policy), which
+/// allows tuning without recompiling with new parameters. The r*-paper suggests reinserting
+/// 30% of `MAX_SIZE` children in close order.
+///
+/// # Example
+/// ```
+/// use rstar::{RTree, ReinsertionOrder, ReinsertionPolicy};
+///
+/// let mut tree = RTree::new();
+/// tree.set_reinsertion_policy(
+///     ReinsertionPolicy::default()
+///         .with_fraction(0.3)
+///         .with_order(ReinsertionOrder::Far),
+/// );
+/// for i in 0..100 {
+///     tree.insert([i as f64, (i % 7) as f64]);
+/// }
+/// assert_eq!(tree.size(), 100);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ReinsertionPolicy {
+    fraction: Option<f64>,
+    order: ReinsertionOrder,
+}
+
+impl ReinsertionPolicy {
+    /// Reinserts a fraction of `MAX_SIZE` children instead of `REINSERTION_COUNT`.
+    ///
+    /// The number of children is rounded and limited to `MAX_SIZE - MIN_SIZE - 1`. A
+    /// fraction of zero disables reinsertion.
+    ///
+    /// # Panics
+    /// Panics if the fraction is not between zero and one.
+    pub fn with_fraction(self, fraction: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&fraction),
+            "Fraction must be between 0 and 1"
+        );
+        ReinsertionPolicy {
+            fraction: Some(fraction),
+            ..self
+        }
+    }
+
+    /// Sets the order in which the removed children are reinserted.
+    pub fn with_order(self, order: ReinsertionOrder) -> Self {
+        ReinsertionPolicy { order, ..self }
+    }
+
+    /// Returns the order in which the removed children are reinserted.
+    pub fn order(&self) -> ReinsertionOrder {
+        self.order
+    }
+
+    /// Returns the number of children that are reinserted for the given parameters.
+    pub fn count<Params: RTreeParams>(&self) -> usize {
+        match self.fraction {
+            None => Params::REINSERTION_COUNT,
+            Some(fraction) => {
+                let count = (fraction * Params::MAX_SIZE as f64).round() as usize;
+                count.min((Params::MAX_SIZE - Params::MIN_SIZE).saturating_sub(1))
+            }
+        }
+    }
+}
+
+/// The order in which the children removed by a [ReinsertionPolicy](struct.ReinsertionPolicy.html)
+/// are reinserted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReinsertionOrder {
+    /// Starts with the child closest to the node's center ("close reinsert").
+    #[default]
+    Close,
+    /// Starts with the child farthest from the node's center ("far reinsert").
+    Far,
+}
+
+/// Determines when a tree caches the envelopes of its elements.
+///
+/// The tree calls [envelope](trait.RTreeObject.html#tymethod.envelope) whenever it requires
+/// an element's envelope. Bulk loading and node splits require the envelopes of the same
+/// elements many times, e.g. to sort them along each axis. For elements whose envelope is
+/// expensive to calculate, like polygons with many vertices, these operations can calculate
+/// each envelope once and keep it until they finish. This requires memory for one envelope
+/// per element while the operation runs.
+///
+/// The policy is set with
+/// [RTreeParams::ENVELOPE_CACHING](trait.RTreeParams.html#associatedconstant.ENVELOPE_CACHING).
+/// No policy keeps envelopes between operations, queries still calculate them on demand.
+/// Wrap elements in a [CachedEnvelope](primitives/struct.CachedEnvelope.html) to store their
+/// envelope permanently. Its envelope is cheap to copy, so trees of `CachedEnvelope`s
+/// should not cache on top of it and keep the default policy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EnvelopeCaching {
+    /// Calculates an element's envelope whenever it is required.
+    #[default]
+    Never,
+    /// Calculates every element's envelope once per bulk operation.
+    ///
+    /// This affects [bulk_load](struct.RTree.html#method.bulk_load) and its variants except
+    /// the presorted one, which requires each envelope only once anyway, as well as
+    /// [rebalance](struct.RTree.html#method.rebalance) and batches passed to `extend` that
+    /// rebuild the tree.
+    BulkOperations,
+    /// Additionally calculates the envelopes of a node's elements once when the node
+    /// overflows during insertion and is split or has some of its elements reinserted.
+    Always,
+}
+
+impl EnvelopeCaching {
+    pub(crate) fn caches_bulk_operations(self) -> bool {
+        self != EnvelopeCaching::Never
+    }
+
+    pub(crate) fn caches_overflows(self) -> bool {
+        self == EnvelopeCaching::Always
+    }
+}
+
 /// Defines how points are inserted into an r-tree.
 ///
 /// Different strategies try to minimize both _insertion time_ (how long does it take to add a new
 /// object into the tree?) and _querying time_ (how long does an average nearest neighbor query
 /// take?).
-/// Currently, only one insertion strategy is implemented: R* (R-star) insertion. R* insertion
-/// tries to minimize querying performance while yielding reasonable insertion times, making it a
-/// good default strategy. More strategies might be implemented in the future.
+/// R* (R-star) insertion tries to minimize querying performance while yielding reasonable
+/// insertion times, making it a good default strategy.
+/// [LinearInsertionStrategy](struct.LinearInsertionStrategy.html) inserts much faster at the
+/// cost of slower queries, [QuadraticInsertionStrategy](struct.QuadraticInsertionStrategy.html)
+/// implements the classic r-tree for comparison.
 ///
-/// Only calls to [insert](struct.RTree.html#method.insert) are affected by this strategy.
+/// Only insertions of single elements, e.g. with [insert](struct.RTree.html#method.insert), are
+/// affected by this strategy.
 ///
 /// This trait is not meant to be implemented by the user.
 pub trait InsertionStrategy {
@@ -83,31 +291,48 @@ pub trait InsertionStrategy {
     where
         Params: RTreeParams,
         T: RTreeObject;
+
+    /// Inserts `t` unless `is_rejected` returns `true` on any level of the insertion's
+    /// descent. It is called with the children of each visited node, except the subtree the
+    /// insertion continues in. A rejected element is returned and the tree is left unchanged.
+    #[doc(hidden)]
+    fn insert_unless<T, Params, F>(
+        tree: &mut RTree<T, Params>,
+        t: T,
+        is_rejected: F,
+    ) -> Result<(), T>
+    where
+        Params: RTreeParams,
+        T: RTreeObject,
+        F: Fn(&[RTreeNode<T>], &T) -> bool;
+}
+
+struct ParameterCheck<T, P>(PhantomData<(T, P)>);
+
+impl<T, P> ParameterCheck<T, P>
+where
+    T: RTreeObject,
+    P: RTreeParams,
+{
+    // Evaluated at compile time whenever a tree is created with these parameters
+    const VALID: () = {
+        assert!(P::MAX_SIZE >= 4, "MAX_SIZE too small. Must be at least 4.");
+        assert!(
+            P::MIN_SIZE <= (P::MAX_SIZE + 1) / 2,
+            "MIN_SIZE too large. Must be at most half as large as MAX_SIZE, rounded up."
+        );
+        assert!(
+            P::REINSERTION_COUNT < P::MAX_SIZE - P::MIN_SIZE,
+            "REINSERTION_COUNT too large. Must be smaller than MAX_SIZE - MIN_SIZE."
+        );
+        assert!(
+            <T::Envelope as Envelope>::Point::DIMENSIONS > 1,
+            "Point dimension too small - must be at least 2"
+        );
+    };
 }
 
 pub fn verify_parameters<T: RTreeObject, P: RTreeParams>() {
-    assert!(
-        P::MAX_SIZE >= 4,
-        "MAX_SIZE too small. Must be larger than 4."
-    );
-
-    let max_min_size = (P::MAX_SIZE + 1) / 2;
-    assert!(
-        P::MIN_SIZE <= max_min_size,
-        "MIN_SIZE too large. Must be less or equal to {:?}",
-        max_min_size
-    );
-
-    let max_reinsertion_count = P::MAX_SIZE - P::MIN_SIZE;
-    assert!(
-        P::REINSERTION_COUNT < max_reinsertion_count,
-        "REINSERTION_COUNT too large. Must be smaller than {:?}",
-        max_reinsertion_count
-    );
-
-    let dimension = <T::Envelope as Envelope>::Point::DIMENSIONS;
-    assert!(
-        dimension > 1,
-        "Point dimension too small - must be at least 2"
-    );
+    #[allow(clippy::let_unit_value)]
+    let () = ParameterCheck::<T, P>::VALID;
 }
@@ -0,0 +1,404 @@
+use crate::binary::BinaryCodable;
+use crate::object::RTreeObject;
+use crate::params::{DefaultParams, RTreeParams};
+use crate::rtree::RTree;
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+const INSERT_TAG: u8 = 0;
+const REMOVE_TAG: u8 = 1;
+
+/// Every record starts with the length and the CRC-32 checksum of its content.
+const RECORD_HEADER_SIZE: usize = 8;
+
+/// An r-tree that records all modifications in an append-only log.
+///
+/// Every [insert](#method.insert) and [remove](#method.remove) is written to the log before
+/// it is applied to the tree. Together with a snapshot in the
+/// [binary format](struct.RTree.html#method.write_to), the log allows to restore the tree
+/// without rewriting the whole snapshot after each modification:
+///
+///  - To recover, load the last snapshot and [replay](#method.replay) the log written since.
+///  - To [checkpoint](#method.checkpoint), write a new snapshot and continue with an empty
+///    log.
+///
+/// Each record is prefixed with its length and a checksum and flushed after it was written.
+/// If writing a record fails, the log is poisoned and all further modifications fail until
+/// the next successful [checkpoint](#method.checkpoint), since the log may end with a partial
+/// record. Flushing does not guarantee durability for
+/// all writers, e.g. files must be synced explicitly with
+/// [File::sync_data](https://doc.rust-lang.org/std/fs/struct.File.html#method.sync_data),
+/// see [log_mut](#method.log_mut).
+///
+/// # Example
+/// ```
+/// use rstar::{PersistentRTree, RTree};
+///
+/// let mut snapshot = Vec::new();
+/// let mut tree = PersistentRTree::new(RTree::new(), Vec::new());
+/// tree.insert([0.0, 1.0]).unwrap();
+/// tree.checkpoint(&mut snapshot, Vec::new()).unwrap();
+/// tree.insert([2.0, 3.0]).unwrap();
+/// tree.remove(&[0.0, 1.0]).unwrap();
+///
+/// // Restore the tree, e.g. after a restart
+/// let log = tree.into_log();
+/// let mut restored = PersistentRTree::new(RTree::<[f64; 2]>::from_bytes(&snapshot).unwrap(), Vec::new());
+/// assert_eq!(restored.replay(&mut log.as_slice()).unwrap(), 2);
+/// assert_eq!(restored.tree().size(), 1);
+/// assert!(restored.tree().contains(&[2.0, 3.0]));
+/// ```
+pub struct PersistentRTree<T, W, Params = DefaultParams>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    tree: RTree<T, Params>,
+    log: W,
+    poisoned: bool,
+}
+
+impl<T, W, Params> PersistentRTree<T, W, Params>
+where
+    T: RTreeObject + BinaryCodable + PartialEq,
+    T::Envelope: BinaryCodable,
+    W: Write,
+    Params: RTreeParams,
+{
+    /// Wraps a tree, all further modifications are appended to `log`.
+    pub fn new(tree: RTree<T, Params>, log: W) -> Self {
+        PersistentRTree {
+            tree,
+            log,
+            poisoned: false,
+        }
+    }
+
+    /// Returns the tree for queries.
+    pub fn tree(&self) -> &RTree<T, Params> {
+        &self.tree
+    }
+
+    /// Returns the log.
+    pub fn log(&self) -> &W {
+        &self.log
+    }
+
+    /// Returns the log, e.g. to sync it to disk.
+    pub fn log_mut(&mut self) -> &mut W {
+        &mut self.log
+    }
+
+    /// Returns the tree and drops the log.
+    pub fn into_tree(self) -> RTree<T, Params> {
+        self.tree
+    }
+
+    /// Returns the log and drops the tree.
+    pub fn into_log(self) -> W {
+        self.log
+    }
+
+    /// Returns `true` if writing to the log failed, see [PersistentRTree](struct.PersistentRTree.html).
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    fn append(&mut self, tag: u8, t: &T) -> io::Result<()> {
+        if self.poisoned {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Log is poisoned by a failed write",
+            ));
+        }
+        // Encode the record first to write it in a single call
+        let record = encode_record(tag, t)?;
+        let result = self.log.write_all(&record).and_then(|()| self.log.flush());
+        self.poisoned = result.is_err();
+        result
+    }
+
+    /// Logs and inserts an element.
+    ///
+    /// The tree is left unchanged if writing the log fails.
+    pub fn insert(&mut self, t: T) -> io::Result<()> {
+        self.append(INSERT_TAG, &t)?;
+        self.tree.insert(t);
+        Ok(())
+    }
+
+    /// Logs and removes an element, see [RTree::remove](struct.RTree.html#method.remove).
+    ///
+    /// The tree is left unchanged if writing the log fails.
+    pub fn remove(&mut self, t: &T) -> io::Result<Option<T>> {
+        self.append(REMOVE_TAG, t)?;
+        Ok(self.tree.remove(t))
+    }
+
+    /// Applies all records of a log to the tree without logging them again.
+    ///
+    /// Returns the number of applied records. Replaying stops at the first incomplete record
+    /// or record with a wrong checksum, as they are left behind by a crash during writing.
+    pub fn replay<R: Read>(&mut self, log: &mut R) -> io::Result<usize> {
+        let mut count = 0;
+        loop {
+            let mut header = [0; RECORD_HEADER_SIZE];
+            if read_up_to(log, &mut header)? < RECORD_HEADER_SIZE {
+                return Ok(count);
+            }
+            let mut bytes = [0; 4];
+            bytes.copy_from_slice(&header[..4]);
+            let len = u32::from_le_bytes(bytes) as u64;
+            bytes.copy_from_slice(&header[4..]);
+            let checksum = u32::from_le_bytes(bytes);
+            let mut content = Vec::new();
+            // Interrupted reads are retried by read_to_end
+            log.take(len).read_to_end(&mut content)?;
+            if content.len() as u64 != len || crc32(&content) != checksum {
+                return Ok(count);
+            }
+            let (tag, mut element) = content
+                .split_first()
+                .ok_or_else(|| invalid_data("Empty log record"))?;
+            let t = T::read_from(&mut element)?;
+            if !element.is_empty() {
+                return Err(invalid_data("Log record is longer than its element"));
+            }
+            match *tag {
+                INSERT_TAG => self.tree.insert(t),
+                REMOVE_TAG => {
+                    self.tree.remove(&t);
+                }
+                _ => return Err(invalid_data("Invalid log record tag")),
+            }
+            count += 1;
+        }
+    }
+
+    /// Writes a snapshot of the tree and continues with a new, empty log.
+    ///
+    /// Returns the previous log, which is no longer needed once the snapshot is persisted.
+    /// The log is not replaced if writing the snapshot fails.
+    pub fn checkpoint<S: Write>(&mut self, snapshot: &mut S, new_log: W) -> io::Result<W> {
+        self.tree.write_to(snapshot)?;
+        snapshot.flush()?;
+        self.poisoned = false;
+        Ok(::std::mem::replace(&mut self.log, new_log))
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Encodes a record, including its length and checksum.
+fn encode_record<T: BinaryCodable>(tag: u8, t: &T) -> io::Result<Vec<u8>> {
+    let mut record = vec![0; RECORD_HEADER_SIZE];
+    record.push(tag);
+    t.write_to(&mut record)?;
+    let content = &record[RECORD_HEADER_SIZE..];
+    let len = u32::try_from(content.len()).map_err(|_| invalid_data("Log record too large"))?;
+    let checksum = crc32(content);
+    record[..4].copy_from_slice(&len.to_le_bytes());
+    record[4..RECORD_HEADER_SIZE].copy_from_slice(&checksum.to_le_bytes());
+    Ok(record)
+}
+
+/// Fills `buf` as far as possible and returns the number of bytes read.
+///
+/// Unlike `read_exact`, this distinguishes the end of the input from a partial read.
+fn read_up_to<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(ref error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(filled)
+}
+
+/// Computes the CRC-32 (IEEE) checksum of some bytes.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod test {
+    use super::{encode_record, PersistentRTree};
+    use crate::test_utilities::{create_random_points, SEED_1};
+    use crate::RTree;
+    use std::io::{self, Read, Write};
+
+    #[test]
+    fn test_replay_and_checkpoint() {
+        let points = create_random_points(300, SEED_1);
+        let mut tree = PersistentRTree::new(RTree::new(), Vec::new());
+        for point in &points[..200] {
+            tree.insert(*point).unwrap();
+        }
+        let mut snapshot = Vec::new();
+        let old_log = tree.checkpoint(&mut snapshot, Vec::new()).unwrap();
+        assert!(!old_log.is_empty());
+        assert!(tree.log().is_empty());
+        for point in &points[200..] {
+            tree.insert(*point).unwrap();
+        }
+        for point in &points[..50] {
+            assert_eq!(tree.remove(point).unwrap(), Some(*point));
+        }
+        assert_eq!(tree.remove(&[10.0, 10.0]).unwrap(), None);
+
+        let log = tree.log().clone();
+        let mut restored = PersistentRTree::new(
+            RTree::<[f64; 2]>::from_bytes(&snapshot).unwrap(),
+            Vec::new(),
+        );
+        assert_eq!(restored.replay(&mut log.as_slice()).unwrap(), 151);
+        assert!(restored.log().is_empty());
+        assert_eq!(restored.tree().size(), 250);
+        for point in &points[50..] {
+            assert!(restored.tree().contains(point));
+        }
+
+        // A torn record at the end of the log is ignored
+        let mut restored = PersistentRTree::new(
+            RTree::<[f64; 2]>::from_bytes(&snapshot).unwrap(),
+            Vec::new(),
+        );
+        assert_eq!(restored.replay(&mut &log[..log.len() - 3]).unwrap(), 150);
+        assert!(!restored.tree().contains(&points[49]));
+        assert_eq!(restored.tree().size(), 250);
+
+        // Replaying stops at a corrupt record
+        let mut restored = PersistentRTree::new(
+            RTree::<[f64; 2]>::from_bytes(&snapshot).unwrap(),
+            Vec::new(),
+        );
+        let record_size = log.len() / 151;
+        let mut corrupt = log.clone();
+        corrupt[10 * record_size + 12] ^= 1;
+        assert_eq!(restored.replay(&mut corrupt.as_slice()).unwrap(), 10);
+        assert_eq!(restored.tree().size(), 210);
+
+        let invalid_tag = encode_record(7, &[0.0, 0.0]).unwrap();
+        assert_eq!(
+            restored
+                .replay(&mut invalid_tag.as_slice())
+                .err()
+                .unwrap()
+                .kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_interrupted_replay() {
+        struct InterruptingReader<'a> {
+            bytes: &'a [u8],
+            interrupt: bool,
+        }
+        impl<'a> Read for InterruptingReader<'a> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.interrupt = !self.interrupt;
+                if self.interrupt {
+                    return Err(io::ErrorKind::Interrupted.into());
+                }
+                // Return single bytes to split records across reads
+                let len = buf.len().min(1);
+                self.bytes.read(&mut buf[..len])
+            }
+        }
+
+        let mut tree = PersistentRTree::new(RTree::new(), Vec::new());
+        for point in create_random_points(10, SEED_1) {
+            tree.insert(point).unwrap();
+        }
+        let log = tree.into_log();
+        let mut restored = PersistentRTree::new(RTree::<[f64; 2]>::new(), Vec::new());
+        let mut reader = InterruptingReader {
+            bytes: &log,
+            interrupt: false,
+        };
+        assert_eq!(restored.replay(&mut reader).unwrap(), 10);
+    }
+
+    #[test]
+    fn test_failing_log() {
+        struct FailingWriter;
+        impl Write for FailingWriter {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::Other.into())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut tree = PersistentRTree::new(RTree::bulk_load(vec![[0, 0]]), FailingWriter);
+        assert!(tree.insert([1, 1]).is_err());
+        assert!(tree.is_poisoned());
+        assert!(tree.remove(&[0, 0]).is_err());
+        assert_eq!(tree.tree().size(), 1);
+        assert!(tree.tree().contains(&[0, 0]));
+    }
+
+    #[test]
+    fn test_poisoned_log() {
+        /// Fails a single write after writing part of its input.
+        struct TearingWriter {
+            bytes: Vec<u8>,
+            fail: bool,
+        }
+        impl Write for TearingWriter {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if self.fail {
+                    self.fail = false;
+                    self.bytes.extend_from_slice(&buf[..buf.len() / 2]);
+                    return Err(io::ErrorKind::Other.into());
+                }
+                self.bytes.write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let log = TearingWriter {
+            bytes: Vec::new(),
+            fail: false,
+        };
+        let mut tree = PersistentRTree::new(RTree::new(), log);
+        tree.insert([0, 0]).unwrap();
+        tree.log_mut().fail = true;
+        assert!(tree.insert([1, 1]).is_err());
+        // No record may follow the torn one
+        assert!(tree.insert([2, 2]).is_err());
+        assert!(tree.is_poisoned());
+        assert_eq!(tree.tree().size(), 1);
+
+        let mut snapshot = Vec::new();
+        let new_log = TearingWriter {
+            bytes: Vec::new(),
+            fail: false,
+        };
+        let old_log = tree.checkpoint(&mut snapshot, new_log).unwrap();
+        assert!(!tree.is_poisoned());
+        tree.insert([3, 3]).unwrap();
+
+        let mut restored = PersistentRTree::new(RTree::<[i32; 2]>::new(), Vec::new());
+        assert_eq!(restored.replay(&mut old_log.bytes.as_slice()).unwrap(), 1);
+    }
+}
@@ -1,4 +1,5 @@
 use num_traits::{Bounded, Num, Signed, Zero};
+use std::cmp::Ordering;
 use std::fmt::Debug;
 
 /// Defines a number type that is compatible with rstar.
@@ -12,6 +13,13 @@ use std::fmt::Debug;
 /// This type cannot be implemented directly. Instead, it is just required to implement
 /// all required traits from the `num_traits` crate.
 ///
+/// # Integer coordinates
+/// Areas, perimeters and squared distances easily exceed the range of integer coordinates.
+/// For integer types, these values saturate at the type's bounds instead of overflowing.
+/// The tree stays valid, but operations comparing saturated values, e.g. nearest neighbor
+/// queries between very distant points, can no longer tell them apart. Use a wider integer
+/// type if squared distances of the relevant points exceed the coordinate type's range.
+///
 /// # Example
 /// ```
 /// # extern crate num_traits;
@@ -107,7 +115,7 @@ impl<S> RTreeNum for S where S: Bounded + Num + Clone + Copy + Signed + PartialO
 /// [`PointWithData`](primitives/struct.PointWithData.html) instead.
 /// This trait defines points, not points with metadata.
 ///
-/// `Point` is implemented out of the box for arrays like `[f32; 2]` or `[f64; 7]` (up to dimension 9).
+/// `Point` is implemented out of the box for arrays of any dimension like `[f32; 2]` or `[f64; 7]`.
 ///
 /// # Implementation example
 /// Supporting a custom point type might look like this:
@@ -232,7 +240,13 @@ pub trait PointExt: Point {
     }
 
     fn length_2(&self) -> Self::Scalar {
-        self.fold(Zero::zero(), |acc, cur| cur * cur + acc)
+        self.fold(Zero::zero(), |acc, cur| {
+            saturating_add(saturating_mul(cur, cur), acc)
+        })
+    }
+
+    fn saturating_sub(&self, other: &Self) -> Self {
+        self.component_wise(other, saturating_sub)
     }
 
     fn sub(&self, other: &Self) -> Self {
@@ -256,6 +270,111 @@ pub trait PointExt: Point {
     }
 }
 
+/// Returns `true` if `S` is an integer type.
+///
+/// Areas and squared distances of integer coordinates overflow easily. These values are
+/// calculated with saturating arithmetic for integers. For primitive types, this check is
+/// evaluated at compile time.
+#[inline]
+fn is_integral<S: RTreeNum>() -> bool {
+    let one = S::one();
+    one / (one + one) == S::zero()
+}
+
+/// Returns a name for a scalar type that is stable across compilers, e.g. `"f64"`.
+///
+/// The name consists of `i` for integral or `f` for other types, followed by the type's
+/// size in bits. It matches the names of the primitive number types.
+pub fn scalar_tag<S: RTreeNum>() -> String {
+    let kind = if is_integral::<S>() { 'i' } else { 'f' };
+    format!("{}{}", kind, ::std::mem::size_of::<S>() * 8)
+}
+
+/// Adds two scalars, saturating at the numeric bounds of integer types.
+#[inline]
+pub fn saturating_add<S: RTreeNum>(a: S, b: S) -> S {
+    if is_integral::<S>() {
+        if b > S::zero() && a > S::max_value() - b {
+            return S::max_value();
+        }
+        if b < S::zero() && a < S::min_value() - b {
+            return S::min_value();
+        }
+    }
+    a + b
+}
+
+/// Subtracts two scalars, saturating at the numeric bounds of integer types.
+#[inline]
+pub fn saturating_sub<S: RTreeNum>(a: S, b: S) -> S {
+    if is_integral::<S>() {
+        if b < S::zero() && a > S::max_value() + b {
+            return S::max_value();
+        }
+        if b > S::zero() && a < S::min_value() + b {
+            return S::min_value();
+        }
+    }
+    a - b
+}
+
+/// Multiplies two scalars, saturating at the numeric bounds of integer types.
+#[inline]
+pub fn saturating_mul<S: RTreeNum>(a: S, b: S) -> S {
+    if is_integral::<S>() && !a.is_zero() && !b.is_zero() {
+        let zero = S::zero();
+        let overflows = match (a > zero, b > zero) {
+            (true, true) => a > S::max_value() / b,
+            (false, false) => a < S::max_value() / b,
+            (true, false) => b < S::min_value() / a,
+            (false, true) => a < S::min_value() / b,
+        };
+        if overflows {
+            return if (a > zero) == (b > zero) {
+                S::max_value()
+            } else {
+                S::min_value()
+            };
+        }
+    }
+    a * b
+}
+
+/// Returns the mean of two scalars, without overflowing for integer types.
+#[inline]
+pub fn midpoint<S: RTreeNum>(a: S, b: S) -> S {
+    let two = S::one() + S::one();
+    if is_integral::<S>() && (a < S::zero()) == (b < S::zero()) {
+        // Rounds towards zero, like (a + b) / 2
+        a / two + b / two + (a % two + b % two) / two
+    } else {
+        (a + b) / two
+    }
+}
+
+/// Returns `false` for NaN and infinite values.
+#[inline]
+pub fn is_finite<S: RTreeNum>(value: S) -> bool {
+    // Multiplying NaN or infinity with zero yields NaN
+    value * S::zero() == S::zero()
+}
+
+/// Returns `true` for NaN.
+#[inline]
+pub fn is_nan<S: RTreeNum>(value: S) -> bool {
+    value.partial_cmp(&value).is_none()
+}
+
+/// Compares two scalars, ordering NaN after all other values.
+///
+/// Unlike `partial_cmp(..).unwrap()`, this never panics. Elements with NaN coordinates end
+/// up in arbitrary nodes, but the tree stays usable.
+#[inline]
+pub fn total_cmp<S: RTreeNum>(a: &S, b: &S) -> Ordering {
+    a.partial_cmp(b)
+        .unwrap_or_else(|| is_nan(*a).cmp(&is_nan(*b)))
+}
+
 #[inline]
 pub fn min_inline<S>(a: S, b: S) -> S
 where
@@ -280,43 +399,42 @@ where
     }
 }
 
-macro_rules! count_exprs {
-    () => (0);
-    ($head:expr) => (1);
-    ($head:expr, $($tail:expr),*) => (1 + count_exprs!($($tail),*));
-}
+impl<S, const N: usize> Point for [S; N]
+where
+    S: RTreeNum,
+{
+    type Scalar = S;
 
-macro_rules! implement_point_for_array {
-    ($($index:expr),*) => {
-        impl<S> Point for [S; count_exprs!($($index),*)]
-        where
-            S: RTreeNum,
-        {
-            type Scalar = S;
+    const DIMENSIONS: usize = N;
 
-            const DIMENSIONS: usize = count_exprs!($($index),*);
+    fn generate(generator: impl Fn(usize) -> S) -> Self {
+        ::std::array::from_fn(generator)
+    }
 
-            fn generate(generator: impl Fn(usize) -> S) -> Self
-            {
-                [$(generator($index)),*]
-            }
+    fn nth(&self, index: usize) -> Self::Scalar {
+        self[index]
+    }
 
-            fn nth(&self, index: usize) -> Self::Scalar {
-                self[index]
-            }
+    fn nth_mut(&mut self, index: usize) -> &mut Self::Scalar {
+        &mut self[index]
+    }
+}
 
-            fn nth_mut(&mut self, index: usize) -> &mut Self::Scalar {
-                &mut self[index]
-            }
+#[cfg(test)]
+mod test {
+    use crate::test_utilities::{create_random_points, SEED_1};
+    use crate::{Point, RTree};
+
+    #[test]
+    fn test_high_dimensional_arrays() {
+        let points: Vec<[f64; 12]> = create_random_points(300, SEED_1)
+            .chunks(6)
+            .map(|chunk| <[f64; 12]>::generate(|i| chunk[i / 2][i % 2]))
+            .collect();
+        assert_eq!(<[f64; 12]>::DIMENSIONS, 12);
+        let tree = RTree::bulk_load(points.clone());
+        for point in &points {
+            assert_eq!(tree.nearest_neighbor(point), Some(point));
         }
-    };
+    }
 }
-
-implement_point_for_array!(0, 1);
-implement_point_for_array!(0, 1, 2);
-implement_point_for_array!(0, 1, 2, 3);
-implement_point_for_array!(0, 1, 2, 3, 4);
-implement_point_for_array!(0, 1, 2, 3, 4, 5);
-implement_point_for_array!(0, 1, 2, 3, 4, 5, 6);
-implement_point_for_array!(0, 1, 2, 3, 4, 5, 6, 7);
-implement_point_for_array!(0, 1, 2, 3, 4, 5, 6, 7, 8);
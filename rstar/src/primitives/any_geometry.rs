@@ -0,0 +1,135 @@
+use crate::aabb::AABB;
+use crate::envelope::Envelope;
+use crate::object::{PointDistance, RTreeObject};
+use crate::point::Point;
+use crate::primitives::{Line, Rectangle};
+
+/// A geometry that is any of the primitives of this module.
+///
+/// Storing different kinds of geometries within the same r-tree requires a common
+/// element type. `AnyGeometry` implements [RTreeObject](../trait.RTreeObject.html) and
+/// [PointDistance](../trait.PointDistance.html) by delegating to the contained primitive.
+///
+/// # Type parameters
+/// `P`: The geometry's [Point](../trait.Point.html) type.
+///
+/// # Example
+/// ```
+/// use rstar::RTree;
+/// use rstar::primitives::{AnyGeometry, Line, Rectangle};
+///
+/// let tree = RTree::bulk_load(vec![
+///     AnyGeometry::Point([0.0, 0.0]),
+///     Line::new([1.0, 0.0], [1.0, 1.0]).into(),
+///     Rectangle::from_corners([2.0, 2.0], [3.0, 3.0]).into(),
+/// ]);
+///
+/// let nearest = tree.nearest_neighbor(&[0.9, 0.5]).unwrap();
+/// assert_eq!(nearest, &AnyGeometry::Line(Line::new([1.0, 0.0], [1.0, 1.0])));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AnyGeometry<P>
+where
+    P: Point,
+{
+    /// A single point
+    Point(P),
+    /// A line segment
+    Line(Line<P>),
+    /// A rectangle
+    Rectangle(Rectangle<P>),
+}
+
+impl<P> RTreeObject for AnyGeometry<P>
+where
+    P: Point,
+{
+    type Envelope = AABB<P>;
+
+    fn envelope(&self) -> Self::Envelope {
+        match self {
+            AnyGeometry::Point(ref point) => point.envelope(),
+            AnyGeometry::Line(ref line) => line.envelope(),
+            AnyGeometry::Rectangle(ref rectangle) => rectangle.envelope(),
+        }
+    }
+}
+
+impl<P> PointDistance for AnyGeometry<P>
+where
+    P: Point,
+{
+    fn distance_2(
+        &self,
+        point: &<Self::Envelope as Envelope>::Point,
+    ) -> <<Self::Envelope as Envelope>::Point as Point>::Scalar {
+        match self {
+            AnyGeometry::Point(ref p) => p.distance_2(point),
+            AnyGeometry::Line(ref line) => line.distance_2(point),
+            AnyGeometry::Rectangle(ref rectangle) => rectangle.distance_2(point),
+        }
+    }
+
+    fn contains_point(&self, point: &<Self::Envelope as Envelope>::Point) -> bool {
+        match self {
+            AnyGeometry::Point(ref p) => p.contains_point(point),
+            AnyGeometry::Line(ref line) => line.contains_point(point),
+            AnyGeometry::Rectangle(ref rectangle) => rectangle.contains_point(point),
+        }
+    }
+
+    fn distance_2_if_less_or_equal(
+        &self,
+        point: &<Self::Envelope as Envelope>::Point,
+        max_distance_2: <<Self::Envelope as Envelope>::Point as Point>::Scalar,
+    ) -> Option<<<Self::Envelope as Envelope>::Point as Point>::Scalar> {
+        match self {
+            AnyGeometry::Point(ref p) => p.distance_2_if_less_or_equal(point, max_distance_2),
+            AnyGeometry::Line(ref line) => line.distance_2_if_less_or_equal(point, max_distance_2),
+            AnyGeometry::Rectangle(ref rectangle) => {
+                rectangle.distance_2_if_less_or_equal(point, max_distance_2)
+            }
+        }
+    }
+}
+
+impl<P> From<Line<P>> for AnyGeometry<P>
+where
+    P: Point,
+{
+    fn from(line: Line<P>) -> Self {
+        AnyGeometry::Line(line)
+    }
+}
+
+impl<P> From<Rectangle<P>> for AnyGeometry<P>
+where
+    P: Point,
+{
+    fn from(rectangle: Rectangle<P>) -> Self {
+        AnyGeometry::Rectangle(rectangle)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::AnyGeometry;
+    use crate::object::{PointDistance, RTreeObject};
+    use crate::primitives::{Line, Rectangle};
+
+    #[test]
+    fn any_geometry_delegation() {
+        let line = Line::new([0.0, 0.0], [0.0, 4.0]);
+        let rectangle = Rectangle::from_corners([1.0, 1.0], [3.0, 3.0]);
+        let point = [5.0, 5.0];
+        let geometries: [AnyGeometry<_>; 3] =
+            [line.into(), rectangle.into(), AnyGeometry::Point(point)];
+        let query_point = [4.0, 2.0];
+        assert_eq!(geometries[0].distance_2(&query_point), 16.0);
+        assert_eq!(geometries[1].distance_2(&query_point), 1.0);
+        assert_eq!(geometries[2].distance_2(&query_point), 10.0);
+        assert!(geometries[1].contains_point(&[2.0, 2.0]));
+        assert_eq!(geometries[1].envelope(), rectangle.envelope());
+    }
+}
@@ -0,0 +1,130 @@
+use crate::aabb::AABB;
+use crate::envelope::Envelope;
+use crate::object::{PointDistance, RTreeObject};
+use crate::point::{Point, PointExt};
+use num_traits::{Float, Zero};
+
+/// An n-dimensional ball defined by its center and radius.
+///
+/// In two dimensions, this is a circle. The ball is solid: Distances are measured to the
+/// closest point on its surface, points inside have a distance of zero.
+///
+/// # Type parameters
+/// `P`: The ball's [Point](../trait.Point.html) type. Distance calculations require a
+/// floating point scalar type.
+///
+/// # Example
+/// ```
+/// use rstar::primitives::Ball;
+/// use rstar::{PointDistance, RTree};
+///
+/// let agents = RTree::bulk_load(vec![
+///     Ball::new([0.0, 0.0], 1.0),
+///     Ball::new([4.0, 0.0], 0.5),
+/// ]);
+/// let closest = agents.nearest_neighbor(&[2.5, 0.0]).unwrap();
+/// assert_eq!(closest.center(), [4.0, 0.0]);
+/// assert_eq!(closest.distance_2(&[2.5, 0.0]), 1.0);
+///
+/// // Find all agents colliding with a point
+/// assert_eq!(agents.locate_all_at_point(&[0.5, 0.5]).count(), 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ball<P>
+where
+    P: Point,
+{
+    center: P,
+    radius: P::Scalar,
+}
+
+impl<P> Ball<P>
+where
+    P: Point,
+{
+    /// Creates a new ball.
+    ///
+    /// # Panics
+    /// Panics if the radius is negative.
+    pub fn new(center: P, radius: P::Scalar) -> Self {
+        assert!(radius >= Zero::zero(), "Radius must not be negative");
+        Ball { center, radius }
+    }
+
+    /// Returns the ball's center.
+    pub fn center(&self) -> P {
+        self.center
+    }
+
+    /// Returns the ball's radius.
+    pub fn radius(&self) -> P::Scalar {
+        self.radius
+    }
+}
+
+impl<P> RTreeObject for Ball<P>
+where
+    P: Point,
+{
+    type Envelope = AABB<P>;
+
+    fn envelope(&self) -> Self::Envelope {
+        let radius = self.radius;
+        AABB::from_corners(
+            self.center.map(|x| x - radius),
+            self.center.map(|x| x + radius),
+        )
+    }
+}
+
+impl<P> PointDistance for Ball<P>
+where
+    P: Point,
+    P::Scalar: Float,
+{
+    fn distance_2(
+        &self,
+        point: &<Self::Envelope as Envelope>::Point,
+    ) -> <<Self::Envelope as Envelope>::Point as Point>::Scalar {
+        let distance_to_surface = point.sub(&self.center).length_2().sqrt() - self.radius;
+        if distance_to_surface > Zero::zero() {
+            distance_to_surface * distance_to_surface
+        } else {
+            Zero::zero()
+        }
+    }
+
+    fn contains_point(&self, point: &<Self::Envelope as Envelope>::Point) -> bool {
+        point.sub(&self.center).length_2() <= self.radius * self.radius
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Ball;
+    use crate::object::{PointDistance, RTreeObject};
+    use crate::AABB;
+    use approx::*;
+
+    #[test]
+    fn ball_distance() {
+        let ball = Ball::new([1.0, 2.0], 2.0);
+        assert_eq!(ball.envelope(), AABB::from_corners([-1.0, 0.0], [3.0, 4.0]));
+
+        assert_abs_diff_eq!(ball.distance_2(&[1.0, 2.0]), 0.0);
+        assert_abs_diff_eq!(ball.distance_2(&[2.0, 3.0]), 0.0);
+        assert_abs_diff_eq!(ball.distance_2(&[1.0, 5.0]), 1.0);
+        assert_abs_diff_eq!(ball.distance_2(&[4.0, 6.0]), 9.0);
+        assert!(ball.contains_point(&[3.0, 2.0]));
+        assert!(!ball.contains_point(&[3.0, 3.0]));
+        // The envelope's corners are not contained
+        assert!(ball.distance_2(&[3.0, 4.0]) > 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn ball_negative_radius() {
+        Ball::new([0.0f32, 0.0], -1.0);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::envelope::Envelope;
+use crate::object::{PointDistance, RTreeObject};
+use crate::point::Point;
+
+/// Caches the envelope of another object.
+///
+/// The r-tree calls [envelope](../trait.RTreeObject.html#tymethod.envelope) whenever it
+/// requires an element's envelope, e.g. during insertion, node splits, removal and most
+/// queries. For objects whose envelope is expensive to calculate, like polygons with many
+/// vertices, wrapping them in a `CachedEnvelope` calculates the envelope only once.
+///
+/// If the memory for an envelope per element is only acceptable temporarily, an
+/// [EnvelopeCaching](../enum.EnvelopeCaching.html) policy caches envelopes during bulk
+/// operations and node splits instead.
+///
+/// The wrapped object can only be accessed immutably. Mutable access could change the
+/// object's envelope without updating the cache.
+///
+/// # Example
+/// ```
+/// use rstar::RTree;
+/// use rstar::primitives::{CachedEnvelope, Line};
+///
+/// let tree = RTree::bulk_load(vec![
+///     CachedEnvelope::new(Line::new([0.0, 0.0], [1.0, 1.0])),
+///     CachedEnvelope::new(Line::new([2.0, 0.0], [3.0, 1.0])),
+/// ]);
+/// let nearest = tree.nearest_neighbor(&[2.0, 1.0]).unwrap();
+/// assert_eq!(nearest.inner(), &Line::new([2.0, 0.0], [3.0, 1.0]));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T: serde::Serialize, T::Envelope: serde::Serialize",
+        deserialize = "T: serde::Deserialize<'de>, T::Envelope: serde::Deserialize<'de>"
+    ))
+)]
+pub struct CachedEnvelope<T>
+where
+    T: RTreeObject,
+{
+    inner: T,
+    cached_envelope: T::Envelope,
+}
+
+impl<T> CachedEnvelope<T>
+where
+    T: RTreeObject,
+{
+    /// Wraps an object and calculates its envelope.
+    pub fn new(inner: T) -> Self {
+        let cached_envelope = inner.envelope();
+        CachedEnvelope {
+            inner,
+            cached_envelope,
+        }
+    }
+
+    /// Returns the wrapped object.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Unwraps the wrapped object.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T> RTreeObject for CachedEnvelope<T>
+where
+    T: RTreeObject,
+{
+    type Envelope = T::Envelope;
+
+    fn envelope(&self) -> Self::Envelope {
+        self.cached_envelope
+    }
+}
+
+impl<T> PointDistance for CachedEnvelope<T>
+where
+    T: PointDistance,
+{
+    fn distance_2(
+        &self,
+        point: &<Self::Envelope as Envelope>::Point,
+    ) -> <<Self::Envelope as Envelope>::Point as Point>::Scalar {
+        self.inner.distance_2(point)
+    }
+
+    fn contains_point(&self, point: &<Self::Envelope as Envelope>::Point) -> bool {
+        self.cached_envelope.contains_point(point) && self.inner.contains_point(point)
+    }
+
+    fn distance_2_if_less_or_equal(
+        &self,
+        point: &<Self::Envelope as Envelope>::Point,
+        max_distance_2: <<Self::Envelope as Envelope>::Point as Point>::Scalar,
+    ) -> Option<<<Self::Envelope as Envelope>::Point as Point>::Scalar> {
+        // Uses the cached envelope instead of the inner object's envelope
+        if self.cached_envelope.distance_2(point) <= max_distance_2 {
+            let distance_2 = self.inner.distance_2(point);
+            if distance_2 <= max_distance_2 {
+                return Some(distance_2);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CachedEnvelope;
+    use crate::test_utilities::{create_random_points, SEED_1};
+    use crate::{RTree, RTreeObject, AABB};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static ENVELOPE_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    struct CountingPoint([f64; 2]);
+
+    impl RTreeObject for CountingPoint {
+        type Envelope = AABB<[f64; 2]>;
+
+        fn envelope(&self) -> Self::Envelope {
+            ENVELOPE_CALLS.fetch_add(1, Ordering::Relaxed);
+            AABB::from_point(self.0)
+        }
+    }
+
+    #[test]
+    fn test_envelope_is_calculated_once() {
+        let points = create_random_points(200, SEED_1);
+        let mut tree = RTree::new();
+        for point in &points {
+            tree.insert(CachedEnvelope::new(CountingPoint(*point)));
+        }
+        let query = AABB::from_corners([0.0, 0.0], [0.5, 0.5]);
+        assert!(tree.locate_in_envelope(&query).count() > 0);
+        assert_eq!(ENVELOPE_CALLS.load(Ordering::Relaxed), points.len());
+    }
+}
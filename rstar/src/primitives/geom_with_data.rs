@@ -0,0 +1,151 @@
+use crate::envelope::Envelope;
+use crate::object::{PointDistance, RTreeObject};
+use crate::params::RTreeParams;
+use crate::point::Point;
+use crate::rtree::RTree;
+#[cfg(feature = "rayon")]
+use rayon::iter::ParallelIterator;
+
+/// A geometry with some associated data that can be inserted into an r-tree.
+///
+/// This is the counterpart of [PointWithData](struct.PointWithData.html) for arbitrary
+/// geometries, e.g. [Line](struct.Line.html)s or [Rectangle](struct.Rectangle.html)s. All
+/// queries are delegated to the geometry, the data is ignored.
+///
+/// The geometry can only be accessed immutably, changing it would invalidate the tree.
+///
+/// # Example
+/// ```
+/// use rstar::RTree;
+/// use rstar::primitives::{GeomWithData, Line};
+///
+/// type Road = GeomWithData<Line<[f64; 2]>, &'static str>;
+///
+/// let roads = RTree::bulk_load(vec![
+///     Road::new(Line::new([0.0, 0.0], [1.0, 0.0]), "Main Street"),
+///     Road::new(Line::new([0.0, 1.0], [1.0, 1.5]), "Elm Street"),
+/// ]);
+/// let closest = roads.nearest_neighbor(&[0.5, 0.2]).unwrap();
+/// assert_eq!(closest.data, "Main Street");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GeomWithData<G, T> {
+    geom: G, // Private to prevent modification.
+    /// Any data associated with the geometry.
+    pub data: T,
+}
+
+impl<G, T> GeomWithData<G, T> {
+    /// Creates a new `GeomWithData` with the provided data.
+    pub fn new(geom: G, data: T) -> Self {
+        GeomWithData { geom, data }
+    }
+
+    /// Returns the geometry.
+    pub fn geom(&self) -> &G {
+        &self.geom
+    }
+
+    /// Unwraps the geometry and its data.
+    pub fn into_parts(self) -> (G, T) {
+        (self.geom, self.data)
+    }
+}
+
+impl<G, T> RTreeObject for GeomWithData<G, T>
+where
+    G: RTreeObject,
+{
+    type Envelope = G::Envelope;
+
+    fn envelope(&self) -> Self::Envelope {
+        self.geom.envelope()
+    }
+}
+
+impl<G, T> PointDistance for GeomWithData<G, T>
+where
+    G: PointDistance,
+{
+    fn distance_2(
+        &self,
+        point: &<Self::Envelope as Envelope>::Point,
+    ) -> <<Self::Envelope as Envelope>::Point as Point>::Scalar {
+        self.geom.distance_2(point)
+    }
+
+    fn contains_point(&self, point: &<Self::Envelope as Envelope>::Point) -> bool {
+        self.geom.contains_point(point)
+    }
+
+    fn distance_2_if_less_or_equal(
+        &self,
+        point: &<Self::Envelope as Envelope>::Point,
+        max_distance_2: <<Self::Envelope as Envelope>::Point as Point>::Scalar,
+    ) -> Option<<<Self::Envelope as Envelope>::Point as Point>::Scalar> {
+        self.geom.distance_2_if_less_or_equal(point, max_distance_2)
+    }
+}
+
+impl<G, T, Params> RTree<GeomWithData<G, T>, Params>
+where
+    G: RTreeObject,
+    Params: RTreeParams,
+{
+    /// Returns an iterator over the data of all geometries contained in the tree.
+    ///
+    /// Unlike [iter_mut](../struct.RTree.html#method.iter_mut), this only grants access to
+    /// each geometry's data, the geometries cannot be changed. Thus, mutating the data can
+    /// never invalidate the tree's structure.
+    ///
+    /// The order in which the elements are returned is not specified.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    /// use rstar::primitives::{GeomWithData, Line};
+    ///
+    /// let mut roads = RTree::bulk_load(vec![
+    ///     GeomWithData::new(Line::new([0.0, 0.0], [1.0, 0.0]), 30),
+    ///     GeomWithData::new(Line::new([0.0, 1.0], [1.0, 1.5]), 50),
+    /// ]);
+    /// for speed_limit in roads.iter_data_mut() {
+    ///     *speed_limit += 10;
+    /// }
+    /// assert_eq!(roads.nearest_neighbor(&[0.5, 0.2]).unwrap().data, 40);
+    /// ```
+    pub fn iter_data_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.iter_mut().map(|geom| &mut geom.data)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<G, T, Params> RTree<GeomWithData<G, T>, Params>
+where
+    G: RTreeObject + Send,
+    G::Envelope: Send,
+    T: Send,
+    Params: RTreeParams,
+{
+    /// Parallel variant of [iter_data_mut](#method.iter_data_mut). Requires the `rayon`
+    /// feature.
+    ///
+    /// # Example
+    /// ```
+    /// use rayon::prelude::*;
+    /// use rstar::RTree;
+    /// use rstar::primitives::{GeomWithData, Line};
+    ///
+    /// let mut roads = RTree::bulk_load(
+    ///     (0..100)
+    ///         .map(|i| GeomWithData::new(Line::new([i as f64, 0.0], [i as f64, 1.0]), i))
+    ///         .collect(),
+    /// );
+    /// roads.par_iter_data_mut().for_each(|data| *data *= 10);
+    /// assert_eq!(roads.nearest_neighbor(&[5.0, 0.5]).unwrap().data, 50);
+    /// ```
+    pub fn par_iter_data_mut(&mut self) -> impl ParallelIterator<Item = &mut T> {
+        self.par_iter_mut().map(|geom| &mut geom.data)
+    }
+}
@@ -21,6 +21,12 @@ use num_traits::{One, Zero};
 /// let tree = RTree::bulk_load(vec![line_1, line_2]);
 ///
 /// assert!(tree.contains(&line_1));
+///
+/// // Snap a position onto the closest line
+/// let position = [0.9, 0.1];
+/// let closest = tree.nearest_neighbor(&position).unwrap();
+/// assert_eq!(closest, &line_1);
+/// assert_eq!(closest.nearest_point(&position), [0.5, 0.5]);
 /// ```
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -92,6 +98,10 @@ where
     pub fn nearest_point(&self, query_point: &P) -> P {
         let (p1, p2) = (self.from, self.to);
         let dir = p2.sub(&p1);
+        if dir.length_2() == P::Scalar::zero() {
+            // Degenerate line, projecting would divide by zero
+            return p1;
+        }
         let s = self.project_point(query_point);
         if P::Scalar::zero() < s && s < One::one() {
             p1.add(&dir.mul(s))
@@ -134,6 +144,13 @@ mod test {
         assert_abs_diff_eq!(edge.distance_2(&[1.0, 3.0]), 0.5 * 0.5 + 1.0);
     }
 
+    #[test]
+    fn degenerate_line_distance() {
+        let line = Line::new([1, 1], [1, 1]);
+        assert_eq!(line.distance_2(&[1, 1]), 0);
+        assert_eq!(line.distance_2(&[4, 5]), 25);
+    }
+
     #[test]
     fn length_2() {
         let line = Line::new([1, -1], [5, 5]);
@@ -1,9 +1,19 @@
 //! Contains primitives ready for insertion into an r-tree.
 
+mod any_geometry;
+mod ball;
+mod cached_envelope;
+mod geom_with_data;
 mod line;
 mod point_with_data;
 mod rectangle;
+mod triangle;
 
+pub use self::any_geometry::AnyGeometry;
+pub use self::ball::Ball;
+pub use self::cached_envelope::CachedEnvelope;
+pub use self::geom_with_data::GeomWithData;
 pub use self::line::Line;
 pub use self::point_with_data::PointWithData;
 pub use self::rectangle::Rectangle;
+pub use self::triangle::Triangle;
@@ -1,4 +1,7 @@
-use crate::{Point, PointDistance, RTreeObject, AABB};
+use crate::algorithm::nearest_neighbor;
+use crate::{Point, PointDistance, RTree, RTreeObject, RTreeParams, AABB};
+#[cfg(feature = "rayon")]
+use rayon::iter::ParallelIterator;
 
 /// A point with some associated data that can be inserted into an r-tree.
 ///
@@ -67,3 +70,100 @@ where
         self.point.contains_point(point)
     }
 }
+
+impl<T, P, Params> RTree<PointWithData<T, P>, Params>
+where
+    P: Point,
+    Params: RTreeParams,
+{
+    /// Returns an iterator over the data of all points contained in the tree.
+    ///
+    /// Unlike [iter_mut](../struct.RTree.html#method.iter_mut), this only grants access to
+    /// each point's data, the positions cannot be changed. Thus, mutating the data can
+    /// never invalidate the tree's structure.
+    ///
+    /// The order in which the elements are returned is not specified.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    /// use rstar::primitives::PointWithData;
+    ///
+    /// let mut tree = RTree::bulk_load(vec![
+    ///     PointWithData::new(1, [0.0, 0.0]),
+    ///     PointWithData::new(2, [1.0, 0.0]),
+    /// ]);
+    /// for data in tree.iter_data_mut() {
+    ///     *data *= 10;
+    /// }
+    /// assert_eq!(tree.nearest_neighbor(&[1.0, 0.0]).unwrap().data, 20);
+    /// ```
+    pub fn iter_data_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.iter_mut().map(|point| &mut point.data)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T, P, Params> RTree<PointWithData<T, P>, Params>
+where
+    T: Send,
+    P: Point + Send,
+    Params: RTreeParams,
+{
+    /// Parallel variant of [iter_data_mut](#method.iter_data_mut). Requires the `rayon`
+    /// feature.
+    ///
+    /// # Example
+    /// ```
+    /// use rayon::prelude::*;
+    /// use rstar::RTree;
+    /// use rstar::primitives::PointWithData;
+    ///
+    /// let mut tree = RTree::bulk_load(
+    ///     (0..100).map(|i| PointWithData::new(i, [i as f64, 0.0])).collect(),
+    /// );
+    /// tree.par_iter_data_mut().for_each(|data| *data *= 10);
+    /// assert_eq!(tree.nearest_neighbor(&[5.0, 0.0]).unwrap().data, 50);
+    /// ```
+    pub fn par_iter_data_mut(&mut self) -> impl ParallelIterator<Item = &mut T> {
+        self.par_iter_mut().map(|point| &mut point.data)
+    }
+}
+
+impl<P, Params> RTree<PointWithData<usize, P>, Params>
+where
+    P: Point,
+    Params: RTreeParams,
+{
+    /// Looks up the nearest neighbor of each query point and writes its index into `out`.
+    ///
+    /// The data of each point is interpreted as its index. After this call, `out[i]`
+    /// contains the index of the point closest to `queries[i]`, or `None` if the tree is empty.
+    /// Any previous content of `out` is cleared. Apart from possibly growing `out`, this
+    /// method does not allocate per query.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    /// use rstar::primitives::PointWithData;
+    ///
+    /// let points = vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]];
+    /// let tree = RTree::bulk_load(
+    ///     points
+    ///         .iter()
+    ///         .enumerate()
+    ///         .map(|(index, point)| PointWithData::new(index, *point))
+    ///         .collect(),
+    /// );
+    /// let mut out = Vec::new();
+    /// tree.nearest_neighbor_join_into(&[[1.8, 0.5], [-1.0, 0.0]], &mut out);
+    /// assert_eq!(out, vec![Some(2), Some(0)]);
+    /// ```
+    pub fn nearest_neighbor_join_into(&self, queries: &[P], out: &mut Vec<Option<usize>>) {
+        out.clear();
+        out.reserve(queries.len());
+        nearest_neighbor::nearest_neighbor_for_each(self.root(), queries, |nearest| {
+            out.push(nearest.map(|point| point.data))
+        });
+    }
+}
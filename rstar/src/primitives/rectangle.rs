@@ -12,6 +12,22 @@ use crate::point::{Point, PointExt};
 ///
 /// # Type parameters
 /// `P`: The rectangle's [Point](../trait.Point.html) type.
+///
+/// # Example
+/// ```
+/// use rstar::primitives::Rectangle;
+/// use rstar::{PointDistance, RTree};
+///
+/// let rooms = RTree::bulk_load(vec![
+///     Rectangle::from_corners([0.0, 0.0], [4.0, 3.0]),
+///     Rectangle::from_corners([5.0, 0.0], [8.0, 3.0]),
+/// ]);
+/// // Distances are measured to the closest point of a rectangle
+/// let closest = rooms.nearest_neighbor(&[4.8, 4.0]).unwrap();
+/// assert_eq!(closest.lower(), [5.0, 0.0]);
+/// assert_eq!(closest.distance_2(&[4.8, 4.0]), 0.2 * 0.2 + 1.0);
+/// assert_eq!(rooms.locate_all_at_point(&[1.0, 1.0]).count(), 1);
+/// ```
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rectangle<P>
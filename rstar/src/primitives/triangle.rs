@@ -0,0 +1,228 @@
+use crate::aabb::AABB;
+use crate::envelope::Envelope;
+use crate::object::PointDistance;
+use crate::object::RTreeObject;
+use crate::point::{Point, PointExt};
+use crate::primitives::Line;
+use num_traits::Zero;
+
+/// A triangle defined by its three vertices.
+///
+/// Triangles are solid, points on the triangle's face have a distance of zero. This works in
+/// any dimension, e.g. for triangles of a 3D mesh.
+///
+/// # Type parameters
+/// `P`: The triangle's [Point](../trait.Point.html) type.
+///
+/// # Example
+/// ```
+/// use rstar::primitives::Triangle;
+/// use rstar::{PointDistance, RTree};
+///
+/// let mesh = RTree::bulk_load(vec![
+///     Triangle::new([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+///     Triangle::new([1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]),
+/// ]);
+/// // Pick the triangle closest to a point above the mesh
+/// let picked = mesh.nearest_neighbor(&[0.25, 0.25, 1.0]).unwrap();
+/// assert_eq!(picked.a, [0.0, 0.0, 0.0]);
+/// assert_eq!(picked.nearest_point(&[0.25, 0.25, 1.0]), [0.25, 0.25, 0.0]);
+/// assert_eq!(picked.distance_2(&[0.25, 0.25, 1.0]), 1.0);
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Triangle<P>
+where
+    P: Point,
+{
+    /// The triangle's first vertex.
+    pub a: P,
+    /// The triangle's second vertex.
+    pub b: P,
+    /// The triangle's third vertex.
+    pub c: P,
+}
+
+impl<P> Triangle<P>
+where
+    P: Point,
+{
+    /// Creates a new triangle from its vertices.
+    pub fn new(a: P, b: P, c: P) -> Self {
+        Triangle { a, b, c }
+    }
+
+    /// Returns the nearest point on this triangle relative to a given point.
+    ///
+    /// If the point lies on the triangle, the point itself is returned.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::primitives::Triangle;
+    ///
+    /// let triangle = Triangle::new([0.0, 0.0], [2.0, 0.0], [0.0, 2.0]);
+    /// assert_eq!(triangle.nearest_point(&[0.5, 0.5]), [0.5, 0.5]);
+    /// assert_eq!(triangle.nearest_point(&[-1.0, -1.0]), [0.0, 0.0]);
+    /// assert_eq!(triangle.nearest_point(&[1.0, -3.0]), [1.0, 0.0]);
+    /// assert_eq!(triangle.nearest_point(&[2.0, 2.0]), [1.0, 1.0]);
+    /// ```
+    pub fn nearest_point(&self, query_point: &P) -> P {
+        // Determines the Voronoi region of the triangle's features that contains the point,
+        // see Ericson, "Real-Time Collision Detection", section 5.1.5
+        let zero = P::Scalar::zero();
+        let (a, b, c) = (self.a, self.b, self.c);
+        let ab = b.sub(&a);
+        let ac = c.sub(&a);
+
+        let ap = query_point.sub(&a);
+        let d1 = ab.dot(&ap);
+        let d2 = ac.dot(&ap);
+        if d1 <= zero && d2 <= zero {
+            return a;
+        }
+
+        let bp = query_point.sub(&b);
+        let d3 = ab.dot(&bp);
+        let d4 = ac.dot(&bp);
+        if d3 >= zero && d4 <= d3 {
+            return b;
+        }
+
+        let cp = query_point.sub(&c);
+        let d5 = ab.dot(&cp);
+        let d6 = ac.dot(&cp);
+        if d6 >= zero && d5 <= d6 {
+            return c;
+        }
+
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= zero && d1 >= zero && d3 <= zero {
+            return Line::new(a, b).nearest_point(query_point);
+        }
+
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= zero && d2 >= zero && d6 <= zero {
+            return Line::new(a, c).nearest_point(query_point);
+        }
+
+        let va = d3 * d6 - d5 * d4;
+        if va <= zero && d4 - d3 >= zero && d5 - d6 >= zero {
+            return Line::new(b, c).nearest_point(query_point);
+        }
+
+        let denominator = va + vb + vc;
+        if denominator == zero {
+            // Degenerate triangle, the point is closest to one of its edges
+            return self.nearest_point_on_edges(query_point);
+        }
+        a.add(&ab.mul(vb / denominator))
+            .add(&ac.mul(vc / denominator))
+    }
+
+    fn nearest_point_on_edges(&self, query_point: &P) -> P {
+        let edges = [
+            Line::new(self.a, self.b),
+            Line::new(self.b, self.c),
+            Line::new(self.c, self.a),
+        ];
+        let mut result = self.a;
+        let mut min_distance_2 = result.sub(query_point).length_2();
+        for edge in &edges {
+            let point = edge.nearest_point(query_point);
+            let distance_2 = point.sub(query_point).length_2();
+            if distance_2 < min_distance_2 {
+                result = point;
+                min_distance_2 = distance_2;
+            }
+        }
+        result
+    }
+}
+
+impl<P> RTreeObject for Triangle<P>
+where
+    P: Point,
+{
+    type Envelope = AABB<P>;
+
+    fn envelope(&self) -> Self::Envelope {
+        AABB::from_points(&[self.a, self.b, self.c])
+    }
+}
+
+impl<P> PointDistance for Triangle<P>
+where
+    P: Point,
+{
+    fn distance_2(
+        &self,
+        point: &<Self::Envelope as Envelope>::Point,
+    ) -> <<Self::Envelope as Envelope>::Point as Point>::Scalar {
+        self.nearest_point(point).sub(point).length_2()
+    }
+
+    fn contains_point(&self, point: &<Self::Envelope as Envelope>::Point) -> bool {
+        self.envelope().contains_point(point) && self.distance_2(point) == Zero::zero()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Triangle;
+    use crate::object::{PointDistance, RTreeObject};
+    use crate::test_utilities::{create_random_points, SEED_1};
+    use crate::AABB;
+    use approx::*;
+
+    #[test]
+    fn triangle_distance() {
+        let triangle = Triangle::new([0.0, 0.0], [4.0, 0.0], [0.0, 2.0]);
+        assert_eq!(
+            triangle.envelope(),
+            AABB::from_corners([0.0, 0.0], [4.0, 2.0])
+        );
+
+        assert_abs_diff_eq!(triangle.distance_2(&[1.0, 1.0]), 0.0);
+        assert_abs_diff_eq!(triangle.distance_2(&[4.0, 0.0]), 0.0);
+        assert_abs_diff_eq!(triangle.distance_2(&[6.0, -1.0]), 5.0);
+        assert_abs_diff_eq!(triangle.distance_2(&[-1.0, 1.0]), 1.0);
+        assert_abs_diff_eq!(triangle.distance_2(&[2.0, -0.5]), 0.25);
+        // Closest to the hypotenuse at [2.4, 0.8]
+        assert_abs_diff_eq!(triangle.distance_2(&[3.0, 2.0]), 1.8, epsilon = 1e-12);
+        assert!(triangle.contains_point(&[0.5, 0.5]));
+        assert!(!triangle.contains_point(&[3.0, 1.0]));
+    }
+
+    #[test]
+    fn triangle_distance_3d() {
+        let triangle = Triangle::new([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]);
+        assert_abs_diff_eq!(triangle.distance_2(&[0.2, 3.0, 0.2]), 9.0);
+        assert_abs_diff_eq!(triangle.distance_2(&[1.0, 1.0, 1.0]), 1.5, epsilon = 1e-12);
+
+        // Compare with a brute force search over the triangle's surface
+        for point in create_random_points(100, SEED_1) {
+            let query = [point[0] * 2.0 - 0.5, point[1] - 0.5, point[1] * 1.5 - 0.2];
+            let steps = 200;
+            let mut min_distance_2 = f64::INFINITY;
+            for i in 0..=steps {
+                for j in 0..=steps - i {
+                    let (u, v) = (i as f64 / steps as f64, j as f64 / steps as f64);
+                    let sample = [u, 0.0, v];
+                    let distance_2: f64 = (0..3).map(|k| (sample[k] - query[k]).powi(2)).sum();
+                    min_distance_2 = min_distance_2.min(distance_2);
+                }
+            }
+            let distance_2 = triangle.distance_2(&query);
+            assert!(distance_2 <= min_distance_2 + 1e-12);
+            assert!(min_distance_2.sqrt() - distance_2.sqrt() < 0.01);
+        }
+    }
+
+    #[test]
+    fn degenerate_triangle_distance() {
+        let triangle = Triangle::new([0.0, 0.0], [1.0, 1.0], [2.0, 2.0]);
+        assert_abs_diff_eq!(triangle.distance_2(&[2.0, 0.0]), 2.0, epsilon = 1e-12);
+        let point = Triangle::new([1.0, 1.0], [1.0, 1.0], [1.0, 1.0]);
+        assert_abs_diff_eq!(point.distance_2(&[2.0, 1.0]), 1.0);
+    }
+}
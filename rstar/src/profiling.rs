@@ -0,0 +1,233 @@
+//! Measures query performance on an existing r-tree.
+//!
+//! Benchmarks usually run on synthetic data. The functions of this module instead run a
+//! workload of queries against an application's actual tree and report throughput as well
+//! as how much of the tree had to be visited. This can be used for self-diagnostics at
+//! startup or to guard against performance regressions in tests.
+//!
+//! # Example
+//! ```
+//! use rstar::RTree;
+//! use rstar::profiling::{profile, Query};
+//! use rstar::AABB;
+//!
+//! let tree = RTree::bulk_load((0..1000).map(|i| [i as f64, 0.0]).collect());
+//! let workload = vec![
+//!     Query::NearestNeighbor([500.2, 1.0]),
+//!     Query::InEnvelope(AABB::from_corners([10.0, -1.0], [19.0, 1.0])),
+//! ];
+//! let report = profile(&tree, &workload);
+//! assert_eq!(report.query_count(), 2);
+//! assert_eq!(report.elements_returned(), 11);
+//! // The queries only visit a small part of the tree
+//! assert!(report.nodes_visited() < 100);
+//! ```
+use crate::algorithm::selection_functions::SelectWithinDistanceFunction;
+use crate::envelope::Envelope;
+use crate::object::{PointDistance, RTreeObject};
+use crate::params::RTreeParams;
+use crate::point::Point;
+use crate::query_stats::QueryStats;
+use crate::rtree::RTree;
+use std::time::Duration;
+
+/// A single query of a workload, see [profile](fn.profile.html).
+pub enum Query<T>
+where
+    T: RTreeObject,
+{
+    /// Returns all elements contained in an envelope, like
+    /// [locate_in_envelope](../struct.RTree.html#method.locate_in_envelope).
+    InEnvelope(T::Envelope),
+    /// Returns all elements intersecting an envelope, like
+    /// [locate_in_envelope_intersecting](../struct.RTree.html#method.locate_in_envelope_intersecting).
+    InEnvelopeIntersecting(T::Envelope),
+    /// Returns the nearest neighbor of a point, like
+    /// [nearest_neighbor](../struct.RTree.html#method.nearest_neighbor).
+    NearestNeighbor(<T::Envelope as Envelope>::Point),
+    /// Returns all elements within a squared distance of a point, like
+    /// [locate_within_distance](../struct.RTree.html#method.locate_within_distance).
+    WithinDistance(
+        <T::Envelope as Envelope>::Point,
+        <<T::Envelope as Envelope>::Point as Point>::Scalar,
+    ),
+}
+
+impl<T> Clone for Query<T>
+where
+    T: RTreeObject,
+{
+    fn clone(&self) -> Self {
+        match self {
+            Query::InEnvelope(envelope) => Query::InEnvelope(*envelope),
+            Query::InEnvelopeIntersecting(envelope) => Query::InEnvelopeIntersecting(*envelope),
+            Query::NearestNeighbor(point) => Query::NearestNeighbor(*point),
+            Query::WithinDistance(point, distance_2) => Query::WithinDistance(*point, *distance_2),
+        }
+    }
+}
+
+/// The result of profiling a workload, see [profile](fn.profile.html).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ProfileReport {
+    query_count: usize,
+    elements_returned: usize,
+    stats: QueryStats,
+    elapsed: Option<Duration>,
+}
+
+impl ProfileReport {
+    /// Returns the number of executed queries.
+    pub fn query_count(&self) -> usize {
+        self.query_count
+    }
+
+    /// Returns the total number of elements returned by all queries.
+    pub fn elements_returned(&self) -> usize {
+        self.elements_returned
+    }
+
+    /// Returns the summed up [statistics](../struct.QueryStats.html) of all queries.
+    pub fn stats(&self) -> QueryStats {
+        self.stats
+    }
+
+    /// Returns the total number of parent nodes whose children were examined by all
+    /// queries, see [QueryStats::nodes_visited](../struct.QueryStats.html#method.nodes_visited).
+    pub fn nodes_visited(&self) -> usize {
+        self.stats.nodes_visited()
+    }
+
+    /// Returns the total number of elements that were tested by all queries.
+    ///
+    /// Comparing this with [elements_returned](#method.elements_returned) indicates how
+    /// precisely the tree's envelopes guide the queries.
+    pub fn elements_tested(&self) -> usize {
+        self.stats.leaves_scanned()
+    }
+
+    /// Returns the average number of visited parent nodes per query.
+    pub fn nodes_visited_per_query(&self) -> f64 {
+        if self.query_count == 0 {
+            0.0
+        } else {
+            self.nodes_visited() as f64 / self.query_count as f64
+        }
+    }
+
+    /// Returns the time it took to run the whole workload.
+    ///
+    /// Returns `None` on platforms without a monotonic clock, e.g. `wasm32-unknown-unknown`.
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.elapsed
+    }
+
+    /// Returns the number of queries per second.
+    ///
+    /// Returns `None` if no time could be measured.
+    pub fn queries_per_second(&self) -> Option<f64> {
+        self.elapsed
+            .map(|elapsed| elapsed.as_secs_f64())
+            .filter(|seconds| *seconds > 0.0)
+            .map(|seconds| self.query_count as f64 / seconds)
+    }
+}
+
+/// Runs the queries of a workload one after another and reports their statistics.
+///
+/// The returned duration includes the small overhead of counting node visits.
+pub fn profile<T, Params>(tree: &RTree<T, Params>, workload: &[Query<T>]) -> ProfileReport
+where
+    T: PointDistance,
+    Params: RTreeParams,
+{
+    let mut stats = QueryStats::default();
+    let mut elements_returned = 0;
+    let elapsed = measure(|| {
+        for query in workload {
+            let (returned, query_stats) = match query {
+                Query::InEnvelope(envelope) => {
+                    let (result, query_stats) = tree.locate_in_envelope_with_stats(envelope);
+                    (result.len(), query_stats)
+                }
+                Query::InEnvelopeIntersecting(envelope) => {
+                    let (result, query_stats) =
+                        tree.locate_in_envelope_intersecting_with_stats(envelope);
+                    (result.len(), query_stats)
+                }
+                Query::NearestNeighbor(point) => {
+                    let (result, query_stats) = tree.nearest_neighbor_with_stats(point);
+                    (result.map_or(0, |_| 1), query_stats)
+                }
+                Query::WithinDistance(point, distance_2) => {
+                    let (result, query_stats) = tree.locate_with_selection_function_with_stats(
+                        SelectWithinDistanceFunction::new(*point, *distance_2),
+                    );
+                    (result.len(), query_stats)
+                }
+            };
+            elements_returned += returned;
+            stats += query_stats;
+        }
+    });
+    ProfileReport {
+        query_count: workload.len(),
+        elements_returned,
+        stats,
+        elapsed,
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn measure<F: FnOnce()>(f: F) -> Option<Duration> {
+    let start = std::time::Instant::now();
+    f();
+    Some(start.elapsed())
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn measure<F: FnOnce()>(f: F) -> Option<Duration> {
+    // No monotonic clock is available
+    f();
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::{profile, Query};
+    use crate::test_utilities::{create_random_points, create_random_rectangles, SEED_1, SEED_2};
+    use crate::{RTree, AABB};
+
+    #[test]
+    fn test_profile() {
+        let tree = RTree::bulk_load(create_random_rectangles(1000, SEED_1));
+        let envelope = AABB::from_corners([0.2, 0.2], [0.3, 0.3]);
+        let query_points = create_random_points(10, SEED_2);
+        let mut workload = vec![
+            Query::InEnvelope(envelope),
+            Query::InEnvelopeIntersecting(envelope),
+            Query::WithinDistance(query_points[0], 0.01),
+        ];
+        workload.extend(
+            query_points
+                .iter()
+                .map(|point| Query::NearestNeighbor(*point)),
+        );
+
+        let expected = tree.locate_in_envelope(&envelope).count()
+            + tree.locate_in_envelope_intersecting(&envelope).count()
+            + tree.locate_within_distance(query_points[0], 0.01).count()
+            + query_points.len();
+        let report = profile(&tree, &workload);
+        assert_eq!(report.query_count(), workload.len());
+        assert_eq!(report.elements_returned(), expected);
+        assert!(report.elements_tested() >= expected);
+        assert!(report.nodes_visited() >= workload.len());
+        assert!(report.stats().envelopes_tested() >= report.nodes_visited() - workload.len());
+        assert!(report.elapsed().is_some());
+
+        let empty = profile(&tree, &[]);
+        assert_eq!(empty.nodes_visited_per_query(), 0.0);
+        assert_eq!(empty.elements_returned(), 0);
+    }
+}
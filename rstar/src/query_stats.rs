@@ -0,0 +1,178 @@
+use crate::algorithm::iterators::SelectionIterator;
+use crate::algorithm::selection_functions::SelectionFunction;
+use crate::node::ParentNode;
+use crate::object::RTreeObject;
+use std::cell::Cell;
+use std::ops::AddAssign;
+
+/// Counters collected while running a single query.
+///
+/// The `*_with_stats` variants of the query methods of [RTree](struct.RTree.html), e.g.
+/// [locate_in_envelope_with_stats](struct.RTree.html#method.locate_in_envelope_with_stats)
+/// or [nearest_neighbor_with_stats](struct.RTree.html#method.nearest_neighbor_with_stats),
+/// return these counters along with their result. Comparing them across different
+/// [parameters](trait.RTreeParams.html) shows how well a tree guides the queries of a
+/// workload. Stats of several queries can be summed up with `+=`, the
+/// [profiling](profiling/index.html) module does so for a whole workload.
+///
+/// # Example
+/// ```
+/// use rstar::{QueryStats, RTree, AABB};
+///
+/// let tree = RTree::bulk_load((0..1000).map(|i| [i as f64, 0.0]).collect());
+/// let (result, stats) =
+///     tree.locate_in_envelope_with_stats(&AABB::from_corners([10.0, -1.0], [19.0, 1.0]));
+/// assert_eq!(result.len(), 10);
+/// assert!(stats.leaves_scanned() >= 10);
+///
+/// let mut total = QueryStats::default();
+/// for i in 0..10 {
+///     let (_, stats) = tree.nearest_neighbor_with_stats(&[i as f64 * 100.0, 1.0]);
+///     total += stats;
+/// }
+/// // The queries only visit a small part of the tree
+/// assert!(total.leaves_scanned() < 1000);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueryStats {
+    pub(crate) nodes_visited: usize,
+    pub(crate) envelopes_tested: usize,
+    pub(crate) leaves_scanned: usize,
+}
+
+impl QueryStats {
+    /// Returns the number of parent nodes whose children were examined, including the root.
+    pub fn nodes_visited(&self) -> usize {
+        self.nodes_visited
+    }
+
+    /// Returns the number of parent node envelopes that were tested against the query.
+    ///
+    /// The remaining `envelopes_tested() - nodes_visited()` nodes (plus one for the root)
+    /// were pruned from the search.
+    pub fn envelopes_tested(&self) -> usize {
+        self.envelopes_tested
+    }
+
+    /// Returns the number of elements that were tested against the query.
+    pub fn leaves_scanned(&self) -> usize {
+        self.leaves_scanned
+    }
+}
+
+impl AddAssign for QueryStats {
+    fn add_assign(&mut self, other: Self) {
+        self.nodes_visited += other.nodes_visited;
+        self.envelopes_tested += other.envelopes_tested;
+        self.leaves_scanned += other.leaves_scanned;
+    }
+}
+
+/// Counts the calls of a wrapped selection function.
+///
+/// Every parent node checked by the selection function has its envelope tested, and its
+/// children are examined if the function selects it. Every checked leaf is scanned.
+pub(crate) struct CountingFunction<'c, F> {
+    inner: F,
+    stats: &'c Cell<QueryStats>,
+}
+
+impl<'c, F> CountingFunction<'c, F> {
+    pub(crate) fn new(inner: F, stats: &'c Cell<QueryStats>) -> Self {
+        CountingFunction { inner, stats }
+    }
+
+    fn count(&self, update: impl FnOnce(&mut QueryStats)) {
+        let mut stats = self.stats.get();
+        update(&mut stats);
+        self.stats.set(stats);
+    }
+}
+
+impl<'c, T, F> SelectionFunction<T> for CountingFunction<'c, F>
+where
+    T: RTreeObject,
+    F: SelectionFunction<T>,
+{
+    fn should_unpack_parent(&self, envelope: &T::Envelope) -> bool {
+        let result = self.inner.should_unpack_parent(envelope);
+        self.count(|stats| {
+            stats.envelopes_tested += 1;
+            stats.nodes_visited += result as usize;
+        });
+        result
+    }
+
+    fn should_unpack_leaf(&self, leaf: &T) -> bool {
+        self.count(|stats| stats.leaves_scanned += 1);
+        self.inner.should_unpack_leaf(leaf)
+    }
+}
+
+/// Returns all elements selected by a selection function along with the query's counters.
+pub(crate) fn locate_with_stats<T, S>(root: &ParentNode<T>, function: S) -> (Vec<&T>, QueryStats)
+where
+    T: RTreeObject,
+    S: SelectionFunction<T>,
+{
+    // The root's children are always examined
+    let stats = Cell::new(QueryStats {
+        nodes_visited: 1,
+        ..Default::default()
+    });
+    let result = SelectionIterator::new(root, CountingFunction::new(function, &stats)).collect();
+    (result, stats.get())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_utilities::{create_random_points, create_random_rectangles, SEED_1, SEED_2};
+    use crate::{QueryStats, RTree, AABB};
+
+    #[test]
+    fn test_locate_with_stats() {
+        let tree = RTree::bulk_load(create_random_rectangles(1000, SEED_1));
+        let envelope = AABB::from_corners([0.2, 0.2], [0.3, 0.3]);
+        let (mut result, stats) = tree.locate_in_envelope_intersecting_with_stats(&envelope);
+        let mut expected: Vec<_> = tree.locate_in_envelope_intersecting(&envelope).collect();
+        result.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(result, expected);
+        assert!(stats.leaves_scanned() >= result.len());
+        assert!(stats.leaves_scanned() < 1000);
+        assert!(stats.nodes_visited() <= stats.envelopes_tested() + 1);
+
+        // Selecting everything visits every node and element exactly once
+        let everything = AABB::from_corners([-1.0, -1.0], [2.0, 2.0]);
+        let (result, stats) = tree.locate_in_envelope_with_stats(&everything);
+        assert_eq!(result.len(), 1000);
+        assert_eq!(stats.leaves_scanned(), 1000);
+        assert_eq!(stats.nodes_visited(), stats.envelopes_tested() + 1);
+
+        let empty: RTree<[f64; 2]> = RTree::new();
+        let (result, stats) = empty.locate_all_at_point_with_stats(&[0.0, 0.0]);
+        assert!(result.is_empty());
+        assert_eq!(stats.nodes_visited(), 1);
+        assert_eq!(stats.leaves_scanned(), 0);
+    }
+
+    #[test]
+    fn test_nearest_neighbor_with_stats() {
+        let tree = RTree::bulk_load(create_random_points(1000, SEED_1));
+        let mut total = QueryStats::default();
+        for query_point in create_random_points(100, SEED_2) {
+            let (nearest, stats) = tree.nearest_neighbor_with_stats(&query_point);
+            assert_eq!(nearest, tree.nearest_neighbor(&query_point));
+            assert!(stats.nodes_visited() > 0);
+            assert!(stats.leaves_scanned() > 0);
+            total += stats;
+        }
+        assert!(total.leaves_scanned() < 100 * 1000 / 10);
+
+        let empty: RTree<[f64; 2]> = RTree::new();
+        let (nearest, stats) = empty.nearest_neighbor_with_stats(&[0.0, 0.0]);
+        assert_eq!(nearest, None);
+        assert_eq!(stats.nodes_visited(), 1);
+        assert_eq!(stats.envelopes_tested(), 0);
+    }
+}
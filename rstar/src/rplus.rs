@@ -0,0 +1,459 @@
+use crate::aabb::AABB;
+use crate::envelope::Envelope;
+use crate::object::{PointDistance, RTreeObject};
+use crate::params::{verify_parameters, DefaultParams, RTreeParams};
+use crate::point::{total_cmp, Point, PointExt};
+use num_traits::Zero;
+use std::collections::BinaryHeap;
+use std::ops::Range;
+
+/// A read-only r+-tree: An r-tree variant whose node regions don't overlap.
+///
+/// The regions of an [RTree](struct.RTree.html)'s nodes may overlap, a query for a point
+/// descends into every node covering the point. An `RPlusTree` instead partitions space: Each
+/// inner node cuts its region along an axis-aligned plane into two disjoint halves. Objects
+/// crossing a cut are stored in both halves, hence a point query follows a single path from
+/// the root to a leaf. This pays off for datasets whose envelopes overlap heavily, e.g.
+/// polygons of multiple administrative levels or large labels on a map.
+///
+/// The tree is built once by [bulk loading](#method.bulk_load) and stores each element only
+/// once, leaves refer to them by index. Leaves hold up to `Params::MAX_SIZE` entries, leaves
+/// whose entries cannot be separated by a cut may be larger. Queries report every element at
+/// most once, even if it is stored in multiple leaves.
+///
+/// The tree works with objects whose envelope is an [AABB](struct.AABB.html).
+///
+/// # Example
+/// ```
+/// use rstar::primitives::Rectangle;
+/// use rstar::{RPlusTree, AABB};
+///
+/// let rectangles: Vec<_> = (0..100)
+///     .map(|i| Rectangle::from_corners([0.0, i as f64], [100.0, i as f64 + 1.5]))
+///     .collect();
+/// let tree = RPlusTree::bulk_load(rectangles);
+/// assert_eq!(tree.size(), 100);
+/// // Each point is covered by at most two overlapping rectangles
+/// assert_eq!(tree.locate_all_at_point(&[50.0, 10.25]).count(), 2);
+/// let area = AABB::from_corners([10.0, 10.0], [20.0, 20.0]);
+/// assert_eq!(tree.locate_in_envelope_intersecting(&area).count(), 12);
+/// ```
+pub struct RPlusTree<T, Params = DefaultParams>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    elements: Vec<T>,
+    // The leaves' entries, indices into `elements`
+    entries: Vec<usize>,
+    // Each inner node is directly followed by its left child
+    nodes: Vec<PlusNode<T::Envelope>>,
+    _params: ::std::marker::PhantomData<Params>,
+}
+
+struct PlusNode<E>
+where
+    E: Envelope,
+{
+    // The envelope of all entries below this node, may exceed the node's region
+    envelope: E,
+    kind: NodeKind<<E::Point as Point>::Scalar>,
+}
+
+enum NodeKind<S> {
+    Leaf(Range<usize>),
+    // Points with a coordinate less than `cut` along `axis` belong to the left child
+    Inner { axis: usize, cut: S, right: usize },
+}
+
+impl<T, P> RPlusTree<T>
+where
+    T: RTreeObject<Envelope = AABB<P>>,
+    P: Point,
+{
+    /// Creates a new r+-tree from a set of elements.
+    ///
+    /// Each inner node is cut at the median of its entries' centers. The cut's axis is
+    /// chosen among the axes along which the entries are spread farthest. A cut is only
+    /// applied if it leaves each half with at most three quarters of the node's entries,
+    /// otherwise the node becomes a leaf. This bounds the number of duplicated entries.
+    ///
+    /// # Runtime
+    /// Bulk loading runs in `O(n * log(n))` for objects that don't overlap each other,
+    /// where `n` is the number of loaded elements.
+    pub fn bulk_load(elements: Vec<T>) -> Self {
+        Self::bulk_load_with_params(elements)
+    }
+}
+
+impl<T, P, Params> RPlusTree<T, Params>
+where
+    T: RTreeObject<Envelope = AABB<P>>,
+    P: Point,
+    Params: RTreeParams,
+{
+    /// Creates a new r+-tree with configurable parameters.
+    ///
+    /// Only `Params::MAX_SIZE` is used, it determines the size of the leaves. For more
+    /// information refer to [bulk_load](#method.bulk_load).
+    pub fn bulk_load_with_params(elements: Vec<T>) -> Self {
+        verify_parameters::<T, Params>();
+        let envelopes: Vec<_> = elements.iter().map(|e| e.envelope()).collect();
+        let mut tree = RPlusTree {
+            elements,
+            entries: Vec::new(),
+            nodes: Vec::new(),
+            _params: Default::default(),
+        };
+        tree.build((0..envelopes.len()).collect(), &envelopes);
+        tree
+    }
+
+    fn build(&mut self, indices: Vec<usize>, envelopes: &[AABB<P>]) -> usize {
+        let node_index = self.nodes.len();
+        let envelope = indices.iter().fold(AABB::new_empty(), |acc, &index| {
+            acc.merged(&envelopes[index])
+        });
+        self.nodes.push(PlusNode {
+            envelope,
+            kind: NodeKind::Leaf(0..0),
+        });
+        if indices.len() > Params::MAX_SIZE {
+            if let Some((axis, cut, left, right)) = find_cut(&indices, envelopes) {
+                self.build(left, envelopes);
+                let right = self.build(right, envelopes);
+                self.nodes[node_index].kind = NodeKind::Inner { axis, cut, right };
+                return node_index;
+            }
+        }
+        let start = self.entries.len();
+        self.entries.extend(indices);
+        self.nodes[node_index].kind = NodeKind::Leaf(start..self.entries.len());
+        node_index
+    }
+
+    /// Returns the number of elements in the tree.
+    pub fn size(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Returns an iterator over all elements, each element is returned once.
+    pub fn iter(&self) -> ::std::slice::Iter<'_, T> {
+        self.elements.iter()
+    }
+
+    /// Returns all elements whose envelope is contained in a given envelope.
+    pub fn locate_in_envelope<'a>(&'a self, envelope: &AABB<P>) -> impl Iterator<Item = &'a T>
+    where
+        P: 'a,
+    {
+        let envelope = *envelope;
+        self.leaves_intersecting(envelope).flat_map(move |leaf| {
+            self.leaf_entries(leaf).filter(move |element| {
+                let element_envelope = element.envelope();
+                envelope.contains_envelope(&element_envelope)
+                    && self.leaf_at(&element_envelope.lower()) == leaf
+            })
+        })
+    }
+
+    /// Returns all elements whose envelope intersects a given envelope.
+    pub fn locate_in_envelope_intersecting<'a>(
+        &'a self,
+        envelope: &AABB<P>,
+    ) -> impl Iterator<Item = &'a T>
+    where
+        P: 'a,
+    {
+        let envelope = *envelope;
+        self.leaves_intersecting(envelope).flat_map(move |leaf| {
+            self.leaf_entries(leaf).filter(move |element| {
+                let element_envelope = element.envelope();
+                // Elements are reported by the leaf containing the lower corner of the
+                // intersection, other leaves skip their duplicates.
+                element_envelope.intersects(&envelope)
+                    && self.leaf_at(&element_envelope.lower().max_point(&envelope.lower())) == leaf
+            })
+        })
+    }
+
+    fn leaves_intersecting(&self, envelope: AABB<P>) -> LeafIterator<'_, AABB<P>> {
+        LeafIterator {
+            nodes: &self.nodes,
+            envelope,
+            stack: vec![0],
+        }
+    }
+
+    fn leaf_entries(&self, leaf: usize) -> impl Iterator<Item = &T> {
+        let range = match &self.nodes[leaf].kind {
+            NodeKind::Leaf(range) => range.clone(),
+            NodeKind::Inner { .. } => 0..0,
+        };
+        self.entries[range]
+            .iter()
+            .map(move |&index| &self.elements[index])
+    }
+
+    /// Returns the index of the leaf whose region contains a point.
+    fn leaf_at(&self, point: &P) -> usize {
+        let mut index = 0;
+        while let NodeKind::Inner { axis, cut, right } = self.nodes[index].kind {
+            if point.nth(axis) < cut {
+                index += 1;
+            } else {
+                index = right;
+            }
+        }
+        index
+    }
+}
+
+impl<T, P, Params> RPlusTree<T, Params>
+where
+    T: PointDistance + RTreeObject<Envelope = AABB<P>>,
+    P: Point,
+    Params: RTreeParams,
+{
+    /// Returns all elements that contain a point.
+    ///
+    /// Only the entries of a single leaf are checked.
+    pub fn locate_all_at_point(&self, point: &P) -> impl Iterator<Item = &T> {
+        let point = *point;
+        self.leaf_entries(self.leaf_at(&point))
+            .filter(move |element| element.contains_point(&point))
+    }
+
+    /// Returns an element that contains a point.
+    ///
+    /// If multiple elements contain the point, any of them is returned.
+    pub fn locate_at_point(&self, point: &P) -> Option<&T> {
+        self.locate_all_at_point(point).next()
+    }
+
+    /// Returns the nearest neighbor of a point.
+    ///
+    /// Returns `None` if the tree is empty.
+    pub fn nearest_neighbor(&self, query_point: &P) -> Option<&T> {
+        let mut heap = BinaryHeap::new();
+        heap.push(HeapEntry {
+            distance_2: Zero::zero(),
+            index: 0,
+            is_element: false,
+        });
+        while let Some(entry) = heap.pop() {
+            if entry.is_element {
+                return Some(&self.elements[entry.index]);
+            }
+            match &self.nodes[entry.index].kind {
+                NodeKind::Leaf(range) => {
+                    for &index in &self.entries[range.clone()] {
+                        heap.push(HeapEntry {
+                            distance_2: self.elements[index].distance_2(query_point),
+                            index,
+                            is_element: true,
+                        });
+                    }
+                }
+                NodeKind::Inner { right, .. } => {
+                    for &child in &[entry.index + 1, *right] {
+                        heap.push(HeapEntry {
+                            distance_2: self.nodes[child].envelope.distance_2(query_point),
+                            index: child,
+                            is_element: false,
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Chooses an axis and a position to cut a node, returns the entries of both halves.
+type Cut<S> = (usize, S, Vec<usize>, Vec<usize>);
+
+fn find_cut<P>(indices: &[usize], envelopes: &[AABB<P>]) -> Option<Cut<P::Scalar>>
+where
+    P: Point,
+{
+    let centers: Vec<_> = indices
+        .iter()
+        .map(|&index| envelopes[index].center())
+        .collect();
+    let bounds = AABB::from_points(&centers);
+    let mut axes: Vec<_> = (0..P::DIMENSIONS).collect();
+    axes.sort_by(|&l, &r| {
+        let spread = |axis| bounds.upper().nth(axis) - bounds.lower().nth(axis);
+        total_cmp(&spread(r), &spread(l))
+    });
+    for axis in axes {
+        let mut values: Vec<_> = centers.iter().map(|center| center.nth(axis)).collect();
+        let median = values.len() / 2;
+        ::pdqselect::select_by(&mut values, median, total_cmp);
+        let cut = values[median];
+        let left: Vec<_> = indices
+            .iter()
+            .cloned()
+            .filter(|&index| envelopes[index].lower().nth(axis) < cut)
+            .collect();
+        let right: Vec<_> = indices
+            .iter()
+            .cloned()
+            .filter(|&index| envelopes[index].upper().nth(axis) >= cut)
+            .collect();
+        let is_balanced = |half: &Vec<usize>| half.len() * 4 <= indices.len() * 3;
+        if !left.is_empty() && !right.is_empty() && is_balanced(&left) && is_balanced(&right) {
+            return Some((axis, cut, left, right));
+        }
+    }
+    None
+}
+
+/// Iterates over the leaves that may contain elements intersecting an envelope.
+struct LeafIterator<'a, E>
+where
+    E: Envelope,
+{
+    nodes: &'a [PlusNode<E>],
+    envelope: AABB<E::Point>,
+    stack: Vec<usize>,
+}
+
+impl<'a, P> Iterator for LeafIterator<'a, AABB<P>>
+where
+    P: Point,
+{
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while let Some(index) = self.stack.pop() {
+            let node = &self.nodes[index];
+            if !node.envelope.intersects(&self.envelope) {
+                continue;
+            }
+            match node.kind {
+                NodeKind::Leaf(_) => return Some(index),
+                NodeKind::Inner { axis, cut, right } => {
+                    if self.envelope.upper().nth(axis) >= cut {
+                        self.stack.push(right);
+                    }
+                    if self.envelope.lower().nth(axis) < cut {
+                        self.stack.push(index + 1);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// A node or element of a nearest neighbor query, ordered by reversed distance.
+///
+/// Nodes and elements are referred to by their index in a flat array.
+pub(crate) struct HeapEntry<S> {
+    pub(crate) distance_2: S,
+    pub(crate) index: usize,
+    pub(crate) is_element: bool,
+}
+
+impl<S> PartialEq for HeapEntry<S>
+where
+    S: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.distance_2 == other.distance_2
+    }
+}
+
+impl<S> Eq for HeapEntry<S> where S: PartialEq {}
+
+impl<S> PartialOrd for HeapEntry<S>
+where
+    S: crate::RTreeNum,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<::std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S> Ord for HeapEntry<S>
+where
+    S: crate::RTreeNum,
+{
+    fn cmp(&self, other: &Self) -> ::std::cmp::Ordering {
+        // Inverse comparison creates a min heap
+        total_cmp(&other.distance_2, &self.distance_2)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RPlusTree;
+    use crate::envelope::Envelope;
+    use crate::object::{PointDistance, RTreeObject};
+    use crate::primitives::Rectangle;
+    use crate::test_utilities::{create_random_points, create_random_rectangles, SEED_1, SEED_2};
+    use crate::{RTree, AABB};
+
+    fn sorted<'a>(rectangles: impl Iterator<Item = &'a Rectangle<[f64; 2]>>) -> Vec<[f64; 2]> {
+        let mut corners: Vec<_> = rectangles.map(|r| r.lower()).collect();
+        corners.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        corners
+    }
+
+    #[test]
+    fn test_rplus_queries() {
+        // Enlarge the rectangles to create heavy overlap
+        let rectangles: Vec<_> = create_random_rectangles(1000, SEED_1)
+            .into_iter()
+            .map(|r| Rectangle::from_corners(r.lower(), r.upper().map(|x| x + 0.1)))
+            .collect();
+        let tree = RPlusTree::bulk_load(rectangles.clone());
+        let reference = RTree::bulk_load(rectangles);
+        assert_eq!(tree.size(), 1000);
+        assert_eq!(sorted(tree.iter()), sorted(reference.iter()));
+
+        let points = create_random_points(100, SEED_2);
+        for (point, other) in points.iter().zip(points.iter().rev()) {
+            assert_eq!(
+                sorted(tree.locate_all_at_point(point)),
+                sorted(reference.locate_all_at_point(point))
+            );
+            let nearest = tree.nearest_neighbor(point).unwrap();
+            let expected = reference.nearest_neighbor(point).unwrap();
+            assert_eq!(nearest.distance_2(point), expected.distance_2(point));
+
+            let envelope = AABB::from_corners(*point, *other);
+            assert_eq!(
+                sorted(tree.locate_in_envelope(&envelope)),
+                sorted(reference.locate_in_envelope(&envelope))
+            );
+            assert_eq!(
+                sorted(tree.locate_in_envelope_intersecting(&envelope)),
+                sorted(reference.locate_in_envelope_intersecting(&envelope))
+            );
+        }
+    }
+
+    #[test]
+    fn test_rplus_degenerate_inputs() {
+        let empty: RPlusTree<[f64; 2]> = RPlusTree::bulk_load(Vec::new());
+        assert_eq!(empty.nearest_neighbor(&[0.0, 0.0]), None);
+        assert_eq!(empty.locate_at_point(&[0.0, 0.0]), None);
+
+        // Identical points and nested rectangles cannot be separated by a cut
+        let tree = RPlusTree::bulk_load(vec![[1.0, 1.0]; 100]);
+        assert_eq!(tree.locate_all_at_point(&[1.0, 1.0]).count(), 100);
+        let nested: Vec<_> = (1..100)
+            .map(|i| Rectangle::from_corners([-i as f64, -i as f64], [i as f64, i as f64]))
+            .collect();
+        let tree = RPlusTree::bulk_load(nested);
+        assert_eq!(tree.locate_all_at_point(&[0.0, 0.0]).count(), 99);
+        let envelope = AABB::from_corners([50.5, -1.0], [60.0, 1.0]);
+        assert_eq!(tree.locate_in_envelope_intersecting(&envelope).count(), 49);
+        assert!(tree
+            .iter()
+            .all(|r| r.envelope().contains_point(&[0.0, 0.0])));
+    }
+}
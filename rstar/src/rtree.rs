@@ -1,14 +1,36 @@
+use crate::algorithm::anti_join;
 use crate::algorithm::bulk_load;
+use crate::algorithm::clustering::{self, ClusteredItem};
+use crate::algorithm::hilbert::HilbertIterator;
 use crate::algorithm::intersection_iterator::IntersectionIterator;
 use crate::algorithm::iterators::*;
 use crate::algorithm::nearest_neighbor;
+use crate::algorithm::overlay;
+#[cfg(feature = "rayon")]
+use crate::algorithm::parallel_iterators::*;
 use crate::algorithm::removal;
+use crate::algorithm::rstar;
 use crate::algorithm::selection_functions::*;
+use crate::algorithm::spanning_tree;
+use crate::algorithm::weighted_sampling::WeightedSampler;
+use crate::binary::{self, BinaryCodable};
 use crate::envelope::Envelope;
-use crate::node::ParentNode;
+use crate::insertion_metrics::InsertionMetrics;
+use crate::metadata::RTreeMetadata;
+use crate::metric::{Metric, SquaredEuclidean};
+use crate::node::{ParentNode, RTreeNode};
 use crate::object::{PointDistance, RTreeObject};
-use crate::params::{verify_parameters, DefaultParams, InsertionStrategy, RTreeParams};
-use crate::Point;
+use crate::params::{
+    verify_parameters, DefaultParams, InsertionStrategy, RTreeParams, ReinsertionPolicy,
+};
+use crate::point::is_finite;
+use crate::query_stats::{self, QueryStats};
+use crate::space_time::SpaceTimeEnvelope;
+use crate::static_tree::StaticRTree;
+use crate::tiles::TileIterator;
+use crate::{AffineTransform, Point, AABB};
+use num_traits::Bounded;
+use std::collections::BTreeMap;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -129,25 +151,85 @@ where
 /// # (De)Serialization
 /// Enable the `serde` feature for [Serde](https://crates.io/crates/serde) support.
 ///
-#[derive(Clone)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// # Concurrency
+/// An r-tree is `Send` and `Sync` if both `T` and `T::Envelope` are. All queries only require
+/// `&self` and never modify the tree, so a shared tree can be queried from many threads at
+/// once without any locking, e.g. by sharing it with an `Arc` or with scoped threads. Only
+/// modifications like [insert](#method.insert) need exclusive access.
+///
+/// With the `rayon` feature, [par_query](#method.par_query) runs a batch of queries in
+/// parallel, and [par_iter](#method.par_iter) and the `par_locate_[...]` methods split a
+/// single query across threads.
+///
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 #[cfg_attr(
     feature = "serde",
-    serde(bound(
-        serialize = "T: Serialize, T::Envelope: Serialize",
-        deserialize = "T: Deserialize<'de>, T::Envelope: Deserialize<'de>"
-    ))
+    serde(bound(deserialize = "T: Deserialize<'de>, T::Envelope: Deserialize<'de>"))
 )]
 pub struct RTree<T, Params = DefaultParams>
 where
     Params: RTreeParams,
     T: RTreeObject,
 {
+    #[cfg_attr(feature = "serde", serde(default))]
+    metadata: RTreeMetadata,
     root: ParentNode<T>,
     size: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    insertion_metrics: InsertionMetrics,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    adaptive_reinsertion: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    reinsertion_policy: ReinsertionPolicy,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    spare_children: Vec<Vec<RTreeNode<T>>>,
     _params: ::std::marker::PhantomData<Params>,
 }
 
+impl<T, Params> Clone for RTree<T, Params>
+where
+    Params: RTreeParams,
+    T: RTreeObject + Clone,
+{
+    fn clone(&self) -> Self {
+        RTree {
+            metadata: self.metadata.clone(),
+            root: self.root.clone(),
+            size: self.size,
+            insertion_metrics: self.insertion_metrics,
+            adaptive_reinsertion: self.adaptive_reinsertion,
+            reinsertion_policy: self.reinsertion_policy,
+            // Nodes kept for reuse are not part of the tree's content
+            spare_children: Vec::new(),
+            _params: Default::default(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T, Params> Serialize for RTree<T, Params>
+where
+    Params: RTreeParams,
+    T: RTreeObject + Serialize,
+    T::Envelope: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+
+        // The metadata is written first and up to date, see RTreeMetadata::from_serialized_tree
+        let mut state = serializer.serialize_struct("RTree", 4)?;
+        state.serialize_field("metadata", &self.metadata())?;
+        state.serialize_field("root", &self.root)?;
+        state.serialize_field("size", &self.size)?;
+        state.skip_field("insertion_metrics")?;
+        state.serialize_field("_params", &self._params)?;
+        state.end()
+    }
+}
+
 struct DebugHelper<'a, T, Params>
 where
     T: RTreeObject + ::std::fmt::Debug + 'a,
@@ -187,10 +269,24 @@ where
     /// Creates a new, empty r-tree.
     ///
     /// The created r-tree is configured with [default parameters](struct.DefaultParams.html).
+    ///
+    /// Creating an empty tree does not allocate. An r-tree with no more than
+    /// [`MAX_SIZE`](trait.RTreeParams.html#associatedconstant.MAX_SIZE) elements stores all
+    /// of them within a single flat node, queries on such trees boil down to a linear scan.
     pub fn new() -> Self {
         Self::new_with_params()
     }
 
+    /// Creates a new, empty r-tree that keeps the node allocations of about `capacity`
+    /// insertions.
+    ///
+    /// See [reserve](#method.reserve) for more information.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut result = Self::new_with_params();
+        result.reserve(capacity);
+        result
+    }
+
     /// Creates a new r-tree with some elements already inserted.
     ///
     /// This method should be the preferred way for creating r-trees. It both
@@ -203,9 +299,83 @@ where
     /// # Runtime
     /// Bulk loading runs in `O(n * log(n))`, where `n` is the number of loaded
     /// elements.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let points: Vec<_> = (0..1000).map(|i| [i as f64, (i % 10) as f64]).collect();
+    /// let tree = RTree::bulk_load(points);
+    /// assert_eq!(tree.size(), 1000);
+    /// assert_eq!(tree.nearest_neighbor(&[500.2, 0.1]), Some(&[500.0, 0.0]));
+    /// ```
     pub fn bulk_load(elements: Vec<T>) -> Self {
         Self::bulk_load_with_params(elements)
     }
+
+    /// Creates a new r-tree from elements that are already sorted, e.g. along a space
+    /// filling curve.
+    ///
+    /// Unlike [bulk_load](#method.bulk_load), this method does not sort or partition the
+    /// elements. Consecutive elements are grouped into nodes of similar size, consecutive
+    /// nodes into their parents. The resulting nodes respect the minimum and maximum node
+    /// sizes of the tree's [parameters](trait.RTreeParams.html).
+    ///
+    /// The tree's quality depends on the given order: Elements that are close in the input
+    /// should also be close in space. An arbitrary order results in a valid tree that is slow
+    /// to query.
+    ///
+    /// # Runtime
+    /// Bulk loading presorted elements runs in `O(n)`.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// // Rows of points, sorted by row
+    /// let points: Vec<_> = (0..1000).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+    /// let tree = RTree::bulk_load_presorted(points);
+    /// assert_eq!(tree.size(), 1000);
+    /// assert_eq!(tree.nearest_neighbor(&[5.2, 50.1]), Some(&[5.0, 50.0]));
+    /// ```
+    pub fn bulk_load_presorted(elements: Vec<T>) -> Self {
+        Self::bulk_load_presorted_with_params(elements)
+    }
+}
+
+impl<T, P> RTree<T>
+where
+    T: RTreeObject<Envelope = AABB<P>>,
+    P: Point,
+{
+    /// Creates a new r-tree with the Priority R-tree (PR-tree) bulk loading algorithm.
+    ///
+    /// Unlike [bulk_load](#method.bulk_load), which partitions elements by their centers,
+    /// the PR-tree algorithm also considers their extent: Each level first groups the elements
+    /// reaching farthest in each direction. This guarantees a good worst-case performance of
+    /// window queries, see Arge et al., "The Priority R-Tree: A Practically Efficient and Worst-Case
+    /// Optimal R-Tree". It pays off for data with very different extents, like long skinny
+    /// rectangles, while `bulk_load` usually results in faster queries for points.
+    ///
+    /// # Runtime
+    /// Bulk loading runs in `O(n * log(n))`, where `n` is the number of loaded
+    /// elements.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::primitives::Rectangle;
+    /// use rstar::{RTree, AABB};
+    ///
+    /// let roads: Vec<_> = (0..100)
+    ///     .map(|i| Rectangle::from_corners([0.0, i as f64], [1000.0, i as f64 + 0.1]))
+    ///     .collect();
+    /// let tree = RTree::bulk_load_priority(roads);
+    /// let query = AABB::from_corners([500.0, 10.0], [510.0, 12.0]);
+    /// assert_eq!(tree.locate_in_envelope_intersecting(&query).count(), 3);
+    /// ```
+    pub fn bulk_load_priority(elements: Vec<T>) -> Self {
+        Self::bulk_load_priority_with_params(elements)
+    }
 }
 
 impl<T, Params> RTree<T, Params>
@@ -220,20 +390,56 @@ where
     pub fn new_with_params() -> Self {
         verify_parameters::<T, Params>();
         RTree {
-            root: ParentNode::new_root::<Params>(),
+            metadata: Default::default(),
+            root: ParentNode::new_empty(),
             size: 0,
+            insertion_metrics: Default::default(),
+            adaptive_reinsertion: false,
+            reinsertion_policy: Default::default(),
+            spare_children: Vec::new(),
             _params: Default::default(),
         }
     }
 
     /// Creates a new r-tree with some given elements and configurable parameters.
     ///
+    /// The elements are packed into nodes of at most `Params::MAX_SIZE` elements, the same
+    /// way [bulk_load](#method.bulk_load) packs them for the default parameters.
     /// For more information refer to [bulk_load](#method.bulk_load)
-    /// and [RTreeParameters](traits.RTreeParameters.html).
+    /// and [RTreeParams](trait.RTreeParams.html).
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, RTreeParams, RStarInsertionStrategy};
+    ///
+    /// struct SmallNodeParams;
+    ///
+    /// impl RTreeParams for SmallNodeParams {
+    ///     const MIN_SIZE: usize = 2;
+    ///     const MAX_SIZE: usize = 4;
+    ///     const REINSERTION_COUNT: usize = 1;
+    ///     type DefaultInsertionStrategy = RStarInsertionStrategy;
+    /// }
+    ///
+    /// let points: Vec<_> = (0..100).map(|i| [i as f64, 0.0]).collect();
+    /// let mut tree: RTree<_, SmallNodeParams> = RTree::bulk_load_with_params(points.clone());
+    /// // Smaller nodes result in a higher tree
+    /// assert!(tree.height() > RTree::bulk_load(points).height());
+    /// tree.insert([100.0, 0.0]);
+    /// assert_eq!(tree.size(), 101);
+    /// ```
     pub fn bulk_load_with_params(elements: Vec<T>) -> Self {
         Self::new_from_bulk_loading(elements, bulk_load::bulk_load_sequential::<_, Params>)
     }
 
+    /// Creates a new r-tree from presorted elements and configurable parameters.
+    ///
+    /// For more information refer to [bulk_load_presorted](#method.bulk_load_presorted)
+    /// and [RTreeParams](trait.RTreeParams.html).
+    pub fn bulk_load_presorted_with_params(elements: Vec<T>) -> Self {
+        Self::new_from_bulk_loading(elements, bulk_load::bulk_load_presorted::<_, Params>)
+    }
+
     /// Returns the number of objects in an r-tree.
     ///
     /// # Example
@@ -251,6 +457,248 @@ where
         self.size
     }
 
+    /// Removes all elements from the tree.
+    ///
+    /// The root node keeps its allocated capacity, and the allocations of all other nodes
+    /// are kept for reuse by later insertions, just like the nodes allocated by
+    /// [reserve](#method.reserve). This makes refilling a cleared tree cheaper than creating
+    /// a new one.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let mut tree = RTree::bulk_load(vec![[0, 0], [1, 1]]);
+    /// tree.clear();
+    /// assert_eq!(tree.size(), 0);
+    /// assert!(!tree.contains(&[0, 0]));
+    /// ```
+    pub fn clear(&mut self) {
+        let mut nodes = Vec::new();
+        for child in self.root.children.drain(..) {
+            if let RTreeNode::Parent(node) = child {
+                nodes.push(node);
+            }
+        }
+        while let Some(node) = nodes.pop() {
+            let mut children = node.children;
+            for child in children.drain(..) {
+                if let RTreeNode::Parent(node) = child {
+                    nodes.push(node);
+                }
+            }
+            self.spare_children.push(children);
+        }
+        self.root.envelope = Envelope::new_empty();
+        self.size = 0;
+    }
+
+    /// Allocates the nodes that about `additional` insertions are expected to create.
+    ///
+    /// Each node stores its children in a separate allocation. This method allocates the
+    /// children of the nodes that inserting `additional` elements is expected to create up
+    /// front, each with room for `MAX_SIZE + 1` children. Node splits take these allocations
+    /// instead of allocating. The number of nodes is estimated from the tree's
+    /// [parameters](trait.RTreeParams.html).
+    ///
+    /// Nodes that are dissolved by removals are kept for reuse as well instead of being
+    /// deallocated, up to the number of reserved nodes. This avoids allocation churn when a
+    /// tree is modified by many removals and insertions.
+    ///
+    /// Kept nodes are not shared by clones of the tree and are dropped on serialization.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// // The following insertions take their nodes from the reserved ones
+    /// let mut tree = RTree::with_capacity(1000);
+    /// for i in 0..1000 {
+    ///     tree.insert([i as f64, 0.0]);
+    /// }
+    /// // The nodes dissolved by these removals are reused by the following insertions
+    /// tree.retain(|p| p[0] < 500.0);
+    /// for i in 0..500 {
+    ///     tree.insert([i as f64, 1.0]);
+    /// }
+    /// assert_eq!(tree.size(), 1000);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        let average_node_size = (Params::MIN_SIZE + Params::MAX_SIZE) / 2;
+        // Leaf nodes plus a geometric series of their ancestors
+        let nodes = additional / average_node_size * average_node_size / (average_node_size - 1);
+        self.spare_children.reserve(nodes);
+        self.spare_children
+            .extend((0..nodes).map(|_| Vec::with_capacity(Params::MAX_SIZE + 1)));
+        if additional > 0 {
+            // The root of a new tree is not allocated yet
+            let root_capacity = Params::MAX_SIZE + 1;
+            let len = self.root.children.len();
+            self.root
+                .children
+                .reserve_exact(root_capacity.saturating_sub(len));
+        }
+    }
+
+    /// Shrinks the capacity of all nodes as much as possible.
+    ///
+    /// Insertions and removals leave unused capacity in the nodes, and nodes that are
+    /// filled up and emptied again keep it. Long-lived trees can call this method to release
+    /// memory after their size has decreased. Nodes kept for reuse due to
+    /// [reserve](#method.reserve) are freed as well.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let mut tree = RTree::with_capacity(1000);
+    /// for i in 0..1000 {
+    ///     tree.insert([i as f64, 0.0]);
+    /// }
+    /// tree.retain(|p| p[0] < 10.0);
+    /// tree.shrink_to_fit();
+    /// assert_eq!(tree.size(), 10);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.root.shrink_to_fit();
+        self.spare_children = Vec::new();
+    }
+
+    /// Repacks all elements of the tree with the [bulk loading](#method.bulk_load) algorithm.
+    ///
+    /// Many insertions and removals can degrade the tree's structure over time and slow down
+    /// queries. Long-lived trees can call this method periodically to restore the query
+    /// performance of a freshly bulk loaded tree. The tree's metadata and insertion
+    /// metrics are kept.
+    ///
+    /// # Runtime
+    /// Rebalancing runs in `O(n * log(n))`.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let mut tree = RTree::new();
+    /// for i in 0..1000 {
+    ///     tree.insert([i as f64, (i % 7) as f64]);
+    /// }
+    /// tree.rebalance();
+    /// assert_eq!(tree.size(), 1000);
+    /// assert!(tree.contains(&[500.0, 3.0]));
+    /// ```
+    pub fn rebalance(&mut self) {
+        self.rebuild_with(Vec::new());
+    }
+
+    /// Bulk loads the tree's elements together with additional elements.
+    fn rebuild_with(&mut self, mut elements: Vec<T>) {
+        elements.reserve(self.size);
+        let root = ::std::mem::replace(&mut self.root, ParentNode::new_empty());
+        removal::collect_leaves(root, &mut elements, &mut self.spare_children);
+        self.size = elements.len();
+        if !elements.is_empty() {
+            self.root = bulk_load::bulk_load_sequential::<_, Params>(elements);
+        }
+    }
+
+    /// Returns the number of levels of this tree.
+    ///
+    /// The leaves of an r-tree are all on the same level. A tree whose elements are all
+    /// contained in its root node has a height of `1`, this includes the empty tree.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let mut tree = RTree::new();
+    /// assert_eq!(tree.height(), 1);
+    /// for x in 0..100 {
+    ///     tree.insert([x, x]);
+    /// }
+    /// assert!(tree.height() > 1);
+    /// ```
+    pub fn height(&self) -> usize {
+        let mut height = 1;
+        let mut node = &self.root;
+        while let Some(RTreeNode::Parent(ref child)) = node.children.first() {
+            height += 1;
+            node = child;
+        }
+        height
+    }
+
+    /// Returns the tree's metadata.
+    ///
+    /// See [RTreeMetadata](struct.RTreeMetadata.html) for more information.
+    pub fn metadata(&self) -> RTreeMetadata {
+        self.metadata.refreshed::<T, Params>(self.size)
+    }
+
+    /// Sets the build timestamp that is stored as part of the tree's metadata.
+    ///
+    /// The timestamp's unit and epoch are up to the caller, e.g. seconds since the unix epoch.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let mut tree = RTree::bulk_load(vec![[0.0, 1.0], [2.0, 3.0]]);
+    /// assert_eq!(tree.metadata().build_timestamp(), None);
+    /// tree.set_build_timestamp(Some(1_577_836_800));
+    /// assert_eq!(tree.metadata().build_timestamp(), Some(1_577_836_800));
+    /// ```
+    pub fn set_build_timestamp(&mut self, build_timestamp: Option<u64>) {
+        self.metadata.set_build_timestamp(build_timestamp);
+    }
+
+    /// Returns the user defined key-value pairs that are stored as part of the tree's metadata.
+    pub fn user_metadata_mut(&mut self) -> &mut BTreeMap<String, String> {
+        self.metadata.user_data_mut()
+    }
+
+    /// Returns the counters collected during insertions into this tree.
+    ///
+    /// See [InsertionMetrics](struct.InsertionMetrics.html) for more information.
+    pub fn insertion_metrics(&self) -> &InsertionMetrics {
+        &self.insertion_metrics
+    }
+
+    /// Enables or disables adaptive reinsertion.
+    ///
+    /// The r*-heuristic reinserts some children of an overflown node instead of splitting it
+    /// right away. For some data distributions, most reinserted children end up in their
+    /// original node, making reinsertion a waste of time. If adaptive reinsertion is enabled,
+    /// the tree [keeps track](struct.InsertionMetrics.html#method.relocated_reinsertions) of
+    /// this and only attempts reinsertion occasionally while it is ineffective.
+    ///
+    /// Adaptive reinsertion is disabled by default. The setting is not serialized.
+    pub fn set_adaptive_reinsertion(&mut self, enabled: bool) {
+        self.adaptive_reinsertion = enabled;
+    }
+
+    /// Returns whether [adaptive reinsertion](#method.set_adaptive_reinsertion) is enabled.
+    pub fn adaptive_reinsertion(&self) -> bool {
+        self.adaptive_reinsertion
+    }
+
+    /// Sets which children an overflowing node reinserts, see
+    /// [ReinsertionPolicy](struct.ReinsertionPolicy.html).
+    ///
+    /// Only the r*-insertion strategy reinserts children. The policy is not serialized.
+    pub fn set_reinsertion_policy(&mut self, policy: ReinsertionPolicy) {
+        self.reinsertion_policy = policy;
+    }
+
+    /// Returns the tree's [reinsertion policy](#method.set_reinsertion_policy).
+    pub fn reinsertion_policy(&self) -> ReinsertionPolicy {
+        self.reinsertion_policy
+    }
+
+    /// Resets all insertion counters to zero.
+    pub fn reset_insertion_metrics(&mut self) {
+        self.insertion_metrics = Default::default();
+    }
+
     /// Returns an iterator over all elements contained in the tree.
     ///
     /// The order in which the elements are returned is not specified.
@@ -264,7 +712,7 @@ where
     /// }
     /// ```
     pub fn iter(&self) -> RTreeIterator<T> {
-        RTreeIterator::new(&self.root, SelectAllFunc)
+        RTreeIterator::new(&self.root, self.size)
     }
 
     /// Returns an iterator over all mutable elements contained in the tree.
@@ -278,7 +726,57 @@ where
     /// and reinsert it.
     ///
     pub fn iter_mut(&mut self) -> RTreeIteratorMut<T> {
-        RTreeIteratorMut::new(&mut self.root, SelectAllFunc)
+        RTreeIteratorMut::new(&mut self.root, self.size)
+    }
+
+    /// Returns the envelopes of all nodes and elements together with their depth.
+    ///
+    /// The root has a depth of zero, its children a depth of one and so on. The envelopes are
+    /// returned in depth first order, a node's envelope is always returned before the
+    /// envelopes of its children. An empty tree returns no envelopes. This allows to draw the
+    /// tree's hierarchy of bounding boxes, e.g. for debugging.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let tree = RTree::bulk_load((0..100).map(|i| [i as f64, 0.0]).collect());
+    /// let leaf_count = tree
+    ///     .iter_envelopes()
+    ///     .filter(|(depth, _)| *depth == tree.height())
+    ///     .count();
+    /// assert_eq!(leaf_count, 100);
+    /// ```
+    pub fn iter_envelopes(&self) -> EnvelopeIterator<'_, T> {
+        EnvelopeIterator::new(&self.root)
+    }
+
+    /// Returns the elements grouped by the node they are stored in.
+    ///
+    /// Each item consists of a node's envelope and an iterator over the elements stored in
+    /// that node. Each group contains at most `Params::MAX_SIZE` elements which are close to
+    /// each other. This allows to exploit the tree's clustering, e.g. to batch draw calls or
+    /// to cache data per group. Elements are stored as [RTreeNode](enum.RTreeNode.html)s,
+    /// hence groups are iterators rather than slices.
+    ///
+    /// The order of the groups is not specified.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{Envelope, RTree};
+    ///
+    /// let tree = RTree::bulk_load((0..100).map(|i| [i as f64, 0.0]).collect());
+    /// let mut count = 0;
+    /// for (envelope, group) in tree.leaf_groups() {
+    ///     for point in group {
+    ///         assert!(envelope.contains_point(point));
+    ///         count += 1;
+    ///     }
+    /// }
+    /// assert_eq!(count, 100);
+    /// ```
+    pub fn leaf_groups(&self) -> LeafGroupIterator<'_, T> {
+        LeafGroupIterator::new(&self.root)
     }
 
     /// Returns all elements contained in an [Envelope](trait.Envelope.html).
@@ -303,11 +801,19 @@ where
     /// ```
     pub fn locate_in_envelope(&self, envelope: &T::Envelope) -> LocateInEnvelope<T> {
         LocateInEnvelope::new(&self.root, SelectInEnvelopeFunction::new(*envelope))
+            .with_size(self.size)
+    }
+
+    /// Variant of [locate_in_envelope](#method.locate_in_envelope) that also returns the
+    /// query's [statistics](struct.QueryStats.html).
+    pub fn locate_in_envelope_with_stats(&self, envelope: &T::Envelope) -> (Vec<&T>, QueryStats) {
+        self.locate_with_selection_function_with_stats(SelectInEnvelopeFunction::new(*envelope))
     }
 
     /// Mutable variant of [locate_in_envelope](#method.locate_in_envelope).
     pub fn locate_in_envelope_mut(&mut self, envelope: &T::Envelope) -> LocateInEnvelopeMut<T> {
         LocateInEnvelopeMut::new(&mut self.root, SelectInEnvelopeFunction::new(*envelope))
+            .with_size(self.size)
     }
 
     /// Returns all elements whose envelope intersects a given envelope.
@@ -352,6 +858,18 @@ where
             &self.root,
             SelectInEnvelopeFuncIntersecting::new(*envelope),
         )
+        .with_size(self.size)
+    }
+
+    /// Variant of [locate_in_envelope_intersecting](#method.locate_in_envelope_intersecting)
+    /// that also returns the query's [statistics](struct.QueryStats.html).
+    pub fn locate_in_envelope_intersecting_with_stats(
+        &self,
+        envelope: &T::Envelope,
+    ) -> (Vec<&T>, QueryStats) {
+        self.locate_with_selection_function_with_stats(SelectInEnvelopeFuncIntersecting::new(
+            *envelope,
+        ))
     }
 
     /// Mutable variant of [locate_in_envelope_intersecting](#method.locate_in_envelope_intersecting)
@@ -363,6 +881,97 @@ where
             &mut self.root,
             SelectInEnvelopeFuncIntersecting::new(*envelope),
         )
+        .with_size(self.size)
+    }
+
+    /// Visits all nodes that intersect an envelope without returning any elements.
+    ///
+    /// Queries within the envelope will then find the nodes in the CPU caches. Latency
+    /// critical applications can use this to warm up caches for an area that is likely to be
+    /// queried next. The elements themselves are not accessed.
+    ///
+    /// Returns the number of visited nodes.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, AABB};
+    ///
+    /// let tree = RTree::bulk_load((0..1000).map(|i| [i as f64, 0.0]).collect());
+    /// let upcoming_area = AABB::from_corners([100.0, -1.0], [120.0, 1.0]);
+    /// let visited = tree.prefetch_in_envelope(&upcoming_area);
+    /// assert!(visited > 0 && visited < 50);
+    /// ```
+    pub fn prefetch_in_envelope(&self, envelope: &T::Envelope) -> usize {
+        let mut visited = 0;
+        let mut todo = vec![&self.root];
+        while let Some(node) = todo.pop() {
+            visited += 1;
+            for child in &node.children {
+                if let RTreeNode::Parent(ref data) = child {
+                    if data.envelope.intersects(envelope) {
+                        todo.push(data);
+                    }
+                }
+            }
+        }
+        visited
+    }
+
+    /// Returns all elements contained in any of several envelopes.
+    ///
+    /// This answers many [locate_in_envelope](#method.locate_in_envelope) queries in a single
+    /// traversal, only carrying those envelopes down the tree that intersect the current node.
+    /// Each result is tagged with the index of the envelope containing it. An element contained
+    /// in multiple envelopes is returned once for each of them.
+    ///
+    /// The order in which the elements are returned is not specified.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, AABB};
+    /// let tree = RTree::bulk_load(vec![[0.5, 0.5], [1.5, 0.5]]);
+    /// let tiles = [
+    ///     AABB::from_corners([0.0, 0.0], [1.0, 1.0]),
+    ///     AABB::from_corners([1.0, 0.0], [2.0, 1.0]),
+    /// ];
+    /// let mut located: Vec<_> = tree.locate_in_envelopes(&tiles).collect();
+    /// located.sort_by_key(|(tile_index, _)| *tile_index);
+    /// assert_eq!(located, vec![(0, &[0.5, 0.5]), (1, &[1.5, 0.5])]);
+    /// ```
+    pub fn locate_in_envelopes(&self, envelopes: &[T::Envelope]) -> LocateInEnvelopes<'_, T> {
+        LocateInEnvelopes::new(&self.root, envelopes.to_vec())
+    }
+
+    /// Returns all elements whose envelope intersects a given envelope but none of the
+    /// `excluded` envelopes.
+    ///
+    /// This is equivalent to filtering the result of
+    /// [locate_in_envelope_intersecting](#method.locate_in_envelope_intersecting) but
+    /// skips any parent node that is fully contained in an excluded envelope during the search.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, AABB};
+    ///
+    /// let tree = RTree::bulk_load(vec![[0.5, 0.5], [1.5, 0.5], [2.5, 0.5]]);
+    /// let viewport = AABB::from_corners([0.0, 0.0], [3.0, 1.0]);
+    /// let already_loaded = AABB::from_corners([1.0, 0.0], [2.0, 1.0]);
+    /// let mut remaining: Vec<_> = tree
+    ///     .locate_in_envelope_intersecting_excluding(&viewport, &[already_loaded])
+    ///     .collect();
+    /// remaining.sort_by(|l, r| l.partial_cmp(r).unwrap());
+    /// assert_eq!(remaining, vec![&[0.5, 0.5], &[2.5, 0.5]]);
+    /// ```
+    pub fn locate_in_envelope_intersecting_excluding(
+        &self,
+        envelope: &T::Envelope,
+        excluded: &[T::Envelope],
+    ) -> LocateInEnvelopeIntersectingExcluding<'_, T> {
+        LocateInEnvelopeIntersectingExcluding::new(
+            &self.root,
+            SelectInEnvelopeExcludingFunction::new(*envelope, excluded.to_vec()),
+        )
+        .with_size(self.size)
     }
 
     /// Locates elements in the r-tree defined by a selection function.
@@ -376,55 +985,224 @@ where
         &self,
         selection_function: S,
     ) -> impl Iterator<Item = &T> {
-        SelectionIterator::new(&self.root, selection_function)
+        SelectionIterator::new(&self.root, selection_function).with_size(self.size)
     }
 
-    /// Mutable variant of [`locate_with_selection_function`](#method.locate_with_selection_function).
-    pub fn locate_with_selection_function_mut<S: SelectionFunction<T>>(
-        &mut self,
+    /// Variant of [locate_with_selection_function](#method.locate_with_selection_function)
+    /// that also returns the query's [statistics](struct.QueryStats.html).
+    ///
+    /// The selected elements are collected, the stats thus always cover the whole query.
+    pub fn locate_with_selection_function_with_stats<S: SelectionFunction<T>>(
+        &self,
         selection_function: S,
-    ) -> impl Iterator<Item = &mut T> {
-        SelectionIteratorMut::new(&mut self.root, selection_function)
+    ) -> (Vec<&T>, QueryStats) {
+        query_stats::locate_with_stats(&self.root, selection_function)
     }
 
-    /// Gets all possible intersecting objects of this and another tree.
+    /// Locates elements and nodes defined by a selection function without descending
+    /// deeper than a given depth.
     ///
-    /// This will return all objects whose _envelopes_ intersect. No geometric intersection
-    /// checking is performed.
-    pub fn intersection_candidates_with_other_tree<'a>(
-        &'a self,
-        other: &'a Self,
-    ) -> IntersectionIterator<T> {
-        IntersectionIterator::new(self.root(), other.root())
+    /// The root's children are at depth `1`, their children at depth `2` and so on. All
+    /// elements are located at depth [`height`](#method.height). Selected elements up to
+    /// `max_depth` are returned as [`RTreeNode::Leaf`](enum.RTreeNode.html). Selected parent
+    /// nodes at exactly `max_depth` are returned as [`RTreeNode::Parent`](enum.RTreeNode.html)
+    /// instead of being unpacked. Their envelope can serve as an approximation of their content,
+    /// e.g. for level of detail rendering. A `max_depth` of `0` returns nothing.
+    pub fn locate_with_selection_function_up_to_depth<S: SelectionFunction<T>>(
+        &self,
+        selection_function: S,
+        max_depth: usize,
+    ) -> impl Iterator<Item = &RTreeNode<T>> {
+        DepthLimitedSelectionIterator::new(&self.root, selection_function, max_depth)
     }
 
-    /// Returns the tree's root node.
+    /// Returns all elements and nodes intersecting an envelope without descending deeper
+    /// than a given depth.
     ///
-    /// Usually, you will not require to call this method. However, for debugging purposes or for
-    /// advanced algorithms, knowledge about the tree's internal structure may be required.
-    /// For these cases, this method serves as an entry point.
-    pub fn root(&self) -> &ParentNode<T> {
-        &self.root
-    }
-
-    pub(crate) fn root_mut(&mut self) -> &mut ParentNode<T> {
-        &mut self.root
-    }
-
-    fn new_from_bulk_loading(
-        elements: Vec<T>,
-        root_loader: impl Fn(Vec<T>) -> ParentNode<T>,
+    /// Refer to [`locate_with_selection_function_up_to_depth`](#method.locate_with_selection_function_up_to_depth)
+    /// for more information.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, RTreeNode, AABB};
+    ///
+    /// let points: Vec<_> = (0..100).map(|x| [x as f64, 0.0]).collect();
+    /// let tree = RTree::bulk_load(points);
+    /// let region = AABB::from_corners([0.0, -1.0], [50.0, 1.0]);
+    /// let coarse: Vec<_> = tree.locate_in_envelope_intersecting_up_to_depth(&region, 1).collect();
+    /// assert!(coarse.iter().all(|node| !node.is_leaf()));
+    /// let exact = tree.locate_in_envelope_intersecting_up_to_depth(&region, tree.height());
+    /// assert_eq!(exact.filter(|node| node.is_leaf()).count(), 51);
+    /// ```
+    pub fn locate_in_envelope_intersecting_up_to_depth(
+        &self,
+        envelope: &T::Envelope,
+        max_depth: usize,
+    ) -> impl Iterator<Item = &RTreeNode<T>> + Clone {
+        DepthLimitedSelectionIterator::new(
+            &self.root,
+            SelectInEnvelopeFuncIntersecting::new(*envelope),
+            max_depth,
+        )
+    }
+
+    /// Returns the elements intersecting an envelope, aggregating dense regions into clusters.
+    ///
+    /// The result contains at most `budget` items. Sparse regions are represented by their
+    /// individual elements while dense regions are summarized by the envelope and element count
+    /// of internal tree nodes, see [ClusteredItem](enum.ClusteredItem.html). Clusters closer to
+    /// the root are split up first. Clusters may contain elements that do not intersect
+    /// `envelope`.
+    ///
+    /// Only the returned clusters are counted, which visits their nodes down to the parents of
+    /// the leaves but not the elements themselves.
+    ///
+    /// The budget is exceeded only if the root already has more intersecting children.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{ClusteredItem, RTree, AABB};
+    ///
+    /// let tree = RTree::bulk_load((0..1000).map(|x| [x as f64, 0.0]).collect());
+    /// let window = AABB::from_corners([0.0, -1.0], [999.0, 1.0]);
+    /// let items = tree.locate_in_envelope_clustered(&window, 20);
+    /// assert!(items.len() <= 20);
+    /// let total: usize = items
+    ///     .iter()
+    ///     .map(|item| match item {
+    ///         ClusteredItem::Element(_) => 1,
+    ///         ClusteredItem::Cluster { size, .. } => *size,
+    ///     })
+    ///     .sum();
+    /// assert_eq!(total, 1000);
+    /// ```
+    pub fn locate_in_envelope_clustered(
+        &self,
+        envelope: &T::Envelope,
+        budget: usize,
+    ) -> Vec<ClusteredItem<'_, T>> {
+        clustering::locate_in_envelope_clustered(&self.root, envelope, budget)
+    }
+
+    /// Creates a sampler that draws elements with a probability proportional to their weight.
+    ///
+    /// `weight` is called once for every element. Returns `None` if a weight is negative, NaN
+    /// or infinite, or if the sum of all weights is zero or infinite, e.g. for an empty tree.
+    /// See [WeightedSampler](struct.WeightedSampler.html) for more information.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let tree = RTree::bulk_load(vec![[0.0, 0.0], [1.0, 0.0]]);
+    /// assert!(tree.weighted_sampler(|point| point[0]).is_some());
+    /// assert!(tree.weighted_sampler(|point| point[0] - 1.0).is_none());
+    /// assert!(tree.weighted_sampler(|_| 0.0).is_none());
+    /// ```
+    pub fn weighted_sampler<F>(&self, weight: F) -> Option<WeightedSampler<'_, T>>
+    where
+        F: FnMut(&T) -> f64,
+    {
+        WeightedSampler::new(&self.root, weight)
+    }
+
+    /// Mutable variant of [`locate_with_selection_function`](#method.locate_with_selection_function).
+    pub fn locate_with_selection_function_mut<S: SelectionFunction<T>>(
+        &mut self,
+        selection_function: S,
+    ) -> impl Iterator<Item = &mut T> {
+        SelectionIteratorMut::new(&mut self.root, selection_function).with_size(self.size)
+    }
+
+    /// Gets all possible intersecting objects of this and another tree.
+    ///
+    /// This will return all objects whose _envelopes_ intersect. No geometric intersection
+    /// checking is performed.
+    pub fn intersection_candidates_with_other_tree<'a>(
+        &'a self,
+        other: &'a Self,
+    ) -> IntersectionIterator<T> {
+        IntersectionIterator::new(self.root(), other.root())
+    }
+
+    /// Returns the tree's root node.
+    ///
+    /// Usually, you will not require to call this method. However, for debugging purposes or for
+    /// advanced algorithms, knowledge about the tree's internal structure may be required.
+    /// For these cases, this method serves as an entry point.
+    pub fn root(&self) -> &ParentNode<T> {
+        &self.root
+    }
+
+    pub(crate) fn root_mut(&mut self) -> &mut ParentNode<T> {
+        &mut self.root
+    }
+
+    /// Creates a tree from an already built root node that contains `size` elements.
+    pub(crate) fn from_root(root: ParentNode<T>, size: usize) -> Self {
+        RTree {
+            root,
+            size,
+            ..Self::new_with_params()
+        }
+    }
+
+    /// Converts this tree into a read-only tree stored in flat arrays.
+    ///
+    /// The nodes are laid out in breadth first order, which makes queries on large trees touch
+    /// fewer cache lines. The tree's structure is kept. Use
+    /// [StaticRTree::thaw](struct.StaticRTree.html#method.thaw) to convert it back into a
+    /// mutable tree.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let tree = RTree::bulk_load(vec![[0.0, 0.0], [1.0, 1.0]]);
+    /// let frozen = tree.freeze();
+    /// assert_eq!(frozen.nearest_neighbor(&[0.9, 0.9]), Some(&[1.0, 1.0]));
+    ///
+    /// let mut tree = frozen.thaw();
+    /// tree.insert([2.0, 2.0]);
+    /// assert_eq!(tree.size(), 3);
+    /// ```
+    pub fn freeze(self) -> StaticRTree<T, Params> {
+        self.into()
+    }
+
+    pub(crate) fn insertion_state_mut(
+        &mut self,
+    ) -> (
+        &mut ParentNode<T>,
+        &mut InsertionMetrics,
+        &mut Vec<Vec<RTreeNode<T>>>,
+    ) {
+        (
+            &mut self.root,
+            &mut self.insertion_metrics,
+            &mut self.spare_children,
+        )
+    }
+
+    fn new_from_bulk_loading(
+        elements: Vec<T>,
+        root_loader: impl Fn(Vec<T>) -> ParentNode<T>,
     ) -> Self {
         verify_parameters::<T, Params>();
         let size = elements.len();
         let root = if size == 0 {
-            ParentNode::new_root::<Params>()
+            ParentNode::new_empty()
         } else {
             root_loader(elements)
         };
         RTree {
+            metadata: Default::default(),
             root,
             size,
+            insertion_metrics: Default::default(),
+            adaptive_reinsertion: false,
+            reinsertion_policy: Default::default(),
+            spare_children: Vec::new(),
             _params: Default::default(),
         }
     }
@@ -432,18 +1210,295 @@ where
     /// Removes and returns a single element from the tree. The element to remove is specified
     /// by a [`SelectionFunction`](trait.SelectionFunction.html).
     ///
+    /// Nodes that contain less than `Params::MIN_SIZE` children after the removal are
+    /// dissolved and their elements are reinserted into the tree.
+    ///
+    /// Neither `PartialEq` nor the exact envelope of the element is required. This allows
+    /// to remove, for example, the first element within an area that also fulfills some
+    /// condition on its payload.
+    ///
     /// See also: [`remove`](#method.remove), [`remove_at_point`](#method.remove_at_point)
     ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, RTreeObject, SelectionFunction, Envelope, AABB};
+    /// use rstar::primitives::PointWithData;
+    ///
+    /// type Shop = PointWithData<&'static str, [f64; 2]>;
+    ///
+    /// struct ClosedShopInArea(AABB<[f64; 2]>);
+    ///
+    /// impl SelectionFunction<Shop> for ClosedShopInArea {
+    ///     fn should_unpack_parent(&self, envelope: &AABB<[f64; 2]>) -> bool {
+    ///         self.0.intersects(envelope)
+    ///     }
+    ///
+    ///     fn should_unpack_leaf(&self, shop: &Shop) -> bool {
+    ///         shop.data.starts_with("closed") && self.0.contains_envelope(&shop.envelope())
+    ///     }
+    /// }
+    ///
+    /// let mut tree = RTree::bulk_load(vec![
+    ///     Shop::new("open bakery", [0.0, 0.0]),
+    ///     Shop::new("closed butcher", [1.0, 1.0]),
+    ///     Shop::new("closed florist", [5.0, 5.0]),
+    /// ]);
+    /// let area = AABB::from_corners([-1.0, -1.0], [2.0, 2.0]);
+    ///
+    /// let removed = tree.remove_with_selection_function(ClosedShopInArea(area));
+    /// assert_eq!(removed.unwrap().data, "closed butcher");
+    /// assert!(tree.remove_with_selection_function(ClosedShopInArea(area)).is_none());
+    /// assert_eq!(tree.size(), 2);
+    /// ```
     pub fn remove_with_selection_function<F>(&mut self, function: F) -> Option<T>
     where
         F: SelectionFunction<T>,
     {
-        let result = removal::remove::<_, Params, _>(&mut self.root, &function);
+        let mut orphans = Vec::new();
+        let result = removal::remove::<_, Params, _>(
+            &mut self.root,
+            &function,
+            &mut orphans,
+            &mut self.spare_children,
+        );
         if result.is_some() {
             self.size -= 1;
+            self.condense(orphans);
+        }
+        result
+    }
+
+    /// Modifies a single element in place. The element to update is specified by a
+    /// [`SelectionFunction`](trait.SelectionFunction.html).
+    ///
+    /// `update` may change the element's envelope, e.g. to move it to a new position. As long
+    /// as the new envelope is still contained in the envelope of the element's parent node,
+    /// the element is not moved within the tree. Only if it has left its parent node, it is
+    /// removed and inserted again. For small movements, this is considerably faster than
+    /// removing and inserting the element manually.
+    ///
+    /// Returns `false` if no element was selected. `update` is not called in this case.
+    ///
+    /// See also: [`update_at_point`](#method.update_at_point)
+    pub fn update_with_selection_function<F, U>(&mut self, function: F, update: U) -> bool
+    where
+        F: SelectionFunction<T>,
+        U: FnOnce(&mut T),
+    {
+        let mut orphans = Vec::new();
+        let result = removal::update::<_, Params, _, _>(
+            &mut self.root,
+            &function,
+            &mut Some(update),
+            &mut orphans,
+            &mut self.spare_children,
+        );
+        match result {
+            removal::UpdateResult::NotFound => false,
+            removal::UpdateResult::Updated => true,
+            removal::UpdateResult::Escaped(t) => {
+                self.size -= 1;
+                self.condense(orphans);
+                self.insert(t);
+                true
+            }
+        }
+    }
+
+    /// Removes all elements selected by a [`SelectionFunction`](trait.SelectionFunction.html)
+    /// and returns them as an iterator.
+    ///
+    /// The tree is traversed only once. All selected elements are removed immediately,
+    /// regardless of whether the returned iterator is consumed.
+    ///
+    /// See also: [`drain_in_envelope`](#method.drain_in_envelope),
+    /// [`drain_within_distance`](#method.drain_within_distance)
+    pub fn drain_with_selection_function<F>(&mut self, function: F) -> ::std::vec::IntoIter<T>
+    where
+        F: SelectionFunction<T>,
+    {
+        let mut drained = Vec::new();
+        let mut orphans = Vec::new();
+        removal::drain::<_, Params, _>(
+            &mut self.root,
+            &function,
+            &mut drained,
+            &mut orphans,
+            &mut self.spare_children,
+        );
+        if !drained.is_empty() {
+            self.size -= drained.len();
+            self.condense(orphans);
+        }
+        drained.into_iter()
+    }
+
+    /// Removes all elements contained in an envelope and returns them as an iterator.
+    ///
+    /// The elements are selected just like [`locate_in_envelope`](#method.locate_in_envelope)
+    /// would select them. All of them are removed immediately, regardless of whether the
+    /// returned iterator is consumed.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, AABB};
+    /// let mut tree = RTree::bulk_load(vec![
+    ///   [0.0, 0.0],
+    ///   [0.0, 1.0],
+    ///   [2.0, 2.0]
+    /// ]);
+    /// let unit_square = AABB::from_corners([0.0, 0.0], [1.0, 1.0]);
+    /// let mut drained: Vec<_> = tree.drain_in_envelope(&unit_square).collect();
+    /// drained.sort_by(|l, r| l.partial_cmp(r).unwrap());
+    /// assert_eq!(drained, vec![[0.0, 0.0], [0.0, 1.0]]);
+    /// assert_eq!(tree.size(), 1);
+    /// ```
+    pub fn drain_in_envelope(&mut self, envelope: &T::Envelope) -> ::std::vec::IntoIter<T> {
+        self.drain_with_selection_function(SelectInEnvelopeFunction::new(*envelope))
+    }
+
+    /// Removes all elements contained in an envelope and returns them as a new tree.
+    ///
+    /// The elements are selected just like [`locate_in_envelope`](#method.locate_in_envelope)
+    /// would select them. Subtrees that are fully contained in the envelope are detached as they
+    /// are, without looking at their elements, and become the nodes of the new tree. Only the
+    /// remaining elements are inserted one by one. If no subtree is fully contained, the new
+    /// tree is [bulk loaded](#method.bulk_load_with_params) from the removed elements.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, AABB};
+    ///
+    /// let mut tree = RTree::bulk_load((0..100).map(|i| [i as f64, 0.0]).collect());
+    /// let west = tree.split_off_in_envelope(&AABB::from_corners([0.0, -1.0], [49.0, 1.0]));
+    /// assert_eq!(west.size(), 50);
+    /// assert_eq!(tree.size(), 50);
+    /// assert!(west.contains(&[10.0, 0.0]));
+    /// assert!(!tree.contains(&[10.0, 0.0]));
+    /// ```
+    pub fn split_off_in_envelope(&mut self, envelope: &T::Envelope) -> Self {
+        let mut split = Vec::new();
+        let mut subtrees = Vec::new();
+        let mut orphans = Vec::new();
+        let height = self.height();
+        removal::split_off_in_envelope::<_, Params>(
+            &mut self.root,
+            envelope,
+            height,
+            &mut split,
+            &mut subtrees,
+            &mut orphans,
+            &mut self.spare_children,
+        );
+        let split_size = split.len()
+            + subtrees
+                .iter()
+                .map(|(subtree, _)| removal::count_leaves(subtree))
+                .sum::<usize>();
+        if split_size != 0 {
+            self.size -= split_size;
+            self.condense(orphans);
+        }
+        // The tallest subtree becomes the new root, all others are attached on their level
+        subtrees.sort_by_key(|&(_, height)| height);
+        let mut result = match subtrees.pop() {
+            Some((root, _)) => Self::from_root(root, 0),
+            None => return Self::bulk_load_with_params(split),
+        };
+        for (subtree, height) in subtrees.into_iter().rev() {
+            rstar::insert_subtree(&mut result, subtree, height);
         }
+        for t in split {
+            Params::DefaultInsertionStrategy::insert(&mut result, t);
+        }
+        result.size = split_size;
         result
     }
+
+    /// Removes all elements contained in an envelope that fulfill a predicate and returns them
+    /// as an iterator.
+    ///
+    /// `predicate` is only called for elements within `envelope`. Like
+    /// [`drain_in_envelope`](#method.drain_in_envelope), the tree is traversed and repaired
+    /// only once.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, AABB};
+    /// let mut tree = RTree::bulk_load(vec![
+    ///   [0.0, 0.0],
+    ///   [0.0, 1.0],
+    ///   [2.0, 2.0]
+    /// ]);
+    /// let unit_square = AABB::from_corners([0.0, 0.0], [1.0, 1.0]);
+    /// let drained: Vec<_> = tree
+    ///     .drain_in_envelope_where(&unit_square, |point| point[1] > 0.5)
+    ///     .collect();
+    /// assert_eq!(drained, vec![[0.0, 1.0]]);
+    /// assert_eq!(tree.size(), 2);
+    /// ```
+    pub fn drain_in_envelope_where<F>(
+        &mut self,
+        envelope: &T::Envelope,
+        predicate: F,
+    ) -> ::std::vec::IntoIter<T>
+    where
+        F: Fn(&T) -> bool,
+    {
+        self.drain_with_selection_function(SelectInEnvelopeWhereFunction::new(*envelope, predicate))
+    }
+
+    /// Retains only the elements specified by a predicate.
+    ///
+    /// All elements for which `f` returns `false` are removed. Unlike repeated calls to
+    /// [`remove`](#method.remove), the tree is traversed only once.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    /// let mut tree = RTree::bulk_load(vec![
+    ///   [0.0, 0.0],
+    ///   [1.0, 1.0],
+    ///   [2.0, 2.0],
+    /// ]);
+    /// tree.retain(|p| p[0] >= 1.0);
+    /// assert_eq!(tree.size(), 2);
+    /// assert!(!tree.contains(&[0.0, 0.0]));
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.retain_mut(|t| f(t))
+    }
+
+    /// Retains only the elements specified by a predicate, passing a mutable reference to it.
+    ///
+    /// The predicate may modify the elements, including their envelopes: The envelopes of
+    /// all nodes are recalculated. However, moving elements far away from their original
+    /// position will degrade the tree's query performance.
+    pub fn retain_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        let mut orphans = Vec::new();
+        let removed = removal::retain::<_, Params, _>(
+            &mut self.root,
+            &mut f,
+            &mut orphans,
+            &mut self.spare_children,
+        );
+        self.size -= removed;
+        self.condense(orphans);
+    }
+
+    fn condense(&mut self, orphans: Vec<T>) {
+        removal::shorten_root(&mut self.root);
+        // Elements of underflown nodes are reinserted, this keeps the tree balanced
+        for orphan in orphans {
+            Params::DefaultInsertionStrategy::insert(self, orphan);
+        }
+    }
 }
 
 impl<T, Params> RTree<T, Params>
@@ -491,7 +1546,16 @@ where
         &self,
         point: &<T::Envelope as Envelope>::Point,
     ) -> LocateAllAtPoint<T> {
-        LocateAllAtPoint::new(&self.root, SelectAtPointFunction::new(*point))
+        LocateAllAtPoint::new(&self.root, SelectAtPointFunction::new(*point)).with_size(self.size)
+    }
+
+    /// Variant of [locate_all_at_point](#method.locate_all_at_point) that also returns the
+    /// query's [statistics](struct.QueryStats.html).
+    pub fn locate_all_at_point_with_stats(
+        &self,
+        point: &<T::Envelope as Envelope>::Point,
+    ) -> (Vec<&T>, QueryStats) {
+        self.locate_with_selection_function_with_stats(SelectAtPointFunction::new(*point))
     }
 
     /// Mutable variant of [locate_at_point_mut](#method.locate_at_point_mut).
@@ -500,6 +1564,7 @@ where
         point: &<T::Envelope as Envelope>::Point,
     ) -> LocateAllAtPointMut<T> {
         LocateAllAtPointMut::new(&mut self.root, SelectAtPointFunction::new(*point))
+            .with_size(self.size)
     }
 
     /// Removes an element containing a given point.
@@ -525,6 +1590,36 @@ where
         let removal_function = SelectAtPointFunction::new(*point);
         self.remove_with_selection_function(removal_function)
     }
+
+    /// Modifies an element containing a given point.
+    ///
+    /// If multiple elements cover the given point, only one of them is updated. Returns
+    /// `false` if no element contains the point.
+    ///
+    /// The element is only reinserted if its new envelope has left the envelope of its parent
+    /// node, see [`update_with_selection_function`](#method.update_with_selection_function).
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let mut tree = RTree::bulk_load(vec![[0.0, 0.0], [1.0, 1.0]]);
+    /// assert!(tree.update_at_point(&[1.0, 1.0], |point| *point = [1.1, 0.9]));
+    /// assert!(!tree.update_at_point(&[1.0, 1.0], |point| *point = [2.0, 2.0]));
+    /// assert!(tree.contains(&[1.1, 0.9]));
+    /// assert_eq!(tree.size(), 2);
+    /// ```
+    pub fn update_at_point<U>(
+        &mut self,
+        point: &<T::Envelope as Envelope>::Point,
+        update: U,
+    ) -> bool
+    where
+        U: FnOnce(&mut T),
+    {
+        let selection_function = SelectAtPointFunction::new(*point);
+        self.update_with_selection_function(selection_function, update)
+    }
 }
 
 impl<T, Params> RTree<T, Params>
@@ -575,57 +1670,436 @@ where
         let removal_function = SelectEqualsFunction::new(t);
         self.remove_with_selection_function(removal_function)
     }
-}
 
-impl<T, Params> RTree<T, Params>
-where
-    Params: RTreeParams,
-    T: PointDistance,
-{
-    /// Returns the nearest neighbor for a given point.
+    /// Inserts a new element into the r-tree unless an equal (`==`) element is already
+    /// contained.
     ///
-    /// The distance is calculated by calling
-    /// [PointDistance::distance_2](traits.PointDistance.html#method.distance_2)
+    /// Returns `Err(t)` if the element was already present. The tree is left unchanged in
+    /// this case.
+    ///
+    /// The equality check is part of the insertion's descent: on every level, the siblings
+    /// of the chosen subtree that contain the element's envelope are searched for an equal
+    /// element before descending further.
+    ///
+    /// This method will only work correctly if two equal elements also have the
+    /// same envelope.
     ///
     /// # Example
     /// ```
     /// use rstar::RTree;
-    /// let tree = RTree::bulk_load(vec![
-    ///   [0.0, 0.0],
-    ///   [0.0, 1.0],
-    /// ]);
-    /// assert_eq!(tree.nearest_neighbor(&[-1., 0.0]), Some(&[0.0, 0.0]));
-    /// assert_eq!(tree.nearest_neighbor(&[0.0, 2.0]), Some(&[0.0, 1.0]));
+    ///
+    /// let mut tree = RTree::new();
+    /// assert_eq!(tree.insert_unique([0.0, 2.0]), Ok(()));
+    /// assert_eq!(tree.insert_unique([0.0, 2.0]), Err([0.0, 2.0]));
+    /// assert_eq!(tree.size(), 1);
     /// ```
-    pub fn nearest_neighbor(&self, query_point: &<T::Envelope as Envelope>::Point) -> Option<&T> {
-        if self.size > 0 {
-            // The single-nearest-neighbor retrieval may in rare cases return None due to
-            // rounding issues. The iterator will still work, though.
-            nearest_neighbor::nearest_neighbor(&self.root, *query_point)
-                .or_else(|| self.nearest_neighbor_iter(query_point).next())
-        } else {
-            None
-        }
+    pub fn insert_unique(&mut self, t: T) -> Result<(), T> {
+        Params::DefaultInsertionStrategy::insert_unless(self, t, contains_equal)?;
+        self.size += 1;
+        Ok(())
     }
 
-    /// Returns all elements of the tree within a certain distance.
+    /// Replaces an element equal (`==`) to `old` by `new` and returns the replaced element.
     ///
-    /// The elements may be returned in any order. Each returned element
-    /// will have a squared distance less or equal to the given squared distance.
+    /// The element is located only once. If the envelope of `new` fits into the located
+    /// element's parent node, `new` takes over its slot. Otherwise, `new` is reinserted.
     ///
-    /// This method makes use of [distance_2_if_less_or_equal](trait.PointDistance.html#method.distance_2_if_less_or_equal).
-    /// If performance is critical and the distance calculation to the object is fast,
-    /// overwriting this function may be beneficial.
-    pub fn locate_within_distance(
-        &self,
-        query_point: <T::Envelope as Envelope>::Point,
-        max_squared_radius: <<T::Envelope as Envelope>::Point as Point>::Scalar,
-    ) -> LocateWithinDistanceIterator<T> {
-        let selection_function = SelectWithinDistanceFunction::new(query_point, max_squared_radius);
-        LocateWithinDistanceIterator::new(self.root(), selection_function)
-    }
-
-    /// Returns all elements of the tree sorted by their distance to a given point.
+    /// Returns `None` if no equal element is contained. The tree is left unchanged and `new`
+    /// is dropped in this case.
+    ///
+    /// This method will only work correctly if two equal elements also have the
+    /// same envelope.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let mut tree = RTree::bulk_load(vec![[0.0, 0.0], [1.0, 1.0]]);
+    /// assert_eq!(tree.replace(&[1.0, 1.0], [0.5, 0.5]), Some([1.0, 1.0]));
+    /// assert_eq!(tree.replace(&[1.0, 1.0], [2.0, 2.0]), None);
+    /// assert!(tree.contains(&[0.5, 0.5]));
+    /// assert_eq!(tree.size(), 2);
+    /// ```
+    pub fn replace(&mut self, old: &T, new: T) -> Option<T> {
+        let mut replaced = None;
+        self.update_with_selection_function(SelectEqualsFunction::new(old), |t| {
+            replaced = Some(::std::mem::replace(t, new))
+        });
+        replaced
+    }
+
+    /// Removes all elements equal (`==`) to any of the given elements.
+    ///
+    /// Each given element removes at most one element from the tree. The removed elements are
+    /// returned, elements that could not be found are skipped.
+    ///
+    /// Unlike calling [`remove`](#method.remove) repeatedly, underflown nodes are dissolved
+    /// and repaired only once, after all elements have been removed.
+    ///
+    /// This method will only work correctly if two equal elements also have the
+    /// same envelope.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let mut tree = RTree::bulk_load(vec![[0.0, 0.0], [1.0, 1.0], [2.0, 2.0]]);
+    /// let removed = tree.remove_all(&[[0.0, 0.0], [2.0, 2.0], [3.0, 3.0]]);
+    /// assert_eq!(removed, vec![[0.0, 0.0], [2.0, 2.0]]);
+    /// assert_eq!(tree.size(), 1);
+    /// ```
+    pub fn remove_all<'a, I>(&mut self, elements: I) -> Vec<T>
+    where
+        I: IntoIterator<Item = &'a T>,
+        T: 'a,
+    {
+        let mut removed = Vec::new();
+        let mut orphans = Vec::new();
+        for t in elements {
+            let removal_function = SelectEqualsFunction::new(t);
+            let removed_element = removal::remove::<_, Params, _>(
+                &mut self.root,
+                &removal_function,
+                &mut orphans,
+                &mut self.spare_children,
+            );
+            match removed_element {
+                Some(element) => removed.push(element),
+                None => {
+                    // The element may have been part of an already dissolved node
+                    if let Some(index) = orphans.iter().position(|orphan| orphan == t) {
+                        removed.push(orphans.swap_remove(index));
+                    }
+                }
+            }
+        }
+        if !removed.is_empty() {
+            self.size -= removed.len();
+            self.condense(orphans);
+        }
+        removed
+    }
+}
+
+impl<T, Params> RTree<T, Params>
+where
+    Params: RTreeParams,
+    T: PointDistance,
+{
+    /// Returns the nearest neighbor for a given point.
+    ///
+    /// The distance is calculated by calling
+    /// [PointDistance::distance_2](traits.PointDistance.html#method.distance_2)
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    /// let tree = RTree::bulk_load(vec![
+    ///   [0.0, 0.0],
+    ///   [0.0, 1.0],
+    /// ]);
+    /// assert_eq!(tree.nearest_neighbor(&[-1., 0.0]), Some(&[0.0, 0.0]));
+    /// assert_eq!(tree.nearest_neighbor(&[0.0, 2.0]), Some(&[0.0, 1.0]));
+    /// ```
+    pub fn nearest_neighbor(&self, query_point: &<T::Envelope as Envelope>::Point) -> Option<&T> {
+        if self.size > 0 {
+            // The single-nearest-neighbor retrieval may in rare cases return None due to
+            // rounding issues. The iterator will still work, though.
+            nearest_neighbor::nearest_neighbor(&self.root, *query_point, Bounded::max_value())
+                .or_else(|| self.nearest_neighbor_iter(query_point).next())
+        } else {
+            None
+        }
+    }
+
+    /// Variant of [nearest_neighbor](#method.nearest_neighbor) that also returns the query's
+    /// [statistics](struct.QueryStats.html).
+    pub fn nearest_neighbor_with_stats(
+        &self,
+        query_point: &<T::Envelope as Envelope>::Point,
+    ) -> (Option<&T>, QueryStats) {
+        let mut stats = QueryStats::default();
+        let result = nearest_neighbor::nearest_neighbor_with_stats(
+            &self.root,
+            *query_point,
+            Bounded::max_value(),
+            &mut stats,
+        );
+        // The iterator fallback for rounding issues is not counted.
+        let result = result.or_else(|| {
+            if self.size > 0 {
+                self.nearest_neighbor_iter(query_point).next()
+            } else {
+                None
+            }
+        });
+        (result, stats)
+    }
+
+    /// Returns the nearest neighbor for a given point if it is within a maximum distance.
+    ///
+    /// `None` is returned if the tree contains no element with a squared distance less
+    /// or equal to `max_distance_2`. Any node beyond this distance is pruned from the search,
+    /// this is considerably faster than a full nearest neighbor query if no element
+    /// is close by.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    /// let tree = RTree::bulk_load(vec![
+    ///   [0.0, 0.0],
+    ///   [0.0, 1.0],
+    /// ]);
+    /// assert_eq!(tree.nearest_neighbor_within(&[0.0, 0.6], 0.25), Some(&[0.0, 1.0]));
+    /// assert_eq!(tree.nearest_neighbor_within(&[5.0, 0.0], 0.25), None);
+    /// ```
+    pub fn nearest_neighbor_within(
+        &self,
+        query_point: &<T::Envelope as Envelope>::Point,
+        max_distance_2: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+    ) -> Option<&T> {
+        if self.size > 0 {
+            // Same rounding issues as for nearest_neighbor may arise. The iterator fallback
+            // is restricted to the search radius to keep unsuccessful queries cheap.
+            nearest_neighbor::nearest_neighbor(&self.root, *query_point, max_distance_2).or_else(
+                || {
+                    nearest_neighbor::NearestNeighborDistanceIterator::new_with_selection_function(
+                        &self.root,
+                        *query_point,
+                        SquaredEuclidean,
+                        SelectWithinDistanceFunction::new(*query_point, max_distance_2),
+                    )
+                    .next()
+                    .map(|(t, _distance)| t)
+                },
+            )
+        } else {
+            None
+        }
+    }
+
+    /// Returns the squared distance of a given point to its nearest neighbor.
+    ///
+    /// Returns the distance of the element that
+    /// [nearest_neighbor](#method.nearest_neighbor) would return, without calculating it a
+    /// second time: the search already knows it. `None` is returned if the tree is empty.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    /// let tree = RTree::bulk_load(vec![
+    ///   [0.0, 0.0],
+    ///   [0.0, 1.0],
+    /// ]);
+    /// assert_eq!(tree.distance_2_to_nearest(&[0.0, 3.0]), Some(4.0));
+    /// ```
+    pub fn distance_2_to_nearest(
+        &self,
+        query_point: &<T::Envelope as Envelope>::Point,
+    ) -> Option<<<T::Envelope as Envelope>::Point as Point>::Scalar> {
+        nearest_neighbor::NearestDistanceSearch::new().distance_2(&self.root, *query_point)
+    }
+
+    /// Returns the squared distances of many points to their respective nearest neighbor.
+    ///
+    /// The distances are returned in the same order as the query points. This can be used
+    /// to sample a distance field, e.g. on a grid of query points. Refer to
+    /// [distance_2_to_nearest](#method.distance_2_to_nearest) for more information.
+    ///
+    /// All queries share a single search queue instead of allocating one per query point.
+    /// The points are looked up in the given order: neighboring query points, e.g. of a row
+    /// major grid, visit mostly the same nodes, which then stay in the CPU caches. With the
+    /// `rayon` feature, [par_distances_2_to_nearest](#method.par_distances_2_to_nearest)
+    /// distributes the queries among multiple threads.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    /// let tree = RTree::bulk_load(vec![[0, 0]]);
+    /// let grid: Vec<_> = (0..3).map(|x| [x, 1]).collect();
+    /// assert_eq!(tree.distances_2_to_nearest(&grid), vec![Some(1), Some(2), Some(5)]);
+    /// ```
+    pub fn distances_2_to_nearest(
+        &self,
+        query_points: &[<T::Envelope as Envelope>::Point],
+    ) -> Vec<Option<<<T::Envelope as Envelope>::Point as Point>::Scalar>> {
+        let mut search = nearest_neighbor::NearestDistanceSearch::new();
+        query_points
+            .iter()
+            .map(|query_point| search.distance_2(&self.root, *query_point))
+            .collect()
+    }
+
+    /// Returns all elements at the minimal distance to a given point.
+    ///
+    /// If multiple elements are equally close to `query_point`,
+    /// [nearest_neighbor](#method.nearest_neighbor) returns an arbitrary one of them. This
+    /// method returns all of them instead, in no specific order. The result is empty if the
+    /// tree is empty.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    /// let tree = RTree::bulk_load(vec![
+    ///   [0, 0],
+    ///   [2, 0],
+    ///   [1, 3],
+    /// ]);
+    /// let mut nearest = tree.nearest_neighbors(&[1, 0]);
+    /// nearest.sort();
+    /// assert_eq!(nearest, vec![&[0, 0], &[2, 0]]);
+    /// ```
+    pub fn nearest_neighbors(&self, query_point: &<T::Envelope as Envelope>::Point) -> Vec<&T> {
+        let mut iter = self.nearest_neighbor_iter_with_distance(query_point);
+        match iter.next() {
+            Some((first, min_distance)) => ::std::iter::once(first)
+                .chain(
+                    iter.take_while(|(_, distance)| *distance == min_distance)
+                        .map(|(t, _distance)| t),
+                )
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the nearest neighbor for a given point that fulfills a predicate.
+    ///
+    /// Elements for which `predicate` returns `false` are skipped during the search.
+    /// The tree is traversed by increasing distance to `query_point`, the first element
+    /// passing the predicate is returned.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    /// let tree = RTree::bulk_load(vec![
+    ///   [0.0, 0.0],
+    ///   [0.0, 1.0],
+    ///   [0.0, 2.0],
+    /// ]);
+    /// // Find the nearest neighbor of a stored point, excluding the point itself
+    /// let query_point = [0.0, 0.0];
+    /// let nearest = tree.nearest_neighbor_where(&query_point, |p| *p != query_point);
+    /// assert_eq!(nearest, Some(&[0.0, 1.0]));
+    /// ```
+    pub fn nearest_neighbor_where<F>(
+        &self,
+        query_point: &<T::Envelope as Envelope>::Point,
+        mut predicate: F,
+    ) -> Option<&T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.nearest_neighbor_iter(query_point)
+            .find(|element| predicate(element))
+    }
+
+    /// Returns an approximate nearest neighbor for a given point.
+    ///
+    /// The returned element's distance to `query_point` is at most `1 + epsilon` times the
+    /// distance of the true nearest neighbor. Larger values of `epsilon` allow pruning more
+    /// nodes during the search, which can speed up queries on large trees considerably.
+    /// Passing an `epsilon` of zero yields an exact nearest neighbor.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    /// let tree = RTree::bulk_load(vec![
+    ///   [0.0, 0.0],
+    ///   [0.0, 1.0],
+    /// ]);
+    /// assert_eq!(tree.nearest_neighbor_approx(&[0.0, 0.1], 0.5), Some(&[0.0, 0.0]));
+    /// ```
+    pub fn nearest_neighbor_approx(
+        &self,
+        query_point: &<T::Envelope as Envelope>::Point,
+        epsilon: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+    ) -> Option<&T> {
+        nearest_neighbor::nearest_neighbor_approx(&self.root, *query_point, epsilon)
+    }
+
+    /// Returns all elements of the tree within a certain distance.
+    ///
+    /// The elements may be returned in any order. Each returned element
+    /// will have a squared distance less or equal to the given squared distance.
+    ///
+    /// This method makes use of [distance_2_if_less_or_equal](trait.PointDistance.html#method.distance_2_if_less_or_equal).
+    /// If performance is critical and the distance calculation to the object is fast,
+    /// overwriting this function may be beneficial.
+    pub fn locate_within_distance(
+        &self,
+        query_point: <T::Envelope as Envelope>::Point,
+        max_squared_radius: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+    ) -> LocateWithinDistanceIterator<T> {
+        let selection_function = SelectWithinDistanceFunction::new(query_point, max_squared_radius);
+        LocateWithinDistanceIterator::new(self.root(), selection_function).with_size(self.size)
+    }
+
+    /// Removes all elements within a certain distance and returns them as an iterator.
+    ///
+    /// The elements are selected just like
+    /// [`locate_within_distance`](#method.locate_within_distance) would select them but are
+    /// removed in a single pass over the tree. All of them are removed immediately, regardless
+    /// of whether the returned iterator is consumed.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    /// let mut tree = RTree::bulk_load(vec![
+    ///   [0.0, 0.0],
+    ///   [0.0, 1.0],
+    ///   [3.0, 0.0],
+    /// ]);
+    /// assert_eq!(tree.drain_within_distance([0.0, 0.5], 0.5 * 0.5).count(), 2);
+    /// assert_eq!(tree.size(), 1);
+    /// ```
+    pub fn drain_within_distance(
+        &mut self,
+        query_point: <T::Envelope as Envelope>::Point,
+        max_squared_radius: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+    ) -> ::std::vec::IntoIter<T> {
+        let selection_function = SelectWithinDistanceFunction::new(query_point, max_squared_radius);
+        self.drain_with_selection_function(selection_function)
+    }
+
+    /// Inserts an element unless another element lies within a certain distance of it.
+    ///
+    /// The distance is measured from the center of the new element's envelope, for points
+    /// this is the point itself. `max_squared_radius` is the squared tolerance: Pass `0` to
+    /// reject only exact duplicates. If a close element exists, the tree is left unchanged
+    /// and `t` is returned.
+    ///
+    /// The proximity check is part of the insertion's descent: on every level, the siblings
+    /// of the chosen subtree within the tolerance are searched for a close element, and the
+    /// search stops at the first one.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let mut tree = RTree::new();
+    /// assert_eq!(tree.insert_dedup_within([0.0, 0.0], 0.1 * 0.1), Ok(()));
+    /// assert_eq!(tree.insert_dedup_within([0.05, 0.0], 0.1 * 0.1), Err([0.05, 0.0]));
+    /// assert_eq!(tree.insert_dedup_within([0.5, 0.0], 0.1 * 0.1), Ok(()));
+    /// assert_eq!(tree.size(), 2);
+    /// ```
+    pub fn insert_dedup_within(
+        &mut self,
+        t: T,
+        max_squared_radius: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+    ) -> Result<(), T> {
+        let center = t.envelope().center();
+        let is_close = |children: &[RTreeNode<T>], _: &T| {
+            let selection_function = SelectWithinDistanceFunction::new(center, max_squared_radius);
+            SelectionIterator::new_with_children(children, selection_function)
+                .next()
+                .is_some()
+        };
+        Params::DefaultInsertionStrategy::insert_unless(self, t, is_close)?;
+        self.size += 1;
+        Ok(())
+    }
+
+    /// Returns all elements of the tree sorted by their distance to a given point.
     ///
     /// # Runtime
     /// Every `next()` call runs in `O(log(n))`. Creating the iterator runs in
@@ -644,75 +2118,844 @@ where
     /// let nearest_neighbors = tree.nearest_neighbor_iter(&[0.5, 0.0]).collect::<Vec<_>>();
     /// assert_eq!(nearest_neighbors, vec![&[0.0, 0.0], &[0.0, 1.0]]);
     /// ```
-    pub fn nearest_neighbor_iter(
+    pub fn nearest_neighbor_iter(
+        &self,
+        query_point: &<T::Envelope as Envelope>::Point,
+    ) -> impl Iterator<Item = &T> + Clone {
+        nearest_neighbor::NearestNeighborIterator::new(&self.root, *query_point)
+    }
+
+    /// Returns all elements contained in an envelope, sorted by their distance to a given point.
+    ///
+    /// This combines [locate_in_envelope](#method.locate_in_envelope) and
+    /// [nearest_neighbor_iter](#method.nearest_neighbor_iter) in a single traversal:
+    /// Parent nodes not intersecting `envelope` are never visited.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, AABB};
+    /// let tree = RTree::bulk_load(vec![
+    ///   [0.0, 0.0],
+    ///   [0.0, 1.0],
+    ///   [0.0, 2.0],
+    ///   [5.0, 0.0],
+    /// ]);
+    ///
+    /// let envelope = AABB::from_corners([-1.0, -1.0], [1.0, 3.0]);
+    /// let ordered = tree.locate_in_envelope_ordered(&envelope, &[0.0, 3.0]).collect::<Vec<_>>();
+    /// assert_eq!(ordered, vec![&[0.0, 2.0], &[0.0, 1.0], &[0.0, 0.0]]);
+    /// ```
+    pub fn locate_in_envelope_ordered(
+        &self,
+        envelope: &T::Envelope,
+        query_point: &<T::Envelope as Envelope>::Point,
+    ) -> impl Iterator<Item = &T> + Clone {
+        nearest_neighbor::NearestNeighborDistanceIterator::new_with_selection_function(
+            &self.root,
+            *query_point,
+            SquaredEuclidean,
+            SelectInEnvelopeFunction::new(*envelope),
+        )
+        .map(|(t, _distance)| t)
+    }
+
+    /// Returns `(element, distance)` tuples of the tree sorted by their distance to a given point.
+    pub fn nearest_neighbor_iter_with_distance(
+        &self,
+        query_point: &<T::Envelope as Envelope>::Point,
+    ) -> impl Iterator<Item = (&T, <<T::Envelope as Envelope>::Point as Point>::Scalar)> + Clone
+    {
+        nearest_neighbor::NearestNeighborDistanceIterator::new(
+            &self.root,
+            *query_point,
+            SquaredEuclidean,
+        )
+    }
+
+    /// Removes the nearest neighbor for a given point and returns it.
+    ///
+    /// The distance is calculated by calling
+    /// [PointDistance::distance_2](traits.PointDistance.html#method.distance_2).
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    /// let mut tree = RTree::bulk_load(vec![
+    ///   [0.0, 0.0],
+    ///   [0.0, 1.0],
+    /// ]);
+    /// assert_eq!(tree.pop_nearest_neighbor(&[0.0, 0.0]), Some([0.0, 0.0]));
+    /// assert_eq!(tree.pop_nearest_neighbor(&[0.0, 0.0]), Some([0.0, 1.0]));
+    /// assert_eq!(tree.pop_nearest_neighbor(&[0.0, 0.0]), None);
+    /// ```
+    pub fn pop_nearest_neighbor(
+        &mut self,
+        query_point: &<T::Envelope as Envelope>::Point,
+    ) -> Option<T> {
+        if let Some(neighbor) = self.nearest_neighbor(query_point) {
+            let removal_function = SelectByAddressFunction::new(neighbor.envelope(), neighbor);
+            self.remove_with_selection_function(removal_function)
+        } else {
+            None
+        }
+    }
+}
+
+impl<P, Params> RTree<P, Params>
+where
+    Params: RTreeParams,
+    P: Point,
+{
+    /// Returns all points that have `query_point` as their nearest neighbor.
+    ///
+    /// This is the _reverse_ nearest neighbor query: A stored point is returned if no other
+    /// stored point is closer to it than `query_point`. Ties count in favor of `query_point`.
+    /// The result is in no specific order.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    /// let tree = RTree::bulk_load(vec![
+    ///   [0.0, 0.0],
+    ///   [1.0, 0.0],
+    ///   [5.0, 0.0],
+    /// ]);
+    /// // [1.0, 0.0] is closer to [0.0, 0.0] than the query point is
+    /// assert_eq!(tree.reverse_nearest_neighbors(&[3.5, 0.0]), vec![&[5.0, 0.0]]);
+    /// ```
+    pub fn reverse_nearest_neighbors(&self, query_point: &P) -> Vec<&P> {
+        nearest_neighbor::reverse_nearest_neighbors(&self.root, *query_point)
+    }
+
+    /// Returns the euclidean minimum spanning tree of all points.
+    ///
+    /// The spanning tree is returned as a list of edges `(from, to, distance_2)`, where
+    /// `distance_2` is the squared length of the edge. A tree with `n` points results in
+    /// `n - 1` edges, the order of the edges is not specified.
+    ///
+    /// Removing the longest `k - 1` edges yields a single-linkage clustering of the points into
+    /// `k` clusters.
+    ///
+    /// # Runtime
+    /// The spanning tree is calculated with a single-tree variant of Borůvka's algorithm, which
+    /// searches the shortest edge leaving its component for every point in each of at most
+    /// `log2(n)` rounds. This takes `O(n log²(n))` for evenly distributed points and
+    /// `O(n² log(n))` in the worst case. Dual-tree Borůvka, which also prunes groups of query
+    /// points, is not implemented.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    /// let tree = RTree::bulk_load(vec![
+    ///   [0.0, 0.0],
+    ///   [1.0, 0.0],
+    ///   [1.0, 3.0],
+    /// ]);
+    /// let mut lengths: Vec<_> = tree.euclidean_mst().into_iter().map(|edge| edge.2).collect();
+    /// lengths.sort_by(|l, r| l.partial_cmp(r).unwrap());
+    /// assert_eq!(lengths, vec![1.0, 9.0]);
+    /// ```
+    pub fn euclidean_mst(&self) -> Vec<(&P, &P, P::Scalar)> {
+        spanning_tree::euclidean_mst(&self.root)
+    }
+
+    /// Returns all points that have no element of another tree within a given distance.
+    ///
+    /// A point is returned if the squared distance to every element of `other` is larger than
+    /// `min_distance_2`. This is also known as an _anti-join_, e.g. to find all features of
+    /// one layer that are not covered by any feature of another layer.
+    ///
+    /// Both trees are traversed simultaneously. Subtrees without any element of `other`
+    /// nearby are returned as a whole. The result is in no specific order.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    /// use rstar::primitives::Line;
+    ///
+    /// let houses = RTree::bulk_load(vec![[0.0, 1.0], [2.0, 0.5], [5.0, 3.0]]);
+    /// let roads = RTree::bulk_load(vec![Line::new([0.0, 0.0], [4.0, 0.0])]);
+    /// // Find all houses that are more than 1.0 away from any road
+    /// assert_eq!(houses.locate_farther_than(&roads, 1.0), vec![&[5.0, 3.0]]);
+    /// ```
+    pub fn locate_farther_than<U, OtherParams>(
+        &self,
+        other: &RTree<U, OtherParams>,
+        min_distance_2: P::Scalar,
+    ) -> Vec<&P>
+    where
+        U: PointDistance<Envelope = AABB<P>>,
+        OtherParams: RTreeParams,
+    {
+        anti_join::locate_farther_than(&self.root, other.root(), min_distance_2)
+    }
+}
+
+impl<T, P, Params> RTree<T, Params>
+where
+    Params: RTreeParams,
+    T: RTreeObject<Envelope = AABB<P>>,
+    P: Point,
+{
+    /// Creates a new r-tree with the Priority R-tree bulk loading algorithm and configurable
+    /// parameters.
+    ///
+    /// For more information refer to [bulk_load_priority](#method.bulk_load_priority)
+    /// and [RTreeParams](trait.RTreeParams.html).
+    pub fn bulk_load_priority_with_params(elements: Vec<T>) -> Self {
+        Self::new_from_bulk_loading(elements, bulk_load::bulk_load_priority::<_, _, Params>)
+    }
+
+    /// Returns all elements approximately ordered along a Hilbert curve.
+    ///
+    /// The nodes storing the elements are sorted by the Hilbert curve position of their
+    /// center, the elements of each node are sorted the same way. Consecutive elements are
+    /// thus usually close to each other, which gives a cache friendly processing order. This
+    /// is cheaper than sorting all elements, but nodes with overlapping envelopes may cause
+    /// jumps along the curve.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let tree = RTree::bulk_load((0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect());
+    /// let sorted: Vec<_> = tree.iter_hilbert_sorted().collect();
+    /// assert_eq!(sorted.len(), 100);
+    /// ```
+    pub fn iter_hilbert_sorted(&self) -> HilbertIterator<'_, T> {
+        HilbertIterator::new(&self.root)
+    }
+
+    /// Returns all elements whose envelope intersects an envelope given in a different
+    /// coordinate frame.
+    ///
+    /// `transform` maps the tree's coordinates into the frame of `envelope`. The envelopes of
+    /// the visited nodes are transformed on the fly with
+    /// [AffineTransform::transform_envelope](struct.AffineTransform.html#method.transform_envelope).
+    /// Since transformed envelopes are conservative, the result may contain elements that
+    /// only intersect `envelope` after their envelope has been enlarged by the transformation.
+    /// No element that truly intersects `envelope` is missed.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{AffineTransform, RTree, AABB};
+    ///
+    /// let tree = RTree::bulk_load(vec![[1.0, 0.0], [0.0, 1.0], [-1.0, 0.0]]);
+    /// // The query frame is rotated by 90 degrees
+    /// let rotation = AffineTransform::new(vec![[0.0, -1.0], [1.0, 0.0]], [0.0, 0.0]);
+    /// let query = AABB::from_corners([-1.5, -0.5], [-0.5, 0.5]);
+    /// let result: Vec<_> = tree
+    ///     .locate_in_transformed_envelope_intersecting(&query, &rotation)
+    ///     .collect();
+    /// assert_eq!(result, vec![&[0.0, 1.0]]);
+    /// ```
+    pub fn locate_in_transformed_envelope_intersecting(
+        &self,
+        envelope: &AABB<P>,
+        transform: &AffineTransform<P>,
+    ) -> LocateInTransformedEnvelopeIntersecting<'_, T, P> {
+        LocateInTransformedEnvelopeIntersecting::new(
+            &self.root,
+            SelectInTransformedEnvelopeIntersectingFunction::new(*envelope, transform.clone()),
+        )
+        .with_size(self.size)
+    }
+
+    /// Returns regions covered by the envelopes of this tree's elements but not by any
+    /// envelope of another tree's elements.
+    ///
+    /// This can be used to find the dirty regions between two versions of a dataset. Both
+    /// trees are traversed simultaneously, each element's envelope is cut into boxes that
+    /// avoid all intersecting envelopes of `other`. The returned boxes of a single element are
+    /// disjoint. Boxes of different elements overlap if the elements' envelopes overlap.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, AABB};
+    /// use rstar::primitives::Rectangle;
+    ///
+    /// let old = RTree::bulk_load(vec![Rectangle::from_corners([0.0, 0.0], [2.0, 1.0])]);
+    /// let new = RTree::bulk_load(vec![Rectangle::from_corners([1.0, 0.0], [2.0, 1.0])]);
+    /// let removed = old.overlay_difference(&new);
+    /// assert_eq!(removed, vec![AABB::from_corners([0.0, 0.0], [1.0, 1.0])]);
+    /// assert!(new.overlay_difference(&old).is_empty());
+    /// ```
+    pub fn overlay_difference<U, OtherParams>(&self, other: &RTree<U, OtherParams>) -> Vec<AABB<P>>
+    where
+        U: RTreeObject<Envelope = AABB<P>>,
+        OtherParams: RTreeParams,
+    {
+        overlay::difference(&self.root, other.root())
+    }
+}
+
+impl<T, Params> RTree<T, Params>
+where
+    Params: RTreeParams,
+    T: RTreeObject<Envelope = AABB<[f64; 2]>>,
+{
+    /// Returns all non-empty web mercator map tiles of a zoom level together with the
+    /// elements intersecting them.
+    ///
+    /// Elements are expected to use `[longitude, latitude]` coordinates in degrees. Elements
+    /// spanning multiple tiles are returned for each of these tiles. A tile contains its
+    /// western and northern border, points on a border are thus returned for a single tile.
+    /// Tiles at the edge of the map also contain the positions beyond it, e.g. close to the
+    /// poles. Refer to the [tiles module](tiles/index.html) for more information.
+    ///
+    /// The tiles are found by splitting the map quadtree-style and pruning empty quadrants,
+    /// the cost thus depends on the number of non-empty tiles, not on the zoom level. Tiles
+    /// are returned in quadtree order: All tiles within a quadrant of a lower zoom level are
+    /// returned before the next quadrant, which are ordered north west, north east, south
+    /// west and south east.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let tree = RTree::bulk_load(vec![[13.4, 52.5], [13.5, 52.4], [-74.0, 40.7]]);
+    /// for (tile, elements) in tree.iter_by_tiles(4) {
+    ///     println!("Tile {}/{}/{}: {} elements", tile.zoom(), tile.x(), tile.y(), elements.len());
+    /// }
+    /// assert_eq!(tree.iter_by_tiles(4).count(), 2);
+    /// ```
+    pub fn iter_by_tiles(&self, zoom: u8) -> TileIterator<'_, T> {
+        TileIterator::new(&self.root, zoom)
+    }
+}
+
+impl<T, Params> RTree<T, Params>
+where
+    Params: RTreeParams,
+    T: RTreeObject,
+    T::Envelope: SpaceTimeEnvelope,
+{
+    /// Returns all elements fully contained within a spatial envelope during a time interval.
+    ///
+    /// Elements are expected to store time as their last dimension, see
+    /// [SpaceTimeEnvelope](trait.SpaceTimeEnvelope.html). The interval is closed, elements
+    /// starting at `end` or ending at `start` are returned if they are within `spatial`.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, AABB};
+    ///
+    /// // Events at [x, y, time]
+    /// let tree = RTree::bulk_load(vec![[0.0, 0.0, 1.0], [0.0, 0.0, 5.0], [3.0, 3.0, 1.0]]);
+    /// let area = AABB::from_corners([-1.0, -1.0], [1.0, 1.0]);
+    /// assert_eq!(tree.locate_in_envelope_during(&area, 0.0, 2.0).count(), 1);
+    /// ```
+    pub fn locate_in_envelope_during(
+        &self,
+        spatial: &<T::Envelope as SpaceTimeEnvelope>::Spatial,
+        start: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+        end: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+    ) -> LocateInEnvelope<'_, T> {
+        self.locate_in_envelope(&T::Envelope::from_space_time(spatial, start, end))
+    }
+
+    /// Returns all elements intersecting a spatial envelope at some time of a time interval.
+    ///
+    /// This is the counterpart of
+    /// [locate_in_envelope_intersecting](#method.locate_in_envelope_intersecting), see
+    /// [locate_in_envelope_during](#method.locate_in_envelope_during).
+    pub fn locate_intersecting_during(
+        &self,
+        spatial: &<T::Envelope as SpaceTimeEnvelope>::Spatial,
+        start: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+        end: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+    ) -> LocateInEnvelopeIntersecting<'_, T> {
+        self.locate_in_envelope_intersecting(&T::Envelope::from_space_time(spatial, start, end))
+    }
+}
+
+impl<T, Params> RTree<T, Params>
+where
+    Params: RTreeParams,
+    T: RTreeObject,
+{
+    /// Returns the nearest neighbor for a given point, measured with a custom
+    /// [Metric](trait.Metric.html).
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, Chebyshev};
+    /// let tree = RTree::bulk_load(vec![
+    ///   [2.0, 2.0],
+    ///   [0.0, 2.5],
+    /// ]);
+    /// assert_eq!(tree.nearest_neighbor(&[0.0, 0.0]), Some(&[0.0, 2.5]));
+    /// assert_eq!(tree.nearest_neighbor_with_metric(&[0.0, 0.0], Chebyshev), Some(&[2.0, 2.0]));
+    /// ```
+    pub fn nearest_neighbor_with_metric<M: Metric<T>>(
+        &self,
+        query_point: &<T::Envelope as Envelope>::Point,
+        metric: M,
+    ) -> Option<&T> {
+        self.nearest_neighbor_iter_with_metric(query_point, metric)
+            .next()
+            .map(|(t, _distance)| t)
+    }
+
+    /// Returns `(element, distance)` tuples of the tree sorted by their distance to a given point,
+    /// measured with a custom [Metric](trait.Metric.html).
+    pub fn nearest_neighbor_iter_with_metric<M: Metric<T>>(
+        &self,
+        query_point: &<T::Envelope as Envelope>::Point,
+        metric: M,
+    ) -> impl Iterator<Item = (&T, <<T::Envelope as Envelope>::Point as Point>::Scalar)> {
+        nearest_neighbor::NearestNeighborDistanceIterator::new(&self.root, *query_point, metric)
+    }
+
+    /// Returns all elements of the tree within a certain distance, measured with a
+    /// custom [Metric](trait.Metric.html).
+    ///
+    /// Unlike [locate_within_distance](#method.locate_within_distance), `max_distance` is
+    /// compared against the metric's distance directly and is thus only squared if the
+    /// metric is.
+    pub fn locate_within_distance_with_metric<M: Metric<T>>(
+        &self,
+        query_point: <T::Envelope as Envelope>::Point,
+        max_distance: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+        metric: M,
+    ) -> LocateWithinMetricDistanceIterator<'_, T, M> {
+        let selection_function =
+            SelectWithinMetricDistanceFunction::new(query_point, max_distance, metric);
+        LocateWithinMetricDistanceIterator::new(self.root(), selection_function)
+            .with_size(self.size)
+    }
+}
+
+impl<T, Params> RTree<T, Params>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    /// Inserts a new element into the r-tree.
+    ///
+    /// If the element has already been present in the tree, it will now be present twice.
+    ///
+    /// # Runtime
+    /// This method runs in `O(log(n))`.
+    /// The [r-tree documentation](struct.RTree.html) contains more information about
+    /// r-tree performance.
+    pub fn insert(&mut self, t: T) {
+        Params::DefaultInsertionStrategy::insert(self, t);
+        self.size += 1;
+    }
+
+    /// Inserts an element if its envelope has only finite coordinates.
+    ///
+    /// Elements with NaN or infinite coordinates are returned as error and the tree is left
+    /// unchanged. Such elements never cause a panic when inserted with
+    /// [insert](#method.insert), but they are placed in arbitrary nodes and make query
+    /// results unreliable. Use this method for input that may contain invalid values, e.g.
+    /// failed GPS fixes.
+    ///
+    /// Only the bounds of the envelope are checked, huge but finite envelopes are accepted.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let mut tree = RTree::new();
+    /// assert_eq!(tree.try_insert([0.0, 2.0]), Ok(()));
+    /// assert!(tree.try_insert([f64::NAN, 1.0]).is_err());
+    /// assert!(tree.try_insert([f64::INFINITY, 1.0]).is_err());
+    /// assert_eq!(tree.size(), 1);
+    /// ```
+    pub fn try_insert(&mut self, t: T) -> Result<(), T> {
+        let envelope = t.envelope();
+        let is_valid = (0..<T::Envelope as Envelope>::Point::DIMENSIONS).all(|axis| {
+            let (lower, upper) = envelope.bounds_along_axis(axis);
+            is_finite(lower) && is_finite(upper)
+        });
+        if is_valid {
+            self.insert(t);
+            Ok(())
+        } else {
+            Err(t)
+        }
+    }
+}
+
+impl<T, Params> RTree<T, Params>
+where
+    Params: RTreeParams,
+    T: RTreeObject + BinaryCodable,
+    T::Envelope: BinaryCodable,
+{
+    /// Writes the tree in a compact binary format.
+    ///
+    /// The format stores the tree's structure, envelopes, node ids and
+    /// [metadata](#method.metadata) in a flat layout with little endian numbers, preceded by a versioned header. Unlike
+    /// serde based formats, loading a tree in this format requires neither recalculating
+    /// envelopes nor rebuilding the tree. Elements must implement
+    /// [BinaryCodable](trait.BinaryCodable.html).
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let tree = RTree::bulk_load(vec![[0.0f32, 1.0], [2.0, 3.0]]);
+    /// let mut file = Vec::new();
+    /// tree.write_to(&mut file).unwrap();
+    /// let loaded: RTree<[f32; 2]> = RTree::read_from(&mut file.as_slice()).unwrap();
+    /// assert_eq!(loaded.size(), 2);
+    /// assert!(loaded.contains(&[2.0, 3.0]));
+    /// ```
+    pub fn write_to<W: ::std::io::Write>(&self, writer: &mut W) -> ::std::io::Result<()> {
+        binary::write_tree::<_, Params, _>(&self.root, &self.metadata(), writer)
+    }
+
+    /// Reads a tree written by [write_to](#method.write_to).
+    ///
+    /// Fails with [InvalidData](https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidData)
+    /// if the header does not match the tree's element type or parameters, or if the
+    /// input is corrupt. The tree's structure and envelopes are taken as they are stored.
+    pub fn read_from<R: ::std::io::Read>(reader: &mut R) -> ::std::io::Result<Self> {
+        let (root, size, metadata) = binary::read_tree::<_, Params, _>(reader)?;
+        Ok(RTree {
+            metadata,
+            root,
+            size,
+            insertion_metrics: Default::default(),
+            adaptive_reinsertion: false,
+            reinsertion_policy: Default::default(),
+            spare_children: Vec::new(),
+            _params: Default::default(),
+        })
+    }
+
+    /// Returns the tree in the binary format of [write_to](#method.write_to).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut result = Vec::new();
+        self.write_to(&mut result)
+            .expect("Writing into a Vec never fails");
+        result
+    }
+
+    /// Reads a tree from bytes created by [to_bytes](#method.to_bytes) or
+    /// [write_to](#method.write_to).
+    pub fn from_bytes(bytes: &[u8]) -> ::std::io::Result<Self> {
+        let mut reader = bytes;
+        Self::read_from(&mut reader)
+    }
+
+    /// Reads a tree like [from_bytes](#method.from_bytes) and checks its structure.
+    ///
+    /// Use this method for data from untrusted sources. Besides the checks of
+    /// [read_from](#method.read_from), the loaded tree must have all leaves at the same
+    /// height, every node's envelope must contain its children and no node besides the root
+    /// may be empty. Otherwise, an error of kind
+    /// [InvalidData](https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidData)
+    /// is returned. Validation takes time linear in the tree's size.
+    ///
+    /// Trees read without validation may return wrong query results if the data was
+    /// corrupted.
+    pub fn from_bytes_validated(bytes: &[u8]) -> ::std::io::Result<Self> {
+        let tree = Self::from_bytes(bytes)?;
+        binary::validate_tree::<_, Params>(&tree.root)?;
+        Ok(tree)
+    }
+}
+
+#[cfg(feature = "geojson")]
+impl<T, Params> RTree<T, Params>
+where
+    Params: RTreeParams,
+    T: RTreeObject + crate::geojson::GeoJsonGeometry,
+    T::Envelope: crate::geojson::GeoJsonGeometry,
+{
+    /// Writes the tree's elements as GeoJSON `FeatureCollection`.
+    ///
+    /// If `include_nodes` is set, the envelopes of all nodes are written as polygons as
+    /// well, tagged with their level in the tree. Requires the `geojson` feature, see the
+    /// [geojson module](geojson/index.html) for more information.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    /// use rstar::primitives::Line;
+    ///
+    /// let tree = RTree::bulk_load(vec![Line::new([0.0, 0.0], [1.0, 1.0])]);
+    /// let mut file = Vec::new();
+    /// tree.write_geojson(&mut file, true).unwrap();
+    /// ```
+    pub fn write_geojson<W: ::std::io::Write>(
+        &self,
+        writer: &mut W,
+        include_nodes: bool,
+    ) -> ::std::io::Result<()> {
+        crate::geojson::write_geojson(&self.root, include_nodes, writer)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T, Params> RTree<T, Params>
+where
+    Params: RTreeParams,
+    T: RTreeObject + Sync,
+    T::Envelope: Sync,
+{
+    /// Returns a parallel iterator over all elements contained in the tree.
+    ///
+    /// The work is split along the tree's subtrees. Requires the `rayon` feature.
+    ///
+    /// # Example
+    /// ```
+    /// use rayon::prelude::*;
+    /// use rstar::RTree;
+    ///
+    /// let tree = RTree::bulk_load((0..1000).map(|i| [i as f64, 0.0]).collect());
+    /// let sum: f64 = tree.par_iter().map(|point| point[0]).sum();
+    /// assert_eq!(sum, 499_500.0);
+    /// ```
+    pub fn par_iter(&self) -> ParallelRTreeIterator<'_, T> {
+        ParallelRTreeIterator::new(&self.root, SelectAllFunc)
+    }
+
+    /// Parallel variant of [locate_in_envelope](#method.locate_in_envelope).
+    pub fn par_locate_in_envelope(
+        &self,
+        envelope: &T::Envelope,
+    ) -> ParallelLocateInEnvelope<'_, T> {
+        ParallelLocateInEnvelope::new(&self.root, SelectInEnvelopeFunction::new(*envelope))
+    }
+
+    /// Parallel variant of
+    /// [locate_in_envelope_intersecting](#method.locate_in_envelope_intersecting).
+    pub fn par_locate_in_envelope_intersecting(
+        &self,
+        envelope: &T::Envelope,
+    ) -> ParallelLocateInEnvelopeIntersecting<'_, T> {
+        ParallelLocateInEnvelopeIntersecting::new(
+            &self.root,
+            SelectInEnvelopeFuncIntersecting::new(*envelope),
+        )
+    }
+
+    /// Runs independent queries in parallel and returns their results in the same order.
+    ///
+    /// `query` is called for each element of `queries`, possibly from multiple threads at
+    /// once. It can use any read-only method of the tree. Requires the `rayon` feature.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::{RTree, AABB};
+    ///
+    /// let tree = RTree::bulk_load((0..100).map(|i| [i as f64, 0.0]).collect());
+    /// let windows: Vec<_> = (0..10)
+    ///     .map(|i| AABB::from_corners([i as f64 * 10.0, 0.0], [i as f64 * 10.0 + 4.5, 0.0]))
+    ///     .collect();
+    /// let counts = tree.par_query(&windows, |tree, window| {
+    ///     tree.locate_in_envelope(window).count()
+    /// });
+    /// assert_eq!(counts, vec![5; 10]);
+    /// ```
+    pub fn par_query<'a, Q, R, F>(&'a self, queries: &[Q], query: F) -> Vec<R>
+    where
+        Q: Sync,
+        R: Send,
+        F: Fn(&'a Self, &Q) -> R + Sync,
+    {
+        use rayon::prelude::*;
+
+        queries.par_iter().map(|q| query(self, q)).collect()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T, Params> RTree<T, Params>
+where
+    Params: RTreeParams,
+    T: PointDistance + Sync,
+    T::Envelope: Sync,
+    <T::Envelope as Envelope>::Point: Sync,
+{
+    /// Returns the `k` nearest neighbors for each of many query points, computed in parallel.
+    ///
+    /// The result contains one vector per query point, in the order of `queries`. Each vector
+    /// contains the `k` elements closest to its query point, the closest element first, or all
+    /// elements if the tree contains fewer than `k`. Ties are broken arbitrarily. Each thread
+    /// reuses its search state for all of its queries. Requires the `rayon` feature.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let tree = RTree::bulk_load((0..100).map(|i| [i as f64, 0.0]).collect());
+    /// let result = tree.nearest_neighbors_batch(&[[10.1, 0.0], [-5.0, 1.0]], 2);
+    /// assert_eq!(result[0], vec![&[10.0, 0.0], &[11.0, 0.0]]);
+    /// assert_eq!(result[1], vec![&[0.0, 0.0], &[1.0, 0.0]]);
+    /// ```
+    pub fn nearest_neighbors_batch(
+        &self,
+        queries: &[<T::Envelope as Envelope>::Point],
+        k: usize,
+    ) -> Vec<Vec<&T>> {
+        use rayon::prelude::*;
+
+        queries
+            .par_iter()
+            .map_init(
+                nearest_neighbor::KNearestNeighborSearch::new,
+                |search, query| search.search(&self.root, self.size, *query, k),
+            )
+            .collect()
+    }
+
+    /// Returns the squared distances of many points to their respective nearest neighbor,
+    /// computed in parallel.
+    ///
+    /// Returns the same distances as
+    /// [distances_2_to_nearest](#method.distances_2_to_nearest). Each thread answers
+    /// contiguous ranges of the query points with its own search queue. Requires the
+    /// `rayon` feature.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::RTree;
+    ///
+    /// let tree = RTree::bulk_load(vec![[0, 0]]);
+    /// let grid: Vec<_> = (0..3).map(|x| [x, 1]).collect();
+    /// assert_eq!(tree.par_distances_2_to_nearest(&grid), vec![Some(1), Some(2), Some(5)]);
+    /// ```
+    pub fn par_distances_2_to_nearest(
         &self,
-        query_point: &<T::Envelope as Envelope>::Point,
-    ) -> impl Iterator<Item = &T> {
-        nearest_neighbor::NearestNeighborIterator::new(&self.root, *query_point)
-    }
+        query_points: &[<T::Envelope as Envelope>::Point],
+    ) -> Vec<Option<<<T::Envelope as Envelope>::Point as Point>::Scalar>>
+    where
+        <<T::Envelope as Envelope>::Point as Point>::Scalar: Send,
+    {
+        use rayon::prelude::*;
 
-    /// Returns `(element, distance)` tuples of the tree sorted by their distance to a given point.
-    pub fn nearest_neighbor_iter_with_distance(
-        &self,
-        query_point: &<T::Envelope as Envelope>::Point,
-    ) -> impl Iterator<Item = (&T, <<T::Envelope as Envelope>::Point as Point>::Scalar)> {
-        nearest_neighbor::NearestNeighborDistanceIterator::new(&self.root, *query_point)
+        query_points
+            .par_iter()
+            .map_init(
+                nearest_neighbor::NearestDistanceSearch::new,
+                |search, query_point| search.distance_2(&self.root, *query_point),
+            )
+            .collect()
     }
+}
 
-    /// Removes the nearest neighbor for a given point and returns it.
+#[cfg(feature = "rayon")]
+impl<T> RTree<T>
+where
+    T: RTreeObject + Send,
+    T::Envelope: Send,
+{
+    /// Creates a new r-tree from a set of elements, using all threads of Rayon's thread pool.
     ///
-    /// The distance is calculated by calling
-    /// [PointDistance::distance_2](traits.PointDistance.html#method.distance_2).
+    /// Creates the same nodes as [bulk_load](#method.bulk_load): the elements are partitioned
+    /// into slabs in parallel, and the subtrees for different slabs are built in parallel.
+    /// Requires the `rayon` feature.
     ///
     /// # Example
     /// ```
     /// use rstar::RTree;
-    /// let mut tree = RTree::bulk_load(vec![
-    ///   [0.0, 0.0],
-    ///   [0.0, 1.0],
-    /// ]);
-    /// assert_eq!(tree.pop_nearest_neighbor(&[0.0, 0.0]), Some([0.0, 0.0]));
-    /// assert_eq!(tree.pop_nearest_neighbor(&[0.0, 0.0]), Some([0.0, 1.0]));
-    /// assert_eq!(tree.pop_nearest_neighbor(&[0.0, 0.0]), None);
+    ///
+    /// let tree = RTree::bulk_load_parallel((0..10_000).map(|i| [i as f64, 0.0]).collect());
+    /// assert_eq!(tree.size(), 10_000);
+    /// assert_eq!(tree.nearest_neighbor(&[20.2, 1.0]), Some(&[20.0, 0.0]));
     /// ```
-    pub fn pop_nearest_neighbor(
-        &mut self,
-        query_point: &<T::Envelope as Envelope>::Point,
-    ) -> Option<T> {
-        if let Some(neighbor) = self.nearest_neighbor(query_point) {
-            let removal_function = SelectByAddressFunction::new(neighbor.envelope(), neighbor);
-            self.remove_with_selection_function(removal_function)
-        } else {
-            None
-        }
+    pub fn bulk_load_parallel(elements: Vec<T>) -> Self {
+        Self::bulk_load_parallel_with_params(elements)
     }
 }
 
+#[cfg(feature = "rayon")]
 impl<T, Params> RTree<T, Params>
 where
-    T: RTreeObject,
     Params: RTreeParams,
+    T: RTreeObject + Send,
+    T::Envelope: Send,
 {
-    /// Inserts a new element into the r-tree.
+    /// Creates a new r-tree in parallel with configurable parameters.
     ///
-    /// If the element has already been present in the tree, it will now be present twice.
+    /// For more information refer to [bulk_load_parallel](#method.bulk_load_parallel)
+    /// and [RTreeParams](trait.RTreeParams.html).
+    pub fn bulk_load_parallel_with_params(elements: Vec<T>) -> Self {
+        Self::new_from_bulk_loading(elements, bulk_load::bulk_load_parallel::<_, Params>)
+    }
+
+    /// Returns a parallel iterator over all mutable elements contained in the tree.
     ///
-    /// # Runtime
-    /// This method runs in `O(log(n))`.
-    /// The [r-tree documentation](struct.RTree.html) contains more information about
-    /// r-tree performance.
-    pub fn insert(&mut self, t: T) {
-        Params::DefaultInsertionStrategy::insert(self, t);
-        self.size += 1;
+    /// The same restrictions as for [iter_mut](#method.iter_mut) apply. Requires the `rayon`
+    /// feature.
+    pub fn par_iter_mut(&mut self) -> ParallelRTreeIteratorMut<'_, T> {
+        ParallelRTreeIteratorMut::new(&mut self.root)
     }
 }
 
-impl<T, Params> RTree<T, Params>
+#[cfg(feature = "rayon")]
+impl<T, Params> rayon::iter::IntoParallelIterator for RTree<T, Params>
+where
+    T: RTreeObject + Send,
+    T::Envelope: Send,
+    Params: RTreeParams,
+{
+    type Iter = ParallelRTreeIntoIterator<T>;
+    type Item = T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        ParallelRTreeIntoIterator::new(self.root)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T, Params> rayon::iter::IntoParallelIterator for &'a RTree<T, Params>
+where
+    T: RTreeObject + Sync,
+    T::Envelope: Sync,
+    Params: RTreeParams,
+{
+    type Iter = ParallelRTreeIterator<'a, T>;
+    type Item = &'a T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.par_iter()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T, Params> rayon::iter::IntoParallelIterator for &'a mut RTree<T, Params>
+where
+    T: RTreeObject + Send,
+    T::Envelope: Send,
+    Params: RTreeParams,
+{
+    type Iter = ParallelRTreeIteratorMut<'a, T>;
+    type Item = &'a mut T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.par_iter_mut()
+    }
+}
+
+/// Consumes the tree and returns its elements in no specified order.
+///
+/// # Example
+/// ```
+/// use rstar::RTree;
+///
+/// let tree = RTree::bulk_load(vec![[0.0, 1.0], [2.0, 3.0]]);
+/// let mut points: Vec<[f64; 2]> = tree.into_iter().collect();
+/// points.sort_by(|l, r| l.partial_cmp(r).unwrap());
+/// assert_eq!(points, vec![[0.0, 1.0], [2.0, 3.0]]);
+/// ```
+impl<T, Params> IntoIterator for RTree<T, Params>
 where
     T: RTreeObject,
-    <T::Envelope as Envelope>::Point: Point,
     Params: RTreeParams,
 {
+    type IntoIter = RTreeIntoIterator<T>;
+    type Item = T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        RTreeIntoIterator::new(self.root, self.size)
+    }
 }
 
 impl<'a, T, Params> IntoIterator for &'a RTree<T, Params>
@@ -741,13 +2984,66 @@ where
     }
 }
 
+/// Collects the elements and [bulk loads](struct.RTree.html#method.bulk_load_with_params) them.
+///
+/// # Example
+/// ```
+/// use rstar::RTree;
+///
+/// let tree: RTree<_> = (0..100).map(|x| [x as f32, 0.0]).collect();
+/// assert_eq!(tree.size(), 100);
+/// ```
+impl<T, Params> ::std::iter::FromIterator<T> for RTree<T, Params>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::bulk_load_with_params(iter.into_iter().collect())
+    }
+}
+
+/// Inserts a batch of elements.
+///
+/// Small batches are inserted one by one. If a batch contains at least as many elements as
+/// the tree, the whole tree is [bulk loaded](struct.RTree.html#method.bulk_load_with_params)
+/// again instead, which is faster and results in a better packed tree.
+///
+/// # Example
+/// ```
+/// use rstar::RTree;
+///
+/// let mut tree = RTree::new();
+/// tree.extend((0..100).map(|x| [x as f32, 0.0]));
+/// tree.extend(vec![[0.0, 1.0], [1.0, 1.0]]);
+/// assert_eq!(tree.size(), 102);
+/// ```
+impl<T, Params> Extend<T> for RTree<T, Params>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let elements: Vec<T> = iter.into_iter().collect();
+        if elements.len() < self.size {
+            for t in elements {
+                self.insert(t);
+            }
+        } else if !elements.is_empty() {
+            self.rebuild_with(elements);
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::RTree;
     use crate::algorithm::rstar::RStarInsertionStrategy;
     use crate::params::RTreeParams;
-    use crate::test_utilities::{create_random_points, SEED_1};
-    use crate::DefaultParams;
+    use crate::test_utilities::{create_random_points, SEED_1, SEED_2};
+    use crate::{
+        DefaultParams, Envelope, ParentNode, RTreeNode, RTreeObject, TraversalOrder, AABB,
+    };
 
     struct TestParams;
     impl RTreeParams for TestParams {
@@ -757,6 +3053,17 @@ mod test {
         type DefaultInsertionStrategy = RStarInsertionStrategy;
     }
 
+    fn count_nodes(node: &ParentNode<[f64; 2]>) -> usize {
+        1 + node
+            .children
+            .iter()
+            .map(|child| match child {
+                RTreeNode::Parent(ref data) => count_nodes(data),
+                RTreeNode::Leaf(_) => 0,
+            })
+            .sum::<usize>()
+    }
+
     #[test]
     fn test_create_rtree_with_parameters() {
         let tree: RTree<[f32; 2], TestParams> = RTree::new_with_params();
@@ -772,6 +3079,196 @@ mod test {
         assert!(!tree.contains(&[0.3, 0.2]));
     }
 
+    #[test]
+    fn test_small_tree_is_flat() {
+        let mut tree = RTree::new();
+        assert_eq!(tree.root.children.capacity(), 0);
+        for p in create_random_points(DefaultParams::MAX_SIZE, SEED_1) {
+            tree.insert(p);
+        }
+        assert!(tree.root.children.iter().all(RTreeNode::is_leaf));
+        let bulk_loaded = RTree::bulk_load(create_random_points(DefaultParams::MAX_SIZE, SEED_1));
+        assert!(bulk_loaded.root.children.iter().all(RTreeNode::is_leaf));
+        assert_eq!(
+            RTree::<[f32; 2]>::bulk_load(Vec::new())
+                .root
+                .children
+                .capacity(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut tree = RTree::new();
+        for p in create_random_points(100, SEED_1) {
+            tree.insert(p);
+        }
+        let capacity = tree.root.children.capacity();
+        tree.clear();
+        assert_eq!(tree.size(), 0);
+        assert_eq!(tree.iter().count(), 0);
+        assert_eq!(tree.height(), 1);
+        assert_eq!(tree.root.children.capacity(), capacity);
+        tree.insert([0.5, 0.5]);
+        assert_eq!(tree.root.envelope, crate::AABB::from_point([0.5, 0.5]));
+        tree.root.sanity_check::<DefaultParams>();
+
+        // All inner nodes are kept and reused by later insertions
+        let points = create_random_points(1000, SEED_1);
+        let mut tree = RTree::new();
+        for p in &points {
+            tree.insert(*p);
+        }
+        let nodes = count_nodes(&tree.root);
+        tree.clear();
+        assert_eq!(tree.spare_children.len(), nodes - 1);
+        assert!(tree.spare_children.iter().all(Vec::is_empty));
+        for p in &points {
+            tree.insert(*p);
+        }
+        assert_eq!(tree.spare_children.len(), nodes - count_nodes(&tree.root));
+        assert_eq!(tree.size(), points.len());
+        tree.root.sanity_check::<DefaultParams>();
+    }
+
+    #[test]
+    fn test_rebalance() {
+        let points = create_random_points(1000, SEED_1);
+        let mut tree: RTree<_> = RTree::new();
+        // Insert sorted points, this results in a degenerated tree
+        let mut sorted = points.clone();
+        sorted.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        for p in &sorted {
+            tree.insert(*p);
+        }
+        let query = crate::AABB::from_corners([0.2, 0.2], [0.3, 0.3]);
+        let expected = tree.locate_in_envelope(&query).count();
+        let visited_before = tree.prefetch_in_envelope(&query);
+        tree.rebalance();
+        assert_eq!(tree.size(), points.len());
+        assert_eq!(tree.locate_in_envelope(&query).count(), expected);
+        assert!(tree.prefetch_in_envelope(&query) <= visited_before);
+        for p in &points {
+            assert!(tree.contains(p));
+        }
+
+        let mut empty: RTree<[f64; 2]> = RTree::new();
+        empty.rebalance();
+        assert_eq!(empty.size(), 0);
+    }
+
+    #[test]
+    fn test_reserve() {
+        let points = create_random_points(1000, SEED_1);
+        let mut tree = RTree::with_capacity(points.len());
+        let reserved = tree.spare_children.len();
+        assert!(reserved > 0);
+        assert!(tree
+            .spare_children
+            .iter()
+            .all(|children| children.capacity() == DefaultParams::MAX_SIZE + 1));
+        assert_eq!(tree.root.children.capacity(), DefaultParams::MAX_SIZE + 1);
+        for p in &points {
+            tree.insert(*p);
+        }
+        // Every node except the root was taken from the reserved ones
+        assert!(!tree.spare_children.is_empty());
+        assert_eq!(
+            count_nodes(&tree.root) - 1,
+            reserved - tree.spare_children.len()
+        );
+        let capacity = tree.spare_children.capacity();
+        tree.retain(|p| p[0] < 0.5);
+        // Dissolved nodes are kept, but never more than reserved
+        let spare = tree.spare_children.len();
+        assert!(spare <= capacity);
+        assert_eq!(tree.spare_children.capacity(), capacity);
+        assert!(tree.spare_children.iter().all(Vec::is_empty));
+        tree.root.sanity_check::<DefaultParams>();
+        for p in &points {
+            if p[0] >= 0.5 {
+                tree.insert(*p);
+            }
+        }
+        assert!(tree.spare_children.len() < spare);
+        assert_eq!(tree.size(), points.len());
+        tree.root.sanity_check::<DefaultParams>();
+
+        // Trees without reserved space don't keep dissolved nodes
+        let mut tree = RTree::bulk_load(points);
+        tree.retain(|p| p[0] < 0.5);
+        assert!(tree.spare_children.is_empty());
+    }
+
+    #[test]
+    fn test_clone_skips_spare_children() {
+        let mut tree = RTree::with_capacity(1000);
+        for p in create_random_points(1000, SEED_1) {
+            tree.insert(p);
+        }
+        tree.retain(|p| p[0] < 0.5);
+        assert!(!tree.spare_children.is_empty());
+        let clone = tree.clone();
+        assert_eq!(clone.spare_children.capacity(), 0);
+        assert_eq!(clone.size(), tree.size());
+        assert!(tree.iter().all(|p| clone.contains(p)));
+    }
+
+    #[test]
+    fn test_shrink_to_fit() {
+        let points = create_random_points(1000, SEED_1);
+        let mut tree = RTree::with_capacity(points.len());
+        for p in &points {
+            tree.insert(*p);
+        }
+        for p in &points[100..] {
+            tree.remove(p);
+        }
+        tree.shrink_to_fit();
+        assert!(tree.spare_children.is_empty());
+        assert_eq!(tree.size(), 100);
+        tree.root.sanity_check::<DefaultParams>();
+        let mut nodes = vec![&tree.root];
+        while let Some(node) = nodes.pop() {
+            assert_eq!(node.children.capacity(), node.children.len());
+            for child in &node.children {
+                if let RTreeNode::Parent(ref data) = child {
+                    nodes.push(data);
+                }
+            }
+        }
+        assert!(points[..100].iter().all(|p| tree.contains(p)));
+    }
+
+    #[test]
+    fn test_insert_unique() {
+        use crate::primitives::PointWithData;
+
+        let points = create_random_points(1000, SEED_1);
+        let mut tree = RTree::new();
+        for point in &points {
+            assert_eq!(tree.insert_unique(*point), Ok(()));
+        }
+        for point in &points {
+            assert_eq!(tree.insert_unique(*point), Err(*point));
+        }
+        assert_eq!(tree.size(), 1000);
+        assert_eq!(tree.insertion_metrics().insertions, 1000);
+        tree.root().sanity_check::<DefaultParams>();
+
+        // Elements at the same position are only rejected if they are equal
+        let mut tree = RTree::new();
+        for index in 0..100 {
+            let point = PointWithData::new(index, [0.5, 0.5]);
+            assert_eq!(tree.insert_unique(point), Ok(()));
+        }
+        assert!(tree
+            .insert_unique(PointWithData::new(57, [0.5, 0.5]))
+            .is_err());
+        assert_eq!(tree.size(), 100);
+    }
+
     #[test]
     fn test_insert_many() {
         const NUM_POINTS: usize = 1000;
@@ -787,6 +3284,66 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_insert_dedup_within() {
+        use crate::point::PointExt;
+
+        let points = create_random_points(500, SEED_1);
+        let max_distance_2 = 0.02 * 0.02;
+        let mut tree = RTree::new();
+        let mut inserted = Vec::new();
+        for p in &points {
+            // Every point is followed by a close duplicate
+            for candidate in &[*p, [p[0] + 0.001, p[1]]] {
+                let is_isolated = inserted
+                    .iter()
+                    .all(|other: &[f64; 2]| other.sub(candidate).length_2() > max_distance_2);
+                assert_eq!(
+                    tree.insert_dedup_within(*candidate, max_distance_2).is_ok(),
+                    is_isolated
+                );
+                if is_isolated {
+                    inserted.push(*candidate);
+                }
+            }
+        }
+        assert_eq!(tree.size(), inserted.len());
+        assert!(inserted.len() < points.len());
+        tree.root.sanity_check::<DefaultParams>();
+    }
+
+    #[test]
+    fn test_f32_envelopes_contain_children() {
+        const NUM_POINTS: usize = 2000;
+        let points: Vec<[f32; 2]> = create_random_points(NUM_POINTS, SEED_1)
+            .iter()
+            .map(|p| [p[0] as f32 * 1.0e6 + 1.0e7, p[1] as f32 * 1.0e-6])
+            .collect();
+        let mut tree = RTree::new();
+        for p in &points {
+            tree.insert(*p);
+        }
+        for p in &points[..NUM_POINTS / 2] {
+            assert!(tree.remove(p).is_some());
+        }
+        tree.root.sanity_check::<DefaultParams>();
+        for p in &points[NUM_POINTS / 2..] {
+            assert_eq!(tree.locate_at_point(p), Some(p));
+        }
+    }
+
+    #[test]
+    fn test_prefetch_in_envelope() {
+        let tree: RTree<_> = RTree::bulk_load(create_random_points(1000, SEED_1));
+        let mut ids = Vec::new();
+        collect_node_ids(tree.root(), &mut ids);
+        let envelope = crate::AABB::from_corners([0.2, 0.2], [0.3, 0.3]);
+        let visited = tree.prefetch_in_envelope(&envelope);
+        assert!(visited > 1 && visited < ids.len());
+        assert_eq!(tree.prefetch_in_envelope(&tree.root.envelope), ids.len());
+        assert_eq!(RTree::<[f64; 2]>::new().prefetch_in_envelope(&envelope), 1);
+    }
+
     #[test]
     fn test_fmt_debug() {
         let tree = RTree::bulk_load(vec![[0, 1], [0, 1]]);
@@ -800,6 +3357,35 @@ mod test {
         assert_eq!(tree.size(), 0);
     }
 
+    #[test]
+    fn test_extend() {
+        let points = create_random_points(1000, SEED_1);
+        let mut tree: RTree<_, TestParams> = RTree::new_with_params();
+        // Large batches rebuild the tree, small batches are inserted
+        tree.extend(points[..400].iter().cloned());
+        tree.extend(points[400..800].iter().cloned());
+        tree.extend(points[800..].iter().cloned());
+        tree.extend(Vec::new());
+        assert_eq!(tree.size(), points.len());
+        assert_eq!(tree.iter().count(), points.len());
+        for p in &points {
+            assert!(tree.contains(p));
+        }
+    }
+
+    #[test]
+    fn test_from_iterator() {
+        let points = create_random_points(1000, SEED_1);
+        let collected: RTree<_, TestParams> = points.iter().cloned().collect();
+        let bulk_loaded: RTree<_, TestParams> = RTree::bulk_load_with_params(points.clone());
+        assert_eq!(collected.size(), points.len());
+        assert_eq!(collected.height(), bulk_loaded.height());
+        assert_eq!(collected.root.envelope, bulk_loaded.root.envelope);
+        for p in &points {
+            assert!(collected.contains(p));
+        }
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn test_serialization() {
@@ -817,4 +3403,226 @@ mod test {
             assert!(parsed.contains(point));
         }
     }
+
+    #[test]
+    fn test_metadata() {
+        let mut tree: RTree<_, TestParams> = RTree::new_with_params();
+        tree.insert([0.0, 1.0]);
+        let metadata: crate::RTreeMetadata = tree.metadata();
+        assert_eq!(metadata.element_count(), 1);
+        assert_eq!(metadata.dimensions(), 2);
+        assert_eq!(metadata.scalar_type(), "f64");
+        assert_eq!(metadata.min_size(), TestParams::MIN_SIZE);
+        assert_eq!(metadata.max_size(), TestParams::MAX_SIZE);
+        assert_eq!(metadata.reinsertion_count(), TestParams::REINSERTION_COUNT);
+        assert_eq!(metadata.build_timestamp(), None);
+
+        let mut bulk_loaded = RTree::bulk_load(create_random_points(10, SEED_1));
+        assert_eq!(bulk_loaded.metadata().element_count(), 10);
+        assert_eq!(bulk_loaded.metadata().build_timestamp(), None);
+        bulk_loaded.set_build_timestamp(Some(42));
+        assert_eq!(bulk_loaded.metadata().build_timestamp(), Some(42));
+
+        let integers: RTree<[i32; 3]> = RTree::bulk_load(vec![[0, 1, 2]]);
+        assert_eq!(integers.metadata().scalar_type(), "i32");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialized_metadata() {
+        use crate::RTreeMetadata;
+
+        let mut tree = RTree::bulk_load(create_random_points(100, SEED_1));
+        tree.insert([0.5, 0.5]);
+        tree.user_metadata_mut()
+            .insert("key".to_string(), "value".to_string());
+        let json = serde_json::to_string(&tree).expect("Serializing tree failed");
+
+        let mut deserializer = serde_json::Deserializer::from_str(&json);
+        let metadata = RTreeMetadata::from_serialized_tree(&mut deserializer)
+            .expect("Deserializing metadata failed");
+        assert_eq!(metadata, tree.metadata());
+        assert_eq!(metadata.element_count(), 101);
+        assert_eq!(metadata.user_data()["key"], "value");
+
+        let mut parsed: RTree<[f64; 2]> =
+            serde_json::from_str(&json).expect("Deserializing tree failed");
+        assert_eq!(parsed.metadata(), metadata);
+        parsed.insert([0.2, 0.2]);
+        assert_eq!(parsed.metadata().element_count(), 102);
+    }
+
+    fn collect_node_ids(node: &ParentNode<[f64; 2]>, ids: &mut Vec<u64>) {
+        ids.push(node.id());
+        for child in node.children() {
+            if let RTreeNode::Parent(ref data) = child {
+                collect_node_ids(data, ids);
+            }
+        }
+    }
+
+    #[test]
+    fn test_node_ids() {
+        let mut tree = RTree::new();
+        let root_id = tree.root().id();
+        tree.insert([0.0, 0.0]);
+        tree.insert([1.0, 1.0]);
+        tree.remove(&[0.0, 0.0]);
+        assert_eq!(tree.root().id(), root_id);
+
+        for point in create_random_points(1000, SEED_1) {
+            tree.insert(point);
+        }
+        let mut ids = Vec::new();
+        collect_node_ids(tree.root(), &mut ids);
+        let node_count = ids.len();
+        assert!(node_count > 1);
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), node_count);
+
+        let clone = tree.clone();
+        assert_eq!(clone.root().id(), tree.root().id());
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<S: Send + Sync>() {}
+        assert_send_sync::<RTree<[f64; 2]>>();
+        assert_send_sync::<RTree<crate::primitives::Rectangle<[i32; 3]>, TestParams>>();
+
+        let tree = RTree::bulk_load(create_random_points(1000, SEED_1));
+        let queries = create_random_points(8, SEED_2);
+        let shared = &tree;
+        let results: Vec<_> = ::std::thread::scope(|scope| {
+            let handles: Vec<_> = queries
+                .iter()
+                .map(|query| scope.spawn(move || *shared.nearest_neighbor(query).unwrap()))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        for (query, result) in queries.iter().zip(results) {
+            assert_eq!(tree.nearest_neighbor(query), Some(&result));
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_query() {
+        let tree = RTree::bulk_load(create_random_points(1000, SEED_1));
+        let queries = create_random_points(100, SEED_2);
+        let results = tree.par_query(&queries, |tree, query| tree.nearest_neighbor(query));
+        let expected: Vec<_> = queries.iter().map(|q| tree.nearest_neighbor(q)).collect();
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn test_visit() {
+        let tree = RTree::bulk_load(create_random_points(1000, SEED_1));
+        let mut leaf_count = 0;
+        let mut parent_ids = Vec::new();
+        tree.root().visit(|node, depth| {
+            match node {
+                RTreeNode::Leaf(_) => {
+                    assert_eq!(depth, tree.height());
+                    leaf_count += 1;
+                }
+                RTreeNode::Parent(data) => parent_ids.push(data.id()),
+            }
+            true
+        });
+        assert_eq!(leaf_count, 1000);
+        let mut ids = Vec::new();
+        collect_node_ids(tree.root(), &mut ids);
+        assert_eq!(parent_ids, ids[1..].to_vec());
+
+        // Prune all subtrees outside of an envelope
+        let envelope = AABB::from_corners([0.2, 0.2], [0.4, 0.5]);
+        let mut found = 0;
+        tree.root().visit(|node, _| {
+            let is_relevant = node.envelope().intersects(&envelope);
+            if is_relevant && node.is_leaf() && envelope.contains_envelope(&node.envelope()) {
+                found += 1;
+            }
+            is_relevant
+        });
+        assert_eq!(found, tree.locate_in_envelope(&envelope).count());
+    }
+
+    #[test]
+    fn test_traversal_orders() {
+        let tree = RTree::bulk_load(create_random_points(1000, SEED_1));
+        let depth_first: Vec<_> = tree.root().nodes(TraversalOrder::DepthFirst).collect();
+        let breadth_first: Vec<_> = tree.root().nodes(TraversalOrder::BreadthFirst).collect();
+        assert_eq!(depth_first.len(), breadth_first.len());
+        assert!(breadth_first.windows(2).all(|w| w[0].1 <= w[1].1));
+        assert!(depth_first.windows(2).any(|w| w[0].1 > w[1].1));
+
+        // Both orders report the elements in the same sequence
+        let leaves = |nodes: &[(&RTreeNode<[f64; 2]>, usize)]| -> Vec<[f64; 2]> {
+            nodes
+                .iter()
+                .filter_map(|(node, _)| match node {
+                    RTreeNode::Leaf(point) => Some(*point),
+                    RTreeNode::Parent(_) => None,
+                })
+                .collect()
+        };
+        assert_eq!(leaves(&depth_first), leaves(&breadth_first));
+        assert_eq!(leaves(&depth_first).len(), 1000);
+
+        let mut visited = Vec::new();
+        tree.root()
+            .visit_with_order(TraversalOrder::BreadthFirst, |node, depth| {
+                visited.push((node.envelope(), depth));
+                true
+            });
+        let expected: Vec<_> = breadth_first
+            .iter()
+            .map(|(node, depth)| (node.envelope(), *depth))
+            .collect();
+        assert_eq!(visited, expected);
+
+        // Pruning the root's children only reports the first level
+        let mut count = 0;
+        tree.root()
+            .visit_with_order(TraversalOrder::BreadthFirst, |_, depth| {
+                assert_eq!(depth, 1);
+                count += 1;
+                false
+            });
+        assert_eq!(count, tree.root().children().len());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialized_node_ids() {
+        let mut tree = RTree::new();
+        for point in create_random_points(500, SEED_1) {
+            tree.insert(point);
+        }
+        let json = serde_json::to_string(&tree).expect("Serializing tree failed");
+        let mut parsed: RTree<[f64; 2]> =
+            serde_json::from_str(&json).expect("Deserializing tree failed");
+        let (mut expected, mut ids) = (Vec::new(), Vec::new());
+        collect_node_ids(tree.root(), &mut expected);
+        collect_node_ids(parsed.root(), &mut ids);
+        assert_eq!(ids, expected);
+
+        for point in create_random_points(500, SEED_2) {
+            parsed.insert(point);
+        }
+        let mut ids = Vec::new();
+        collect_node_ids(parsed.root(), &mut ids);
+        let node_count = ids.len();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), node_count);
+
+        // Ids that could not be handed out without wrapping around are rejected
+        let root_id = format!("\"id\":{}", tree.root().id());
+        assert!(json.contains(&root_id));
+        let invalid = json.replace(&root_id, &format!("\"id\":{}", u64::MAX));
+        assert!(serde_json::from_str::<RTree<[f64; 2]>>(&invalid).is_err());
+    }
 }
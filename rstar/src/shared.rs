@@ -0,0 +1,670 @@
+use crate::algorithm::guttman::choose_subtree;
+use crate::algorithm::iterators::SelectionIterator;
+use crate::algorithm::nearest_neighbor::NearestNeighborDistanceIterator;
+use crate::algorithm::rstar::sort_for_split;
+use crate::algorithm::selection_functions::*;
+use crate::envelope::Envelope;
+use crate::metric::SquaredEuclidean;
+use crate::node::{ParentNode, RTreeNode, TreeChild, UnpackedChild};
+use crate::object::{PointDistance, RTreeObject};
+use crate::params::{verify_parameters, DefaultParams, RTreeParams};
+use crate::rtree::RTree;
+use std::sync::Arc;
+
+/// An immutable r-tree whose versions share unchanged subtrees.
+///
+/// [insert](#method.insert) and [remove](#method.remove) leave a tree untouched and return a
+/// new version instead. Only the nodes on the path to the modified element are copied, all
+/// other nodes are shared between both versions by reference counting. This makes snapshots
+/// cheap: a writer can keep producing new versions while readers, possibly on other threads,
+/// continue to query the version they hold. [Cloning](#impl-Clone) a tree only increments a
+/// reference count. Like an `RTree`, a shared tree is `Send` and `Sync` if both `T` and
+/// `T::Envelope` are.
+///
+/// Copying a path requires `T: Clone`. Overflowing nodes are split like the
+/// [r*-insertion strategy](struct.RStarInsertionStrategy.html) splits them, but elements are
+/// not reinserted on overflow like in an [RTree](struct.RTree.html). Removal reinserts the
+/// elements of nodes that would hold fewer than `Params::MIN_SIZE` children. Queries share
+/// their implementation with `RTree`.
+///
+/// # Example
+/// ```
+/// use rstar::SharedRTree;
+///
+/// let empty = SharedRTree::new();
+/// let first = empty.insert([0.0, 0.0]);
+/// let second = first.insert([1.0, 1.0]);
+/// assert_eq!(empty.size(), 0);
+/// assert_eq!(first.size(), 1);
+/// assert_eq!(second.size(), 2);
+///
+/// let (third, removed) = second.remove(&[0.0, 0.0]).unwrap();
+/// assert_eq!(removed, [0.0, 0.0]);
+/// assert!(!third.contains(&[0.0, 0.0]));
+/// assert!(second.contains(&[0.0, 0.0]));
+/// ```
+pub struct SharedRTree<T, Params = DefaultParams>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    root: Arc<SharedNode<T>>,
+    // The number of parent levels below the root, zero if the root stores the elements
+    height: usize,
+    size: usize,
+    _params: ::std::marker::PhantomData<Params>,
+}
+
+struct SharedNode<T>
+where
+    T: RTreeObject,
+{
+    envelope: T::Envelope,
+    children: Vec<SharedChild<T>>,
+}
+
+enum SharedChild<T>
+where
+    T: RTreeObject,
+{
+    Leaf(T),
+    Parent(Arc<SharedNode<T>>),
+}
+
+impl<T, Params> Clone for SharedRTree<T, Params>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    fn clone(&self) -> Self {
+        SharedRTree {
+            root: self.root.clone(),
+            height: self.height,
+            size: self.size,
+            _params: Default::default(),
+        }
+    }
+}
+
+impl<T> Clone for SharedNode<T>
+where
+    T: RTreeObject + Clone,
+{
+    fn clone(&self) -> Self {
+        SharedNode {
+            envelope: self.envelope,
+            children: self.children.clone(),
+        }
+    }
+}
+
+impl<T> Clone for SharedChild<T>
+where
+    T: RTreeObject + Clone,
+{
+    fn clone(&self) -> Self {
+        match self {
+            SharedChild::Leaf(t) => SharedChild::Leaf(t.clone()),
+            SharedChild::Parent(node) => SharedChild::Parent(node.clone()),
+        }
+    }
+}
+
+impl<T> RTreeObject for SharedChild<T>
+where
+    T: RTreeObject,
+{
+    type Envelope = T::Envelope;
+
+    fn envelope(&self) -> Self::Envelope {
+        match self {
+            SharedChild::Leaf(t) => t.envelope(),
+            SharedChild::Parent(node) => node.envelope,
+        }
+    }
+}
+
+impl<T> TreeChild<T> for SharedChild<T>
+where
+    T: RTreeObject,
+{
+    #[inline]
+    fn unpack(&self) -> UnpackedChild<'_, T, Self> {
+        match self {
+            SharedChild::Leaf(t) => UnpackedChild::Leaf(t),
+            SharedChild::Parent(node) => UnpackedChild::Parent(&node.envelope, &node.children),
+        }
+    }
+}
+
+impl<T> SharedNode<T>
+where
+    T: RTreeObject,
+{
+    fn new(children: Vec<SharedChild<T>>) -> Self {
+        let mut result = SharedNode {
+            envelope: Envelope::new_empty(),
+            children,
+        };
+        result.update_envelope();
+        result
+    }
+
+    fn update_envelope(&mut self) {
+        let mut envelope = T::Envelope::new_empty();
+        for child in &self.children {
+            envelope.merge(&child.envelope());
+        }
+        self.envelope = envelope;
+    }
+
+    fn contains(&self, t: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        SelectionIterator::new_with_children(&self.children, SelectEqualsFunction::new(t))
+            .next()
+            .is_some()
+    }
+}
+
+impl<T> SharedRTree<T>
+where
+    T: RTreeObject,
+{
+    /// Creates a new, empty tree.
+    pub fn new() -> Self {
+        Self::new_with_params()
+    }
+
+    /// Creates a new tree from a set of elements.
+    ///
+    /// The elements are arranged like [RTree::bulk_load](struct.RTree.html#method.bulk_load)
+    /// would arrange them.
+    pub fn bulk_load(elements: Vec<T>) -> Self {
+        Self::bulk_load_with_params(elements)
+    }
+}
+
+impl<T> Default for SharedRTree<T>
+where
+    T: RTreeObject,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, Params> SharedRTree<T, Params>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    /// Creates a new, empty tree with configurable parameters.
+    pub fn new_with_params() -> Self {
+        verify_parameters::<T, Params>();
+        SharedRTree {
+            root: Arc::new(SharedNode::new(Vec::new())),
+            height: 0,
+            size: 0,
+            _params: Default::default(),
+        }
+    }
+
+    /// Creates a new tree with configurable parameters.
+    ///
+    /// For more information refer to [bulk_load](#method.bulk_load)
+    /// and [RTreeParams](trait.RTreeParams.html).
+    pub fn bulk_load_with_params(elements: Vec<T>) -> Self {
+        RTree::bulk_load_with_params(elements).into()
+    }
+
+    /// Returns the number of elements in the tree.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns an iterator over all elements in the tree.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.locate_with_selection_function(SelectAllFunc)
+    }
+
+    /// Returns all elements selected by a [SelectionFunction](trait.SelectionFunction.html),
+    /// see [RTree::locate_with_selection_function](struct.RTree.html#method.locate_with_selection_function).
+    pub fn locate_with_selection_function<S: SelectionFunction<T>>(
+        &self,
+        function: S,
+    ) -> impl Iterator<Item = &T> {
+        SelectionIterator::new_with_children(&self.root.children, function).with_size(self.size)
+    }
+
+    /// Returns all elements contained in an envelope, see
+    /// [RTree::locate_in_envelope](struct.RTree.html#method.locate_in_envelope).
+    pub fn locate_in_envelope(&self, envelope: &T::Envelope) -> impl Iterator<Item = &T> {
+        self.locate_with_selection_function(SelectInEnvelopeFunction::new(*envelope))
+    }
+
+    /// Returns all elements whose envelope intersects an envelope, see
+    /// [RTree::locate_in_envelope_intersecting](struct.RTree.html#method.locate_in_envelope_intersecting).
+    pub fn locate_in_envelope_intersecting(
+        &self,
+        envelope: &T::Envelope,
+    ) -> impl Iterator<Item = &T> {
+        self.locate_with_selection_function(SelectInEnvelopeFuncIntersecting::new(*envelope))
+    }
+
+    /// Returns `true` if the tree contains an element equal (`==`) to a given element.
+    pub fn contains(&self, t: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.root.contains(t)
+    }
+}
+
+impl<T, Params> SharedRTree<T, Params>
+where
+    T: RTreeObject + Clone,
+    Params: RTreeParams,
+{
+    /// Returns a new version of this tree that additionally contains an element.
+    ///
+    /// Copies the nodes on the path to the new element, all other nodes are shared with
+    /// this tree.
+    pub fn insert(&self, t: T) -> Self {
+        let mut result = self.clone();
+        result.insert_in_place(t);
+        result
+    }
+
+    fn insert_in_place(&mut self, t: T) {
+        let root = Arc::make_mut(&mut self.root);
+        if let Some(sibling) = insert::<_, Params>(root, t, self.height) {
+            let old_root =
+                ::std::mem::replace(&mut self.root, Arc::new(SharedNode::new(Vec::new())));
+            self.root = Arc::new(SharedNode::new(vec![
+                SharedChild::Parent(old_root),
+                SharedChild::Parent(Arc::new(sibling)),
+            ]));
+            self.height += 1;
+        }
+        self.size += 1;
+    }
+
+    /// Returns a new version of this tree without an element that is equal (`==`) to `t`,
+    /// together with the removed element.
+    ///
+    /// Returns `None` if the tree contains no such element.
+    pub fn remove(&self, t: &T) -> Option<(Self, T)>
+    where
+        T: PartialEq,
+    {
+        // The path is searched first, a failed search must not copy any nodes
+        let mut path = Vec::new();
+        if !find_path(&self.root, t, &t.envelope(), &mut path) {
+            return None;
+        }
+        let mut result = self.clone();
+        let mut orphans = Vec::new();
+        let root = Arc::make_mut(&mut result.root);
+        let removed = remove::<_, Params>(root, &mut path, &mut orphans);
+        result.size -= 1 + orphans.len();
+        while result.height > 0 && result.root.children.len() <= 1 {
+            let root = Arc::make_mut(&mut result.root);
+            match root.children.pop() {
+                Some(SharedChild::Parent(child)) => result.root = child,
+                _ => result.root = Arc::new(SharedNode::new(Vec::new())),
+            }
+            result.height -= 1;
+        }
+        if result.root.children.is_empty() {
+            result.height = 0;
+        }
+        for orphan in orphans {
+            result.insert_in_place(orphan);
+        }
+        Some((result, removed))
+    }
+}
+
+/// Inserts an element below a node with `height` parent levels.
+///
+/// Returns a new sibling if the node had to be split.
+fn insert<T, Params>(node: &mut SharedNode<T>, t: T, height: usize) -> Option<SharedNode<T>>
+where
+    T: RTreeObject + Clone,
+    Params: RTreeParams,
+{
+    let envelope = t.envelope();
+    node.envelope.merge(&envelope);
+    if height == 0 {
+        node.children.push(SharedChild::Leaf(t));
+    } else {
+        let index = choose_subtree(&node.children, &envelope);
+        let child = match node.children[index] {
+            SharedChild::Parent(ref mut child) => Arc::make_mut(child),
+            SharedChild::Leaf(_) => unreachable!("Elements are stored at the lowest level"),
+        };
+        if let Some(sibling) = insert::<_, Params>(child, t, height - 1) {
+            node.children.push(SharedChild::Parent(Arc::new(sibling)));
+        }
+    }
+    if node.children.len() > Params::MAX_SIZE {
+        Some(split::<_, Params>(node))
+    } else {
+        None
+    }
+}
+
+/// Moves the children of an overflowing node into a new sibling, using the r* split.
+fn split<T, Params>(node: &mut SharedNode<T>) -> SharedNode<T>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    let (index, envelope, sibling_envelope) = sort_for_split::<_, Params>(&mut node.children);
+    let sibling = node.children.split_off(index);
+    node.envelope = envelope;
+    SharedNode {
+        envelope: sibling_envelope,
+        children: sibling,
+    }
+}
+
+/// Searches an element equal to `t` and pushes the child indices leading to it to `path`.
+///
+/// The search backtracks from subtrees that do not contain the element. The indices are pushed
+/// in reverse order, the index of the leaf comes first.
+fn find_path<T>(node: &SharedNode<T>, t: &T, envelope: &T::Envelope, path: &mut Vec<usize>) -> bool
+where
+    T: RTreeObject + PartialEq,
+{
+    for (index, child) in node.children.iter().enumerate() {
+        let found = match child {
+            SharedChild::Leaf(leaf) => leaf == t,
+            SharedChild::Parent(child) => {
+                child.envelope.contains_envelope(envelope) && find_path(child, t, envelope, path)
+            }
+        };
+        if found {
+            path.push(index);
+            return true;
+        }
+    }
+    false
+}
+
+/// Removes the element that `path`, as returned by `find_path`, leads to.
+///
+/// Children that become too small are removed, their elements are added to `orphans`.
+fn remove<T, Params>(node: &mut SharedNode<T>, path: &mut Vec<usize>, orphans: &mut Vec<T>) -> T
+where
+    T: RTreeObject + Clone,
+    Params: RTreeParams,
+{
+    let index = path.pop().expect("This is a bug in rstar.");
+    let (removed, is_underfull) = match node.children[index] {
+        SharedChild::Leaf(_) => match node.children.swap_remove(index) {
+            SharedChild::Leaf(leaf) => (leaf, false),
+            SharedChild::Parent(_) => unreachable!(),
+        },
+        SharedChild::Parent(ref mut child) => {
+            // Only the nodes on the path to the element are copied
+            let child = Arc::make_mut(child);
+            let removed = remove::<_, Params>(child, path, orphans);
+            (removed, child.children.len() < Params::MIN_SIZE)
+        }
+    };
+    if is_underfull {
+        if let SharedChild::Parent(child) = node.children.swap_remove(index) {
+            orphans.extend(
+                SelectionIterator::new_with_children(&child.children, SelectAllFunc).cloned(),
+            );
+        }
+    }
+    node.update_envelope();
+    removed
+}
+
+impl<T, Params> SharedRTree<T, Params>
+where
+    T: PointDistance,
+    Params: RTreeParams,
+{
+    /// Returns a single element that contains a point, see
+    /// [RTree::locate_at_point](struct.RTree.html#method.locate_at_point).
+    pub fn locate_at_point(&self, point: &<T::Envelope as Envelope>::Point) -> Option<&T> {
+        self.locate_all_at_point(point).next()
+    }
+
+    /// Returns all elements that contain a point, see
+    /// [RTree::locate_all_at_point](struct.RTree.html#method.locate_all_at_point).
+    pub fn locate_all_at_point(
+        &self,
+        point: &<T::Envelope as Envelope>::Point,
+    ) -> impl Iterator<Item = &T> {
+        self.locate_with_selection_function(SelectAtPointFunction::new(*point))
+    }
+
+    /// Returns the nearest neighbor of a point, see
+    /// [RTree::nearest_neighbor](struct.RTree.html#method.nearest_neighbor).
+    ///
+    /// Returns `None` if the tree is empty.
+    pub fn nearest_neighbor(&self, query_point: &<T::Envelope as Envelope>::Point) -> Option<&T> {
+        self.nearest_neighbor_iter(query_point).next()
+    }
+
+    /// Returns all elements in the order of their distance to a point, see
+    /// [RTree::nearest_neighbor_iter](struct.RTree.html#method.nearest_neighbor_iter).
+    pub fn nearest_neighbor_iter(
+        &self,
+        query_point: &<T::Envelope as Envelope>::Point,
+    ) -> impl Iterator<Item = &T> {
+        NearestNeighborDistanceIterator::new_with_children(
+            &self.root.children,
+            *query_point,
+            SquaredEuclidean,
+            SelectAllFunc,
+        )
+        .map(|(t, _distance)| t)
+    }
+}
+
+impl<T, Params> From<RTree<T, Params>> for SharedRTree<T, Params>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    /// Converts a tree into a shared tree, keeping its structure.
+    fn from(mut tree: RTree<T, Params>) -> Self {
+        let size = tree.size();
+        let height = tree.height().saturating_sub(1);
+        let root = ::std::mem::replace(tree.root_mut(), ParentNode::new_empty());
+        SharedRTree {
+            root: Arc::new(convert(root)),
+            height,
+            size,
+            _params: Default::default(),
+        }
+    }
+}
+
+fn convert<T>(node: ParentNode<T>) -> SharedNode<T>
+where
+    T: RTreeObject,
+{
+    SharedNode {
+        envelope: node.envelope,
+        children: node
+            .children
+            .into_iter()
+            .map(|child| match child {
+                RTreeNode::Leaf(t) => SharedChild::Leaf(t),
+                RTreeNode::Parent(data) => SharedChild::Parent(Arc::new(convert(data))),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SharedChild, SharedNode, SharedRTree};
+    use crate::envelope::Envelope;
+    use crate::object::{PointDistance, RTreeObject};
+    use crate::params::{DefaultParams, RTreeParams};
+    use crate::test_utilities::{create_random_points, create_random_rectangles, SEED_1, SEED_2};
+    use crate::{RTree, AABB};
+
+    /// Checks the envelopes and the depth of all elements and returns the number of elements.
+    fn sanity_check<T: RTreeObject>(node: &SharedNode<T>, height: usize) -> usize {
+        let mut envelope = T::Envelope::new_empty();
+        let mut count = 0;
+        for child in &node.children {
+            envelope.merge(&child.envelope());
+            match child {
+                SharedChild::Leaf(_) => {
+                    assert_eq!(height, 0);
+                    count += 1;
+                }
+                SharedChild::Parent(child) => {
+                    assert!(height > 0);
+                    count += sanity_check(child, height - 1);
+                }
+            }
+        }
+        assert_eq!(node.envelope, envelope);
+        count
+    }
+
+    fn collect_nodes<'a, T: RTreeObject>(
+        node: &'a SharedNode<T>,
+        result: &mut Vec<&'a SharedNode<T>>,
+    ) {
+        result.push(node);
+        for child in &node.children {
+            if let SharedChild::Parent(child) = child {
+                collect_nodes(child, result);
+            }
+        }
+    }
+
+    /// Bulk loading may create nodes of any size, this is only checked after insertion.
+    fn check_node_sizes<T: RTreeObject>(tree: &SharedRTree<T>) {
+        let max_size = <DefaultParams as RTreeParams>::MAX_SIZE;
+        let min_size = <DefaultParams as RTreeParams>::MIN_SIZE;
+        let mut nodes = Vec::new();
+        collect_nodes(&tree.root, &mut nodes);
+        assert!(nodes.iter().all(|node| node.children.len() <= max_size));
+        assert!(nodes[1..]
+            .iter()
+            .all(|node| node.children.len() >= min_size));
+    }
+
+    /// Returns the number of nodes of `r` that are not shared with `l`.
+    fn count_copied_nodes<T: RTreeObject>(l: &SharedRTree<T>, r: &SharedRTree<T>) -> usize {
+        let (mut left, mut right) = (Vec::new(), Vec::new());
+        collect_nodes(&l.root, &mut left);
+        collect_nodes(&r.root, &mut right);
+        right
+            .iter()
+            .filter(|node| !left.iter().any(|other| ::std::ptr::eq(**node, *other)))
+            .count()
+    }
+
+    #[test]
+    fn test_shared_insert_and_remove() {
+        let points = create_random_points(1000, SEED_1);
+        let mut versions = vec![SharedRTree::new()];
+        for point in &points {
+            let next = versions.last().unwrap().insert(*point);
+            versions.push(next);
+        }
+        for (size, version) in versions.iter().enumerate() {
+            check_node_sizes(version);
+            assert_eq!(version.size(), size);
+            assert_eq!(sanity_check(&version.root, version.height), size);
+        }
+        let tree = versions.last().unwrap().clone();
+        assert!(points.iter().all(|p| tree.contains(p)));
+        // Old versions are unchanged
+        assert!(!versions[500].contains(&points[500]));
+        assert!(versions[500].contains(&points[499]));
+
+        let mut current = tree.clone();
+        for point in &points[..900] {
+            let (next, removed) = current.remove(point).unwrap();
+            assert_eq!(&removed, point);
+            assert!(!next.contains(point));
+            assert_eq!(next.size(), current.size() - 1);
+            check_node_sizes(&next);
+            assert_eq!(sanity_check(&next.root, next.height), next.size());
+            current = next;
+        }
+        assert!(current.remove(&points[0]).is_none());
+        assert!(points[900..].iter().all(|p| current.contains(p)));
+        assert_eq!(tree.size(), 1000);
+        assert!(points.iter().all(|p| tree.contains(p)));
+    }
+
+    #[test]
+    fn test_shared_structural_sharing() {
+        let mut tree = SharedRTree::new();
+        for point in create_random_points(1000, SEED_1) {
+            tree = tree.insert(point);
+        }
+        let mut nodes = Vec::new();
+        collect_nodes(&tree.root, &mut nodes);
+        assert!(nodes.len() > 100);
+        // Only the nodes on the path to the new element and new siblings are created
+        let updated = tree.insert([0.5, 0.5]);
+        assert!(count_copied_nodes(&tree, &updated) <= 2 * (updated.height + 1));
+        let (removed, _) = updated.remove(&[0.5, 0.5]).unwrap();
+        assert_eq!(removed.size(), 1000);
+        assert!(count_copied_nodes(&updated, &removed) <= 2 * (removed.height + 1));
+        // Cloning shares the whole tree
+        assert_eq!(count_copied_nodes(&tree, &tree.clone()), 0);
+    }
+
+    #[test]
+    fn test_shared_queries() {
+        let rectangles = create_random_rectangles(500, SEED_1);
+        let tree = RTree::bulk_load(rectangles.clone());
+        let shared = SharedRTree::bulk_load(rectangles.clone());
+        assert_eq!(sanity_check(&shared.root, shared.height), 500);
+        assert_eq!(shared.iter().count(), 500);
+        for point in create_random_points(50, SEED_2) {
+            assert_eq!(
+                shared.locate_all_at_point(&point).count(),
+                tree.locate_all_at_point(&point).count()
+            );
+            let distances: Vec<_> = shared
+                .nearest_neighbor_iter(&point)
+                .map(|r| r.distance_2(&point))
+                .collect();
+            assert_eq!(distances.len(), 500);
+            assert!(distances.windows(2).all(|w| w[0] <= w[1]));
+            let envelope = AABB::from_corners(point, [point[0] + 0.2, point[1] + 0.2]);
+            assert_eq!(
+                shared.locate_in_envelope_intersecting(&envelope).count(),
+                tree.locate_in_envelope_intersecting(&envelope).count()
+            );
+        }
+        fn assert_send_sync<S: Send + Sync>() {}
+        assert_send_sync::<SharedRTree<[f64; 2]>>();
+
+        let empty: SharedRTree<[f64; 2]> = SharedRTree::new();
+        assert_eq!(empty.nearest_neighbor(&[0.0, 0.0]), None);
+        assert_eq!(empty.iter().count(), 0);
+
+        // Overlapping rectangles are often contained in the envelopes of several nodes
+        let mut current = shared;
+        for rectangle in &rectangles {
+            let (next, removed) = current.remove(rectangle).unwrap();
+            assert_eq!(&removed, rectangle);
+            assert_eq!(sanity_check(&next.root, next.height), next.size());
+            current = next;
+        }
+        assert_eq!(current.size(), 0);
+        assert!(current.remove(&rectangles[0]).is_none());
+    }
+}
@@ -0,0 +1,158 @@
+use crate::aabb::AABB;
+use crate::envelope::Envelope;
+use crate::point::{Point, RTreeNum};
+
+/// An envelope with a spatial extent and a time interval.
+///
+/// Spatiotemporal data, e.g. trajectories or events, is indexed by appending time as the
+/// last dimension of each point: A 2D location at time `t` is stored as `[x, y, t]`, an
+/// object moving through 3D space uses 4D points. This trait converts between such
+/// envelopes and their spatial part and time interval. It is implemented for
+/// [AABB](struct.AABB.html)s of three and four dimensions.
+///
+/// Together with [locate_in_envelope_during](struct.RTree.html#method.locate_in_envelope_during)
+/// and [locate_intersecting_during](struct.RTree.html#method.locate_intersecting_during),
+/// queries can be expressed in terms of space and time.
+///
+/// Note that time and space share a scalar type and are weighted equally by the insertion
+/// strategy. Choose their units such that typical extents in space and in time are of a similar
+/// magnitude.
+///
+/// # Example
+/// ```
+/// use rstar::{RTree, SpaceTimeEnvelope, AABB};
+/// use rstar::primitives::Rectangle;
+///
+/// // A vehicle moving from [0, 0] to [2, 1] between t = 10 and t = 20
+/// let spatial = AABB::from_corners([0.0, 0.0], [2.0, 1.0]);
+/// let segment: AABB<[f64; 3]> = AABB::from_space_time(&spatial, 10.0, 20.0);
+/// assert_eq!(segment.spatial(), spatial);
+/// assert_eq!(segment.time_interval(), (10.0, 20.0));
+///
+/// let tree = RTree::bulk_load(vec![Rectangle::from_aabb(segment)]);
+/// let area = AABB::from_corners([1.0, 0.0], [3.0, 3.0]);
+/// assert_eq!(tree.locate_intersecting_during(&area, 15.0, 30.0).count(), 1);
+/// assert_eq!(tree.locate_intersecting_during(&area, 25.0, 30.0).count(), 0);
+/// ```
+pub trait SpaceTimeEnvelope: Envelope {
+    /// The envelope type of the spatial dimensions.
+    type Spatial: Envelope<Point = Self::SpatialPoint>;
+    /// The point type of the spatial dimensions.
+    type SpatialPoint: Point<Scalar = <Self::Point as Point>::Scalar>;
+
+    /// Creates an envelope covering a spatial envelope during a closed time interval.
+    ///
+    /// The envelope is empty if `start` is greater than `end`.
+    fn from_space_time(
+        spatial: &Self::Spatial,
+        start: <Self::Point as Point>::Scalar,
+        end: <Self::Point as Point>::Scalar,
+    ) -> Self;
+
+    /// Returns the envelope's spatial part.
+    fn spatial(&self) -> Self::Spatial;
+
+    /// Returns the envelope's time interval as `(start, end)`.
+    fn time_interval(
+        &self,
+    ) -> (
+        <Self::Point as Point>::Scalar,
+        <Self::Point as Point>::Scalar,
+    );
+}
+
+macro_rules! implement_space_time_envelope {
+    ($dimensions:expr, $spatial_dimensions:expr) => {
+        impl<S> SpaceTimeEnvelope for AABB<[S; $dimensions]>
+        where
+            S: RTreeNum,
+        {
+            type Spatial = AABB<[S; $spatial_dimensions]>;
+            type SpatialPoint = [S; $spatial_dimensions];
+
+            fn from_space_time(spatial: &Self::Spatial, start: S, end: S) -> Self {
+                let mut lower = [start; $dimensions];
+                let mut upper = [end; $dimensions];
+                lower[..$spatial_dimensions].copy_from_slice(&spatial.lower());
+                upper[..$spatial_dimensions].copy_from_slice(&spatial.upper());
+                AABB::from_lower_and_upper(lower, upper)
+            }
+
+            fn spatial(&self) -> Self::Spatial {
+                let mut lower = [S::zero(); $spatial_dimensions];
+                let mut upper = [S::zero(); $spatial_dimensions];
+                lower.copy_from_slice(&self.lower()[..$spatial_dimensions]);
+                upper.copy_from_slice(&self.upper()[..$spatial_dimensions]);
+                AABB::from_lower_and_upper(lower, upper)
+            }
+
+            fn time_interval(&self) -> (S, S) {
+                (
+                    self.lower()[$spatial_dimensions],
+                    self.upper()[$spatial_dimensions],
+                )
+            }
+        }
+    };
+}
+
+implement_space_time_envelope!(3, 2);
+implement_space_time_envelope!(4, 3);
+
+#[cfg(test)]
+mod test {
+    use super::SpaceTimeEnvelope;
+    use crate::envelope::Envelope;
+    use crate::object::RTreeObject;
+    use crate::primitives::Rectangle;
+    use crate::test_utilities::{create_random_points, SEED_1, SEED_2};
+    use crate::{RTree, AABB};
+
+    #[test]
+    fn test_space_time_envelope() {
+        let spatial = AABB::from_corners([1, 2, 3], [4, 5, 6]);
+        let envelope = AABB::from_space_time(&spatial, -1, 1);
+        assert_eq!(envelope, AABB::from_corners([1, 2, 3, -1], [4, 5, 6, 1]));
+        assert_eq!(envelope.spatial(), spatial);
+        assert_eq!(envelope.time_interval(), (-1, 1));
+
+        let empty = AABB::from_space_time(&AABB::from_corners([0, 0], [1, 1]), 1, 0);
+        assert!(!empty.contains_point(&[0, 0, 0]));
+        assert!(!empty.contains_point(&[0, 0, 1]));
+    }
+
+    #[test]
+    fn test_space_time_queries() {
+        let events: Vec<Rectangle<[f64; 3]>> = create_random_points(500, SEED_1)
+            .into_iter()
+            .zip(create_random_points(500, SEED_2))
+            .map(|(location, time)| {
+                let spatial = AABB::from_corners(location, [location[0] + 0.1, location[1]]);
+                Rectangle::from_aabb(AABB::from_space_time(&spatial, time[0], time[0] + 0.05))
+            })
+            .collect();
+        let tree = RTree::bulk_load(events.clone());
+        let area = AABB::from_corners([0.2, 0.3], [0.6, 0.5]);
+
+        let during = tree.locate_in_envelope_during(&area, 0.25, 0.75).count();
+        let intersecting = tree.locate_intersecting_during(&area, 0.25, 0.75).count();
+        let expected_during = events
+            .iter()
+            .filter(|event| {
+                let (start, end) = event.envelope().time_interval();
+                area.contains_envelope(&event.envelope().spatial()) && start >= 0.25 && end <= 0.75
+            })
+            .count();
+        let expected_intersecting = events
+            .iter()
+            .filter(|event| {
+                let (start, end) = event.envelope().time_interval();
+                area.intersects(&event.envelope().spatial()) && end >= 0.25 && start <= 0.75
+            })
+            .count();
+        assert_eq!(during, expected_during);
+        assert_eq!(intersecting, expected_intersecting);
+        assert!(during > 0);
+        assert!(intersecting > during);
+    }
+}
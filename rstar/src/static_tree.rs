@@ -0,0 +1,486 @@
+use crate::algorithm::selection_functions::*;
+use crate::envelope::Envelope;
+use crate::node::{ParentNode, RTreeNode};
+use crate::object::{PointDistance, RTreeObject};
+use crate::params::{DefaultParams, RTreeParams};
+use crate::point::Point;
+use crate::rplus::HeapEntry;
+use crate::rtree::RTree;
+use num_traits::Zero;
+use std::collections::{BinaryHeap, VecDeque};
+use std::ops::Range;
+
+/// A read-only r-tree stored in flat, contiguous arrays.
+///
+/// An [RTree](struct.RTree.html) allocates a separate vector for the children of each node.
+/// A `StaticRTree` stores all nodes in a single array in breadth first order and all elements
+/// in another one, the elements below a node are stored next to each other. Each node only
+/// stores its envelope and two index ranges. For immutable datasets, this requires less
+/// memory and queries touch fewer cache lines.
+///
+/// The tree is created once, either by [bulk loading](#method.bulk_load) or by
+/// converting an existing `RTree`. It cannot be modified afterwards.
+///
+/// # Example
+/// ```
+/// use rstar::{RTree, StaticRTree, AABB};
+///
+/// let tree = StaticRTree::bulk_load(vec![[0.0, 0.0], [1.0, 1.0], [2.0, 0.5]]);
+/// assert_eq!(tree.size(), 3);
+/// assert_eq!(tree.nearest_neighbor(&[1.8, 0.4]), Some(&[2.0, 0.5]));
+/// let unit_square = AABB::from_corners([0.0, 0.0], [1.0, 1.0]);
+/// assert_eq!(tree.locate_in_envelope(&unit_square).count(), 2);
+///
+/// // Freeze a tree that was built by insertion
+/// let mut tree = RTree::new();
+/// tree.insert([3.0, 3.0]);
+/// let frozen = StaticRTree::from(tree);
+/// assert!(frozen.contains(&[3.0, 3.0]));
+/// ```
+pub struct StaticRTree<T, Params = DefaultParams>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    // The root is stored first, the children of a node are stored consecutively
+    nodes: Vec<StaticNode<T::Envelope>>,
+    elements: Vec<T>,
+    _params: ::std::marker::PhantomData<Params>,
+}
+
+#[derive(Clone)]
+struct StaticNode<E> {
+    envelope: E,
+    // Child nodes and elements directly below this node
+    children: Range<usize>,
+    elements: Range<usize>,
+}
+
+impl<T> StaticRTree<T>
+where
+    T: RTreeObject,
+{
+    /// Creates a new static r-tree from a set of elements.
+    ///
+    /// The elements are arranged like [RTree::bulk_load](struct.RTree.html#method.bulk_load)
+    /// would arrange them.
+    ///
+    /// # Runtime
+    /// Bulk loading runs in `O(n * log(n))`, where `n` is the number of loaded
+    /// elements.
+    pub fn bulk_load(elements: Vec<T>) -> Self {
+        Self::bulk_load_with_params(elements)
+    }
+}
+
+impl<T, Params> StaticRTree<T, Params>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    /// Creates a new static r-tree with configurable parameters.
+    ///
+    /// For more information refer to [bulk_load](#method.bulk_load)
+    /// and [RTreeParams](trait.RTreeParams.html).
+    pub fn bulk_load_with_params(elements: Vec<T>) -> Self {
+        RTree::bulk_load_with_params(elements).into()
+    }
+
+    /// Returns the number of elements in the tree.
+    pub fn size(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Returns all elements in the tree, in storage order.
+    pub fn elements(&self) -> &[T] {
+        &self.elements
+    }
+
+    /// Returns an iterator over all elements in the tree.
+    pub fn iter(&self) -> ::std::slice::Iter<'_, T> {
+        self.elements.iter()
+    }
+
+    /// Returns all elements selected by a [SelectionFunction](trait.SelectionFunction.html),
+    /// see [RTree::locate_with_selection_function](struct.RTree.html#method.locate_with_selection_function).
+    pub fn locate_with_selection_function<S: SelectionFunction<T>>(
+        &self,
+        function: S,
+    ) -> impl Iterator<Item = &T> {
+        StaticSelectionIterator {
+            tree: self,
+            function,
+            nodes: vec![0],
+            elements: [].iter(),
+        }
+    }
+
+    /// Returns all elements contained in an envelope, see
+    /// [RTree::locate_in_envelope](struct.RTree.html#method.locate_in_envelope).
+    pub fn locate_in_envelope(&self, envelope: &T::Envelope) -> impl Iterator<Item = &T> {
+        self.locate_with_selection_function(SelectInEnvelopeFunction::new(*envelope))
+    }
+
+    /// Returns all elements whose envelope intersects an envelope, see
+    /// [RTree::locate_in_envelope_intersecting](struct.RTree.html#method.locate_in_envelope_intersecting).
+    pub fn locate_in_envelope_intersecting(
+        &self,
+        envelope: &T::Envelope,
+    ) -> impl Iterator<Item = &T> {
+        self.locate_with_selection_function(SelectInEnvelopeFuncIntersecting::new(*envelope))
+    }
+
+    /// Returns `true` if the tree contains an element equal (`==`) to a given element.
+    pub fn contains(&self, t: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.locate_in_envelope(&t.envelope()).any(|e| e == t)
+    }
+
+    /// Converts this tree back into a mutable [RTree](struct.RTree.html), keeping its
+    /// structure.
+    ///
+    /// This is the inverse of [RTree::freeze](struct.RTree.html#method.freeze).
+    pub fn thaw(self) -> RTree<T, Params> {
+        self.into()
+    }
+}
+
+impl<T, Params> StaticRTree<T, Params>
+where
+    T: PointDistance,
+    Params: RTreeParams,
+{
+    /// Returns a single element that contains a point, see
+    /// [RTree::locate_at_point](struct.RTree.html#method.locate_at_point).
+    pub fn locate_at_point(&self, point: &<T::Envelope as Envelope>::Point) -> Option<&T> {
+        self.locate_all_at_point(point).next()
+    }
+
+    /// Returns all elements that contain a point, see
+    /// [RTree::locate_all_at_point](struct.RTree.html#method.locate_all_at_point).
+    pub fn locate_all_at_point(
+        &self,
+        point: &<T::Envelope as Envelope>::Point,
+    ) -> impl Iterator<Item = &T> {
+        self.locate_with_selection_function(SelectAtPointFunction::new(*point))
+    }
+
+    /// Returns all elements within a squared distance of a point, see
+    /// [RTree::locate_within_distance](struct.RTree.html#method.locate_within_distance).
+    pub fn locate_within_distance(
+        &self,
+        query_point: <T::Envelope as Envelope>::Point,
+        max_squared_radius: <<T::Envelope as Envelope>::Point as Point>::Scalar,
+    ) -> impl Iterator<Item = &T> {
+        self.locate_with_selection_function(SelectWithinDistanceFunction::new(
+            query_point,
+            max_squared_radius,
+        ))
+    }
+
+    /// Returns the nearest neighbor of a point, see
+    /// [RTree::nearest_neighbor](struct.RTree.html#method.nearest_neighbor).
+    ///
+    /// Returns `None` if the tree is empty.
+    pub fn nearest_neighbor(&self, query_point: &<T::Envelope as Envelope>::Point) -> Option<&T> {
+        self.nearest_neighbor_iter(query_point).next()
+    }
+
+    /// Returns all elements in the order of their distance to a point, see
+    /// [RTree::nearest_neighbor_iter](struct.RTree.html#method.nearest_neighbor_iter).
+    pub fn nearest_neighbor_iter(
+        &self,
+        query_point: &<T::Envelope as Envelope>::Point,
+    ) -> impl Iterator<Item = &T> {
+        let mut heap = BinaryHeap::new();
+        heap.push(HeapEntry {
+            distance_2: Zero::zero(),
+            index: 0,
+            is_element: false,
+        });
+        StaticNearestNeighborIterator {
+            tree: self,
+            query_point: *query_point,
+            heap,
+        }
+    }
+}
+
+impl<T, Params> From<RTree<T, Params>> for StaticRTree<T, Params>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    /// Converts a tree into its flat representation, keeping its structure.
+    fn from(mut tree: RTree<T, Params>) -> Self {
+        let root = ::std::mem::replace(tree.root_mut(), ParentNode::new_empty());
+        let mut nodes = vec![StaticNode {
+            envelope: root.envelope,
+            children: 0..0,
+            elements: 0..0,
+        }];
+        let mut elements = Vec::with_capacity(tree.size());
+        // Nodes are stored in the order they are visited, each node's index is thus known
+        // when its children are appended.
+        let mut queue = VecDeque::new();
+        queue.push_back(root);
+        let mut index = 0;
+        while let Some(parent) = queue.pop_front() {
+            let elements_start = elements.len();
+            let children_start = nodes.len();
+            for child in parent.children {
+                match child {
+                    RTreeNode::Leaf(t) => elements.push(t),
+                    RTreeNode::Parent(data) => {
+                        nodes.push(StaticNode {
+                            envelope: data.envelope,
+                            children: 0..0,
+                            elements: 0..0,
+                        });
+                        queue.push_back(data);
+                    }
+                }
+            }
+            nodes[index].children = children_start..nodes.len();
+            nodes[index].elements = elements_start..elements.len();
+            index += 1;
+        }
+        StaticRTree {
+            nodes,
+            elements,
+            _params: Default::default(),
+        }
+    }
+}
+
+impl<T, Params> From<StaticRTree<T, Params>> for RTree<T, Params>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    /// Converts a flat tree back into nested nodes, keeping its structure.
+    fn from(tree: StaticRTree<T, Params>) -> Self {
+        let size = tree.size();
+        let mut elements = tree.elements;
+        // All children of a node are stored after it, building the nodes back to front thus
+        // creates every child before its parent. The elements of later nodes are stored
+        // last and can be split off.
+        let mut built: Vec<Option<ParentNode<T>>> = Vec::with_capacity(tree.nodes.len());
+        built.resize_with(tree.nodes.len(), || None);
+        for (index, node) in tree.nodes.iter().enumerate().rev() {
+            let mut children: Vec<_> = elements
+                .split_off(node.elements.start)
+                .into_iter()
+                .map(RTreeNode::Leaf)
+                .collect();
+            children.extend(
+                node.children
+                    .clone()
+                    .map(|child| RTreeNode::Parent(built[child].take().unwrap())),
+            );
+            built[index] = Some(ParentNode::new_with_envelope(children, node.envelope));
+        }
+        let root = built[0].take().unwrap();
+        RTree::from_root(root, size)
+    }
+}
+
+struct StaticSelectionIterator<'a, T, Params, Func>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+{
+    tree: &'a StaticRTree<T, Params>,
+    function: Func,
+    // Nodes whose children remain to be checked
+    nodes: Vec<usize>,
+    // The current node's remaining elements
+    elements: ::std::slice::Iter<'a, T>,
+}
+
+impl<'a, T, Params, Func> Iterator for StaticSelectionIterator<'a, T, Params, Func>
+where
+    T: RTreeObject,
+    Params: RTreeParams,
+    Func: SelectionFunction<T>,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            for t in &mut self.elements {
+                if self.function.should_unpack_leaf(t) {
+                    return Some(t);
+                }
+            }
+            let node = &self.tree.nodes[self.nodes.pop()?];
+            for child in node.children.clone() {
+                if self
+                    .function
+                    .should_unpack_parent(&self.tree.nodes[child].envelope)
+                {
+                    self.nodes.push(child);
+                }
+            }
+            self.elements = self.tree.elements[node.elements.clone()].iter();
+        }
+    }
+}
+
+struct StaticNearestNeighborIterator<'a, T, Params>
+where
+    T: PointDistance,
+    Params: RTreeParams,
+{
+    tree: &'a StaticRTree<T, Params>,
+    query_point: <T::Envelope as Envelope>::Point,
+    heap: BinaryHeap<HeapEntry<<<T::Envelope as Envelope>::Point as Point>::Scalar>>,
+}
+
+impl<'a, T, Params> Iterator for StaticNearestNeighborIterator<'a, T, Params>
+where
+    T: PointDistance,
+    Params: RTreeParams,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        while let Some(entry) = self.heap.pop() {
+            if entry.is_element {
+                return Some(&self.tree.elements[entry.index]);
+            }
+            let node = &self.tree.nodes[entry.index];
+            for index in node.elements.clone() {
+                self.heap.push(HeapEntry {
+                    distance_2: self.tree.elements[index].distance_2(&self.query_point),
+                    index,
+                    is_element: true,
+                });
+            }
+            for index in node.children.clone() {
+                self.heap.push(HeapEntry {
+                    distance_2: self.tree.nodes[index]
+                        .envelope
+                        .distance_2(&self.query_point),
+                    index,
+                    is_element: false,
+                });
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::StaticRTree;
+    use crate::envelope::Envelope;
+    use crate::object::{PointDistance, RTreeObject};
+    use crate::test_utilities::{create_random_points, create_random_rectangles, SEED_1, SEED_2};
+    use crate::{DefaultParams, RTree, AABB};
+
+    #[test]
+    fn test_static_queries() {
+        let points = create_random_points(1000, SEED_1);
+        let tree = RTree::bulk_load(points.clone());
+        let static_tree = StaticRTree::bulk_load(points.clone());
+        assert_eq!(static_tree.size(), 1000);
+        assert_eq!(static_tree.iter().count(), 1000);
+        assert!(points.iter().all(|p| static_tree.contains(p)));
+
+        let mut previous = [0.0, 0.0];
+        for query_point in create_random_points(100, SEED_2) {
+            assert_eq!(
+                static_tree.nearest_neighbor(&query_point),
+                tree.nearest_neighbor(&query_point)
+            );
+            let distances: Vec<_> = static_tree
+                .nearest_neighbor_iter(&query_point)
+                .map(|p| p.distance_2(&query_point))
+                .collect();
+            assert_eq!(distances.len(), 1000);
+            assert!(distances.windows(2).all(|w| w[0] <= w[1]));
+
+            let envelope = AABB::from_corners(previous, query_point);
+            assert_eq!(
+                static_tree.locate_in_envelope(&envelope).count(),
+                tree.locate_in_envelope(&envelope).count()
+            );
+            assert_eq!(
+                static_tree
+                    .locate_within_distance(query_point, 0.01)
+                    .count(),
+                tree.locate_within_distance(query_point, 0.01).count()
+            );
+            previous = query_point;
+        }
+    }
+
+    #[test]
+    fn test_static_from_inserted_tree() {
+        let rectangles = create_random_rectangles(500, SEED_1);
+        let mut tree = RTree::new();
+        for rectangle in &rectangles {
+            tree.insert(*rectangle);
+        }
+        let expected: Vec<_> = create_random_points(50, SEED_2)
+            .iter()
+            .map(|p| tree.locate_all_at_point(p).count())
+            .collect();
+        let static_tree = StaticRTree::from(tree);
+        assert_eq!(static_tree.size(), 500);
+        for (point, expected) in create_random_points(50, SEED_2).iter().zip(expected) {
+            assert_eq!(static_tree.locate_all_at_point(point).count(), expected);
+        }
+        let envelope = AABB::from_corners([0.2, 0.2], [0.4, 0.4]);
+        let intersecting = static_tree
+            .locate_in_envelope_intersecting(&envelope)
+            .count();
+        let expected = rectangles
+            .iter()
+            .filter(|r| r.envelope().intersects(&envelope))
+            .count();
+        assert_eq!(intersecting, expected);
+
+        let empty: StaticRTree<[f64; 2]> = StaticRTree::bulk_load(Vec::new());
+        assert_eq!(empty.nearest_neighbor(&[0.0, 0.0]), None);
+        assert_eq!(
+            empty
+                .locate_in_envelope(&AABB::from_point([0.0, 0.0]))
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_freeze_and_thaw() {
+        let points = create_random_points(1000, SEED_1);
+        let mut tree = RTree::new();
+        for point in &points {
+            tree.insert(*point);
+        }
+        let height = tree.height();
+        let frozen = tree.freeze();
+        assert_eq!(frozen.size(), 1000);
+
+        let mut tree = frozen.thaw();
+        assert_eq!(tree.size(), 1000);
+        assert_eq!(tree.height(), height);
+        tree.root().sanity_check::<DefaultParams>();
+        assert!(points.iter().all(|p| tree.contains(p)));
+        for point in create_random_points(100, SEED_2) {
+            tree.insert(point);
+        }
+        for point in &points[..100] {
+            assert_eq!(tree.remove(point), Some(*point));
+        }
+        tree.root().sanity_check::<DefaultParams>();
+        assert_eq!(tree.size(), 1000);
+
+        let empty: RTree<[f64; 2]> = RTree::new().freeze().thaw();
+        assert_eq!(empty.size(), 0);
+        assert_eq!(empty.nearest_neighbor(&[0.0, 0.0]), None);
+    }
+}
@@ -1,8 +1,9 @@
 use crate::primitives::*;
-use crate::{Point, RTreeObject};
+use crate::{Point, RTreeObject, AABB};
 use rand::distributions::{Distribution, Uniform};
 use rand::{Rng, SeedableRng};
 use rand_hc::Hc128Rng;
+use std::cell::Cell;
 
 pub type Seed = [u8; 32];
 
@@ -53,3 +54,25 @@ pub fn create_random_rectangles(num_rectangles: usize, seed: &Seed) -> Vec<Recta
     let lines = create_random_lines(num_rectangles, seed);
     lines.iter().map(|line| line.envelope().into()).collect()
 }
+
+thread_local! {
+    static ENVELOPE_CALLS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A point that counts how often its envelope is calculated on the current thread.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CountingPoint(pub [f64; 2]);
+
+impl RTreeObject for CountingPoint {
+    type Envelope = AABB<[f64; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        ENVELOPE_CALLS.with(|calls| calls.set(calls.get() + 1));
+        AABB::from_point(self.0)
+    }
+}
+
+/// Returns and resets the number of `CountingPoint` envelopes calculated on this thread.
+pub fn take_envelope_calls() -> usize {
+    ENVELOPE_CALLS.with(|calls| calls.replace(0))
+}
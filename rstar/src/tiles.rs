@@ -0,0 +1,329 @@
+//! Helpers for slicing geographic data into web mercator map tiles.
+//!
+//! Map tiles follow the common XYZ scheme: At zoom level `z`, the world is divided into
+//! `2^z * 2^z` tiles. Tile `(0, 0)` is located in the north west corner.
+//!
+//! All functions of this module expect points as `[longitude, latitude]` in degrees.
+//!
+//! # Example
+//! ```
+//! use rstar::RTree;
+//! use rstar::tiles::Tile;
+//!
+//! let tree = RTree::bulk_load(vec![[13.4, 52.5], [2.35, 48.86], [-74.0, 40.7]]);
+//! let tiles: Vec<_> = tree.iter_by_tiles(1).collect();
+//! // Berlin and Paris share the north eastern tile, New York is located in the north west
+//! assert_eq!(tiles.len(), 2);
+//! assert_eq!(tiles[0].0, Tile::new(0, 0, 1));
+//! assert_eq!(tiles[1].0, Tile::new(1, 0, 1));
+//! assert_eq!(tiles[1].1.len(), 2);
+//! ```
+use crate::aabb::AABB;
+use crate::envelope::Envelope;
+use crate::node::{ParentNode, RTreeNode};
+use crate::object::RTreeObject;
+use std::f64::consts::PI;
+
+/// The largest latitude that can be displayed by web mercator tiles, in degrees.
+pub const MAX_LATITUDE: f64 = 85.051_128_779_806_59;
+
+/// Half the circumference of the earth in web mercator coordinates, in meters.
+const HALF_CIRCUMFERENCE: f64 = 20_037_508.342_789_244;
+
+/// A web mercator map tile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Tile {
+    zoom: u8,
+    x: u32,
+    y: u32,
+}
+
+impl Tile {
+    /// Creates a new tile.
+    ///
+    /// # Panics
+    /// Panics if the zoom level is larger than `31` or if a coordinate is not smaller than
+    /// `2^zoom`.
+    pub fn new(x: u32, y: u32, zoom: u8) -> Self {
+        assert!(zoom < 32, "Zoom level must be smaller than 32");
+        assert!(
+            x < tile_count(zoom) && y < tile_count(zoom),
+            "Tile coordinates out of range"
+        );
+        Tile { zoom, x, y }
+    }
+
+    /// Returns the tile that contains a `[longitude, latitude]` position.
+    ///
+    /// Positions outside of the tiled area are clamped to the closest tile.
+    pub fn containing(position: [f64; 2], zoom: u8) -> Self {
+        assert!(zoom < 32, "Zoom level must be smaller than 32");
+        let n = f64::from(tile_count(zoom));
+        let x = (position[0] + 180.0) / 360.0 * n;
+        let latitude = position[1].clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+        let y = (1.0 - (latitude.tan() + 1.0 / latitude.cos()).ln() / PI) / 2.0 * n;
+        Tile {
+            zoom,
+            x: clamp_coordinate(x, zoom),
+            y: clamp_coordinate(y, zoom),
+        }
+    }
+
+    /// Returns the tile's x coordinate, counted from west to east.
+    pub fn x(&self) -> u32 {
+        self.x
+    }
+
+    /// Returns the tile's y coordinate, counted from north to south.
+    pub fn y(&self) -> u32 {
+        self.y
+    }
+
+    /// Returns the tile's zoom level.
+    pub fn zoom(&self) -> u8 {
+        self.zoom
+    }
+
+    /// Returns the area covered by this tile as `[longitude, latitude]` envelope in degrees.
+    ///
+    /// # Example
+    /// ```
+    /// use rstar::tiles::{Tile, MAX_LATITUDE};
+    ///
+    /// let envelope = Tile::new(0, 0, 0).envelope();
+    /// assert_eq!(envelope.lower(), [-180.0, -MAX_LATITUDE]);
+    /// assert_eq!(envelope.upper(), [180.0, MAX_LATITUDE]);
+    /// ```
+    pub fn envelope(&self) -> AABB<[f64; 2]> {
+        let n = f64::from(tile_count(self.zoom));
+        let longitude = |x: u32| f64::from(x) / n * 360.0 - 180.0;
+        let latitude = |y: u32| {
+            let (north, south) = (0, tile_count(self.zoom));
+            if y == north {
+                MAX_LATITUDE
+            } else if y == south {
+                -MAX_LATITUDE
+            } else {
+                (PI * (1.0 - 2.0 * f64::from(y) / n))
+                    .sinh()
+                    .atan()
+                    .to_degrees()
+            }
+        };
+        AABB::from_corners(
+            [longitude(self.x), latitude(self.y + 1)],
+            [longitude(self.x + 1), latitude(self.y)],
+        )
+    }
+
+    /// Returns the area covered by this tile in web mercator coordinates (EPSG:3857), in
+    /// meters.
+    pub fn web_mercator_envelope(&self) -> AABB<[f64; 2]> {
+        let tile_size = 2.0 * HALF_CIRCUMFERENCE / f64::from(tile_count(self.zoom));
+        let x = |x: u32| f64::from(x) * tile_size - HALF_CIRCUMFERENCE;
+        let y = |y: u32| HALF_CIRCUMFERENCE - f64::from(y) * tile_size;
+        AABB::from_corners([x(self.x), y(self.y + 1)], [x(self.x + 1), y(self.y)])
+    }
+}
+
+fn tile_count(zoom: u8) -> u32 {
+    1 << zoom
+}
+
+fn clamp_coordinate(value: f64, zoom: u8) -> u32 {
+    let max = tile_count(zoom) - 1;
+    if value.is_nan() || value < 0.0 {
+        0
+    } else if value >= f64::from(max) {
+        max
+    } else {
+        value as u32
+    }
+}
+
+/// Returns `true` if an envelope intersects the area assigned to a tile.
+///
+/// Tiles include their western and northern border but not their eastern and southern one,
+/// such that a point on a border belongs to a single tile. Tiles at the edge of the map
+/// extend to infinity, as positions outside of the tiled area are clamped to them by
+/// [Tile::containing](struct.Tile.html#method.containing).
+fn intersects_tile(envelope: &AABB<[f64; 2]>, tile: &Tile) -> bool {
+    let bounds = tile.envelope();
+    let last = tile_count(tile.zoom) - 1;
+    let (lower, upper) = (envelope.lower(), envelope.upper());
+    (tile.x == 0 || upper[0] >= bounds.lower()[0])
+        && (tile.x == last || lower[0] < bounds.upper()[0])
+        && (tile.y == 0 || lower[1] <= bounds.upper()[1])
+        && (tile.y == last || upper[1] > bounds.lower()[1])
+}
+
+/// Iterates over all non-empty tiles of a zoom level, see
+/// [iter_by_tiles](../struct.RTree.html#method.iter_by_tiles).
+pub struct TileIterator<'a, T>
+where
+    T: RTreeObject<Envelope = AABB<[f64; 2]>>,
+{
+    zoom: u8,
+    // Tiles that remain to be split, together with the nodes intersecting them
+    tiles: Vec<(Tile, Vec<&'a RTreeNode<T>>)>,
+}
+
+impl<'a, T> TileIterator<'a, T>
+where
+    T: RTreeObject<Envelope = AABB<[f64; 2]>>,
+{
+    pub(crate) fn new(root: &'a ParentNode<T>, zoom: u8) -> Self {
+        assert!(zoom < 32, "Zoom level must be smaller than 32");
+        let mut tiles = Vec::new();
+        if !root.children().is_empty() {
+            tiles.push((Tile::new(0, 0, 0), root.children().iter().collect()));
+        }
+        TileIterator { zoom, tiles }
+    }
+}
+
+impl<'a, T> Iterator for TileIterator<'a, T>
+where
+    T: RTreeObject<Envelope = AABB<[f64; 2]>>,
+{
+    type Item = (Tile, Vec<&'a T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((tile, mut nodes)) = self.tiles.pop() {
+            if tile.zoom == self.zoom {
+                let mut elements = Vec::new();
+                while let Some(node) = nodes.pop() {
+                    match node {
+                        RTreeNode::Leaf(ref t) => elements.push(t),
+                        RTreeNode::Parent(ref data) => nodes.extend(
+                            data.children()
+                                .iter()
+                                .filter(|child| intersects_tile(&child.envelope(), &tile)),
+                        ),
+                    }
+                }
+                return Some((tile, elements));
+            }
+            // Push the quadrants in reverse to return the north western quadrant first
+            for &(dx, dy) in &[(1, 1), (0, 1), (1, 0), (0, 0)] {
+                let quadrant = Tile {
+                    zoom: tile.zoom + 1,
+                    x: tile.x * 2 + dx,
+                    y: tile.y * 2 + dy,
+                };
+                let quadrant_envelope = quadrant.envelope();
+                let mut quadrant_nodes = Vec::new();
+                let mut todo: Vec<_> = nodes.clone();
+                while let Some(node) = todo.pop() {
+                    let envelope = node.envelope();
+                    if !intersects_tile(&envelope, &quadrant) {
+                        continue;
+                    }
+                    match node {
+                        // Nodes only partially within the quadrant are split to prune
+                        // empty quadrants early
+                        RTreeNode::Parent(ref data)
+                            if !quadrant_envelope.contains_envelope(&envelope) =>
+                        {
+                            todo.extend(data.children())
+                        }
+                        _ => quadrant_nodes.push(node),
+                    }
+                }
+                if !quadrant_nodes.is_empty() {
+                    self.tiles.push((quadrant, quadrant_nodes));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Tile, MAX_LATITUDE};
+    use crate::test_utilities::{create_random_points, SEED_1};
+    use crate::{Envelope, RTree};
+
+    #[test]
+    fn test_tile_envelopes() {
+        let berlin = [13.4, 52.5];
+        let tile = Tile::containing(berlin, 10);
+        assert_eq!(tile, Tile::new(550, 335, 10));
+        assert!(tile.envelope().contains_point(&berlin));
+
+        let world = Tile::new(0, 0, 0).web_mercator_envelope();
+        assert!((world.upper()[0] - 20_037_508.34).abs() < 0.01);
+        assert_eq!(Tile::containing([-200.0, 90.0], 3), Tile::new(0, 0, 3));
+        assert_eq!(Tile::containing([200.0, -90.0], 3), Tile::new(7, 7, 3));
+
+        let south_west = Tile::new(0, 3, 2).envelope();
+        assert_eq!(south_west.lower(), [-180.0, -MAX_LATITUDE]);
+        let north_east = Tile::new(1, 0, 1).envelope();
+        assert_eq!(north_east.lower(), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_iter_by_tiles() {
+        let points: Vec<_> = create_random_points(500, SEED_1)
+            .iter()
+            .map(|p| [p[0] * 40.0 - 20.0, p[1] * 40.0 + 10.0])
+            .collect();
+        let tree = RTree::bulk_load(points.clone());
+        let zoom = 5;
+        let mut count = 0;
+        for (tile, elements) in tree.iter_by_tiles(zoom) {
+            assert!(!elements.is_empty());
+            for point in elements {
+                assert!(tile.envelope().contains_point(point));
+                count += 1;
+            }
+        }
+        // Points are located strictly inside of a single tile
+        assert_eq!(count, points.len());
+        let expected_tiles = {
+            let mut tiles: Vec<_> = points.iter().map(|p| Tile::containing(*p, zoom)).collect();
+            tiles.sort();
+            tiles.dedup();
+            tiles
+        };
+        let mut tiles: Vec<_> = tree.iter_by_tiles(zoom).map(|(tile, _)| tile).collect();
+        tiles.sort();
+        assert_eq!(tiles, expected_tiles);
+
+        let empty: RTree<[f64; 2]> = RTree::new();
+        assert_eq!(empty.iter_by_tiles(zoom).count(), 0);
+    }
+
+    #[test]
+    fn test_tile_borders() {
+        let points = vec![[0.0, 0.0], [0.0, 89.0], [-179.0, -89.9], [180.0, 0.0]];
+        let tree = RTree::bulk_load(points.clone());
+        let tiles: Vec<_> = tree.iter_by_tiles(1).collect();
+        // Each point is returned exactly once, including points beyond MAX_LATITUDE
+        let count: usize = tiles.iter().map(|(_, elements)| elements.len()).sum();
+        assert_eq!(count, points.len());
+        // Tiles contain their northern border, [0.0, 0.0] belongs to the south eastern tile
+        assert_eq!(tiles[0], (Tile::new(1, 0, 1), vec![&[0.0, 89.0]]));
+        assert_eq!(tiles[1], (Tile::new(0, 1, 1), vec![&[-179.0, -89.9]]));
+        assert_eq!(tiles[2].0, Tile::new(1, 1, 1));
+        assert_eq!(tiles[2].1.len(), 2);
+        for point in &points {
+            let tile = Tile::containing(*point, 1);
+            assert!(tiles.iter().any(|(t, e)| *t == tile && e.contains(&point)));
+        }
+    }
+
+    #[test]
+    fn test_iter_by_tiles_deep_zoom() {
+        // A global dataset at a zoom level with 2^60 tiles
+        let points = vec![[-170.0, 60.0], [10.0, 10.0], [170.0, -60.0]];
+        let tree = RTree::bulk_load(points.clone());
+        let tiles: Vec<_> = tree.iter_by_tiles(30).collect();
+        assert_eq!(tiles.len(), 3);
+        for (point, (tile, elements)) in points.iter().zip(tiles) {
+            assert_eq!(tile, Tile::containing(*point, 30));
+            assert_eq!(elements, vec![point]);
+        }
+    }
+}
@@ -0,0 +1,460 @@
+//! Conversions from and to [well-known text](https://en.wikipedia.org/wiki/Well-known_text_representation_of_geometry).
+//!
+//! Requires the `wkt` feature. Points are mapped to `POINT`, [Line](../primitives/struct.Line.html)s
+//! to `LINESTRING`s with two positions and rectangles to `POLYGON`s with a single, axis
+//! aligned ring. Points with three or four dimensions use the `Z` and `ZM` tags.
+//!
+//! # Example
+//! ```
+//! use rstar::primitives::Line;
+//! use rstar::wkt::{FromWkt, ToWkt};
+//! use rstar::RTree;
+//!
+//! let rows = ["LINESTRING (0 0, 1 1)", "LINESTRING (2 0, 2 1.5)"];
+//! let tree: RTree<Line<[f64; 2]>> = rows
+//!     .iter()
+//!     .map(|row| Line::from_wkt(row))
+//!     .collect::<Result<_, _>>()
+//!     .unwrap();
+//! assert_eq!(tree.size(), 2);
+//!
+//! let nearest = tree.nearest_neighbor(&[1.9, 0.0]).unwrap();
+//! assert_eq!(nearest.to_wkt(), "LINESTRING (2 0, 2 1.5)");
+//! ```
+use crate::aabb::AABB;
+use crate::object::RTreeObject;
+use crate::point::{Point, RTreeNum};
+use crate::primitives::{AnyGeometry, Line, Rectangle};
+use std::fmt::{self, Display, Write};
+use std::str::FromStr;
+
+/// Geometries that can be converted to well-known text.
+pub trait ToWkt {
+    /// Returns the geometry as well-known text.
+    fn to_wkt(&self) -> String;
+}
+
+/// Geometries that can be parsed from well-known text.
+pub trait FromWkt: Sized {
+    /// Parses a geometry.
+    ///
+    /// Keywords are case insensitive. Fails if the text describes a different type of
+    /// geometry, has a different number of dimensions or is malformed.
+    fn from_wkt(wkt: &str) -> Result<Self, WktError>;
+}
+
+/// The error returned when parsing well-known text fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WktError {
+    message: String,
+}
+
+impl WktError {
+    fn new(message: impl Into<String>) -> Self {
+        WktError {
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for WktError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid WKT: {}", self.message)
+    }
+}
+
+impl std::error::Error for WktError {}
+
+fn dimension_tag<P: Point>() -> &'static str {
+    match P::DIMENSIONS {
+        3 => " Z",
+        4 => " ZM",
+        _ => "",
+    }
+}
+
+fn write_position<P>(point: &P, result: &mut String)
+where
+    P: Point,
+    P::Scalar: Display,
+{
+    for axis in 0..P::DIMENSIONS {
+        if axis > 0 {
+            result.push(' ');
+        }
+        write!(result, "{}", point.nth(axis)).expect("Writing into a String never fails");
+    }
+}
+
+fn write_positions<P>(positions: &[P], result: &mut String)
+where
+    P: Point,
+    P::Scalar: Display,
+{
+    result.push('(');
+    for (index, position) in positions.iter().enumerate() {
+        if index > 0 {
+            result.push_str(", ");
+        }
+        write_position(position, result);
+    }
+    result.push(')');
+}
+
+fn rectangle_ring<S: RTreeNum>(envelope: &AABB<[S; 2]>) -> [[S; 2]; 5] {
+    let ([x0, y0], [x1, y1]) = (envelope.lower(), envelope.upper());
+    [[x0, y0], [x1, y0], [x1, y1], [x0, y1], [x0, y0]]
+}
+
+/// A minimal recursive descent parser for the supported subset of WKT.
+struct Parser<'a> {
+    remaining: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn new(wkt: &'a str) -> Self {
+        Parser { remaining: wkt }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        self.remaining = self.remaining.trim_start();
+        let end = self
+            .remaining
+            .find(|c| !predicate(c))
+            .unwrap_or(self.remaining.len());
+        let (token, remaining) = self.remaining.split_at(end);
+        self.remaining = remaining;
+        token
+    }
+
+    fn keyword(&mut self) -> String {
+        self.take_while(|c| c.is_ascii_alphabetic())
+            .to_ascii_uppercase()
+    }
+
+    fn expect_keyword(&mut self, expected: &str) -> Result<(), WktError> {
+        let keyword = self.keyword();
+        if keyword == expected {
+            Ok(())
+        } else {
+            Err(WktError::new(format!(
+                "Expected {}, found {:?}",
+                expected, keyword
+            )))
+        }
+    }
+
+    /// Reads the optional dimension tag following a geometry's keyword.
+    fn dimensions<P: Point>(&mut self) -> Result<(), WktError> {
+        let tag = self.keyword();
+        if tag.is_empty() || tag == dimension_tag::<P>().trim_start() {
+            Ok(())
+        } else {
+            Err(WktError::new(format!(
+                "Expected a {}-dimensional geometry, found tag {:?}",
+                P::DIMENSIONS,
+                tag
+            )))
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), WktError> {
+        self.remaining = self.remaining.trim_start();
+        if self.remaining.starts_with(expected) {
+            self.remaining = &self.remaining[expected.len_utf8()..];
+            Ok(())
+        } else {
+            Err(WktError::new(format!("Expected {:?}", expected)))
+        }
+    }
+
+    fn position<P>(&mut self) -> Result<P, WktError>
+    where
+        P: Point,
+        P::Scalar: FromStr,
+    {
+        let mut scalars = Vec::with_capacity(P::DIMENSIONS);
+        for _ in 0..P::DIMENSIONS {
+            let token = self.take_while(|c| !c.is_whitespace() && c != ',' && c != ')');
+            let scalar = token
+                .parse()
+                .map_err(|_| WktError::new(format!("Invalid number {:?}", token)))?;
+            scalars.push(scalar);
+        }
+        Ok(P::generate(|axis| scalars[axis]))
+    }
+
+    fn positions<P>(&mut self) -> Result<Vec<P>, WktError>
+    where
+        P: Point,
+        P::Scalar: FromStr,
+    {
+        self.expect('(')?;
+        let mut result = vec![self.position()?];
+        while self.expect(',').is_ok() {
+            result.push(self.position()?);
+        }
+        self.expect(')')?;
+        Ok(result)
+    }
+
+    fn point<P>(&mut self) -> Result<P, WktError>
+    where
+        P: Point,
+        P::Scalar: FromStr,
+    {
+        self.dimensions::<P>()?;
+        let mut positions = self.positions()?;
+        if positions.len() != 1 {
+            return Err(WktError::new("A point must contain a single position"));
+        }
+        Ok(positions.remove(0))
+    }
+
+    fn line<P>(&mut self) -> Result<Line<P>, WktError>
+    where
+        P: Point,
+        P::Scalar: FromStr,
+    {
+        self.dimensions::<P>()?;
+        match self.positions()?[..] {
+            [from, to] => Ok(Line::new(from, to)),
+            _ => Err(WktError::new("A line must contain exactly two positions")),
+        }
+    }
+
+    fn rectangle<S>(&mut self) -> Result<AABB<[S; 2]>, WktError>
+    where
+        S: RTreeNum + FromStr,
+    {
+        self.dimensions::<[S; 2]>()?;
+        self.expect('(')?;
+        let ring: Vec<[S; 2]> = self.positions()?;
+        self.expect(')')?;
+        let envelope = AABB::from_points(ring.iter());
+        let is_rectangle = ring.len() == 5 && ring[0] == ring[4] && {
+            let corners = rectangle_ring(&envelope);
+            corners[..4].iter().all(|corner| ring[..4].contains(corner))
+                && ring.windows(2).all(|edge| {
+                    // Consecutive corners must share one coordinate
+                    edge[0][0] == edge[1][0] || edge[0][1] == edge[1][1]
+                })
+        };
+        if is_rectangle {
+            Ok(envelope)
+        } else {
+            Err(WktError::new(
+                "A polygon must consist of a single axis aligned rectangle",
+            ))
+        }
+    }
+
+    fn finish<T>(&mut self, result: T) -> Result<T, WktError> {
+        if self.remaining.trim().is_empty() {
+            Ok(result)
+        } else {
+            Err(WktError::new(format!(
+                "Unexpected trailing input {:?}",
+                self.remaining.trim()
+            )))
+        }
+    }
+}
+
+macro_rules! implement_wkt_for_point {
+    ($($index:expr),*) => {
+        $(
+            impl<S> ToWkt for [S; $index]
+            where
+                S: RTreeNum + Display,
+            {
+                fn to_wkt(&self) -> String {
+                    let mut result = format!("POINT{} (", dimension_tag::<Self>());
+                    write_position(self, &mut result);
+                    result.push(')');
+                    result
+                }
+            }
+
+            impl<S> FromWkt for [S; $index]
+            where
+                S: RTreeNum + FromStr,
+            {
+                fn from_wkt(wkt: &str) -> Result<Self, WktError> {
+                    let mut parser = Parser::new(wkt);
+                    parser.expect_keyword("POINT")?;
+                    let point = parser.point()?;
+                    parser.finish(point)
+                }
+            }
+        )*
+    };
+}
+
+implement_wkt_for_point!(2, 3, 4);
+
+impl<P> ToWkt for Line<P>
+where
+    P: Point,
+    P::Scalar: Display,
+{
+    fn to_wkt(&self) -> String {
+        let mut result = format!("LINESTRING{} ", dimension_tag::<P>());
+        write_positions(&[self.from, self.to], &mut result);
+        result
+    }
+}
+
+impl<P> FromWkt for Line<P>
+where
+    P: Point,
+    P::Scalar: FromStr,
+{
+    fn from_wkt(wkt: &str) -> Result<Self, WktError> {
+        let mut parser = Parser::new(wkt);
+        parser.expect_keyword("LINESTRING")?;
+        let line = parser.line()?;
+        parser.finish(line)
+    }
+}
+
+impl<S> ToWkt for AABB<[S; 2]>
+where
+    S: RTreeNum + Display,
+{
+    fn to_wkt(&self) -> String {
+        let mut result = "POLYGON (".to_string();
+        write_positions(&rectangle_ring(self), &mut result);
+        result.push(')');
+        result
+    }
+}
+
+impl<S> FromWkt for AABB<[S; 2]>
+where
+    S: RTreeNum + FromStr,
+{
+    fn from_wkt(wkt: &str) -> Result<Self, WktError> {
+        let mut parser = Parser::new(wkt);
+        parser.expect_keyword("POLYGON")?;
+        let envelope = parser.rectangle()?;
+        parser.finish(envelope)
+    }
+}
+
+impl<S> ToWkt for Rectangle<[S; 2]>
+where
+    S: RTreeNum + Display,
+{
+    fn to_wkt(&self) -> String {
+        self.envelope().to_wkt()
+    }
+}
+
+impl<S> FromWkt for Rectangle<[S; 2]>
+where
+    S: RTreeNum + FromStr,
+{
+    fn from_wkt(wkt: &str) -> Result<Self, WktError> {
+        AABB::from_wkt(wkt).map(Rectangle::from_aabb)
+    }
+}
+
+impl<S> ToWkt for AnyGeometry<[S; 2]>
+where
+    S: RTreeNum + Display,
+{
+    fn to_wkt(&self) -> String {
+        match self {
+            AnyGeometry::Point(point) => point.to_wkt(),
+            AnyGeometry::Line(line) => line.to_wkt(),
+            AnyGeometry::Rectangle(rectangle) => rectangle.to_wkt(),
+        }
+    }
+}
+
+impl<S> FromWkt for AnyGeometry<[S; 2]>
+where
+    S: RTreeNum + FromStr,
+{
+    fn from_wkt(wkt: &str) -> Result<Self, WktError> {
+        let mut parser = Parser::new(wkt);
+        let geometry = match parser.keyword().as_str() {
+            "POINT" => AnyGeometry::Point(parser.point()?),
+            "LINESTRING" => AnyGeometry::Line(parser.line()?),
+            "POLYGON" => AnyGeometry::Rectangle(Rectangle::from_aabb(parser.rectangle()?)),
+            keyword => {
+                return Err(WktError::new(format!(
+                    "Unsupported geometry type {:?}",
+                    keyword
+                )))
+            }
+        };
+        parser.finish(geometry)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FromWkt, ToWkt};
+    use crate::primitives::{AnyGeometry, Line, Rectangle};
+    use crate::AABB;
+
+    #[test]
+    fn test_points() {
+        assert_eq!([1.5, -2.0].to_wkt(), "POINT (1.5 -2)");
+        assert_eq!([1, 2, 3].to_wkt(), "POINT Z (1 2 3)");
+        assert_eq!([1, 2, 3, 4].to_wkt(), "POINT ZM (1 2 3 4)");
+        assert_eq!(<[f64; 2]>::from_wkt("POINT (1.5 -2)"), Ok([1.5, -2.0]));
+        assert_eq!(<[f64; 2]>::from_wkt(" point(1e3   2)\n"), Ok([1000.0, 2.0]));
+        assert_eq!(<[i32; 3]>::from_wkt("POINT Z (1 2 3)"), Ok([1, 2, 3]));
+        assert_eq!(<[i32; 3]>::from_wkt("POINT (1 2 3)"), Ok([1, 2, 3]));
+
+        assert!(<[f64; 2]>::from_wkt("POINT (1 2 3)").is_err());
+        assert!(<[f64; 2]>::from_wkt("POINT (1)").is_err());
+        assert!(<[f64; 2]>::from_wkt("POINT Z (1 2)").is_err());
+        assert!(<[f64; 2]>::from_wkt("POINT (1 2), POINT (3 4)").is_err());
+        assert!(<[f64; 2]>::from_wkt("POINT EMPTY").is_err());
+        assert!(<[i32; 2]>::from_wkt("POINT (1.5 2)").is_err());
+        assert!(<[f64; 2]>::from_wkt("LINESTRING (0 0, 1 1)").is_err());
+    }
+
+    #[test]
+    fn test_lines_and_polygons() {
+        let line = Line::new([0.0, 1.0], [2.5, 3.0]);
+        assert_eq!(line.to_wkt(), "LINESTRING (0 1, 2.5 3)");
+        assert_eq!(Line::from_wkt(&line.to_wkt()), Ok(line));
+        assert!(Line::<[f64; 2]>::from_wkt("LINESTRING (0 1, 2 3, 4 5)").is_err());
+
+        let rectangle = Rectangle::from_corners([0, 1], [2, 3]);
+        assert_eq!(rectangle.to_wkt(), "POLYGON ((0 1, 2 1, 2 3, 0 3, 0 1))");
+        assert_eq!(Rectangle::from_wkt(&rectangle.to_wkt()), Ok(rectangle));
+        // Rings may start at any corner and use any orientation
+        assert_eq!(
+            AABB::from_wkt("POLYGON ((2 3, 2 1, 0 1, 0 3, 2 3))"),
+            Ok(AABB::from_corners([0, 1], [2, 3]))
+        );
+        assert!(AABB::<[i32; 2]>::from_wkt("POLYGON ((0 0, 2 0, 1 1, 0 0))").is_err());
+        assert!(AABB::<[i32; 2]>::from_wkt("POLYGON ((0 0, 2 2, 2 0, 0 2, 0 0))").is_err());
+    }
+
+    #[test]
+    fn test_any_geometry() {
+        let geometries = vec![
+            AnyGeometry::Point([1.0, 2.0]),
+            AnyGeometry::Line(Line::new([0.0, 0.0], [1.0, 1.0])),
+            AnyGeometry::Rectangle(Rectangle::from_corners([0.0, 0.0], [1.0, 0.5])),
+        ];
+        for geometry in &geometries {
+            assert_eq!(
+                AnyGeometry::from_wkt(&geometry.to_wkt()).as_ref(),
+                Ok(geometry)
+            );
+        }
+        let error = AnyGeometry::<[f64; 2]>::from_wkt("MULTIPOINT ((0 0))").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            r#"Invalid WKT: Unsupported geometry type "MULTIPOINT""#
+        );
+    }
+}